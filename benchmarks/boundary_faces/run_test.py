@@ -0,0 +1,51 @@
+import os
+import subprocess
+import sys
+
+SCRIPT = """
+import sys
+from time import time
+import numpy as np
+from pytucanos.mesh import Mesh33, get_cube
+
+n_split = int(sys.argv[1])
+coords, elems, etags, faces, ftags = get_cube()
+msh = Mesh33(coords, elems, etags, faces[:2], ftags[:2])
+for _ in range(n_split):
+    msh = msh.split()
+coords, elems = msh.get_coords(), msh.get_elems()
+centers = coords[elems].mean(axis=1)
+etags = np.where(centers[:, 0] < 0.5, 1, 2).astype(msh.get_etags().dtype)
+msh = Mesh33(coords, elems, etags, msh.get_faces(), msh.get_ftags())
+
+t0 = time()
+msh.add_boundary_faces()
+t1 = time()
+print(msh.n_elems(), t1 - t0)
+"""
+
+
+def run(n_split, n_threads):
+
+    env = dict(os.environ, RAYON_NUM_THREADS=str(n_threads))
+    out = subprocess.run(
+        [sys.executable, "-c", SCRIPT, str(n_split)],
+        env=env,
+        check=True,
+        capture_output=True,
+        text=True,
+    ).stdout.split()
+    return int(out[0]), float(out[1])
+
+
+if __name__ == "__main__":
+
+    n_cpus = os.cpu_count()
+    print("add_boundary_faces - serial vs. %d threads" % n_cpus)
+    for n_split in [4, 5, 6]:
+        n_elems, t_serial = run(n_split, 1)
+        _, t_parallel = run(n_split, n_cpus)
+        print(
+            "%d elems: %f s (1 thread), %f s (%d threads), speedup = %.2f"
+            % (n_elems, t_serial, t_parallel, n_cpus, t_serial / t_parallel)
+        )
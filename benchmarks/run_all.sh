@@ -1,7 +1,7 @@
 #!/bin/bash
 set -ex
 
-for test in square_iso square_linear cube_iso cube_linear cube_cylinder
+for test in square_iso square_linear cube_iso cube_linear cube_cylinder boundary_faces
 do
     cd $test
     python run_test.py
@@ -1,3 +1,26 @@
+use std::{env, fs, path::Path};
+
+/// Get the version of tucanos (and the git commit it was built from, if any) from Cargo.lock
+fn tucanos_version() -> Option<String> {
+    let dir = env::var("CARGO_MANIFEST_DIR").ok()?;
+    let lock = fs::read_to_string(Path::new(&dir).join("Cargo.lock")).ok()?;
+    let pkg = lock
+        .split("[[package]]")
+        .find(|pkg| pkg.lines().any(|l| l.trim() == "name = \"tucanos\""))?;
+    let field = |name: &str| {
+        pkg.lines()
+            .find_map(|l| l.trim().strip_prefix(name))
+            .map(|v| v.trim_start_matches(" = ").trim_matches('"').to_string())
+    };
+    let version = field("version")?;
+    Some(
+        match field("source").and_then(|s| s.split('#').nth(1).map(str::to_string)) {
+            Some(commit) => format!("{version} ({commit})"),
+            None => version,
+        },
+    )
+}
+
 fn main() {
     if let Ok(rpath) = std::env::var("DEP_TUCANOS_RPATH") {
         for s in rpath.split(':') {
@@ -5,4 +28,11 @@ fn main() {
             println!("cargo:rustc-link-arg=-Wl,-rpath,{s}");
         }
     }
+
+    println!(
+        "cargo:rustc-env=TUCANOS_VERSION={}",
+        tucanos_version().unwrap_or_else(|| "unknown".to_string())
+    );
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=build.rs");
 }
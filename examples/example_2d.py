@@ -36,9 +36,8 @@ if __name__ == "__main__":
     ax.set_title("Fix boundaries")
 
     # Hilbert renumbering
-    new_vert_indices, new_elem_indices, new_face_indices = msh.reorder_hilbert()
-    f2 = np.empty(f.shape)
-    f2[new_vert_indices] = f
+    _, _, _, fields = msh.reorder_hilbert(fields={"f": f.reshape((-1, 1))})
+    f2 = fields["f"][:, 0]
 
     fig, ax = plt.subplots()
     plot_mesh(ax, msh, etag=True)
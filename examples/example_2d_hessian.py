@@ -34,7 +34,7 @@ if __name__ == "__main__":
         m = Remesher2dAniso.hessian_to_metric(msh, hessian)
         m = Remesher2dAniso.smooth_metric(msh, m)
         for _ in range(2):
-            m = Remesher2dAniso.scale_metric(
+            m, _ = Remesher2dAniso.scale_metric(
                 msh, m, h_min=0.0001, h_max=0.3, n_elems=1000
             )
             m = Remesher2dAniso.apply_metric_gradation(msh, m, beta=1.5, n_iter=3)
@@ -262,7 +262,7 @@ if __name__ == "__main__":
         m = np.zeros((msh.n_verts(), 3))
         m[:, :2] = h
 
-        m = Remesher2dAniso.scale_metric(
+        m, _ = Remesher2dAniso.scale_metric(
             msh,
             m,
             1e-5,
@@ -1 +1,13 @@
-from ._pytucanos import HAVE_LIBMESHB, HAVE_METIS, HAVE_SCOTCH
+from ._pytucanos import (
+    HAVE_GMSH,
+    HAVE_LIBMESHB,
+    HAVE_METIS,
+    HAVE_SCOTCH,
+    HAVE_XDMF,
+    IDX_DTYPE,
+    TAG_DTYPE,
+    TAG_MIN,
+    TAG_MAX,
+    VERSION,
+    TUCANOS_VERSION,
+)
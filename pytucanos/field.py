@@ -1 +1,9 @@
-from ._pytucanos import read_solb
+from ._pytucanos import (
+    FIELD_KINDS,
+    SYMTENSOR_2D,
+    SYMTENSOR_3D,
+    field_shape,
+    symtensor_to_full,
+    full_to_symtensor,
+    read_solb,
+)
@@ -5,6 +5,16 @@ from ._pytucanos import (
     Mesh22,
     Mesh32,
     Mesh33,
+    MeshView22,
+    MeshView33,
+    ElemTree,
+    VertTree,
+    compare_tags,
+    hausdorff_distance,
+    IDX_DTYPE,
+    TAG_DTYPE,
+    TAG_MIN,
+    TAG_MAX,
 )
 from .metric import sym2mat
 
@@ -20,6 +30,15 @@ def create_mesh(coords, elems, etags, faces, ftags):
             return Mesh33(coords, elems, etags, faces, ftags)
 
 
+def mesh_info(msh, m=None):
+    """
+    Get a summary of a mesh (and optionally of a metric defined at its vertices) as a
+    nested dict
+    """
+
+    return msh.info(m)
+
+
 def __plot_boundary(ax, bdy, normals):
 
     xy = bdy.get_coords()
@@ -140,9 +159,9 @@ def get_cube():
             [0, 5, 7, 4],
             [2, 7, 5, 6],
         ],
-        dtype=np.uint32,
+        dtype=IDX_DTYPE,
     )
-    etags = np.array([1, 1, 1, 1, 1], dtype=np.int16)
+    etags = np.array([1, 1, 1, 1, 1], dtype=TAG_DTYPE)
     faces = np.array(
         [
             [0, 1, 2],
@@ -158,9 +177,9 @@ def get_cube():
             [0, 3, 7],
             [0, 7, 4],
         ],
-        dtype=np.uint32,
+        dtype=IDX_DTYPE,
     )
-    ftags = np.array([1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6], dtype=np.int16)
+    ftags = np.array([1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6], dtype=TAG_DTYPE)
 
     return coords, elems, etags, faces, ftags
 
@@ -180,10 +199,10 @@ def get_square(two_tags=True):
             [0, 1, 2],
             [0, 2, 3],
         ],
-        dtype=np.uint32,
+        dtype=IDX_DTYPE,
     )
     if two_tags:
-        etags = np.array([1, 2], dtype=np.int16)
+        etags = np.array([1, 2], dtype=TAG_DTYPE)
         faces = np.array(
             [
                 [0, 1],
@@ -192,11 +211,11 @@ def get_square(two_tags=True):
                 [3, 0],
                 [0, 2],
             ],
-            dtype=np.uint32,
+            dtype=IDX_DTYPE,
         )
-        ftags = np.array([1, 2, 3, 4, 5], dtype=np.int16)
+        ftags = np.array([1, 2, 3, 4, 5], dtype=TAG_DTYPE)
     else:
-        etags = np.array([1, 1], dtype=np.int16)
+        etags = np.array([1, 1], dtype=TAG_DTYPE)
         faces = np.array(
             [
                 [0, 1],
@@ -204,8 +223,8 @@ def get_square(two_tags=True):
                 [2, 3],
                 [3, 0],
             ],
-            dtype=np.uint32,
+            dtype=IDX_DTYPE,
         )
-        ftags = np.array([1, 2, 3, 4], dtype=np.int16)
+        ftags = np.array([1, 2, 3, 4], dtype=TAG_DTYPE)
 
     return coords, elems, etags, faces, ftags
@@ -1,14 +1,20 @@
 import os
 import json
 import subprocess
+from collections import namedtuple
 import numpy as np
 import matplotlib.pyplot as plt
 from ._pytucanos import Remesher2dIso, Remesher2dAniso, Remesher3dIso, Remesher3dAniso
 from ._pytucanos import (
+    BackgroundMetric2d,
+    BackgroundMetric3d,
     ParallelRemesher2dIso,
     ParallelRemesher2dAniso,
     ParallelRemesher3dIso,
     ParallelRemesher3dAniso,
+    ParallelRemeshingParams,
+    RemesherParams,
+    available_partitioners,
 )
 
 from .mesh import Mesh22, Mesh33
@@ -69,6 +75,50 @@ def plot_stats(remesher):
     return fig, axs
 
 
+def load_stats(fname):
+    """
+    Load a file written by the remeshers' save_stats() as a dict with entries "stats"
+    (the remeshing steps, as in stats_json()) and "summary" (as in summary_json(),
+    including the parameters, input and versions used in "provenance")
+    """
+
+    with open(fname) as f:
+        return json.load(f)
+
+
+LevelStats = namedtuple(
+    "LevelStats",
+    [
+        "level",
+        "max_partition_time",
+        "n_interface_elems",
+        "n_partitions",
+        "min_partition_time",
+        "mean_partition_time",
+    ],
+)
+
+
+def parallel_level_stats(info):
+    """
+    Get the statistics of each level of a parallel remeshing run from the info (json string or
+    dict) returned by ParallelRemesher*.remesh(), as a list of LevelStats
+    """
+    if isinstance(info, str):
+        info = json.loads(info)
+    return [
+        LevelStats(
+            level["level"],
+            level["max_partition_time"],
+            level["n_interface_elems"],
+            level["n_partitions"],
+            level["partition_time"]["min"],
+            level["partition_time"]["mean"],
+        )
+        for level in info.get("levels", [])
+    ]
+
+
 def __write_tmp_meshb(msh, h):
 
     if isinstance(msh, Mesh22):
@@ -103,20 +153,43 @@ def __iso_to_aniso_3d(h):
     return h
 
 
-def remesh(msh, h, bdy=None, step=None, **remesh_params):
+def remesher_class(msh, m):
     """
-    Remesh using tucanos
+    Get the remesher class for mesh msh and metric m, i.e. the isotropic remesher if m
+    has 1 column and the anisotropic one otherwise
     """
 
     if isinstance(msh, Mesh33):
-        LinearGeometry = LinearGeometry3d
-        Remesher = Remesher3dIso if h.shape[1] == 1 else Remesher3dAniso
+        return Remesher3dIso if m.shape[1] == 1 else Remesher3dAniso
     elif isinstance(msh, Mesh22):
-        LinearGeometry = LinearGeometry2d
-        Remesher = Remesher2dIso if h.shape[1] == 1 else Remesher2dAniso
+        return Remesher2dIso if m.shape[1] == 1 else Remesher2dAniso
     else:
         raise NotImplementedError
 
+
+def make_remesher(msh, geometry, m, internal_interfaces=None):
+    """
+    Create a remesher for mesh msh, geometry and metric m, choosing the class from the
+    mesh dimension and the number of columns of m (1 for sizes)
+    """
+
+    return remesher_class(msh, m)(msh, geometry, m, internal_interfaces)
+
+
+def remesh(msh, h, bdy=None, step=None, **remesh_params):
+    """
+    Remesh using tucanos. remesh_params are passed to Remesher.remesh(), e.g.
+    debug=True to check the mesh after each operation, and debug_dir to write the mesh
+    before remeshing, after each operation and after remeshing to this directory
+    (created if needed) as input.vtu, pass{p:02}_iter{i:03}_{op}.vtu and output.vtu
+    """
+
+    Remesher = remesher_class(msh, h)
+    if isinstance(msh, Mesh33):
+        LinearGeometry = LinearGeometry3d
+    else:
+        LinearGeometry = LinearGeometry2d
+
     msh.compute_topology()
     geom = LinearGeometry(msh, bdy)
 
@@ -134,6 +207,65 @@ def remesh(msh, h, bdy=None, step=None, **remesh_params):
     return remesher.to_mesh()
 
 
+def remesh_sweep(msh, geom, metrics, params=None, n_threads=None, callback=None):
+    """
+    Remesh msh for each metric in metrics, sharing the mesh and the geometry, using
+    n_threads threads. If callback is given, it is called as
+    callback(i, mesh, stats_json) as soon as remeshing for metric i is done and None is
+    returned, otherwise the list of (mesh, stats_json) is returned
+    """
+
+    Remesher = remesher_class(msh, metrics[0])
+    return Remesher.remesh_sweep(msh, geom, metrics, params, n_threads, callback)
+
+
+def remesh_group(
+    meshes, geometries, metrics, params=None, n_threads=None, n_elems=None
+):
+    """
+    Remesh each mesh in meshes with its geometry and metric (sizes or anisotropic
+    metrics), using the same remesh() keyword parameters params for all, on n_threads
+    threads (default: 1).
+    If n_elems is given, the metrics are scaled by the same factor so that their total
+    complexity is n_elems, i.e. the budget is split proportionally to their
+    complexities.
+    Return the lists of adapted meshes, of stats_json and of metric complexities
+    """
+    from concurrent.futures import ThreadPoolExecutor
+
+    if not len(meshes) == len(geometries) == len(metrics):
+        raise ValueError("meshes, geometries and metrics must have the same length")
+    params = {} if params is None else params
+
+    remeshers = []
+    for msh, m in zip(meshes, metrics):
+        dim = 3 if isinstance(msh, Mesh33) else 2
+        remeshers.append((remesher_class(msh, m), dim))
+
+    complexities = [
+        Remesher.complexity_per_tag(msh, m)[1]
+        for (Remesher, _), msh, m in zip(remeshers, meshes, metrics)
+    ]
+    if n_elems is not None:
+        s = n_elems / sum(complexities)
+        metrics = [
+            m * s ** (-1.0 / dim) if m.shape[1] == 1 else m * s ** (2.0 / dim)
+            for (_, dim), m in zip(remeshers, metrics)
+        ]
+        complexities = [c * s for c in complexities]
+
+    def run(i):
+        Remesher, _ = remeshers[i]
+        remesher = Remesher(meshes[i], geometries[i], metrics[i])
+        remesher.remesh(geometries[i], **params)
+        return remesher.to_mesh(), remesher.stats_json()
+
+    with ThreadPoolExecutor(max_workers=n_threads or 1) as executor:
+        res = list(executor.map(run, range(len(meshes))))
+
+    return [r[0] for r in res], [r[1] for r in res], complexities
+
+
 def remesh_mmg(msh, h, hgrad=10.0, hausd=10.0):
     """
     Remesh using MMG.
@@ -1,7 +1,17 @@
 import os
+import struct
+import warnings
 import numpy as np
 import unittest
 from .mesh import Mesh22, get_square, Mesh33, get_cube
+from .field import (
+    SYMTENSOR_2D,
+    SYMTENSOR_3D,
+    field_shape,
+    symtensor_to_full,
+    full_to_symtensor,
+    read_solb,
+)
 
 
 class TestField(unittest.TestCase):
@@ -76,3 +86,186 @@ class TestField(unittest.TestCase):
         self.assertTrue(np.allclose(f, g))
 
         os.remove("tmp.solb")
+
+    def test_3d_elem_sol(self):
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        for m in [1, 3, 6, 2]:
+            f = np.random.rand(msh.n_elems(), m)
+            msh.write_solb("tmp.solb", f, location="elem")
+            g = Mesh33.read_solb("tmp.solb", location="elem")
+            self.assertTrue(np.allclose(f, g))
+            with self.assertRaises(ValueError):
+                Mesh33.read_solb("tmp.solb")
+
+        f = np.random.rand(msh.n_verts(), 1)
+        msh.write_solb("tmp.solb", f, location="vertex")
+        self.assertTrue(np.allclose(f, Mesh33.read_solb("tmp.solb")))
+        with self.assertRaises(ValueError):
+            Mesh33.read_solb("tmp.solb", location="elem")
+
+        os.remove("tmp.solb")
+
+    def test_solb_metadata(self):
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        f = np.random.rand(msh.n_verts(), 3)
+
+        msh.write_solb("tmp.solb", f)
+        g, metadata = Mesh33.read_solb("tmp.solb", metadata=True)
+        self.assertTrue(np.allclose(f, g))
+        self.assertEqual(metadata, {})
+
+        msh.write_solb("tmp.solb", f, iteration=120, time=0.25)
+        g, metadata = Mesh33.read_solb("tmp.solb", metadata=True)
+        self.assertTrue(np.allclose(f, g))
+        self.assertEqual(metadata, {"iteration": 120, "time": 0.25})
+        self.assertTrue(np.allclose(f, Mesh33.read_solb("tmp.solb")))
+
+        f = np.random.rand(msh.n_elems(), 1)
+        msh.write_solb("tmp.solb", f, location="elem", time=1.5)
+        g, metadata = Mesh33.read_solb("tmp.solb", location="elem", metadata=True)
+        self.assertTrue(np.allclose(f, g))
+        self.assertEqual(metadata, {"time": 1.5})
+
+        os.remove("tmp.solb")
+
+        with self.assertRaises(ValueError):
+            msh.write_solb("tmp.sol", np.random.rand(msh.n_verts(), 1), iteration=1)
+        self.assertFalse(os.path.exists("tmp.sol"))
+
+    def test_solb_checks(self):
+
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        self.assertNotEqual(msh.n_verts(), msh.n_elems())
+
+        with self.assertRaises(ValueError):
+            msh.write_solb("tmp.solb", np.random.rand(msh.n_elems(), 1))
+        with self.assertRaises(ValueError):
+            msh.write_solb(
+                "tmp.solb", np.random.rand(msh.n_verts(), 1), location="elem"
+            )
+        with self.assertRaises(ValueError):
+            msh.write_solb(
+                "tmp.solb", np.random.rand(msh.n_verts(), 1), location="face"
+            )
+
+        f = np.random.rand(msh.n_elems(), 3)
+        f[0, 1] = np.nan
+        with self.assertRaises(ValueError):
+            msh.write_solb("tmp.solb", f, location="elem")
+        msh.write_solb("tmp.solb", f, location="elem", allow_nan=True)
+        g = Mesh22.read_solb("tmp.solb", location="elem")
+        self.assertTrue(np.isnan(g[0, 1]))
+        self.assertTrue(np.allclose(f[1:], g[1:]))
+
+        # corrupted number of elements (at byte 28) and of solution types (at byte 32)
+        with open("tmp.solb", "rb") as fh:
+            data = fh.read()
+        for offset, count in [(28, -1), (28, 2**31 - 1), (32, -1), (32, 2**31 - 1)]:
+            with open("tmp.solb", "wb") as fh:
+                fh.write(data[:offset] + struct.pack("<i", count) + data[offset + 4 :])
+            with self.assertRaises(RuntimeError):
+                Mesh22.read_solb("tmp.solb", location="elem")
+
+        os.remove("tmp.solb")
+
+    def test_read_solb_function(self):
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+
+        f = np.random.rand(msh.n_verts(), 6)
+        msh.write_solb("tmp.solb", f)
+        with self.assertWarns(DeprecationWarning):
+            g, location = read_solb("tmp.solb")
+        self.assertEqual(location, "vertex")
+        self.assertTrue(np.allclose(f, g))
+        with self.assertWarns(DeprecationWarning):
+            g, location = read_solb("tmp.solb", mesh=msh)
+        self.assertEqual(location, "vertex")
+        self.assertTrue(np.allclose(f, g))
+
+        f = np.random.rand(msh.n_elems(), 3)
+        msh.write_solb("tmp.solb", f, location="elem")
+        with warnings.catch_warnings():
+            warnings.simplefilter("ignore", DeprecationWarning)
+            g, location = read_solb("tmp.solb")
+            self.assertEqual(location, "elem")
+            self.assertTrue(np.allclose(f, g))
+            g, location = read_solb("tmp.solb", mesh=msh)
+            self.assertEqual(location, "elem")
+            self.assertTrue(np.allclose(f, g))
+
+            with self.assertRaises(ValueError):
+                read_solb("tmp.solb", mesh=msh.split())
+
+        os.remove("tmp.solb")
+
+    def test_clip_field(self):
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split().split()
+        f = np.random.rand(msh.n_verts(), 2)
+        f[0, 0] = 1e6
+
+        g, lo, hi = msh.clip_field(f, percentile=(5.0, 95.0))
+        self.assertTrue(np.allclose(lo, np.percentile(f, 5.0, axis=0)))
+        self.assertTrue(np.allclose(hi, np.percentile(f, 95.0, axis=0)))
+        self.assertTrue(np.allclose(g, np.clip(f, lo, hi)))
+        n_clipped = (g != f).sum(axis=0)
+        self.assertTrue(
+            np.array_equal(n_clipped, (f < lo).sum(axis=0) + (f > hi).sum(axis=0))
+        )
+        self.assertLessEqual(g[0, 0], 1.0)
+
+        g, lo, hi = msh.clip_field(f, min_val=0.2, percentile=(5.0, 95.0))
+        self.assertTrue(np.allclose(lo, 0.2))
+        self.assertTrue(np.allclose(hi, np.percentile(f, 95.0, axis=0)))
+
+        g, lo, hi = msh.clip_field(f, max_val=0.5)
+        self.assertTrue(np.all(np.isinf(lo)))
+        self.assertTrue(np.allclose(g, np.minimum(f, 0.5)))
+        self.assertEqual((g != f).sum(), (f > 0.5).sum())
+
+        g, lo, hi = Mesh33.empty().clip_field(np.zeros((0, 2)), percentile=(5.0, 95.0))
+        self.assertEqual(g.shape, (0, 2))
+        self.assertTrue(np.all(np.isnan(lo)))
+        self.assertTrue(np.all(np.isnan(hi)))
+
+    def test_field_shape(self):
+        msh = Mesh22(*get_square())
+        self.assertEqual(field_shape(msh, "scalar"), 1)
+        self.assertEqual(field_shape(msh, "vector"), 2)
+        self.assertEqual(field_shape(msh, "symtensor"), 3)
+        msh = Mesh33(*get_cube())
+        self.assertEqual(field_shape(msh, "vector"), 3)
+        self.assertEqual(field_shape(msh, "symtensor"), 6)
+        self.assertEqual(len(SYMTENSOR_3D), 6)
+
+        with self.assertRaises(ValueError):
+            field_shape(msh, "tensor")
+
+    def test_symtensor_conversions(self):
+        for dim, names in [(2, SYMTENSOR_2D), (3, SYMTENSOR_3D)]:
+            t = np.random.rand(10, dim * (dim + 1) // 2)
+            m = symtensor_to_full(t)
+            self.assertEqual(m.shape, (10, dim, dim))
+            self.assertTrue(np.allclose(m, m.transpose((0, 2, 1))))
+            for k, name in enumerate(names):
+                i, j = ["xyz".index(c) for c in name]
+                self.assertTrue(np.allclose(m[:, i, j], t[:, k]))
+            self.assertTrue(np.allclose(full_to_symtensor(m), t))
+
+    def test_2d_hessian(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_vertex_to_vertices()
+
+        x, y = msh.get_coords().T
+        f = (x**2 + 3 * x * y + 2 * y**2).reshape((-1, 1))
+        h = symtensor_to_full(msh.compute_hessian(f))
+        self.assertTrue(np.allclose(h, [[2.0, 3.0], [3.0, 4.0]]))
@@ -1,7 +1,7 @@
 import os
 import numpy as np
 import unittest
-from .mesh import Mesh22, get_square, Mesh33, get_cube, Mesh21
+from .mesh import Mesh22, get_square, Mesh33, get_cube, Mesh21, Mesh32
 from .geometry import LinearGeometry2d, LinearGeometry3d
 
 
@@ -24,6 +24,100 @@ class TestGeometry3d(unittest.TestCase):
         msh.compute_topology()
         geom = LinearGeometry3d(msh)
 
+    def test_pickle(self):
+        import copy
+        import pickle
+
+        msh = Mesh33(*get_cube()).split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+        other = msh.split()
+        other.compute_topology()
+        d = geom.max_distance(other, per_tag=True)
+        for g in [pickle.loads(pickle.dumps(geom)), copy.deepcopy(geom)]:
+            self.assertIsInstance(g, LinearGeometry3d)
+            self.assertEqual(g.max_distance(other, per_tag=True), d)
+
+        msh = Mesh22(*get_square())
+        msh.compute_topology()
+        geom = pickle.loads(pickle.dumps(LinearGeometry2d(msh)))
+        self.assertLess(geom.max_distance(msh), 1e-12)
+
+    def test_init_3d_multiple(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        bdy, _ = msh.boundary()
+
+        parts = {tag: p for tag, (p, _, _) in msh.boundary_per_tag().items()}
+        geom = LinearGeometry3d(msh, parts)
+        gmsh = geom.mesh()
+        self.assertEqual(gmsh.n_verts(), bdy.n_verts())
+        self.assertEqual(gmsh.n_elems(), bdy.n_elems())
+        self.assertEqual(set(gmsh.get_etags()), {1, 2, 3, 4, 5, 6})
+
+        parts = [
+            (
+                Mesh32(
+                    p.get_coords(),
+                    p.get_elems(),
+                    np.zeros(p.n_elems(), dtype=np.int16),
+                    np.zeros([0, 2], dtype=np.uint32),
+                    np.zeros(0, dtype=np.int16),
+                ),
+                tag,
+            )
+            for tag, p in parts.items()
+        ]
+        geom = LinearGeometry3d(msh, parts)
+        self.assertEqual(geom.mesh().n_verts(), bdy.n_verts())
+        self.assertAlmostEqual(geom.max_distance(msh), 0.0)
+
+        with self.assertRaisesRegex(ValueError, "6"):
+            LinearGeometry3d(msh, parts[:-1])
+
+    def test_max_distance_per_tag(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        # move the vertices inside the faces with the first tag towards the center
+        faces, ftags = msh.get_faces(), msh.get_ftags()
+        tag = ftags[0]
+        ids = np.setdiff1d(faces[ftags == tag], faces[ftags != tag])
+        self.assertGreater(ids.size, 0)
+        coords = msh.get_coords()
+        coords[ids] += 0.05 * (0.5 - coords[ids])
+        msh = Mesh22(coords, msh.get_elems(), msh.get_etags(), faces, ftags)
+
+        d = geom.max_distance(msh)
+        a = geom.max_normal_angle(msh)
+        self.assertGreater(d, 0.0)
+        self.assertGreater(a, 0.0)
+
+        d_tags = geom.max_distance(msh, per_tag=True)
+        a_tags = geom.max_normal_angle(msh, per_tag=True)
+        self.assertEqual(set(d_tags.keys()), set(ftags))
+        self.assertAlmostEqual(max(d_tags.values()), d)
+        self.assertAlmostEqual(max(a_tags.values()), a)
+        self.assertAlmostEqual(d_tags[tag], d)
+        others = [t for t in d_tags.keys() if t != tag]
+        self.assertLess(geom.max_distance(msh, others), d)
+        self.assertLess(geom.max_normal_angle(msh, others), a)
+        self.assertAlmostEqual(geom.max_distance(msh, [tag]), d)
+        for t in others:
+            self.assertLessEqual(d_tags[t], d)
+            self.assertLessEqual(a_tags[t], a)
+
+        d_faces = geom.face_distances(msh, [tag])
+        self.assertEqual(d_faces.shape, (msh.n_faces(),))
+        self.assertTrue(np.all(np.isnan(d_faces[ftags != tag])))
+        self.assertAlmostEqual(np.nanmax(d_faces), d)
+        a_faces = geom.face_normal_angles(msh)
+        self.assertFalse(np.any(np.isnan(a_faces)))
+        self.assertAlmostEqual(a_faces.max(), a)
+
     def test_curvature_3d(self):
         coords, elems, etags, faces, ftags = get_cube()
         msh = Mesh33(coords, elems, etags, faces, ftags)
@@ -31,6 +125,91 @@ class TestGeometry3d(unittest.TestCase):
         geom = LinearGeometry3d(msh)
         geom.compute_curvature()
 
+    def test_curvature_metric_boundary(self):
+        for Mesh, Geometry, get_mesh, n in [
+            (Mesh33, LinearGeometry3d, get_cube, 6),
+            (Mesh22, LinearGeometry2d, get_square, 3),
+        ]:
+            msh = Mesh(*get_mesh()).split().split()
+            msh.compute_topology()
+            geom = Geometry(msh)
+            geom.compute_curvature()
+
+            ref, ref_sizes = msh.curvature_metric(geom, 0.1, 1.5, 0.05)
+            m, sizes, m_bdy, ids = msh.curvature_metric(
+                geom, 0.1, 1.5, 0.05, None, None, True
+            )
+            self.assertTrue(np.array_equal(m, ref))
+            self.assertEqual(sizes, ref_sizes)
+
+            self.assertTrue(np.array_equal(ids, np.unique(msh.get_faces())))
+            self.assertEqual(m_bdy.shape, (ids.size, n))
+            self.assertTrue(np.array_equal(m_bdy, m[ids]))
+
+    def test_curvature_metric_normal_sizes(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+        geom.compute_curvature()
+
+        def normal_sizes(h_n, tags):
+            h_n = np.array(h_n, dtype=np.float64)
+            tags = np.array(tags, dtype=np.int16)
+            return msh.curvature_metric(geom, 0.1, 1.5, None, h_n, tags)
+
+        m, sizes = msh.curvature_metric(geom, 0.1, 1.5)
+        self.assertEqual(m.shape, (msh.n_verts(), 6))
+        self.assertEqual(sizes, {tag: None for tag in range(1, 7)})
+
+        _, sizes = normal_sizes([0.05, 0.2], [1, 3])
+        self.assertEqual(sizes, {1: 0.05, 2: None, 3: 0.2, 4: None, 5: None, 6: None})
+
+        # tag 0 stands for all the other tags
+        _, sizes = normal_sizes([0.05, 0.1], [1, 0])
+        self.assertEqual(sizes, {1: 0.05, 2: 0.1, 3: 0.1, 4: 0.1, 5: 0.1, 6: 0.1})
+
+        with self.assertRaisesRegex(ValueError, "7"):
+            normal_sizes([0.05, 0.1], [1, 7])
+        with self.assertRaises(ValueError):
+            normal_sizes([0.05, 0.1], [1, 1])
+        with self.assertRaises(ValueError):
+            normal_sizes([0.05], [1, 2])
+        with self.assertRaises(ValueError):
+            msh.curvature_metric(geom, 0.1, 1.5, None, np.array([0.05]))
+
+    def test_metric_normal_sizes(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        # flat plate metric: the normal size to z = 0 (tag 1) and z = 1 (tag 2) is h_n
+        coords = msh.get_coords()
+        h_n = 0.01 + 0.1 * coords[:, 0]
+        m = np.zeros((msh.n_verts(), 6))
+        m[:, 0] = m[:, 1] = 1.0 / 0.1**2
+        m[:, 2] = 1.0 / h_n**2
+        m[:, 3] = 10.0
+
+        for g in [None, geom]:
+            ids, sizes = msh.metric_normal_sizes(m, [1], g)
+            self.assertTrue(np.array_equal(ids, np.nonzero(coords[:, 2] == 0.0)[0]))
+            self.assertTrue(np.allclose(sizes, h_n[ids]))
+
+            ids, sizes = msh.metric_normal_sizes(m, [1, 2], g)
+            self.assertTrue(np.all(np.isin(coords[ids, 2], [0.0, 1.0])))
+            self.assertTrue(np.allclose(sizes, h_n[ids]))
+
+        # on x = 1 (tag 5) the normal size is 0.1
+        _, sizes = msh.metric_normal_sizes(m, [5])
+        self.assertTrue(np.allclose(sizes, 0.1))
+
+        with self.assertRaises(ValueError):
+            msh.metric_normal_sizes(m, [7])
+        with self.assertRaises(ValueError):
+            msh.metric_normal_sizes(m[:, :3], [1])
+
     def test_project_2d(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags).split()
@@ -81,3 +260,261 @@ class TestGeometry3d(unittest.TestCase):
         flg = np.logical_and(r > 0.5**0.5 - 0.01, r < 0.5**0.5 + 0.01)
         (ok,) = np.nonzero(~flg)
         self.assertEqual(ok.size, 1)
+
+    def test_vertex_curvature_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+
+        # sphere circumscribed to the cube
+        r = 0.5 * 3**0.5
+        bdy, _ = msh.split().split().boundary()
+        x = bdy.get_coords() - 0.5
+        x = 0.5 + r * x / np.linalg.norm(x, axis=1)[:, np.newaxis]
+        bdy = Mesh32(
+            x, bdy.get_elems(), bdy.get_etags(), bdy.get_faces(), bdy.get_ftags()
+        )
+
+        geom = LinearGeometry3d(msh, bdy)
+        geom.compute_curvature()
+        ids, k1, k2, d1, d2 = geom.vertex_curvature(msh)
+        self.assertGreater(ids.size, 0)
+        self.assertTrue(np.all(k1 >= k2))
+        self.assertTrue(np.allclose(k1, 1.0 / r, rtol=0.2))
+        self.assertTrue(np.allclose(k2, 1.0 / r, rtol=0.2))
+
+        n = msh.get_coords()[ids, :] - 0.5
+        n /= np.linalg.norm(n, axis=1)[:, np.newaxis]
+        self.assertTrue(np.allclose(np.einsum("ij,ij->i", d1, n), 0.0, atol=0.1))
+        self.assertTrue(np.allclose(np.einsum("ij,ij->i", d2, n), 0.0, atol=0.1))
+
+    def test_refine_to(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        ftags = np.ones_like(ftags)
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+
+        # coarsely faceted sphere circumscribed to the cube, with a single tag
+        r = 0.5 * 3**0.5
+        bdy, _ = msh.boundary()
+        x = bdy.get_coords() - 0.5
+        x = 0.5 + r * x / np.linalg.norm(x, axis=1)[:, np.newaxis]
+        bdy = Mesh32(
+            x, bdy.get_elems(), bdy.get_etags(), bdy.get_faces(), bdy.get_ftags()
+        )
+
+        def max_chordal_deviation(geom):
+            gmsh = geom.mesh()
+            centers = gmsh.get_coords()[gmsh.get_elems()].mean(axis=1)
+            return np.abs(np.linalg.norm(centers - 0.5, axis=1) - r).max()
+
+        geom = LinearGeometry3d(msh, bdy)
+        n = geom.mesh().n_elems()
+        d = max_chordal_deviation(geom)
+
+        with self.assertRaises(ValueError):
+            geom.refine_to(msh)
+
+        n_new = geom.refine_to(msh, r_h=0.3)
+        self.assertGreater(n_new, n)
+        self.assertEqual(geom.mesh().n_elems(), n_new)
+        d_new = max_chordal_deviation(geom)
+        self.assertLess(d_new, 0.5 * d)
+
+        n_new = geom.refine_to(msh, h_max=0.2)
+        self.assertLess(max_chordal_deviation(geom), d_new)
+        gmsh = geom.mesh()
+        edges = gmsh.get_coords()[gmsh.get_elems()]
+        lengths = np.linalg.norm(edges - np.roll(edges, 1, axis=1), axis=2)
+        self.assertLessEqual(lengths.max(), 0.2)
+
+        new_coords = geom.project(msh)
+        self.assertTrue(np.all(np.linalg.norm(new_coords - 0.5, axis=1) < r + 1e-2))
+
+    def test_vertex_curvature_2d(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_topology()
+
+        # circle circumscribed to the square
+        r = 0.5**0.5
+        bdy, _ = msh.split().split().boundary()
+        x = bdy.get_coords() - 0.5
+        x = 0.5 + r * x / np.linalg.norm(x, axis=1)[:, np.newaxis]
+        bdy = Mesh21(
+            x, bdy.get_elems(), bdy.get_etags(), bdy.get_faces(), bdy.get_ftags()
+        )
+
+        geom = LinearGeometry2d(msh, bdy)
+        geom.compute_curvature()
+        ids, k, d = geom.vertex_curvature(msh)
+        self.assertGreater(ids.size, 0)
+        self.assertTrue(np.allclose(k, 1.0 / r, rtol=0.1))
+
+        n = msh.get_coords()[ids, :] - 0.5
+        n /= np.linalg.norm(n, axis=1)[:, np.newaxis]
+        self.assertTrue(np.allclose(np.einsum("ij,ij->i", d, n), 0.0, atol=0.1))
+
+    def test_mesh_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        gmsh = geom.mesh()
+        bdy, _ = msh.boundary()
+        self.assertEqual(gmsh.n_elems(), bdy.n_elems())
+        gtags = np.sort(gmsh.get_etags())
+        self.assertTrue(np.array_equal(gtags, np.sort(msh.get_ftags())))
+        self.assertAlmostEqual(gmsh.vol(), 6.0)
+
+        geom.write_vtk("geom.vtu")
+        self.assertTrue(os.path.exists("geom.vtu"))
+        os.remove("geom.vtu")
+
+    def test_mesh_2d(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        gmsh = geom.mesh()
+        self.assertEqual(gmsh.n_elems(), msh.n_faces())
+        gtags = np.sort(gmsh.get_etags())
+        self.assertTrue(np.array_equal(gtags, np.sort(msh.get_ftags())))
+        self.assertAlmostEqual(gmsh.vol(), 4.0)
+
+        geom.write_vtk("geom.vtu")
+        self.assertTrue(os.path.exists("geom.vtu"))
+        os.remove("geom.vtu")
+
+    def test_p2_3d(self):
+        r = 0.4
+        box = ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5])
+
+        def sdf(x):
+            return np.linalg.norm(x, axis=1) - r
+
+        msh = Mesh33.from_sdf(sdf, box, 0.15)
+        msh.compute_topology()
+        fine = Mesh33.from_sdf(sdf, box, 0.04)
+        bdy, _ = fine.boundary()
+        geom = LinearGeometry3d(msh, bdy)
+
+        elems = msh.get_elems()
+        edges = np.vstack([elems[:, [i, j]] for i in range(4) for j in range(i + 1, 4)])
+        n_edges = np.unique(np.sort(edges, axis=1), axis=0).shape[0]
+
+        coords, p2_elems, p2_faces = msh.to_p2()
+        self.assertEqual(coords.shape[0], msh.n_verts() + n_edges)
+        self.assertEqual(p2_elems.shape, (msh.n_elems(), 10))
+        self.assertEqual(p2_faces.shape, (msh.n_faces(), 6))
+        self.assertTrue(np.array_equal(p2_elems[:, :4], elems))
+        mid = 0.5 * (coords[p2_elems[:, 0]] + coords[p2_elems[:, 3]])
+        self.assertTrue(np.allclose(coords[p2_elems[:, 7]], mid))
+
+        r_lin = np.linalg.norm(coords[p2_faces[:, 3:]], axis=-1)
+        coords, _, p2_faces = msh.to_p2(geom)
+        r_p2 = np.linalg.norm(coords[p2_faces[:, 3:]], axis=-1)
+        self.assertLess(np.abs(r_p2 - r).max(), 0.005)
+        self.assertLess(np.abs(r_p2 - r).max(), np.abs(r_lin - r).max())
+
+        # the faces whose tag is not part of the geometry are not projected
+        ftags = msh.get_ftags() + 100
+        other = Mesh33(
+            msh.get_coords(), msh.get_elems(), msh.get_etags(), msh.get_faces(), ftags
+        )
+        coords, _, p2_faces = other.to_p2(geom)
+        r_other = np.linalg.norm(coords[p2_faces[:, 3:]], axis=-1)
+        self.assertTrue(np.allclose(r_other, r_lin))
+
+        msh.write_vtu_p2("p2.vtu", geom)
+        self.assertTrue(os.path.exists("p2.vtu"))
+        os.remove("p2.vtu")
+
+    def test_refine_boundary(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        ftags = np.ones_like(ftags)
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+
+        # sphere circumscribed to the cube, with the boundary vertices moved onto it
+        r = 0.5 * 3**0.5
+        bdy, _ = msh.split().split().boundary()
+        x = bdy.get_coords() - 0.5
+        x = 0.5 + r * x / np.linalg.norm(x, axis=1)[:, np.newaxis]
+        bdy = Mesh32(
+            x, bdy.get_elems(), bdy.get_etags(), bdy.get_faces(), bdy.get_ftags()
+        )
+        coords = msh.get_coords()
+        ids = np.unique(msh.get_faces())
+        x = coords[ids] - 0.5
+        coords[ids] = 0.5 + r * x / np.linalg.norm(x, axis=1)[:, np.newaxis]
+        msh = Mesh33(
+            coords, msh.get_elems(), msh.get_etags(), msh.get_faces(), msh.get_ftags()
+        )
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh, bdy)
+
+        def max_chordal_deviation(m):
+            centers = m.get_coords()[m.get_faces()].mean(axis=1)
+            return np.abs(np.linalg.norm(centers - 0.5, axis=1) - r).max()
+
+        d = geom.max_distance(msh)
+        d_chord = max_chordal_deviation(msh)
+        new, elem_parents, face_parents = msh.refine_boundary([1], 2, geom)
+        new.check()
+        new.compute_topology()
+        self.assertLess(geom.max_distance(new), d)
+        self.assertLess(max_chordal_deviation(new), d_chord)
+        self.assertTrue(np.all(new.vols() > 0))
+        self.assertAlmostEqual(new.vol(), new.vols().sum())
+
+        self.assertEqual(elem_parents.shape, (new.n_elems(),))
+        self.assertEqual(face_parents.shape, (new.n_faces(),))
+        self.assertEqual(new.n_faces(), 16 * msh.n_faces())
+        self.assertTrue(np.array_equal(new.get_etags(), msh.get_etags()[elem_parents]))
+
+        # the elements without vertices on the boundary are not split
+        interior = np.all(np.isin(msh.get_elems(), ids, invert=True), axis=1)
+        self.assertTrue(np.any(interior))
+        n_children = np.bincount(elem_parents, minlength=msh.n_elems())
+        self.assertTrue(np.all(n_children[interior] == 1))
+        self.assertTrue(np.all(n_children[~interior] >= 1))
+        self.assertGreater(new.n_elems(), msh.n_elems())
+
+        # no refinement for the other tags
+        new, elem_parents, _ = msh.refine_boundary([2])
+        self.assertEqual(new.n_elems(), msh.n_elems())
+
+    def test_smooth_boundary(self):
+        r = 0.4
+        msh = Mesh33.from_sdf(
+            lambda x: np.linalg.norm(x, axis=1) - r,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            0.1,
+        )
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        # move the boundary vertices tangentially
+        coords = msh.get_coords()
+        bdy = np.unique(msh.get_faces())
+        n = coords[bdy] / np.linalg.norm(coords[bdy], axis=1)[:, np.newaxis]
+        d = 0.01 * np.random.default_rng(0).standard_normal((bdy.size, 3))
+        d -= np.sum(d * n, axis=1)[:, np.newaxis] * n
+        coords[bdy] += d
+        msh = Mesh33(
+            coords, msh.get_elems(), msh.get_etags(), msh.get_faces(), msh.get_ftags()
+        )
+        msh.compute_topology()
+        self.assertTrue(np.all(msh.vols() > 0))
+
+        before, after = msh.smooth_boundary([1], geom, 5)
+        self.assertGreater(after[1], before[1])
+
+        new_coords = msh.get_coords()
+        interior = np.setdiff1d(np.arange(msh.n_verts()), bdy)
+        self.assertTrue(np.array_equal(new_coords[interior], coords[interior]))
+        r_bdy = np.linalg.norm(new_coords[bdy], axis=1)
+        self.assertTrue(np.allclose(r_bdy, r, atol=0.02))
+        self.assertTrue(np.all(msh.vols() > 0))
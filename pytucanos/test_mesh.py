@@ -1,12 +1,16 @@
 import os
 import numpy as np
 import unittest
+from . import HAVE_GMSH, HAVE_XDMF, IDX_DTYPE, TAG_DTYPE, TAG_MIN, TAG_MAX
 from .mesh import (
     Mesh22,
     Mesh32,
     Mesh33,
+    compare_tags,
     get_square,
     get_cube,
+    hausdorff_distance,
+    mesh_info,
 )
 
 
@@ -43,6 +47,180 @@ class TestMeshes(unittest.TestCase):
         self.assertTrue(np.allclose(msh.get_faces(), faces))
         self.assertTrue(np.allclose(msh.get_ftags(), ftags))
 
+    def test_dtypes(self):
+        self.assertEqual(IDX_DTYPE, np.uint32)
+        self.assertEqual(TAG_DTYPE, np.int16)
+
+        for msh in [Mesh22(*get_square()), Mesh33(*get_cube())]:
+            self.assertEqual(msh.get_elems().dtype, IDX_DTYPE)
+            self.assertEqual(msh.get_faces().dtype, IDX_DTYPE)
+            self.assertEqual(msh.get_etags().dtype, TAG_DTYPE)
+            self.assertEqual(msh.get_ftags().dtype, TAG_DTYPE)
+
+            bdy, ids = msh.boundary()
+            self.assertEqual(ids.dtype, IDX_DTYPE)
+            self.assertEqual(bdy.get_elems().dtype, IDX_DTYPE)
+            self.assertEqual(bdy.get_etags().dtype, TAG_DTYPE)
+
+            perms = msh.reorder_hilbert()[:3]
+            for old_to_new, new_to_old in perms:
+                self.assertEqual(old_to_new.dtype, IDX_DTYPE)
+                self.assertEqual(new_to_old.dtype, IDX_DTYPE)
+
+    def test_get_slices(self):
+        msh = Mesh33(*get_cube()).split().split()
+        coords, elems, faces = msh.get_coords(), msh.get_elems(), msh.get_faces()
+
+        self.assertTrue(np.array_equal(msh.get_coords_slice(3, 10), coords[3:10]))
+        self.assertTrue(np.array_equal(msh.get_elems_slice(20, 45), elems[20:45]))
+        self.assertTrue(np.array_equal(msh.get_faces_slice(0, 7), faces[0:7]))
+        n = msh.n_elems()
+        self.assertEqual(msh.get_elems_slice(n, n).shape, (0, 4))
+        self.assertEqual(msh.get_elems_slice(0, n).dtype, IDX_DTYPE)
+
+        ids = np.array([5, 0, 17, 5], dtype=IDX_DTYPE)
+        self.assertTrue(np.array_equal(msh.get_coords_at(ids), coords[ids]))
+        self.assertTrue(np.array_equal(msh.get_elems_at(ids), elems[ids]))
+        self.assertTrue(np.array_equal(msh.get_faces_at(ids), faces[ids]))
+
+        with self.assertRaises(ValueError):
+            msh.get_elems_slice(10, n + 1)
+        with self.assertRaises(ValueError):
+            msh.get_faces_slice(10, 5)
+        with self.assertRaises(ValueError):
+            msh.get_coords_at(np.array([msh.n_verts()], dtype=IDX_DTYPE))
+
+    def test_remove_elems(self):
+        msh = Mesh33(*get_cube()).split().split()
+        coords, elems = msh.get_coords(), msh.get_elems()
+        n_elems, n_faces = msh.n_elems(), msh.n_faces()
+        new_tag = msh.get_ftags().max() + 1
+
+        ids = np.array([0, 10], dtype=IDX_DTYPE)
+        vert_ids, elem_ids = msh.remove_elems(ids)
+        msh.check()
+        self.assertEqual(msh.n_elems(), n_elems - 2)
+        self.assertFalse(np.isin(ids, elem_ids).any())
+        self.assertTrue(np.allclose(msh.get_coords(), coords[vert_ids]))
+        self.assertTrue(np.array_equal(vert_ids[msh.get_elems()], elems[elem_ids]))
+        n_new = (msh.get_ftags() == new_tag).sum()
+        self.assertGreater(n_new, 0)
+        self.assertGreaterEqual(msh.n_faces(), n_faces + n_new - 8)
+
+        n_faces = msh.n_faces()
+        msh.remove_elems(np.array([5], dtype=IDX_DTYPE), tag=42, compact=False)
+        msh.check()
+        self.assertEqual(msh.n_verts(), len(vert_ids))
+        self.assertGreater((msh.get_ftags() == 42).sum(), 0)
+
+        with self.assertRaises(ValueError):
+            msh.remove_elems(np.array([msh.n_elems()], dtype=IDX_DTYPE))
+
+    def test_pickle(self):
+        import copy
+        import pickle
+
+        msh = Mesh33(*get_cube()).split()
+        msh.set_global_ids(np.arange(msh.n_verts(), dtype=np.int64) + 10)
+        for other in [pickle.loads(pickle.dumps(msh)), copy.deepcopy(msh)]:
+            self.assertIsInstance(other, Mesh33)
+            self.assertTrue(np.array_equal(other.get_coords(), msh.get_coords()))
+            self.assertTrue(np.array_equal(other.get_elems(), msh.get_elems()))
+            self.assertTrue(np.array_equal(other.get_etags(), msh.get_etags()))
+            self.assertTrue(np.array_equal(other.get_faces(), msh.get_faces()))
+            self.assertTrue(np.array_equal(other.get_ftags(), msh.get_ftags()))
+            self.assertTrue(
+                np.array_equal(other.get_global_ids(), msh.get_global_ids())
+            )
+
+        msh = Mesh22(*get_square())
+        other = pickle.loads(pickle.dumps(msh))
+        self.assertTrue(np.array_equal(other.get_elems(), msh.get_elems()))
+        self.assertIsNone(other.get_global_ids())
+
+    @unittest.skipUnless(HAVE_GMSH, "gmsh support not available")
+    def test_gmsh(self):
+        def rows(conn, tags):
+            return sorted(map(tuple, np.column_stack([conn, tags]).tolist()))
+
+        for msh in [
+            Mesh33(*get_cube()).split(),
+            Mesh33(*get_cube()).split().boundary()[0],
+            Mesh22(*get_square()).split(),
+            Mesh22(*get_square()).split().boundary()[0],
+        ]:
+            msh.write_gmsh("tmp.msh")
+            other = type(msh).from_gmsh("tmp.msh")
+            self.assertTrue(np.allclose(other.get_coords(), msh.get_coords()))
+            # the elements are grouped by tag
+            self.assertEqual(
+                rows(other.get_elems(), other.get_etags()),
+                rows(msh.get_elems(), msh.get_etags()),
+            )
+            self.assertEqual(
+                rows(other.get_faces(), other.get_ftags()),
+                rows(msh.get_faces(), msh.get_ftags()),
+            )
+
+        # file written by gmsh, with a physical tag on the volume only, parametric
+        # nodes and point elements that are ignored
+        with open("tmp.msh", "w") as f:
+            f.write(
+                "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n"
+                '$PhysicalNames\n1\n3 7 "fluid"\n$EndPhysicalNames\n'
+                "$Entities\n1 0 1 1\n1 0 0 0 0\n"
+                "3 0 0 0 1 1 0 0 0\n5 0 0 0 1 1 1 1 7 1 3\n$EndEntities\n"
+                "$Nodes\n2 4 1 4\n0 1 0 1\n1\n0 0 0\n3 5 0 3\n2\n3\n4\n"
+                "1 0 0\n0 1 0\n0 0 1\n$EndNodes\n"
+                "$Elements\n3 3 1 3\n0 1 15 1\n1 1\n2 3 2 1\n2 1 3 2\n"
+                "3 5 4 1\n3 1 2 3 4\n$EndElements\n"
+            )
+        msh = Mesh33.from_gmsh("tmp.msh")
+        self.assertEqual((msh.n_verts(), msh.n_elems(), msh.n_faces()), (4, 1, 1))
+        self.assertTrue(np.array_equal(msh.get_etags(), [7]))
+        self.assertTrue(np.array_equal(msh.get_ftags(), [3]))
+        self.assertTrue(np.array_equal(msh.get_faces(), [[0, 2, 1]]))
+        self.assertAlmostEqual(msh.vol(), 1.0 / 6.0)
+
+        with open("tmp.msh", "w") as f:
+            f.write("$MeshFormat\n2.2 0 8\n$EndMeshFormat\n")
+        with self.assertRaisesRegex(RuntimeError, "format 4.1"):
+            Mesh33.from_gmsh("tmp.msh")
+
+        os.remove("tmp.msh")
+
+    @unittest.skipUnless(HAVE_XDMF, "xdmf support not available")
+    def test_xdmf(self):
+        for msh in [
+            Mesh33(*get_cube()).split(),
+            Mesh22(*get_square()).split(),
+            Mesh22(*get_square()).split().boundary()[0],
+        ]:
+            msh.write_xdmf("tmp.xdmf")
+            self.assertTrue(os.path.exists("tmp.h5"))
+            other = type(msh).from_xdmf("tmp.xdmf")
+            self.assertTrue(np.array_equal(other.get_coords(), msh.get_coords()))
+            self.assertTrue(np.array_equal(other.get_elems(), msh.get_elems()))
+            self.assertTrue(np.array_equal(other.get_etags(), msh.get_etags()))
+            self.assertTrue(np.array_equal(other.get_faces(), msh.get_faces()))
+            self.assertTrue(np.array_equal(other.get_ftags(), msh.get_ftags()))
+
+        msh = Mesh33(*get_cube()).split()
+        f = msh.get_coords()[:, :1].copy()
+        g = msh.vol().reshape((-1, 1))
+        msh.write_xdmf("tmp.xdmf", {"f": f}, {"g": g})
+        with open("tmp.xdmf") as fp:
+            data = fp.read()
+        self.assertIn('Name="f" AttributeType="Scalar" Center="Node"', data)
+        self.assertIn('Name="g" AttributeType="Scalar" Center="Cell"', data)
+        self.assertIn("tmp.h5:/vert_data/f", data)
+
+        with self.assertRaises(ValueError):
+            msh.write_xdmf("tmp.xdmf", {"f": g})
+
+        os.remove("tmp.xdmf")
+        os.remove("tmp.h5")
+
     def test_meshb_2d(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags)
@@ -118,6 +296,101 @@ class TestMeshes(unittest.TestCase):
         self.assertEqual(bdy.n_faces(), 0)
         self.assertTrue(np.allclose(bdy.vol(), 6.0))
 
+    def test_boundary_which_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        coords, elems = msh.get_coords(), msh.get_elems()
+        faces, ftags = msh.get_faces(), msh.get_ftags()
+
+        # two materials separated at x = 0.5, with the interface faces tagged
+        etags = np.where(coords[elems].mean(axis=1)[:, 0] < 0.5, 1, 2)
+        etags = etags.astype(TAG_DTYPE)
+        owner = {}
+        ifc = []
+        for i, e in enumerate(elems):
+            for j in range(4):
+                f = tuple(sorted(np.delete(e, j)))
+                if f in owner and etags[owner[f]] != etags[i]:
+                    ifc.append(f)
+                owner[f] = i
+        self.assertGreater(len(ifc), 0)
+        faces = np.vstack([faces, np.array(ifc, dtype=IDX_DTYPE)])
+        ftags = np.concatenate([ftags, np.full(len(ifc), 7, dtype=TAG_DTYPE)])
+        msh = Mesh33(coords, elems, etags, faces, ftags)
+
+        n_ext = 4**2 * 12
+        for which, n in [
+            (None, n_ext + len(ifc)),
+            ("all", n_ext + len(ifc)),
+            ("exterior", n_ext),
+            ("internal", len(ifc)),
+        ]:
+            bdy, ids = msh.boundary(which)
+            self.assertEqual(bdy.n_elems(), n)
+            self.assertTrue(np.array_equal(bdy.get_coords(), coords[ids]))
+
+        bdy, _ = msh.boundary("exterior")
+        self.assertAlmostEqual(bdy.vol(), 6.0)
+        self.assertNotIn(7, bdy.get_etags())
+        bdy, _ = msh.boundary("internal")
+        self.assertTrue(np.all(bdy.get_etags() == 7))
+
+        with self.assertRaises(ValueError):
+            msh.boundary("interior")
+
+    def test_compare_tags(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+
+        ids_a, ids_b, counts = compare_tags(msh, msh, 1e-6)
+        self.assertEqual(ids_a.size, 0)
+        self.assertEqual(ids_b.size, 0)
+        self.assertEqual(counts, {})
+
+        # renumbered copy with some modified tags
+        other = Mesh33(
+            msh.get_coords(),
+            msh.get_elems(),
+            msh.get_etags(),
+            msh.get_faces(),
+            msh.get_ftags(),
+        )
+        other.reorder_hilbert()
+        ftags = other.get_ftags()
+        rng = np.random.default_rng(0)
+        modified = np.sort(rng.choice(other.n_faces(), 10, replace=False))
+        ref = msh.get_ftags()
+        expected = {}
+        for i in modified:
+            old = ftags[i]
+            ftags[i] = 10 + old
+            expected[(old, 10 + old)] = expected.get((old, 10 + old), 0) + 1
+        other = Mesh33(
+            other.get_coords(),
+            other.get_elems(),
+            other.get_etags(),
+            other.get_faces(),
+            ftags,
+        )
+
+        ids_a, ids_b, counts = compare_tags(msh, other, 1e-6)
+        self.assertTrue(np.array_equal(ids_b, modified))
+        self.assertTrue(np.array_equal(ref[ids_a] + 10, ftags[ids_b]))
+        self.assertEqual(counts, expected)
+
+        # faces without match
+        coords = other.get_coords() + 0.1
+        shifted = Mesh33(
+            coords, other.get_elems(), other.get_etags(), other.get_faces(), ftags
+        )
+        ids_a, ids_b, counts = compare_tags(msh, shifted, 1e-6)
+        self.assertEqual(ids_a.size, 0)
+        self.assertEqual(sum(counts.values()), other.n_faces())
+        self.assertTrue(all(k[0] is None for k in counts))
+
+        with self.assertRaises(ValueError):
+            compare_tags(msh, msh.boundary()[0], 1e-6)
+
     def test_hilbert_2d(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags)
@@ -156,6 +429,53 @@ class TestMeshes(unittest.TestCase):
 
         self.assertTrue(after < 0.5 * before)
 
+    def test_hilbert_permutations_2d(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        msh = msh.split().split().split()
+
+        coords = msh.get_coords()
+        elems = msh.get_elems()
+        faces = msh.get_faces()
+        f = coords[:, 0] + 2.0 * coords[:, 1]
+
+        (
+            (vert_old_to_new, vert_new_to_old),
+            (elem_old_to_new, elem_new_to_old),
+            (face_old_to_new, face_new_to_old),
+            fields,
+        ) = msh.reorder_hilbert(fields={"f": f.reshape((-1, 1)), "xy": coords})
+
+        new_coords = msh.get_coords()
+        self.assertTrue(np.allclose(new_coords, coords[vert_new_to_old, :]))
+        self.assertTrue(np.allclose(new_coords[vert_old_to_new, :], coords))
+        self.assertTrue(
+            np.array_equal(vert_old_to_new[vert_new_to_old], np.arange(msh.n_verts()))
+        )
+
+        self.assertTrue(np.allclose(fields["xy"], new_coords))
+        new_f = new_coords[:, 0] + 2.0 * new_coords[:, 1]
+        self.assertTrue(np.allclose(fields["f"][:, 0], new_f))
+
+        new_elems = msh.get_elems()
+        self.assertTrue(
+            np.array_equal(new_elems, vert_old_to_new[elems[elem_new_to_old, :]])
+        )
+        self.assertTrue(
+            np.array_equal(new_elems[elem_old_to_new, :], vert_old_to_new[elems])
+        )
+
+        new_faces = msh.get_faces()
+        self.assertTrue(
+            np.array_equal(new_faces, vert_old_to_new[faces[face_new_to_old, :]])
+        )
+        self.assertTrue(
+            np.array_equal(new_faces[face_old_to_new, :], vert_old_to_new[faces])
+        )
+
+        with self.assertRaises(ValueError):
+            msh.reorder_hilbert(fields={"f": f[:-1].reshape((-1, 1))})
+
     def test_boundary_faces_2d(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags)
@@ -266,6 +586,110 @@ class TestMeshes(unittest.TestCase):
         tag = bdy[1]
         self.assertEqual((msh.get_ftags() == tag).sum(), 10 * 4**3)
 
+    def test_boundary_faces_3d_threads(self):
+        import json
+        import subprocess
+        import sys
+        import tempfile
+
+        # add_boundary_faces() on a mesh with interfaces, run serially and in parallel
+        script = """
+import json, sys
+from pytucanos import TAG_DTYPE
+from pytucanos.mesh import Mesh33, get_cube
+
+coords, elems, etags, faces, ftags = get_cube()
+msh = Mesh33(coords, elems, etags, faces[:4], ftags[:4])
+msh = msh.split().split().split().split()
+coords, elems = msh.get_coords(), msh.get_elems()
+centers = coords[elems].mean(axis=1)
+etags = (1 + (centers[:, 0] > 0.5) + 2 * (centers[:, 1] > 0.3)).astype(TAG_DTYPE)
+msh = Mesh33(coords, elems, etags, msh.get_faces(), msh.get_ftags())
+bdy, ifc = msh.add_boundary_faces()
+with open(sys.argv[1], "w") as f:
+    json.dump({
+        "bdy": sorted((int(k), int(v)) for k, v in bdy.items()),
+        "ifc": sorted((int(k), v.tolist()) for k, v in ifc.items()),
+        "faces": msh.get_faces().tolist(),
+        "ftags": msh.get_ftags().tolist(),
+    }, f)
+"""
+        res = []
+        with tempfile.TemporaryDirectory() as tmp:
+            for n_threads in [1, 4]:
+                fname = os.path.join(tmp, f"faces_{n_threads}.json")
+                env = dict(os.environ, RAYON_NUM_THREADS=str(n_threads))
+                cmd = [sys.executable, "-c", script, fname]
+                subprocess.run(cmd, env=env, check=True)
+                with open(fname) as f:
+                    res.append(json.load(f))
+
+        self.assertGreater(len(res[0]["ifc"]), 0)
+        self.assertEqual(res[0], res[1])
+
+    def test_interface_orientation_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split().split()
+
+        # two materials: tag 1 for x < 0.5 and tag 2 for x > 0.5
+        coords, elems = msh.get_coords(), msh.get_elems()
+        etags = np.where(coords[elems].mean(axis=1)[:, 0] < 0.5, 1, 2)
+        etags = etags.astype(TAG_DTYPE)
+        msh = Mesh33(coords, elems, etags, msh.get_faces(), msh.get_ftags())
+
+        _, ifc = msh.add_boundary_faces()
+        self.assertEqual(len(ifc), 1)
+        self.assertEqual(msh.interface_orientation((1, 2)), 1)
+        self.assertEqual(msh.interface_orientation((2, 1)), -1)
+        self.assertIsNone(msh.interface_orientation((1, 3)))
+        self.assertEqual(msh.orient_interfaces(), 0)
+
+        def interface_normals(msh):
+            faces = msh.get_faces()[np.isin(msh.get_ftags(), list(ifc.keys()))]
+            x = msh.get_coords()[faces]
+            return 0.5 * np.cross(x[:, 1] - x[:, 0], x[:, 2] - x[:, 0])
+
+        # the flux of (1, 0, 0) from tag 1 to tag 2 through the interface is its area
+        n = interface_normals(msh)
+        self.assertTrue(np.all(n[:, 0] > 0))
+        self.assertTrue(np.allclose(n[:, 0].sum(), 1.0))
+
+        # flip some of the interface faces
+        faces, ftags = msh.get_faces(), msh.get_ftags()
+        ids = np.nonzero(np.isin(ftags, list(ifc.keys())))[0][::2]
+        faces[ids] = faces[ids][:, [1, 0, 2]]
+        msh = Mesh33(coords, elems, etags, faces, ftags)
+        self.assertEqual(msh.interface_orientation((1, 2)), 0)
+        self.assertEqual(msh.orient_interfaces(), ids.size)
+        self.assertEqual(msh.interface_orientation((1, 2)), 1)
+        self.assertTrue(np.all(interface_normals(msh)[:, 0] > 0))
+
+    def test_boundary_face_elems_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+
+        face_ids, elem_ids, local_ids = msh.boundary_face_elems()
+        self.assertTrue(np.array_equal(face_ids, np.arange(msh.n_faces())))
+        self.assertTrue(np.all(local_ids < 4))
+        faces = msh.get_faces()
+        elems = msh.get_elems()
+        for i_face, i_elem in zip(face_ids, elem_ids):
+            self.assertTrue(set(faces[i_face]).issubset(elems[i_elem]))
+
+    def test_boundary_face_elems_2d(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+
+        # the faces with tag 5 are between the two element tags
+        face_ids, elem_ids, local_ids = msh.boundary_face_elems()
+        ftags = msh.get_ftags()
+        self.assertTrue(np.array_equal(face_ids, np.nonzero(ftags != 5)[0]))
+        self.assertTrue(np.all(local_ids < 3))
+        faces = msh.get_faces()
+        elems = msh.get_elems()
+        for i_face, i_elem in zip(face_ids, elem_ids):
+            self.assertTrue(set(faces[i_face]).issubset(elems[i_elem]))
+
     def test_fields_2d(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags)
@@ -295,6 +719,49 @@ class TestMeshes(unittest.TestCase):
 
         msh.elem_data_to_vertex_data(f_e)
 
+    def test_fields_weighting(self):
+        from .remesh import Remesher2dAniso
+
+        coords = np.array([[0, 0], [1, 0], [0, 1], [10, 10]], dtype=np.float64)
+        elems = np.array([[0, 1, 2], [1, 3, 2]], dtype=IDX_DTYPE)
+        etags = np.array([1, 1], dtype=TAG_DTYPE)
+        faces = np.array([[0, 1], [1, 3], [3, 2], [2, 0]], dtype=IDX_DTYPE)
+        ftags = np.array([1, 1, 1, 1], dtype=TAG_DTYPE)
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        msh.compute_vertex_to_elems()
+        msh.compute_volumes()
+
+        f_e = np.array([[1.0], [2.0]])
+        vol = np.array([0.5, 9.5])
+        centers = coords[elems].mean(axis=1)
+
+        def expected(w0, w1):
+            return (w0 * f_e[0, 0] + w1 * f_e[1, 0]) / (w0 + w1)
+
+        f = msh.elem_data_to_vertex_data(f_e)
+        self.assertTrue(np.allclose(f, msh.elem_data_to_vertex_data(f_e, "volume")))
+        self.assertTrue(np.allclose(f[[0, 3], 0], [1.0, 2.0]))
+        self.assertTrue(np.allclose(f[[1, 2], 0], expected(*vol)))
+
+        f = msh.elem_data_to_vertex_data(f_e, "uniform")
+        self.assertTrue(np.allclose(f[[0, 3], 0], [1.0, 2.0]))
+        self.assertTrue(np.allclose(f[[1, 2], 0], 1.5))
+
+        f = msh.elem_data_to_vertex_data(f_e, "inverse_distance")
+        self.assertTrue(np.allclose(f[[0, 3], 0], [1.0, 2.0]))
+        for i in [1, 2]:
+            d = np.linalg.norm(centers - coords[i], axis=1)
+            self.assertTrue(np.allclose(f[i, 0], expected(*(1.0 / d))))
+
+        with self.assertRaises(ValueError):
+            msh.elem_data_to_vertex_data(f_e, "max")
+
+        m_e = np.array([[1.0, 4.0, 0.5], [2.0, 1.0, 0.0]])
+        for weighting in [None, "uniform", "inverse_distance"]:
+            m = Remesher2dAniso.elem_data_to_vertex_data_metric(msh, m_e, weighting)
+            self.assertTrue(np.allclose(m[0], m_e[0]))
+            self.assertTrue(np.allclose(m[3], m_e[1]))
+
     def test_meshb_3d(self):
         coords, elems, etags, faces, ftags = get_cube()
         msh = Mesh33(coords, elems, etags, faces, ftags)
@@ -327,6 +794,57 @@ class TestMeshes(unittest.TestCase):
         vols = msh.vols()
         self.assertTrue(np.allclose(vols.sum(), 1.0))
 
+    def test_vertex_volumes(self):
+        coords = np.array(
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+        )
+        elems = np.array([[0, 1, 2, 3]], dtype=IDX_DTYPE)
+        etags = np.array([1], dtype=TAG_DTYPE)
+        faces = np.zeros((0, 3), dtype=IDX_DTYPE)
+        ftags = np.zeros(0, dtype=TAG_DTYPE)
+        msh = Mesh33(coords, elems, etags, faces, ftags)
+        self.assertTrue(np.allclose(msh.get_vertex_volumes(), 1.0 / 24.0))
+        self.assertAlmostEqual(msh.get_total_volume(), 1.0 / 6.0)
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        v = msh.get_vertex_volumes()
+        self.assertEqual(v.shape, (msh.n_verts(),))
+        self.assertTrue(np.all(v > 0.0))
+        self.assertAlmostEqual(v.sum(), msh.vol())
+        self.assertAlmostEqual(msh.get_total_volume(), msh.vol())
+
+    def test_elem_anisotropy(self):
+        coords = np.array(
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.5, 0.5 * np.sqrt(3.0), 0.0],
+                [0.5, np.sqrt(3.0) / 6.0, np.sqrt(2.0 / 3.0)],
+            ]
+        )
+        elems = np.array([[0, 1, 2, 3]], dtype=IDX_DTYPE)
+        etags = np.array([1], dtype=TAG_DTYPE)
+        faces = np.zeros((0, 3), dtype=IDX_DTYPE)
+        ftags = np.zeros(0, dtype=TAG_DTYPE)
+
+        for scale, expected in [((2.0, 2.0, 2.0), 1.0), ((5.0, 1.0, 1.0), 5.0)]:
+            msh = Mesh33(coords * np.array(scale), elems, etags, faces, ftags)
+            self.assertTrue(np.allclose(msh.elem_anisotropy(), expected))
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        aniso = msh.elem_anisotropy()
+        self.assertEqual(aniso.shape, (msh.n_elems(),))
+        self.assertTrue(np.all(aniso >= 1.0 - 1e-12))
+
+        report = msh.quality_report()
+        self.assertEqual(report["n_elems"], msh.n_elems())
+        self.assertAlmostEqual(report["anisotropy"]["min"], aniso.min())
+        self.assertAlmostEqual(report["anisotropy"]["max"], aniso.max())
+        self.assertAlmostEqual(report["anisotropy"]["mean"], aniso.mean())
+        self.assertAlmostEqual(report["vol_min"], msh.vols().min())
+
     def test_autotag_2d(self):
 
         coords, elems, etags, faces, ftags = get_square(two_tags=False)
@@ -381,3 +899,1089 @@ class TestMeshes(unittest.TestCase):
         vals.sort()
         self.assertTrue(np.array_equal(vals, [1, 2, 3, 4, 5, 6]))
         self.assertTrue(np.array_equal(counts, [8, 8, 8, 8, 8, 8]))
+
+    def test_set_ftags_from_boundary(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        ftags[:] = 1
+
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+
+        bdy, ids = msh.boundary()
+        bdy.compute_face_to_elems()
+        bdy.autotag(30.0)
+
+        tags = msh.set_ftags_from_boundary(bdy, ids)
+        self.assertTrue(np.array_equal(tags, msh.get_ftags()))
+        vals, counts = np.unique(tags, return_counts=True)
+        self.assertTrue(np.array_equal(vals, [1, 2, 3, 4, 5, 6]))
+        self.assertTrue(np.array_equal(counts, [8, 8, 8, 8, 8, 8]))
+
+        # the faces are matched by their vertices
+        bdy_tags = bdy.get_etags()
+        bdy_faces = ids[bdy.get_elems()]
+        key = {tuple(sorted(f)): t for f, t in zip(bdy_faces, bdy_tags)}
+        for f, t in zip(msh.get_faces(), tags):
+            self.assertEqual(key[tuple(sorted(f))], t)
+
+        with self.assertRaises(ValueError):
+            msh.set_ftags_from_boundary(bdy, ids[1:])
+        other, other_ids = msh.split().boundary()
+        with self.assertRaises(ValueError):
+            msh.set_ftags_from_boundary(other, other_ids)
+
+    def test_untangle(self):
+        def signed_vols(msh):
+            x = msh.get_coords()[msh.get_elems()]
+            e = x[:, 1:, :] - x[:, :1, :]
+            return np.einsum("ij,ij->i", np.cross(e[:, 0], e[:, 1]), e[:, 2]) / 6
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        coords = msh.get_coords()
+        elems = msh.get_elems()
+        self.assertTrue(np.all(signed_vols(msh) > 0))
+
+        # push an interior vertex through one of its interior neighbors
+        interior = np.all((coords > 0.1) & (coords < 0.9), axis=1)
+        i = np.nonzero(interior)[0][0]
+        neighbors = np.unique(elems[np.any(elems == i, axis=1)])
+        j = [k for k in neighbors if k != i and interior[k]][0]
+        coords[i] += 1.5 * (coords[j] - coords[i])
+
+        bdy = np.unique(msh.get_faces())
+        for frozen in [None, np.unique(ftags)]:
+            msh = Mesh33(
+                coords, elems, msh.get_etags(), msh.get_faces(), msh.get_ftags()
+            )
+            self.assertGreater(np.count_nonzero(signed_vols(msh) <= 0), 0)
+
+            self.assertEqual(msh.untangle(20, frozen), 0)
+            self.assertTrue(np.all(signed_vols(msh) > 0))
+            self.assertTrue(np.array_equal(msh.get_elems(), elems))
+            if frozen is not None:
+                new_coords = msh.get_coords()
+                self.assertTrue(np.array_equal(new_coords[bdy], coords[bdy]))
+                self.assertTrue(np.allclose(msh.vol(), 1.0))
+
+    def test_insert_points(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags)
+        msh.set_global_ids(np.arange(8, dtype=np.int64))
+
+        points = np.array(
+            [
+                coords[elems[0]].mean(axis=0),  # element interior
+                coords[[5, 6, 7]].mean(axis=0),  # boundary face
+                coords[[0, 2]].mean(axis=0),  # edge between 3 elements
+                coords[6] + 1e-8,  # close to a vertex
+                [2.0, 2.0, 2.0],  # outside
+            ]
+        )
+        ids, outside = msh.insert_points(points, 1e-6)
+        self.assertEqual(ids.dtype, IDX_DTYPE)
+        self.assertTrue(np.array_equal(ids[:4], [8, 9, 10, 6]))
+        self.assertEqual(ids[4], np.iinfo(IDX_DTYPE).max)
+        self.assertTrue(np.array_equal(outside, [4]))
+
+        msh.check()
+        self.assertEqual(msh.n_verts(), 11)
+        self.assertTrue(np.allclose(msh.get_coords()[ids[:3]], points[:3]))
+        self.assertTrue(np.allclose(msh.get_coords()[:8], coords))
+        self.assertAlmostEqual(msh.vol(), 1.0)
+        # the boundary face is split in 3, and the 2 faces along the edge in 2
+        self.assertEqual(msh.n_faces(), 16)
+        self.assertTrue(np.all(msh.get_ftags() > 0))
+        self.assertTrue(np.array_equal(msh.get_global_ids()[8:], [-1, -1, -1]))
+
+        # points close to a face are projected onto it
+        ids, outside = msh.insert_points(np.array([[0.3, 0.2, -1e-8]]), 1e-6)
+        self.assertEqual(len(outside), 0)
+        self.assertEqual(msh.get_coords()[ids[0], 2], 0.0)
+        msh.check()
+        self.assertAlmostEqual(msh.vol(), 1.0)
+
+        with self.assertRaises(ValueError):
+            msh.insert_points(np.zeros((1, 2)), 1e-6)
+
+    def test_boundary_verts(self):
+        for msh in [
+            Mesh33(*get_cube()).split().split(),
+            Mesh22(*get_square()).split().split(),
+        ]:
+            coords = msh.get_coords()
+            expected = np.nonzero(np.any((coords == 0) | (coords == 1), axis=1))[0]
+
+            ids = msh.get_boundary_verts()
+            self.assertEqual(ids.dtype, IDX_DTYPE)
+            self.assertTrue(np.array_equal(ids, expected))
+            flags = msh.is_boundary_vertex()
+            self.assertEqual(flags.dtype, bool)
+            self.assertTrue(np.array_equal(np.nonzero(flags)[0], expected))
+
+            msh.compute_face_to_elems()
+            self.assertTrue(np.array_equal(msh.get_boundary_verts(), expected))
+
+    def test_locate_point(self):
+        for msh, dim in [
+            (Mesh33(*get_cube()).split(), 3),
+            (Mesh22(*get_square()).split(), 2),
+        ]:
+            coords = msh.get_coords()
+            elems = msh.get_elems()
+            ids = np.array([0, 3, msh.n_elems() - 1])
+            pts = np.vstack([coords[elems[ids]].mean(axis=1), 2 * np.ones(dim)])
+
+            self.assertFalse(msh.has_octree())
+            elem_ids, bary = msh.locate_point(pts)
+            self.assertTrue(msh.has_octree())
+            self.assertEqual(elem_ids.dtype, np.int32)
+            self.assertEqual(bary.shape, (4, dim + 1))
+            self.assertTrue(np.array_equal(elem_ids, [*ids, -1]))
+            self.assertTrue(np.allclose(bary[:3], 1.0 / (dim + 1)))
+            self.assertTrue(np.all(np.isnan(bary[3])))
+
+            # P1 interpolation of a linear field is exact
+            f = coords @ np.arange(1.0, dim + 1.0)
+            pts = 0.1 + 0.8 * np.random.rand(10, dim)
+            elem_ids, bary = msh.locate_point(pts)
+            self.assertTrue(np.all(elem_ids >= 0))
+            self.assertTrue(np.allclose(bary.sum(axis=1), 1.0))
+            self.assertTrue(np.all(bary >= -1e-12))
+            vals = np.einsum("ij,ij->i", bary, f[elems[elem_ids]])
+            self.assertTrue(np.allclose(vals, pts @ np.arange(1.0, dim + 1.0)))
+
+            with self.assertRaises(ValueError):
+                msh.locate_point(np.zeros((1, dim + 1)))
+
+    def test_split_by_wall_distance(self):
+        msh = Mesh33(*get_cube()).split().split()
+        msh.set_global_ids(np.arange(msh.n_verts(), dtype=np.int64))
+        coords, elems = msh.get_coords(), msh.get_elems()
+        wall = np.unique(msh.get_faces()[msh.get_ftags() == 1])
+        axis = np.argmin(np.ptp(coords[wall], axis=0))
+        dist = np.abs(coords[:, axis] - coords[wall[0], axis])
+        expected = np.nonzero(np.all(dist[elems] <= 0.3, axis=1))[0]
+
+        tag = msh.get_ftags().max() + 1
+        tags = np.array([1], dtype=TAG_DTYPE)
+        inner, outer, ifc_tag, inner_ids, outer_ids = msh.split_by_wall_distance(
+            tags, 0.3
+        )
+        self.assertEqual(ifc_tag, tag)
+        self.assertTrue(np.array_equal(inner_ids, expected))
+        ids = np.concatenate([inner_ids, outer_ids])
+        self.assertTrue(np.array_equal(np.sort(ids), np.arange(msh.n_elems())))
+        for part, part_ids in [(inner, inner_ids), (outer, outer_ids)]:
+            part.check()
+            gids = part.get_global_ids()
+            self.assertTrue(np.allclose(part.get_coords(), coords[gids]))
+            self.assertTrue(np.array_equal(gids[part.get_elems()], elems[part_ids]))
+        n_ifc = (inner.get_ftags() == tag).sum()
+        self.assertGreater(n_ifc, 0)
+        self.assertEqual((outer.get_ftags() == tag).sum(), n_ifc)
+        self.assertAlmostEqual(inner.vol() + outer.vol(), msh.vol())
+
+        # Join the parts back by merging their common vertices
+        parts = [inner, outer]
+        offsets = [0, inner.n_verts()]
+        all_coords = np.vstack([p.get_coords() for p in parts])
+        new_coords, new_idx = np.unique(
+            np.round(all_coords, 8), axis=0, return_inverse=True
+        )
+        new_idx = new_idx.ravel()
+        new_elems = [new_idx[p.get_elems() + o] for p, o in zip(parts, offsets)]
+        new_faces = [new_idx[p.get_faces() + o] for p, o in zip(parts, offsets)]
+        _, idx = np.unique(np.round(coords, 8), axis=0, return_inverse=True)
+        self.assertEqual(new_coords.shape[0], msh.n_verts())
+        self.assertTrue(np.array_equal(np.vstack(new_elems), idx.ravel()[elems[ids]]))
+
+        # The interface faces of the two parts match
+        ifc = [
+            np.unique(np.sort(f[p.get_ftags() == tag], axis=1), axis=0)
+            for p, f in zip(parts, new_faces)
+        ]
+        self.assertTrue(np.array_equal(ifc[0], ifc[1]))
+
+        ftags = np.concatenate([p.get_ftags() for p in parts])
+        joined = Mesh33(
+            new_coords,
+            np.vstack(new_elems),
+            np.concatenate([p.get_etags() for p in parts]),
+            np.vstack(new_faces)[ftags != tag],
+            ftags[ftags != tag],
+        )
+        joined.check()
+        self.assertEqual(joined.n_faces(), msh.n_faces())
+        self.assertAlmostEqual(joined.vol(), msh.vol())
+
+        with self.assertRaises(ValueError):
+            msh.split_by_wall_distance(np.array([42], dtype=TAG_DTYPE), 0.3)
+        with self.assertRaises(ValueError):
+            msh.split_by_wall_distance(tags, -1.0)
+
+    def test_face_normals(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        etags[::2] = 3
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.add_boundary_faces()
+        coords, faces = msh.get_coords(), msh.get_faces()
+        centers = coords[faces].mean(axis=1)
+        is_bdy = np.any((centers < 1e-12) | (centers > 1 - 1e-12), axis=1)
+
+        normals = msh.compute_face_normals()
+        self.assertEqual(normals.shape, (msh.n_faces(), 3))
+        self.assertTrue(np.allclose(np.linalg.norm(normals, axis=1), 1.0))
+        dots = np.einsum("ij,ij->i", normals, centers - 0.5)
+        self.assertTrue(np.all(dots[is_bdy] > 0))
+        p = coords[faces[~is_bdy]]
+        n = np.cross(p[:, 1] - p[:, 0], p[:, 2] - p[:, 0])
+        n /= np.linalg.norm(n, axis=1)[:, np.newaxis]
+        self.assertTrue(np.allclose(normals[~is_bdy], n))
+
+        normals = msh.compute_vertex_normals()
+        self.assertEqual(normals.shape, (msh.n_verts(), 3))
+        flags = msh.is_boundary_vertex()
+        self.assertTrue(np.allclose(np.linalg.norm(normals[flags], axis=1), 1.0))
+        self.assertTrue(np.all(normals[~flags] == 0))
+        self.assertTrue(np.all(np.einsum("ij,ij->i", normals, coords - 0.5)[flags] > 0))
+        # vertices on a single side of the cube
+        on_side = np.sum((coords < 1e-12) | (coords > 1 - 1e-12), axis=1) == 1
+        self.assertTrue(np.allclose(np.abs(normals[on_side]).max(axis=1), 1.0))
+
+        msh = Mesh22(*get_square()).split()
+        coords, faces = msh.get_coords(), msh.get_faces()
+        tangents = msh.compute_face_normals()
+        self.assertEqual(tangents.shape, (msh.n_faces(), 2))
+        self.assertTrue(np.allclose(np.linalg.norm(tangents, axis=1), 1.0))
+        normals = np.stack([tangents[:, 1], -tangents[:, 0]], axis=1)
+        centers = coords[faces].mean(axis=1)
+        self.assertTrue(np.all(np.einsum("ij,ij->i", normals, centers - 0.5) > 0))
+
+        coords, elems, etags, _, _ = get_cube()
+        faces = np.zeros((0, 3), dtype=IDX_DTYPE)
+        ftags = np.zeros(0, dtype=TAG_DTYPE)
+        msh = Mesh33(coords, elems, etags, faces, ftags)
+        with self.assertRaises(RuntimeError):
+            msh.compute_face_normals()
+        with self.assertRaises(RuntimeError):
+            msh.compute_vertex_normals()
+
+    def test_tag_values(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        etags[::2] = 3
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+
+        for values, tags in [
+            (msh.etag_values(), msh.get_etags()),
+            (msh.ftag_values(), msh.get_ftags()),
+        ]:
+            vals, counts = np.unique(tags, return_counts=True)
+            self.assertEqual(list(values.keys()), list(vals))
+            self.assertEqual(list(values.values()), list(counts))
+            self.assertEqual(sum(values.values()), tags.size)
+
+        self.assertTrue(msh.has_etag(1))
+        self.assertTrue(msh.has_etag(3))
+        self.assertFalse(msh.has_etag(2))
+        self.assertTrue(msh.has_ftag(6))
+        self.assertFalse(msh.has_ftag(7))
+
+        msh = Mesh22(*get_square())
+        self.assertEqual(msh.etag_values(), {1: 1, 2: 1})
+        self.assertEqual(msh.ftag_values(), {1: 1, 2: 1, 3: 1, 4: 1, 5: 1})
+
+    def test_stl(self):
+        import struct
+
+        coords, _, _, faces, _ = get_cube()
+
+        def is_watertight(msh):
+            edges = msh.get_elems()[:, [0, 1, 1, 2, 2, 0]].reshape((-1, 2))
+            edges = np.sort(edges, axis=1)
+            _, counts = np.unique(edges, axis=0, return_counts=True)
+            return np.all(counts == 2)
+
+        # ASCII file with two solids, the vertices of the second one being moved by 1e-9
+        solids = [("bottom", faces[:2], 0.0), ("rest", faces[2:], 1e-9)]
+        with open("tmp.stl", "w") as f:
+            for name, tris, eps in solids:
+                f.write(f"solid {name}\n")
+                for tri in tris:
+                    f.write("facet normal 0 0 0\nouter loop\n")
+                    for x in coords[tri] + eps:
+                        f.write(f"vertex {x[0]:.12e} {x[1]:.12e} {x[2]:.12e}\n")
+                    f.write("endloop\nendfacet\n")
+                f.write(f"endsolid {name}\n")
+
+        msh = Mesh32.from_stl("tmp.stl")
+        self.assertGreater(msh.n_verts(), 8)
+
+        msh, names = Mesh32.from_stl_with_names("tmp.stl", 1e-6)
+        self.assertEqual(names, {1: "bottom", 2: "rest"})
+        self.assertEqual(msh.n_verts(), 8)
+        self.assertEqual(msh.n_elems(), 12)
+        self.assertEqual(msh.etag_values(), {1: 2, 2: 10})
+        self.assertTrue(is_watertight(msh))
+
+        # binary file with 2 attribute bytes after each record
+        with open("tmp.stl", "wb") as f:
+            f.write(b"binary".ljust(80, b"\0"))
+            f.write(struct.pack("<I", faces.shape[0]))
+            for tri in faces:
+                f.write(struct.pack("<3f", 0.0, 0.0, 0.0))
+                for x in coords[tri]:
+                    f.write(struct.pack("<3f", *x))
+                f.write(struct.pack("<H", 2))
+                f.write(b"\1\2")
+
+        msh, names = Mesh32.from_stl_with_names("tmp.stl")
+        self.assertEqual(names, {1: "binary"})
+        self.assertEqual(msh.n_verts(), 8)
+        self.assertEqual(msh.etag_values(), {1: 12})
+        self.assertTrue(is_watertight(msh))
+
+        # malformed file
+        with open("tmp.stl", "w") as f:
+            f.write("solid a\nfacet normal 0 0 0\nouter loop\nvertex 0 0\n")
+        with self.assertRaisesRegex(RuntimeError, "offset 38"):
+            Mesh32.from_stl("tmp.stl")
+
+        os.remove("tmp.stl")
+
+    def test_hausdorff_distance(self):
+        r, s = 0.4, 1.05
+        sphere = Mesh33.from_sdf(
+            lambda x: np.linalg.norm(x, axis=1) - r,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            0.05,
+        )
+        sphere, _ = sphere.boundary()
+        coords = sphere.get_coords()
+        scaled = Mesh32(
+            s * coords,
+            sphere.get_elems(),
+            sphere.get_etags(),
+            sphere.get_faces(),
+            sphere.get_ftags(),
+        )
+
+        d, _ = hausdorff_distance(sphere, sphere)
+        self.assertLess(d, 1e-12)
+
+        # the faceting error, about h^2 / (8r) = 8e-4, is small compared to (s - 1) r
+        tol = 0.2 * (s - 1) * r
+        d, p = hausdorff_distance(sphere, scaled, sample_per_tri=4)
+        self.assertAlmostEqual(d, (s - 1) * r, delta=tol)
+        self.assertEqual(p.shape, (3,))
+        self.assertTrue(r - tol <= np.linalg.norm(p) <= s * r + tol)
+
+        scaled.compute_octree()
+        d1, _ = hausdorff_distance(scaled, sphere, symmetric=False)
+        self.assertAlmostEqual(d1, (s - 1) * r, delta=tol)
+        self.assertLessEqual(d1, d + 1e-12)
+
+    def test_fill_holes(self):
+        r = 0.4
+        sphere = Mesh33.from_sdf(
+            lambda x: np.linalg.norm(x, axis=1) - r,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            0.1,
+        )
+        sphere, _ = sphere.boundary()
+        area = sphere.vol()
+
+        def n_open_edges(msh):
+            edges = msh.get_elems()[:, [0, 1, 1, 2, 2, 0]].reshape((-1, 2))
+            _, counts = np.unique(np.sort(edges, axis=1), axis=0, return_counts=True)
+            return np.count_nonzero(counts == 1)
+
+        self.assertEqual(n_open_edges(sphere), 0)
+
+        # remove the triangles around the vertices with min and max x
+        coords, elems = sphere.get_coords(), sphere.get_elems()
+        fan_sizes = []
+        keep = np.ones(elems.shape[0], dtype=bool)
+        for i in [np.argmin(coords[:, 0]), np.argmax(coords[:, 0])]:
+            fan = np.any(elems == i, axis=1)
+            fan_sizes.append(np.count_nonzero(fan))
+            keep &= ~fan
+        etags = sphere.get_etags()[keep]
+        faces = np.zeros((0, 2), dtype=IDX_DTYPE)
+        ftags = np.zeros(0, dtype=TAG_DTYPE)
+
+        msh = Mesh32(coords, elems[keep], etags, faces, ftags)
+        self.assertEqual(n_open_edges(msh), sum(fan_sizes))
+
+        n_large = sum(n > 3 for n in fan_sizes)
+        self.assertEqual(msh.fill_holes(3), (2 - n_large, n_large))
+
+        msh = Mesh32(coords, elems[keep], etags, faces, ftags)
+        self.assertEqual(msh.fill_holes(10, 5), (2, 0))
+        self.assertEqual(n_open_edges(msh), 0)
+        self.assertEqual(msh.n_elems(), elems.shape[0] - 4)
+        self.assertEqual(msh.ftag_values(), {})
+        self.assertEqual(msh.etag_values()[5], sum(fan_sizes) - 4)
+        self.assertLess(abs(msh.vol() - area), 0.02 * area)
+
+        # the new triangles are oriented consistently
+        edges = msh.get_elems()[:, [0, 1, 1, 2, 2, 0]].reshape((-1, 2))
+        self.assertEqual(np.unique(edges, axis=0).shape[0], edges.shape[0])
+
+        self.assertEqual(msh.fill_holes(), (0, 0))
+
+    def test_bounding_box(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        coords = 2.0 * coords + np.array([1.0, -1.0, 3.0])
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+
+        pmin, pmax = msh.bounding_box()
+        self.assertTrue(np.allclose(pmin, [1.0, -1.0, 3.0]))
+        self.assertTrue(np.allclose(pmax, [3.0, 1.0, 5.0]))
+        self.assertAlmostEqual(msh.diameter(), np.sqrt(12.0))
+        self.assertTrue(np.allclose(msh.centroid(), [2.0, 0.0, 4.0]))
+
+        bdy, _ = msh.boundary()
+        self.assertAlmostEqual(bdy.diameter(), np.sqrt(12.0))
+        self.assertTrue(np.allclose(bdy.centroid(), [2.0, 0.0, 4.0]))
+
+        coords, elems, etags, faces, ftags = get_square()
+        coords = 0.5 * coords - 1.0
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        pmin, pmax = msh.bounding_box()
+        self.assertTrue(np.allclose(pmin, [-1.0, -1.0]))
+        self.assertTrue(np.allclose(pmax, [-0.5, -0.5]))
+        self.assertAlmostEqual(msh.diameter(), 0.5 * np.sqrt(2.0))
+        self.assertTrue(np.allclose(msh.centroid(), [-0.75, -0.75]))
+
+    def test_write_vtk_tags(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+        f = msh.get_coords()[:, :1].copy()
+        g = msh.vol().reshape((-1, 1))
+
+        msh.write_vtk("tmp_tags.vtu", {"f": f}, {"g": g}, [2], True)
+        with open("tmp_tags.vtu", "rb") as fp:
+            data = fp.read()
+        self.assertIn(b'NumberOfCells="4"', data)
+        self.assertIn(b'NumberOfPoints="6"', data)
+        self.assertIn(b"parent_id", data)
+        os.remove("tmp_tags.vtu")
+
+        with self.assertRaises(ValueError):
+            msh.write_vtk("tmp_tags.vtu", {"f": g}, None, [2])
+        self.assertFalse(os.path.exists("tmp_tags.vtu"))
+
+        msh.write_boundary_vtk("tmp_tags_bdy.vtu", [1, 2], False)
+        with open("tmp_tags_bdy.vtu", "rb") as fp:
+            data = fp.read()
+        self.assertIn(b'NumberOfCells="4"', data)
+        self.assertNotIn(b"parent_id", data)
+        os.remove("tmp_tags_bdy.vtu")
+
+        # no element with these tags: nothing is written
+        msh.write_vtk("tmp_tags.vtu", None, None, [10])
+        self.assertFalse(os.path.exists("tmp_tags.vtu"))
+
+    def test_write_precision(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        coords = msh.get_coords()
+        coords += 0.01 * np.sin(np.arange(coords.size)).reshape(coords.shape)
+        msh = Mesh33(
+            coords,
+            msh.get_elems(),
+            msh.get_etags(),
+            msh.get_faces(),
+            msh.get_ftags(),
+        )
+
+        msh.write_meshb("tmp_f64.meshb")
+        msh.write_meshb("tmp_f32.meshb", "f32")
+        self.assertLess(
+            os.path.getsize("tmp_f32.meshb"), os.path.getsize("tmp_f64.meshb")
+        )
+        msh2 = Mesh33.from_meshb("tmp_f32.meshb")
+        eps = np.finfo(np.float32).eps
+        self.assertTrue(np.allclose(msh2.get_coords(), coords, rtol=eps, atol=0))
+        self.assertFalse(np.array_equal(msh2.get_coords(), coords))
+        self.assertTrue(np.array_equal(msh2.get_elems(), msh.get_elems()))
+        self.assertTrue(np.array_equal(msh2.get_etags(), msh.get_etags()))
+        self.assertTrue(np.array_equal(msh2.get_faces(), msh.get_faces()))
+        self.assertTrue(np.array_equal(msh2.get_ftags(), msh.get_ftags()))
+        os.remove("tmp_f64.meshb")
+        os.remove("tmp_f32.meshb")
+
+        f = coords[:, :1].copy()
+        msh.write_vtk("tmp_f64.vtu", {"f": f})
+        msh.write_vtk("tmp_f32.vtu", {"f": f}, precision="f32")
+        self.assertLess(
+            os.path.getsize("tmp_f32.vtu"), os.path.getsize("tmp_f64.vtu")
+        )
+        with open("tmp_f32.vtu", "rb") as fp:
+            data = fp.read()
+        self.assertIn(b'NumberOfCells="%d"' % msh.n_elems(), data)
+        self.assertIn(b'type="Float32" Name="f"', data)
+        os.remove("tmp_f64.vtu")
+        os.remove("tmp_f32.vtu")
+
+        with self.assertRaises(ValueError):
+            msh.write_meshb("tmp_f32.meshb", "f16")
+        with self.assertRaises(ValueError):
+            msh.write_meshb("tmp_f32.mesh", "f32")
+        with self.assertRaises(ValueError):
+            msh.write_vtk("tmp_f32.vtu", {"f": 1e-50 * f}, precision="f32")
+
+        # lossy writes are only allowed with force=True
+        msh = Mesh33(
+            1e40 * coords,
+            msh.get_elems(),
+            msh.get_etags(),
+            msh.get_faces(),
+            msh.get_ftags(),
+        )
+        with self.assertRaises(ValueError):
+            msh.write_meshb("tmp_f32.meshb", "f32")
+        self.assertFalse(os.path.exists("tmp_f32.meshb"))
+        msh.write_meshb("tmp_f32.meshb", "f32", True)
+        self.assertTrue(os.path.exists("tmp_f32.meshb"))
+        os.remove("tmp_f32.meshb")
+
+    def test_concurrent_access(self):
+        import threading
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split().split()
+        n_verts = msh.n_verts()
+        f = msh.get_coords()[:, :1].copy()
+
+        # read-only methods only take shared borrows
+        self.assertTrue(np.allclose(msh.interpolate_linear(msh, f), f))
+        self.assertTrue(np.allclose(msh.interpolate_nearest(msh, f), f))
+
+        errors = []
+
+        def write():
+            try:
+                for _ in range(5):
+                    msh.write_vtk("tmp_thread.vtu", {"f": f})
+            except Exception as e:
+                errors.append(e)
+
+        thread = threading.Thread(target=write)
+        thread.start()
+        while thread.is_alive():
+            self.assertEqual(msh.n_verts(), n_verts)
+            self.assertEqual(msh.get_coords().shape, (n_verts, 3))
+            self.assertTrue(np.allclose(msh.interpolate_linear(msh, f), f))
+            # modifications while the mesh is written raise a RuntimeError
+            try:
+                msh.compute_volumes()
+            except RuntimeError:
+                pass
+        thread.join()
+        self.assertEqual(errors, [])
+        os.remove("tmp_thread.vtu")
+
+    def test_global_ids(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        self.assertIsNone(msh.get_global_ids())
+
+        ids = np.arange(msh.n_verts(), dtype=np.int64) + 100
+        msh.set_global_ids(ids)
+        self.assertTrue(np.array_equal(msh.get_global_ids(), ids))
+
+        (vert_old_to_new, _), _, _, _ = msh.reorder_hilbert()
+        new_ids = msh.get_global_ids()
+        self.assertTrue(np.array_equal(new_ids[vert_old_to_new], ids))
+
+        bdy, bdy_ids = msh.boundary()
+        self.assertTrue(np.array_equal(bdy.get_global_ids(), new_ids[bdy_ids]))
+
+        self.assertIsNone(msh.split().get_global_ids())
+
+        with self.assertRaises(ValueError):
+            msh.set_global_ids(ids[1:])
+
+    def test_set_tags_where_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+
+        coords = msh.get_coords()
+        centers = coords[msh.get_elems()].mean(axis=1)
+        vols = msh.vols()
+        flg = centers[:, 0] < 0.5
+
+        n = msh.set_etags_where(([-1.0, -1.0, -1.0], [0.5, 2.0, 2.0]), 2)
+        self.assertEqual(n, flg.sum())
+        tag_vols = msh.tag_volumes()
+        self.assertAlmostEqual(tag_vols[2], vols[flg].sum())
+        self.assertAlmostEqual(tag_vols[1], vols[~flg].sum())
+
+        n = msh.set_etags_where(lambda x: x[:, 0] > 0.5, 3)
+        self.assertEqual(n, (~flg).sum())
+        tag_vols = msh.tag_volumes()
+        self.assertEqual(set(tag_vols.keys()), {2, 3})
+        self.assertAlmostEqual(tag_vols[2], vols[flg].sum())
+        self.assertAlmostEqual(tag_vols[3], vols[~flg].sum())
+        self.assertAlmostEqual(tag_vols[2] + tag_vols[3], 1.0)
+
+        n = msh.set_ftags_where(lambda x: x[:, 0] < 1e-6, 10)
+        self.assertEqual(n, 32)
+        self.assertEqual((msh.get_ftags() == 10).sum(), 32)
+
+    def test_tag_from_field_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split().split().split()
+        r = 0.15
+        v_ball = 4.0 / 3.0 * np.pi * r**3
+
+        def indicator(x):
+            d0 = np.linalg.norm(x - [0.25, 0.5, 0.5], axis=1)
+            d1 = np.linalg.norm(x - [0.75, 0.5, 0.5], axis=1)
+            return (np.minimum(d0, d1) < r).astype(np.float64).reshape((-1, 1))
+
+        centers = msh.get_coords()[msh.get_elems()].mean(axis=1)
+        f = indicator(centers)
+
+        counts = msh.tag_from_field(f, [0.5], split_components=False)
+        self.assertEqual(set(counts.keys()), {1, 2})
+        self.assertEqual(counts[2], int(f.sum()))
+        tag_vols = msh.tag_volumes()
+        self.assertAlmostEqual(tag_vols[1] + tag_vols[2], 1.0)
+        self.assertTrue(np.allclose(tag_vols[2], 2 * v_ball, rtol=0.3))
+
+        n_faces = msh.n_faces()
+        counts = msh.tag_from_field(f, [0.5], add_faces=True)
+        self.assertEqual(len(counts), 3)
+        self.assertEqual(sum(counts.values()), msh.n_elems())
+        vols = sorted(msh.tag_volumes().values())
+        self.assertTrue(np.allclose(vols[:2], v_ball, rtol=0.3))
+        self.assertAlmostEqual(sum(vols), 1.0)
+        self.assertGreater(msh.n_faces(), n_faces)
+
+        # P1 field
+        counts = msh.tag_from_field(indicator(msh.get_coords()), [0.5])
+        self.assertEqual(len(counts), 3)
+
+        with self.assertRaises(ValueError):
+            msh.tag_from_field(f, [0.5, 0.1])
+
+    def test_boundary_per_tag_3d(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        ftags = msh.get_ftags()
+
+        bdys = msh.boundary_per_tag()
+        self.assertEqual(set(bdys.keys()), {1, 2, 3, 4, 5, 6})
+        for tag, (bdy, vert_ids, face_ids) in bdys.items():
+            self.assertAlmostEqual(bdy.vol(), 1.0)
+            self.assertTrue(np.all(bdy.get_etags() == tag))
+            self.assertTrue(np.array_equal(face_ids, np.nonzero(ftags == tag)[0]))
+            self.assertTrue(
+                np.allclose(bdy.get_coords(), msh.get_coords()[vert_ids, :])
+            )
+
+        bdys = msh.boundary_per_tag(tags=[1, 3])
+        self.assertEqual(set(bdys.keys()), {1, 3})
+
+    def test_collapse_small_edges(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        bdy, _ = msh.boundary()
+
+        coords = bdy.get_coords()
+        elems = bdy.get_elems()
+        edges = np.vstack([elems[:, [0, 1]], elems[:, [1, 2]], elems[:, [2, 0]]])
+
+        # move some vertices inside the cube faces close to one of their neighbors
+        on_bdy = np.logical_or(coords < 1e-6, coords > 1 - 1e-6).sum(axis=1)
+        moved = []
+        for i in np.nonzero(on_bdy == 1)[0]:
+            j = edges[edges[:, 0] == i, 1][0]
+            if i in moved or j in moved:
+                continue
+            coords[i, :] = coords[j, :] + 1e-3 * (coords[i, :] - coords[j, :])
+            moved += [i, j]
+        n_slivers = len(moved) // 2
+        self.assertGreater(n_slivers, 0)
+
+        bdy = Mesh32(coords, elems, bdy.get_etags(), bdy.get_faces(), bdy.get_ftags())
+        self.assertAlmostEqual(bdy.vol(), 6.0)
+
+        new_bdy, n_collapses = bdy.collapse_small_edges(0.05)
+        self.assertEqual(n_collapses, n_slivers)
+        self.assertEqual(new_bdy.n_verts(), bdy.n_verts() - n_slivers)
+        self.assertAlmostEqual(new_bdy.vol(), 6.0)
+        tag_vols = new_bdy.tag_volumes()
+        for tag in range(1, 7):
+            self.assertAlmostEqual(tag_vols[tag], 1.0)
+
+        coords = new_bdy.get_coords()
+        elems = new_bdy.get_elems()
+        for i, j in [(0, 1), (1, 2), (2, 0)]:
+            l = np.linalg.norm(coords[elems[:, j], :] - coords[elems[:, i], :], axis=1)
+            self.assertGreater(l.min(), 0.05)
+
+    def test_version(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        other = Mesh22(coords, elems, etags, faces, ftags)
+        self.assertNotEqual(msh.version(), other.version())
+
+        v = msh.version()
+        msh.compute_topology()
+        self.assertEqual(msh.version(), v)
+        msh.reorder_hilbert()
+        self.assertNotEqual(msh.version(), v)
+
+        self.assertNotEqual(msh.split().version(), msh.version())
+
+    def test_from_sdf_2d(self):
+        r = 0.4
+        h = 0.02
+        msh = Mesh22.from_sdf(
+            lambda x: np.linalg.norm(x, axis=1) - r, ([-0.5, -0.5], [0.5, 0.5]), h
+        )
+        msh.check()
+        self.assertTrue(np.all(msh.vols() > 0))
+        self.assertTrue(np.all(msh.get_ftags() == 1))
+        self.assertLess(abs(msh.vol() - np.pi * r**2), 2 * np.pi * r * h)
+
+        bdy, _ = msh.boundary()
+        r_bdy = np.linalg.norm(bdy.get_coords(), axis=1)
+        self.assertTrue(np.allclose(r_bdy, r, atol=h))
+
+    def test_from_sdf_3d(self):
+        r = 0.4
+        h = 0.05
+        msh = Mesh33.from_sdf(
+            lambda x: np.linalg.norm(x, axis=1) - r,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            h,
+        )
+        msh.check()
+        self.assertTrue(np.all(msh.vols() > 0))
+        self.assertTrue(np.all(msh.get_ftags() == 1))
+        self.assertLess(abs(msh.vol() - 4.0 / 3.0 * np.pi * r**3), 4 * np.pi * r**2 * h)
+
+    def test_mesh_info(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+
+        info = mesh_info(msh)
+        self.assertEqual(
+            set(info.keys()),
+            {
+                "dim",
+                "version",
+                "n_verts",
+                "n_elems",
+                "n_faces",
+                "bounding_box",
+                "volume",
+                "etags",
+                "ftags",
+                "quality",
+                "cache",
+            },
+        )
+        self.assertEqual(info["dim"], 2)
+        self.assertEqual(info["n_verts"], msh.n_verts())
+        self.assertEqual(info["n_elems"], 8)
+        self.assertEqual(info["n_faces"], 10)
+        self.assertTrue(np.allclose(info["bounding_box"], [[0.0, 0.0], [1.0, 1.0]]))
+        self.assertAlmostEqual(info["volume"], 1.0)
+        self.assertEqual(set(info["etags"].keys()), {1, 2})
+        for tag in [1, 2]:
+            self.assertEqual(info["etags"][tag]["count"], 4)
+            self.assertAlmostEqual(info["etags"][tag]["volume"], 0.5)
+        for tag in [1, 2, 3, 4]:
+            self.assertEqual(info["ftags"][tag]["count"], 2)
+            self.assertAlmostEqual(info["ftags"][tag]["volume"], 1.0)
+        self.assertAlmostEqual(info["ftags"][5]["volume"], 2**0.5)
+        self.assertLessEqual(info["quality"]["min"], info["quality"]["mean"])
+        self.assertLessEqual(info["quality"]["mean"], info["quality"]["max"])
+        self.assertFalse(info["cache"]["topology"])
+
+        msh.compute_topology()
+        h = 0.1 * np.ones((msh.n_verts(), 1))
+        info = msh.info(h)
+        self.assertTrue(info["cache"]["topology"])
+        self.assertAlmostEqual(info["metric"]["h_min"], 0.1)
+        self.assertAlmostEqual(info["metric"]["h_max"], 0.1)
+        self.assertAlmostEqual(info["metric"]["anisotropy"], 1.0)
+
+    def test_cache(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_vertex_to_elems()
+        msh.compute_volumes()
+        m = msh.implied_metric()
+        d = np.linalg.norm(msh.get_coords(), axis=1).reshape((-1, 1))
+
+        msh.cache_to("tmp.cache", {"implied_metric": m, "wall_distance": d})
+        other, arrays = Mesh33.load_cached("tmp.cache", msh.content_hash())
+        self.assertEqual(other.content_hash(), msh.content_hash())
+        self.assertTrue(np.array_equal(other.get_coords(), msh.get_coords()))
+        self.assertTrue(np.array_equal(other.get_elems(), msh.get_elems()))
+        self.assertTrue(np.array_equal(other.get_ftags(), msh.get_ftags()))
+        self.assertEqual(set(arrays.keys()), {"implied_metric", "wall_distance"})
+        self.assertTrue(np.array_equal(arrays["implied_metric"], m))
+        self.assertTrue(np.array_equal(arrays["wall_distance"], d))
+
+        with self.assertRaises(ValueError):
+            Mesh33.load_cached("tmp.cache", "0" * 16)
+        other, _ = Mesh33.load_cached("tmp.cache", "0" * 16, force=True)
+        self.assertEqual(other.n_elems(), msh.n_elems())
+
+        with open("tmp.cache", "rb") as f:
+            data = f.read()
+        os.remove("tmp.cache")
+
+        # header: magic, hash, dim, element size, then the vertices and elements
+        offset = 4 * 8
+        n_verts = msh.n_verts()
+        elems_offset = offset + 8 + 3 * 8 * n_verts + 8
+        corrupted = [
+            data[: len(data) // 2],
+            data[:offset] + (2**60).to_bytes(8, "little") + data[offset + 8 :],
+            data[:elems_offset]
+            + (n_verts).to_bytes(np.dtype(IDX_DTYPE).itemsize, "little")
+            + data[elems_offset + np.dtype(IDX_DTYPE).itemsize :],
+        ]
+        for data in corrupted:
+            with open("tmp.cache", "wb") as f:
+                f.write(data)
+            with self.assertRaises(OSError):
+                Mesh33.load_cached("tmp.cache", force=True)
+            os.remove("tmp.cache")
+
+    def test_trees(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        other = Mesh33(coords, elems, etags, faces, ftags).split().split().split()
+
+        x, y, z = msh.get_coords().T
+        f = np.stack([x + 2 * y - z, x * y], axis=-1)
+
+        elem_tree = msh.build_elem_tree()
+        vert_tree = msh.build_vert_tree()
+        self.assertEqual(elem_tree.version(), msh.version())
+        for _ in range(3):
+            g = msh.interpolate_linear(other, f, tree=elem_tree)
+            self.assertTrue(np.array_equal(g, msh.interpolate_linear(other, f)))
+            g = msh.interpolate_nearest(other, f, tree=vert_tree)
+            self.assertTrue(np.array_equal(g, msh.interpolate_nearest(other, f)))
+
+        with self.assertRaises(ValueError):
+            other.interpolate_linear(
+                msh, np.ones((other.n_verts(), 1)), tree=elem_tree
+            )
+
+        msh.reorder_hilbert()
+        with self.assertRaises(ValueError):
+            msh.interpolate_nearest(other, f, tree=vert_tree)
+
+    def test_octree(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        other = msh.split()
+        f = np.linalg.norm(msh.get_coords(), axis=1).reshape((-1, 1))
+
+        self.assertFalse(msh.has_octree())
+        res = msh.interpolate_linear(other, f)
+
+        msh.compute_octree()
+        self.assertTrue(msh.has_octree())
+        self.assertTrue(np.array_equal(msh.interpolate_linear(other, f), res))
+
+        msh.clear_octree()
+        self.assertFalse(msh.has_octree())
+
+        # the stored tree is discarded when the mesh is modified
+        msh.compute_octree()
+        msh.reorder_hilbert()
+        self.assertFalse(msh.has_octree())
+
+    def test_permutations(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        ref = Mesh33(coords, elems, etags, faces, ftags).split()
+        self.assertTrue(msh == ref)
+
+        rng = np.random.default_rng(0)
+        for n, apply in [
+            (msh.n_verts(), msh.apply_vertex_permutation),
+            (msh.n_elems(), msh.apply_elem_permutation),
+            (msh.n_faces(), msh.apply_face_permutation),
+        ]:
+            perm = rng.permutation(n).astype(IDX_DTYPE)
+            apply(perm)
+            self.assertFalse(msh == ref)
+            inv = np.empty_like(perm)
+            inv[perm] = np.arange(n, dtype=IDX_DTYPE)
+            apply(inv)
+            self.assertTrue(msh == ref)
+
+        coords = msh.get_coords()
+        perm = rng.permutation(msh.n_verts()).astype(IDX_DTYPE)
+        msh.apply_vertex_permutation(perm)
+        self.assertTrue(np.array_equal(msh.get_coords()[perm], coords))
+        msh.check()
+
+        (_, new_to_old), _, _, _ = msh.reorder_hilbert()
+        msh.apply_vertex_permutation(new_to_old)
+        self.assertTrue(np.array_equal(msh.get_coords()[perm], coords))
+
+        with self.assertRaises(ValueError):
+            msh.apply_vertex_permutation(np.zeros(msh.n_verts(), dtype=IDX_DTYPE))
+        with self.assertRaises(ValueError):
+            msh.apply_elem_permutation(np.arange(3, dtype=IDX_DTYPE))
+
+    def test_tag_dtypes(self):
+        coords, elems, etags, faces, ftags = get_square()
+        ref = Mesh22(coords, elems, etags, faces, ftags)
+
+        self.assertEqual(TAG_MIN, np.iinfo(TAG_DTYPE).min)
+        self.assertEqual(TAG_MAX, np.iinfo(TAG_DTYPE).max)
+
+        for dtype in [np.int64, np.int32, np.uint8]:
+            with self.subTest(dtype=dtype.__name__):
+                msh = Mesh22(
+                    coords, elems, etags.astype(dtype), faces, ftags.astype(dtype)
+                )
+                self.assertTrue(np.array_equal(msh.get_etags(), ref.get_etags()))
+                self.assertTrue(np.array_equal(msh.get_ftags(), ref.get_ftags()))
+
+        # TAG_DTYPE is signed, so negative tags are valid
+        msh = Mesh22(coords, elems, -etags.astype(np.int64), faces, ftags)
+        self.assertTrue(np.array_equal(msh.get_etags(), -ref.get_etags()))
+
+        big = ftags.astype(np.int64)
+        big[2] = int(TAG_MAX) + 1
+        with self.assertRaisesRegex(ValueError, f"Tag {int(TAG_MAX) + 1} at row 2"):
+            Mesh22(coords, elems, etags, faces, big)
+        big[2] = int(TAG_MIN) - 1
+        with self.assertRaisesRegex(ValueError, "at row 2 is out of range"):
+            Mesh22(coords, elems, etags, faces, big)
+        with self.assertRaises(TypeError):
+            Mesh22(coords, elems, etags.astype(np.float64), faces, ftags)
+
+        msh = Mesh22.from_basic_elems(
+            coords, tris=elems, tri_tags=etags.astype(np.int64)
+        )
+        self.assertTrue(np.array_equal(msh.get_etags(), ref.get_etags()))
+
+    def test_empty(self):
+        for cls, dim in [(Mesh33, 3), (Mesh32, 3), (Mesh22, 2)]:
+            with self.subTest(cls=cls.__name__):
+                msh = cls.empty()
+                other = cls.empty()
+                f = np.zeros((0, 1))
+                calls = [
+                    ("n_verts", ()),
+                    ("n_elems", ()),
+                    ("n_faces", ()),
+                    ("vol", ()),
+                    ("vols", ()),
+                    ("tag_volumes", ()),
+                    ("get_coords", ()),
+                    ("get_elems", ()),
+                    ("get_etags", ()),
+                    ("get_faces", ()),
+                    ("get_ftags", ()),
+                    ("check", ()),
+                    ("split", ()),
+                    ("compute_topology", ()),
+                    ("compute_volumes", ()),
+                    ("get_vertex_volumes", ()),
+                    ("get_total_volume", ()),
+                    ("write_vtk", ("empty.vtu",)),
+                    ("write_boundary_vtk", ("empty_bdy.vtu",)),
+                    ("elem_data_to_vertex_data", (f,)),
+                    ("vertex_data_to_elem_data", (f,)),
+                    ("interpolate_linear", (other, f)),
+                    ("interpolate_nearest", (other, f)),
+                    ("smooth", (f,)),
+                    ("compute_gradient", (f,)),
+                    ("compute_hessian", (f,)),
+                    ("compute_hessian_l2proj", (f,)),
+                    ("clip_field", (f, None, None, (5.0, 95.0))),
+                    ("reorder_hilbert", ({"f": f},)),
+                ]
+                if cls is not Mesh32:
+                    calls += [
+                        ("boundary", ()),
+                        ("boundary", ("exterior",)),
+                        ("boundary", ("internal",)),
+                    ]
+                for name, args in calls:
+                    with self.subTest(method=name, args=args):
+                        getattr(msh, name)(*args)
+
+                self.assertEqual(msh.get_coords().shape, (0, dim))
+                self.assertEqual(msh.vols().shape, (0,))
+                self.assertEqual(msh.vol(), 0.0)
+                self.assertFalse(os.path.exists("empty.vtu"))
+                self.assertFalse(os.path.exists("empty_bdy.vtu"))
+
+                n = 3 if cls is Mesh22 else 6
+                self.assertEqual(msh.compute_gradient(f).shape, (0, dim))
+                self.assertEqual(msh.compute_hessian(f).shape, (0, n))
+                self.assertEqual(msh.interpolate_linear(other, f).shape, (0, 1))
+
+                res, lo, hi = msh.clip_field(f, None, None, (5.0, 95.0))
+                self.assertEqual(res.shape, (0, 1))
+                self.assertTrue(np.isnan(lo[0]) and np.isnan(hi[0]))
+
+                (new_v, old_v), (new_e, old_e), (new_f, old_f), fields = (
+                    msh.reorder_hilbert({"f": f})
+                )
+                for ids in (new_v, old_v, new_e, old_e, new_f, old_f):
+                    self.assertEqual(ids.shape, (0,))
+                self.assertEqual(fields["f"].shape, (0, 1))
+
+                if cls is not Mesh32:
+                    bdy, ids = msh.boundary()
+                    self.assertEqual(bdy.n_verts(), 0)
+                    self.assertEqual(bdy.n_elems(), 0)
+                    self.assertEqual(ids.shape, (0,))
+
+        # interpolating from an empty mesh to a non-empty mesh is an error
+        msh = Mesh33.empty()
+        other = Mesh33(*get_cube())
+        with self.assertRaises(ValueError):
+            msh.interpolate_linear(other, np.zeros((0, 1)))
+
+    def test_view(self):
+        from concurrent.futures import ThreadPoolExecutor
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        others = [msh.split(), msh.split().split()]
+        f = np.linalg.norm(msh.get_coords(), axis=1).reshape((-1, 1))
+
+        view = msh.view()
+        self.assertEqual(view.n_verts(), msh.n_verts())
+        self.assertEqual(view.n_elems(), msh.n_elems())
+        self.assertAlmostEqual(view.vol(), msh.vol())
+        self.assertTrue(np.array_equal(view.get_coords(), msh.get_coords()))
+        self.assertTrue(np.array_equal(view.get_elems(), msh.get_elems()))
+
+        refs = [msh.interpolate_linear(other, f) for other in others]
+        with ThreadPoolExecutor(max_workers=2) as pool:
+            futures = [
+                pool.submit(view.interpolate_linear, other, f)
+                for _ in range(4)
+                for other in others
+            ]
+            res = [x.result() for x in futures]
+        for i, x in enumerate(res):
+            self.assertTrue(np.allclose(x, refs[i % 2]))
+
+        # the view is not affected by modifications of the mesh
+        coords = view.get_coords()
+        msh.reorder_hilbert()
+        self.assertTrue(np.array_equal(view.get_coords(), coords))
+        self.assertFalse(np.array_equal(msh.get_coords(), coords))
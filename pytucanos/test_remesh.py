@@ -1,19 +1,67 @@
+import os
+import json
+import tempfile
 import numpy as np
 import unittest
 from .mesh import (
     Mesh21,
     Mesh22,
+    Mesh33,
     get_square,
+    get_cube,
 )
-from .geometry import LinearGeometry2d
+from .geometry import LinearGeometry2d, LinearGeometry3d
+from .metric import sym2mat
+from . import VERSION, TUCANOS_VERSION, HAVE_METIS, HAVE_SCOTCH
 from .remesh import (
+    BackgroundMetric3d,
+    load_stats,
+    parallel_level_stats,
+    make_remesher,
+    remesh_group,
+    remesh_sweep,
     Remesher2dIso,
     Remesher2dAniso,
+    Remesher3dIso,
+    Remesher3dAniso,
     ParallelRemesher2dIso,
     ParallelRemesher2dAniso,
+    ParallelRemeshingParams,
+    RemesherParams,
+    available_partitioners,
 )
 
 
+def get_circle(n):
+    """
+    Get a piecewise linear representation of the circle circumscribed to the unit
+    square, with 4 * n edges, and the edge between (0, 0) and (1, 1)
+    """
+
+    theta = 0.25 * np.pi + np.linspace(0, 2 * np.pi, 4 * n + 1)
+    r = 0.5 * 2**0.5
+    x = 0.5 + r * np.cos(theta)
+    y = 0.5 + r * np.sin(theta)
+    coords = np.stack([x, y], axis=-1)
+
+    idx = np.arange(4 * n, dtype=np.uint32)
+    elems = np.stack([idx, idx + 1], axis=-1)
+    elems[-1, 1] = 0
+    etags = np.zeros(4 * n, dtype=np.int16)
+    etags[0 * n : 1 * n] = 3
+    etags[1 * n : 2 * n] = 4
+    etags[2 * n : 3 * n] = 1
+    etags[3 * n : 4 * n] = 2
+
+    elems = np.vstack([elems, np.array([[2 * n, 0]], dtype=np.uint32)])
+    etags = np.append(etags, np.array([5], dtype=np.int16))
+
+    faces = np.zeros((0, 1), dtype=np.uint32)
+    ftags = np.zeros(0, dtype=np.int16)
+
+    return Mesh21(coords, elems, etags, faces, ftags)
+
+
 class TestRemesh(unittest.TestCase):
     @classmethod
     def setUpClass(cls):
@@ -48,6 +96,112 @@ class TestRemesh(unittest.TestCase):
         self.assertGreater(msh.n_verts(), 100)
         self.assertLess(msh.n_verts(), 200)
 
+    def test_2d_pickle(self):
+        import copy
+        import pickle
+
+        msh = Mesh22(*get_square()).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+
+        for other in [pickle.loads(pickle.dumps(remesher)), copy.deepcopy(remesher)]:
+            self.assertIsInstance(other, Remesher2dIso)
+            self.assertTrue(
+                np.array_equal(
+                    other.to_mesh().get_coords(), remesher.to_mesh().get_coords()
+                )
+            )
+            self.assertTrue(np.allclose(other.metric(), remesher.metric()))
+            other.remesh(geom, num_iter=2)
+            other.to_mesh().check()
+            self.assertTrue(np.allclose(other.to_mesh().vol(), 1.0))
+
+        # the input mesh restored by pickle is used for the rollback
+        remesher.remesh(geom, num_iter=1)
+        other = pickle.loads(pickle.dumps(remesher))
+        other.remesh(
+            geom,
+            num_iter=2,
+            accept_if={"min_quality_not_worse": False, "max_distance": -1.0},
+        )
+        self.assertTrue(json.loads(other.summary_json())["rejected"])
+        self.assertEqual(other.n_elems(), msh.n_elems())
+        other.to_mesh().check()
+        other.remesh(geom, num_iter=2)
+        other.to_mesh().check()
+
+    def test_2d_to_mesh_input_first(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.2 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2)
+
+        coords = msh.get_coords()
+        new = remesher.to_mesh()
+        ids = remesher.input_vert_ids()
+        self.assertEqual(ids.shape[0], new.n_verts())
+        # at least the corners are not modified
+        self.assertGreaterEqual((ids >= 0).sum(), 4)
+        flg = ids >= 0
+        self.assertTrue(np.array_equal(new.get_coords()[flg], coords[ids[flg]]))
+
+        ordered = remesher.to_mesh(order="input_first")
+        ordered_ids = remesher.input_vert_ids("input_first")
+        n = (ordered_ids >= 0).sum()
+        self.assertTrue(np.all(ordered_ids[n:] == -1))
+        self.assertTrue(np.all(np.diff(ordered_ids[:n]) > 0))
+        self.assertTrue(np.array_equal(np.sort(ordered_ids), np.sort(ids)))
+        self.assertTrue(
+            np.array_equal(ordered.get_coords()[:n], coords[ordered_ids[:n]])
+        )
+        self.assertEqual(ordered.n_elems(), new.n_elems())
+        self.assertTrue(np.allclose(ordered.vol(), new.vol()))
+        ordered.check()
+
+        with self.assertRaises(ValueError):
+            remesher.to_mesh(order="sorted")
+
+    def test_2d_metric(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        coords = msh.get_coords()
+        h = (0.1 + 0.2 * coords[:, 0]).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2)
+
+        m = remesher.metric()
+        self.assertEqual(m.shape, (remesher.n_verts(), 1))
+        self.assertTrue(np.array_equal(remesher.sizes(), m[:, 0]))
+        ids = remesher.input_vert_ids()
+        flg = ids >= 0
+        self.assertTrue(np.allclose(m[flg], h[ids[flg]]))
+        self.assertTrue(np.all(m >= 0.1 - 1e-12) and np.all(m <= 0.3 + 1e-12))
+
+        m = remesher.metric("input_first")
+        ids = remesher.input_vert_ids("input_first")
+        n = (ids >= 0).sum()
+        self.assertTrue(np.allclose(m[:n], h[ids[:n]]))
+
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / 0.1**2
+        m[:, 1] = 1.0 / (0.1 + 0.2 * coords[:, 0]) ** 2
+        remesher = Remesher2dAniso(msh, geom, m)
+        remesher.remesh(geom, num_iter=2)
+        new_m = remesher.metric()
+        self.assertEqual(new_m.shape, (remesher.n_verts(), 3))
+        ids = remesher.input_vert_ids()
+        flg = ids >= 0
+        self.assertTrue(np.allclose(new_m[flg], m[ids[flg]]))
+
     def test_2d_iso_parallel(self):
         coords, elems, etags, faces, ftags = get_square(two_tags=False)
         msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
@@ -70,6 +224,36 @@ class TestRemesh(unittest.TestCase):
         self.assertGreater(msh.n_verts(), 100)
         self.assertLess(msh.n_verts(), 200)
 
+    def test_partitioners(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+
+        available = available_partitioners()
+        self.assertEqual(available[0], "hilbert")
+        self.assertEqual("scotch" in available, HAVE_SCOTCH)
+        self.assertEqual("metis_kway" in available, HAVE_METIS)
+        self.assertEqual("metis_recursive" in available, HAVE_METIS)
+
+        # hilbert is used by default
+        ref = ParallelRemesher2dIso(msh, "hilbert", 2).partitionned_mesh()
+        part = ParallelRemesher2dIso(msh, None, 2).partitionned_mesh()
+        self.assertTrue(np.array_equal(part.get_etags(), ref.get_etags()))
+
+        msg = f"available: {', '.join(available)}$"
+        if not HAVE_METIS:
+            msg_metis = "^metis not available in this build"
+            with self.assertRaisesRegex(ValueError, msg_metis):
+                ParallelRemesher2dIso(msh, "metis_kway", 2)
+            with self.assertRaisesRegex(ValueError, msg):
+                ParallelRemesher2dIso(msh, "metis_recursive", 2)
+        if not HAVE_SCOTCH:
+            msg_scotch = "^scotch not available in this build"
+            with self.assertRaisesRegex(ValueError, msg_scotch):
+                ParallelRemesher2dIso(msh, "scotch", 2)
+        with self.assertRaisesRegex(ValueError, "Invalid partition type"):
+            ParallelRemesher2dIso(msh, "random", 2)
+
     def test_2d_iso_circle(self):
         coords, elems, etags, faces, ftags = get_square()
         msh = Mesh22(coords, elems, etags, faces, ftags)
@@ -179,6 +363,43 @@ class TestRemesh(unittest.TestCase):
         self.assertGreater(msh.n_verts(), 150)
         self.assertLess(msh.n_verts(), 300)
 
+    def test_2d_hessian_to_metric_regularization(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_topology()
+        x, y = msh.get_coords().T
+
+        # Hessian of (x - 0.5)^3 - y^2 for x > 0.5 (indefinite), zero elsewhere
+        h = np.zeros((msh.n_verts(), 3))
+        flg = x > 0.5
+        h[flg, 0] = 6.0 * (x[flg] - 0.5)
+        h[flg, 1] = -2.0
+        h[flg, 2] = 0.1
+        self.assertTrue(np.any(~flg))
+
+        m = Remesher2dAniso.hessian_to_metric(msh, h, 2)
+        self.assertFalse(np.any(np.isnan(m)))
+        eigvals = np.linalg.eigvalsh(sym2mat(m))
+        self.assertTrue(np.all(eigvals > 0.0))
+
+        # larger floor: larger eigenvalues where the Hessian vanishes
+        eigvals = np.linalg.eigvalsh(sym2mat(Remesher2dAniso.hessian_to_metric(msh, h)))
+        m2 = Remesher2dAniso.hessian_to_metric(msh, h, None, 1e-2)
+        eigvals2 = np.linalg.eigvalsh(sym2mat(m2))
+        self.assertTrue(np.all(eigvals2[~flg, :] > eigvals[~flg, :]))
+        self.assertTrue(np.allclose(eigvals2[flg, 1], eigvals[flg, 1]))
+
+        with self.assertRaises(ValueError):
+            Remesher2dAniso.hessian_to_metric(msh, np.zeros_like(h))
+        with self.assertRaises(ValueError):
+            Remesher2dAniso.hessian_to_metric(msh, h, None, 0.0)
+
+        m, _ = Remesher2dAniso.scale_metric(msh, m, 0.01, 0.3, 500)
+        geom = LinearGeometry2d(msh)
+        remesher = Remesher2dAniso(msh, geom, m)
+        remesher.remesh(geom)
+        self.assertTrue(np.allclose(remesher.to_mesh().vol(), 1.0))
+
     def test_2d_aniso_parallel(self):
         coords, elems, etags, faces, ftags = get_square(two_tags=False)
         msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
@@ -207,3 +428,1244 @@ class TestRemesh(unittest.TestCase):
 
         self.assertGreater(msh.n_verts(), 150)
         self.assertLess(msh.n_verts(), 300)
+
+    def test_3d_iso_internal_interfaces(self):
+        msh = Mesh33.from_sdf(
+            lambda x: np.abs(x).max(axis=1) - 0.5 - 1e-12,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            0.125,
+        )
+        msh.set_etags_where(lambda x: x[:, 0] > 0.0, 2)
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher3dIso(msh, geom, h, internal_interfaces=[(1, 2)])
+        remesher.remesh(geom, num_iter=2)
+
+        msh = remesher.to_mesh()
+        msh.check()
+        self.assertTrue(np.allclose(msh.vol(), 1.0))
+        self.assertTrue(np.array_equal(np.unique(msh.get_etags()), [1, 2]))
+
+        coords = msh.get_coords()
+        faces = msh.get_faces()
+        ifc = faces[msh.get_ftags() != 1]
+        self.assertGreater(ifc.shape[0], 0)
+        self.assertTrue(np.allclose(coords[ifc, 0], 0.0, atol=1e-6))
+
+    def test_3d_iso_internal_interfaces_subset(self):
+        msh = Mesh33.from_sdf(
+            lambda x: np.abs(x).max(axis=1) - 0.5 - 1e-12,
+            ([-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]),
+            0.125,
+        )
+        msh.set_etags_where(lambda x: x[:, 0] > 0.0, 2)
+        msh.set_etags_where(lambda x: x[:, 0] > 0.25, 3)
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher3dIso(msh, geom, h, internal_interfaces=[(1, 2)])
+        with self.assertRaises(ValueError):
+            remesher.remesh(LinearGeometry3d(msh), num_iter=2)
+        remesher.remesh(geom, num_iter=2)
+
+        msh = remesher.to_mesh()
+        msh.check()
+        self.assertTrue(np.allclose(msh.vol(), 1.0))
+        self.assertTrue(np.array_equal(np.unique(msh.get_etags()), [1, 2, 3]))
+
+        # only the interface between tags 1 and 2 is added and preserved
+        coords = msh.get_coords()
+        faces = msh.get_faces()
+        ifc = faces[msh.get_ftags() != 1]
+        self.assertGreater(ifc.shape[0], 0)
+        self.assertTrue(np.allclose(coords[ifc, 0], 0.0, atol=1e-6))
+
+    def test_3d_iso_threads(self):
+        from concurrent.futures import ThreadPoolExecutor
+
+        def run(h):
+            coords, elems, etags, faces, ftags = get_cube()
+            msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+            msh.compute_topology()
+            geom = LinearGeometry3d(msh)
+            remesher = Remesher3dIso(msh, geom, h * np.ones((msh.n_verts(), 1)))
+            remesher.remesh(geom, num_iter=2)
+            return remesher.to_mesh()
+
+        refs = [run(h) for h in [0.1, 0.15]]
+        with ThreadPoolExecutor(max_workers=2) as executor:
+            res = list(executor.map(run, [0.1, 0.15]))
+
+        for msh, ref in zip(res, refs):
+            msh.check()
+            self.assertEqual(msh.n_elems(), ref.n_elems())
+            self.assertTrue(np.allclose(msh.get_coords(), ref.get_coords()))
+            self.assertTrue(np.array_equal(msh.get_elems(), ref.get_elems()))
+
+    def test_2d_aniso_metric_threads(self):
+        from concurrent.futures import ThreadPoolExecutor
+
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_topology()
+
+        def run(hy):
+            m = np.zeros((msh.n_verts(), 3))
+            m[:, 0] = 1.0 / 0.3**2
+            m[:, 1] = 1.0 / hy**2
+            m, _ = Remesher2dAniso.scale_metric(msh, m, 0.01, 0.3, 500)
+            m = Remesher2dAniso.apply_metric_gradation(msh, m, 1.5, 10)
+            c_tags, c = Remesher2dAniso.complexity_per_tag(msh, m)
+            return (
+                m,
+                Remesher2dAniso.metric_info(msh, m),
+                c_tags,
+                c,
+                Remesher2dAniso.conformity_field(msh, m),
+            )
+
+        args = [0.03, 0.05, 0.1]
+        refs = [run(hy) for hy in args]
+        with ThreadPoolExecutor(max_workers=3) as executor:
+            res = list(executor.map(run, args))
+
+        for (m, info, c_tags, c, err), ref in zip(res, refs):
+            self.assertTrue(np.array_equal(m, ref[0]))
+            self.assertEqual(info, ref[1])
+            self.assertEqual(c_tags, ref[2])
+            self.assertEqual(c, ref[3])
+            self.assertTrue(np.array_equal(err, ref[4]))
+
+    def test_3d_iso_max_n_elems(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+
+        h = 1e-6 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = Remesher3dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4, max_n_elems=10000)
+
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["truncated"])
+        self.assertGreater(summary["rejected_ops"]["max_n_elems"], 0)
+
+        msh = remesher.to_mesh()
+        msh.check()
+        self.assertTrue(np.allclose(msh.vol(), 1.0))
+        self.assertLessEqual(msh.n_elems(), 10000)
+
+    def test_2d_iso_max_n_elems_params(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 1e-6 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        params = RemesherParams(num_iter=2, max_n_elems=1000)
+        self.assertEqual(params.max_n_elems, 1000)
+        self.assertIn("max_n_elems=1000", repr(params))
+        self.assertIsNone(RemesherParams().max_n_elems)
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, params=params)
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["truncated"])
+        self.assertEqual(summary["provenance"]["controls"]["max_n_elems"], 1000)
+        self.assertLessEqual(remesher.to_mesh().n_elems(), 1000)
+
+        # the keyword arguments take precedence
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, params=params, max_n_elems=500)
+        summary = json.loads(remesher.summary_json())
+        self.assertEqual(summary["provenance"]["controls"]["max_n_elems"], 500)
+        self.assertLessEqual(remesher.to_mesh().n_elems(), 500)
+
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        (other, info) = remesher.remesh(geom, h, params=params)
+        self.assertTrue(json.loads(info)["truncated"])
+        self.assertLessEqual(other.n_elems(), 1000)
+
+    def test_2d_iso_parallel_max_n_elems(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 1e-6 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        (msh, info) = remesher.remesh(geom, h, max_n_elems=1000)
+
+        info = json.loads(info)
+        self.assertTrue(info["truncated"])
+        self.assertGreater(info["rejected_ops"]["max_n_elems"], 0)
+
+        msh.check()
+        self.assertTrue(np.allclose(msh.vol(), 1.0))
+        self.assertLessEqual(msh.n_elems(), 1000)
+
+    def test_2d_iso_parallel_levels(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        (msh, info) = remesher.remesh(geom, h, num_iter=4, n_levels=2)
+
+        info = json.loads(info)
+        self.assertGreater(info["elapsed"], 0.0)
+        levels = info["levels"]
+        self.assertGreaterEqual(len(levels), 2)
+        self.assertEqual([level["level"] for level in levels], list(range(len(levels))))
+        self.assertEqual(levels[0]["n_partitions"], 2)
+        for level in levels:
+            self.assertGreater(level["max_partition_time"], 0.0)
+            times = level["partition_time"]
+            self.assertLessEqual(times["min"], times["mean"])
+            self.assertLessEqual(times["mean"], times["max"])
+            self.assertAlmostEqual(level["max_partition_time"], times["max"])
+        # the keys parsed from the tucanos statistics
+        for level in levels[:-1]:
+            self.assertGreater(level["n_interface_elems"], 0)
+        self.assertIsNone(levels[-1]["n_interface_elems"])
+        total = sum(level["max_partition_time"] for level in levels)
+        self.assertLessEqual(total, info["elapsed"])
+
+        stats = parallel_level_stats(info)
+        self.assertEqual(len(stats), len(levels))
+        self.assertEqual(stats[0].n_partitions, 2)
+        self.assertEqual(stats[0].max_partition_time, levels[0]["max_partition_time"])
+        self.assertEqual(stats[0].n_interface_elems, levels[0]["n_interface_elems"])
+
+    def test_2d_iso_parallel_progress(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            fname = os.path.join(tmp_dir, "progress.json")
+            remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+            remesher.remesh(
+                geom, h, num_iter=4, progress_json=fname, progress_interval=0.01
+            )
+
+            self.assertTrue(os.path.exists(fname))
+            with open(fname) as f:
+                progress = json.load(f)
+
+        self.assertEqual(progress["n_partitions"], 2)
+        self.assertEqual(progress["phase"], "done")
+        self.assertGreater(progress["elapsed"], 0.0)
+        self.assertEqual(len(progress["partitions"]), 2)
+        self.assertEqual(
+            sorted(p["partition"] for p in progress["partitions"]), [0, 1]
+        )
+        self.assertEqual(
+            sum(p["n_elems_init"] for p in progress["partitions"]), msh.n_elems()
+        )
+        for p in progress["partitions"]:
+            self.assertEqual(p["phase"], "done")
+            self.assertGreater(p["time"], 0.0)
+
+        for interval in [0.0, -1.0, np.nan]:
+            with self.assertRaises(ValueError):
+                remesher.remesh(
+                    geom, h, progress_json=fname, progress_interval=interval
+                )
+
+    def test_2d_iso_parallel_from_partitioned(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=True)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 3)
+        part = remesher.partitionned_mesh()
+        self.assertEqual(set(part.get_etags()), {1, 2, 3})
+        restored = ParallelRemesher2dIso.from_partitioned(part)
+        self.assertTrue(
+            np.array_equal(restored.partitionned_mesh().get_etags(), part.get_etags())
+        )
+
+        (ref, _) = remesher.remesh(geom, h, num_iter=4, n_levels=2)
+        (msh, _) = restored.remesh(geom, h, num_iter=4, n_levels=2)
+        self.assertTrue(np.allclose(msh.vol(), 1.0))
+        self.assertLess(abs(msh.n_verts() - ref.n_verts()), 0.1 * ref.n_verts())
+
+        # the physical element tags are lost: those of the partitionned mesh are used
+        self.assertEqual(set(ref.get_etags()), {1, 2})
+        self.assertEqual(set(msh.get_etags()), {1, 2, 3})
+
+        coords, elems = part.get_coords(), part.get_elems()
+        faces, ftags = part.get_faces(), part.get_ftags()
+
+        # missing partition id
+        etags = part.get_etags()
+        etags[etags == etags.max()] += 1
+        with self.assertRaisesRegex(ValueError, "missing"):
+            ParallelRemesher2dIso.from_partitioned(
+                Mesh22(coords, elems, etags, faces, ftags)
+            )
+
+        # consecutive tags that do not start at 1
+        etags = part.get_etags() + 4
+        with self.assertRaisesRegex(ValueError, "start at 5"):
+            ParallelRemesher2dIso.from_partitioned(
+                Mesh22(coords, elems, etags, faces, ftags)
+            )
+
+    def test_2d_iso_max_geom_deviation(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh, get_circle(20))
+
+        h = 0.05 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4)
+        msh = remesher.to_mesh()
+        msh.compute_topology()
+
+        # coarsen
+        h = 0.5 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher_ref = Remesher2dIso(msh, geom, h)
+        remesher_ref.remesh(geom, num_iter=4)
+        d_ref = geom.max_distance(remesher_ref.to_mesh())
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4, max_geom_deviation=0.01)
+        d = geom.max_distance(remesher.to_mesh())
+
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(np.allclose(summary["geom_deviation"], d))
+        self.assertTrue(summary["geom_deviation_exceeded"])
+        self.assertGreater(summary["rejected_ops"]["max_geom_deviation"], 0)
+        self.assertGreater(d_ref, 0.01)
+        self.assertLessEqual(d, 0.01)
+        self.assertGreater(remesher.n_verts(), remesher_ref.n_verts())
+        remesher.to_mesh().check()
+
+    def test_2d_iso_parallel_max_geom_deviation(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags)
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh, get_circle(20))
+
+        h = 0.05 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4)
+        msh = remesher.to_mesh()
+        msh.compute_topology()
+
+        # coarsen
+        h = 0.5 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        (ref, _) = remesher.remesh(geom, h, num_iter=4)
+        self.assertGreater(geom.max_distance(ref), 0.01)
+
+        (msh, info) = remesher.remesh(geom, h, num_iter=4, max_geom_deviation=0.01)
+        info = json.loads(info)
+        d = geom.max_distance(msh)
+        self.assertLessEqual(d, 0.01)
+        self.assertTrue(np.allclose(info["geom_deviation"], d))
+        self.assertGreater(info["rejected_ops"]["max_geom_deviation"], 0)
+        msh.check()
+
+    def test_2d_iso_accept_if(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.05 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        # impossible threshold
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2, accept_if={"max_distance": -1.0})
+        self.assertTrue(json.loads(remesher.summary_json())["rejected"])
+        new_msh = remesher.to_mesh()
+        self.assertEqual(new_msh.n_verts(), msh.n_verts())
+        self.assertEqual(new_msh.n_elems(), msh.n_elems())
+        self.assertTrue(np.allclose(new_msh.get_coords(), msh.get_coords()))
+        self.assertTrue(np.array_equal(new_msh.get_elems(), msh.get_elems()))
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(
+            geom,
+            num_iter=2,
+            accept_if={"min_quality_not_worse": False, "max_distance": 1.0},
+        )
+        self.assertFalse(json.loads(remesher.summary_json())["rejected"])
+        self.assertGreater(remesher.n_elems(), msh.n_elems())
+
+        with self.assertRaises(ValueError):
+            remesher.remesh(geom, accept_if={"min_q": 0.1})
+
+    def test_2d_iso_tag_removal(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh = msh.split().split()
+        msh.set_etags_where(lambda x: x[:, 0] < 0.015, 2)
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+        n_thin = int(np.sum(msh.get_etags() == 2))
+        self.assertGreater(n_thin, 0)
+
+        h = 0.5 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2)
+        summary = json.loads(remesher.summary_json())
+        self.assertFalse(summary["rejected"])
+        self.assertGreater(summary["rejected_ops"]["tag_removal"], 0)
+        n_final = summary["tag_counts"]["2"][1]
+        self.assertEqual(summary["tag_counts"]["2"][0], n_thin)
+        self.assertGreater(n_final, 0)
+        new_msh = remesher.to_mesh()
+        new_msh.check()
+        self.assertIn(2, new_msh.get_etags())
+        self.assertEqual(int(np.sum(new_msh.get_etags() == 2)), n_final)
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2, allow_tag_removal=True)
+        summary = json.loads(remesher.summary_json())
+        self.assertFalse(summary["rejected"])
+        self.assertEqual(summary["tag_counts"]["2"], [n_thin, 0])
+        self.assertNotIn(2, remesher.to_mesh().get_etags())
+
+    def test_2d_iso_tag_protection_baseline(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        # no tag is removed: the protection does not change the default remeshing
+        baseline = Remesher2dIso(msh, geom, h)
+        baseline.remesh(geom, num_iter=2, allow_tag_removal=True)
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2)
+
+        summary = json.loads(remesher.summary_json())
+        self.assertNotIn("tag_removal", summary["rejected_ops"])
+        self.assertEqual(remesher.n_elems(), baseline.n_elems())
+        self.assertTrue(
+            np.array_equal(
+                remesher.to_mesh().get_elems(), baseline.to_mesh().get_elems()
+            )
+        )
+        self.assertEqual(
+            len(json.loads(remesher.stats_json())),
+            len(json.loads(baseline.stats_json())),
+        )
+
+    def test_2d_iso_target_quality(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = Remesher2dIso(msh, geom, h)
+        self.assertAlmostEqual(remesher.min_quality(), remesher.qualities().min())
+
+        remesher.remesh(geom, num_iter=4, target_min_quality=0.01)
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["early_exit"])
+        self.assertLess(summary["n_iter"], 4)
+        self.assertGreaterEqual(remesher.min_quality(), 0.01)
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4, target_mean_quality=1.1)
+        summary = json.loads(remesher.summary_json())
+        self.assertFalse(summary["early_exit"])
+        self.assertEqual(summary["n_iter"], 4)
+
+        params = RemesherParams(num_iter=4, target_min_quality=0.01)
+        self.assertEqual(params.target_min_quality, 0.01)
+        self.assertIsNone(params.target_mean_quality)
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, params=params)
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["early_exit"])
+        self.assertEqual(summary["provenance"]["controls"]["target_min_quality"], 0.01)
+
+        # the keyword arguments take precedence
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, params=params, target_min_quality=1.1)
+        summary = json.loads(remesher.summary_json())
+        self.assertFalse(summary["early_exit"])
+
+    def test_2d_iso_max_seconds(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.05 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4, max_seconds=1e-6)
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["timed_out"])
+        self.assertEqual(summary["n_iter"], 1)
+        new = remesher.to_mesh()
+        new.check()
+        self.assertTrue(np.allclose(new.vol(), 1.0))
+
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4, max_seconds=1e6)
+        summary = json.loads(remesher.summary_json())
+        self.assertFalse(summary["timed_out"])
+        self.assertEqual(summary["n_iter"], 4)
+
+        with self.assertRaises(ValueError):
+            remesher.remesh(geom, max_seconds=-1.0)
+        with self.assertRaises(ValueError):
+            remesher.remesh(geom, max_seconds=0.0)
+
+        # the budget is also checked between the passes
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(
+            geom, num_iter=2, max_seconds=1e-6, pre_passes=[{"num_iter": 1}]
+        )
+        summary = json.loads(remesher.summary_json())
+        self.assertTrue(summary["timed_out"])
+        self.assertEqual(len(summary["passes"]), 1)
+        self.assertIsNone(summary["n_iter"])
+        remesher.to_mesh().check()
+
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        with self.assertRaises(NotImplementedError):
+            remesher.remesh(geom, h, max_seconds=1.0)
+
+    def test_2d_iso_debug_dir(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        with tempfile.TemporaryDirectory() as tmp:
+            debug_dir = os.path.join(tmp, "debug", "run")
+            remesher = Remesher2dIso(msh, geom, h)
+            remesher.remesh(
+                geom,
+                num_iter=2,
+                debug=True,
+                debug_dir=debug_dir,
+                pre_passes=[{"num_iter": 1}],
+            )
+            remesher.to_mesh().check()
+
+            default_params = remesher.default_params()
+            ops = [
+                op
+                for op, key in [
+                    ("split", "split_max_iter"),
+                    ("collapse", "collapse_max_iter"),
+                    ("swap", "swap_max_iter"),
+                    ("smooth", "smooth_iter"),
+                ]
+                if default_params[key] > 0
+            ]
+            names = ["input.vtu"]
+            for prefix in ["pass00_iter000", "pass01_iter000", "pass01_iter001"]:
+                names += [f"{prefix}_{op}.vtu" for op in ops]
+            names.append("output.vtu")
+            expected = [os.path.join(debug_dir, name) for name in names]
+            summary = json.loads(remesher.summary_json())
+            self.assertEqual(summary["debug_files"], expected)
+            self.assertEqual(sorted(os.listdir(debug_dir)), sorted(names))
+
+            other = Remesher2dIso(msh, geom, h)
+            other.remesh(geom, num_iter=2, debug=True)
+            self.assertNotIn("debug_files", json.loads(other.summary_json()))
+
+            with self.assertRaises(ValueError):
+                remesher.remesh(geom, debug=False, debug_dir=debug_dir)
+
+    def test_2d_params(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+
+        params = RemesherParams()
+        for k, v in remesher.default_params().items():
+            if k == "smooth_relax":
+                self.assertTrue(np.array_equal(params.smooth_relax, v))
+            else:
+                self.assertEqual(getattr(params, k), v)
+        self.assertTrue(repr(params).startswith("RemesherParams(num_iter="))
+
+        params = RemesherParams(num_iter=1, smooth_type="laplacian2")
+        params.split_min_q_abs = 0.123
+        with self.assertRaisesRegex(ValueError, "Invalid smoothing type"):
+            params.smooth_type = "unknown"
+        with self.assertRaises(ValueError):
+            RemesherParams(smooth_type="unknown")
+
+        with self.assertRaisesRegex(ValueError, "Invalid smoothing type"):
+            remesher.remesh(geom, smooth_type="unknown")
+
+        remesher.remesh(geom, params=params, swap_max_iter=0)
+        provenance = json.loads(remesher.summary_json())["provenance"]["params"]
+        self.assertEqual(provenance["num_iter"], 1)
+        self.assertEqual(provenance["split_min_q_abs"], 0.123)
+        self.assertEqual(provenance["smooth_type"], "laplacian2")
+        self.assertEqual(provenance["swap_max_iter"], 0)
+        self.assertNotEqual(params.swap_max_iter, 0)
+
+        dd_params = ParallelRemeshingParams(n_levels=2)
+        self.assertEqual(
+            (dd_params.n_layers, dd_params.n_levels, dd_params.min_verts), (2, 2, 0)
+        )
+        self.assertEqual(
+            repr(dd_params),
+            "ParallelRemeshingParams(n_layers=2, n_levels=2, min_verts=0)",
+        )
+        remesher = ParallelRemesher2dIso(msh, "hilbert", 2)
+        with self.assertRaisesRegex(ValueError, "Invalid smoothing type"):
+            remesher.remesh(geom, h, smooth_type="unknown")
+        with self.assertRaises(NotImplementedError):
+            remesher.remesh(geom, h, target_min_quality=0.1)
+        with self.assertRaises(NotImplementedError):
+            remesher.remesh(geom, h, params=RemesherParams(target_mean_quality=0.1))
+        (other, info) = remesher.remesh(
+            geom, h, params=RemesherParams(num_iter=2), parallel_params=dd_params
+        )
+        self.assertTrue(np.allclose(other.vol(), 1.0))
+        self.assertGreaterEqual(len(json.loads(info)["levels"]), 2)
+
+    def test_2d_save_stats(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        default_params = remesher.default_params()
+        remesher.remesh(
+            geom,
+            num_iter=1,
+            split_min_q_abs=0.123,
+            smooth_type="laplacian2",
+            max_n_elems=10000,
+            pre_passes=[{"smooth_iter": 0}],
+        )
+        remesher.save_stats("tmp_stats.json")
+        stats = load_stats("tmp_stats.json")
+        os.remove("tmp_stats.json")
+
+        self.assertEqual(stats["stats"], json.loads(remesher.stats_json()))
+        self.assertEqual(stats["summary"], json.loads(remesher.summary_json()))
+
+        provenance = stats["summary"]["provenance"]
+        params = provenance["params"]
+        self.assertEqual(set(params), set(default_params))
+        self.assertEqual(params["num_iter"], 1)
+        self.assertEqual(params["split_min_q_abs"], 0.123)
+        self.assertEqual(params["smooth_type"], "laplacian2")
+        self.assertEqual(params["swap_max_iter"], default_params["swap_max_iter"])
+        self.assertEqual(len(provenance["pre_passes"]), 1)
+        self.assertEqual(provenance["pre_passes"][0]["smooth_iter"], 0)
+        self.assertEqual(provenance["pre_passes"][0]["split_min_q_abs"], 0.123)
+        self.assertEqual(provenance["controls"]["max_n_elems"], 10000)
+        self.assertIsNone(provenance["controls"]["max_seconds"])
+
+        self.assertEqual(provenance["input_hash"], msh.content_hash())
+        c = Remesher2dIso.metric_info(msh, h)[3]
+        self.assertAlmostEqual(provenance["metric_info"]["complexity"], c)
+        self.assertEqual(
+            provenance["versions"],
+            {"pytucanos": VERSION, "tucanos": TUCANOS_VERSION},
+        )
+
+    def test_2d_iso_pre_passes(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones(msh.n_verts()).reshape((-1, 1))
+
+        # two_steps is a shortcut for a pre-pass
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=3, two_steps=True)
+        ref = remesher.to_mesh()
+
+        remesher = Remesher2dIso(msh, geom, h)
+        pre_pass = {"num_iter": 1, "two_steps": True}
+        remesher.remesh(geom, num_iter=2, two_steps=False, pre_passes=[pre_pass])
+        new = remesher.to_mesh()
+        self.assertEqual(new.n_elems(), ref.n_elems())
+        self.assertTrue(np.allclose(new.get_coords(), ref.get_coords()))
+
+        summary = json.loads(remesher.summary_json())
+        self.assertEqual(len(summary["passes"]), 2)
+        self.assertEqual([p["num_iter"] for p in summary["passes"]], [1, 2])
+        self.assertEqual(summary["passes"][-1]["n_elems"], new.n_elems())
+
+        # collapse-only pre-pass
+        remesher = Remesher2dIso(msh, geom, h)
+        pre_pass = {
+            "num_iter": 2,
+            "split_max_iter": 0,
+            "swap_max_iter": 0,
+            "smooth_iter": 0,
+            "collapse_min_q_abs": 0.1,
+        }
+        remesher.remesh(geom, num_iter=0, pre_passes=[pre_pass])
+        summary = json.loads(remesher.summary_json())
+        self.assertEqual(len(summary["passes"]), 2)
+        n_elems = summary["passes"][0]["n_elems"]
+        self.assertLess(n_elems, msh.n_elems())
+        self.assertEqual(summary["passes"][1]["n_elems"], n_elems)
+        self.assertEqual(remesher.n_elems(), n_elems)
+        remesher.check()
+
+        # no breakdown without pre-passes
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=1)
+        self.assertNotIn("passes", json.loads(remesher.summary_json()))
+
+        with self.assertRaises(ValueError):
+            remesher.remesh(geom, pre_passes=[{"invalid": 1}])
+
+    def test_size_achievement(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        h = 0.1 * np.ones((msh.n_verts(), 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4)
+        ratio = remesher.size_achievement()
+        self.assertEqual(ratio.shape, (remesher.n_elems(),))
+        self.assertLess(abs(np.median(ratio) - 1.0), 0.15)
+
+        # the elements are not refined if the splits are disabled
+        h = 0.02 * np.ones((msh.n_verts(), 1))
+        remesher = Remesher2dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=2, split_max_iter=0)
+        ratio = remesher.size_achievement()
+        self.assertGreater(np.min(ratio), 2.0)
+
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / 0.1**2
+        m[:, 1] = 1.0 / 0.05**2
+        remesher = Remesher2dAniso(msh, geom, m)
+        remesher.remesh(geom, num_iter=4)
+        ratio = remesher.size_achievement()
+        self.assertEqual(ratio.shape, (remesher.n_elems(),))
+        self.assertLess(abs(np.median(ratio) - 1.0), 0.2)
+
+    def test_conformity_field(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        def aniso(n):
+            m = np.zeros((n, 3))
+            m[:, 0] = 1.0 / 0.1**2
+            m[:, 1] = 1.0 / 0.02**2
+            return m
+
+        remesher = Remesher2dAniso(msh, geom, aniso(msh.n_verts()))
+        remesher.remesh(geom, num_iter=4)
+        new = remesher.to_mesh()
+        err = Remesher2dAniso.conformity_field(new, aniso(new.n_verts()))
+        self.assertEqual(err.shape, (new.n_elems(),))
+        self.assertTrue(np.all(err >= 0.0) and np.all(err <= 1.0))
+        self.assertLess(np.median(err), 0.4)
+        err = Remesher2dAniso.conformity_field(new, 100.0 * aniso(new.n_verts()))
+        self.assertGreater(np.median(err), 0.8)
+
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+        h = 0.2 * np.ones((msh.n_verts(), 1))
+        remesher = Remesher3dIso(msh, geom, h)
+        remesher.remesh(geom, num_iter=4)
+        new = remesher.to_mesh()
+        h = 0.2 * np.ones((new.n_verts(), 1))
+        err = Remesher3dIso.conformity_field(new, h)
+        self.assertEqual(err.shape, (new.n_elems(),))
+        self.assertLess(np.median(err), 0.4)
+        err = Remesher3dIso.conformity_field(new, 0.02 * np.ones_like(h))
+        self.assertGreater(np.median(err), 0.8)
+
+        with self.assertRaises(ValueError):
+            Remesher3dAniso.conformity_field(new, h)
+
+    def test_2d_aniso_parallel_complexity(self):
+        coords, elems, etags, faces, ftags = get_square(two_tags=False)
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        hx = 0.3
+        hy = 0.03
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / hx**2
+        m[:, 1] = 1.0 / hy**2
+
+        remesher = Remesher2dAniso(msh, geom, m)
+        c_ref = remesher.complexity()
+
+        dd = ParallelRemesher2dAniso(msh, "hilbert", 2)
+        c = dd.complexity(m)
+        self.assertTrue(np.allclose(c, c_ref))
+        self.assertTrue(np.allclose(c, 4.0 / 3.0**0.5 / (hx * hy)))
+
+        info = dd.metric_info(m)
+        info_ref = Remesher2dAniso.metric_info(msh, m)
+        self.assertTrue(np.allclose(info, info_ref))
+
+        with self.assertRaises(ValueError):
+            dd.complexity(m[:, :1])
+
+    def test_2d_aniso_complexity_per_tag(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        hx = 0.3
+        hy = 0.03
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / hx**2
+        m[:, 1] = 1.0 / hy**2
+
+        c_tags, c = Remesher2dAniso.complexity_per_tag(msh, m)
+        self.assertEqual(set(c_tags.keys()), {1, 2})
+        self.assertAlmostEqual(sum(c_tags.values()), c)
+        self.assertTrue(np.allclose(c, Remesher2dAniso(msh, geom, m).complexity()))
+        self.assertTrue(np.allclose(c, 4.0 / 3.0**0.5 / (hx * hy)))
+
+        # scaling the metric by s scales the complexity by s^(d/2)
+        c_tags_2, _ = Remesher2dAniso.complexity_per_tag(msh, 4.0 * m)
+        for tag in [1, 2]:
+            self.assertAlmostEqual(c_tags_2[tag], 4.0 * c_tags[tag])
+
+        # scale the metric at the vertices that only belong to elements with tag 2
+        elems = msh.get_elems()
+        etags = msh.get_etags()
+        only_2 = np.ones(msh.n_verts(), dtype=bool)
+        only_2[elems[etags != 2].ravel()] = False
+        self.assertTrue(only_2.any())
+        m[only_2, :] *= 4.0
+        c_tags_2, c_2 = Remesher2dAniso.complexity_per_tag(msh, m)
+        self.assertAlmostEqual(c_tags_2[1], c_tags[1])
+        self.assertGreater(c_tags_2[2], c_tags[2])
+        self.assertAlmostEqual(c_2, c_tags_2[1] + c_tags_2[2])
+
+    def test_2d_aniso_scale_metric_fixed_etags(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+
+        # boundary layer metric in the elements with tag 1
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / 0.1**2
+        m[:, 1] = 1.0 / 0.1**2
+        elems = msh.get_elems()
+        fixed = np.zeros(msh.n_verts(), dtype=bool)
+        fixed[elems[msh.get_etags() == 1].ravel()] = True
+        m[fixed, 1] = 1.0 / 0.005**2
+
+        n_elems = 5000
+        m_scaled, split = Remesher2dAniso.scale_metric(
+            msh, m, 1e-4, 10.0, n_elems, fixed_etags=[1]
+        )
+        self.assertTrue(np.array_equal(m_scaled[fixed, :], m[fixed, :]))
+        self.assertFalse(np.allclose(m_scaled[~fixed, :], m[~fixed, :]))
+
+        _, c = Remesher2dAniso.complexity_per_tag(msh, m_scaled)
+        self.assertAlmostEqual(split["fixed"] + split["scaled"], c)
+        self.assertTrue(np.allclose(c, n_elems, rtol=1e-2))
+
+        _, split_ref = Remesher2dAniso.scale_metric(msh, m, 1e-4, 10.0, n_elems)
+        self.assertEqual(split_ref["fixed"], 0.0)
+
+        with self.assertRaises(ValueError):
+            Remesher2dAniso.scale_metric(msh, m, 1e-4, 10.0, 10, fixed_etags=[1])
+        with self.assertRaises(ValueError):
+            Remesher2dAniso.scale_metric(
+                msh, m, 1e-4, 10.0, n_elems, fixed_m=m, fixed_etags=[1]
+            )
+
+    def test_2d_metric_from_indicator(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+
+        eta = np.ones((msh.n_elems(), 1))
+        m = Remesher2dIso.metric_from_indicator(msh, eta, 1000, 1e-3, 1.0)
+        self.assertEqual(m.shape, (msh.n_verts(), 1))
+        self.assertTrue(np.allclose(m, m[0, 0]))
+        _, c = Remesher2dIso.complexity_per_tag(msh, m)
+        self.assertTrue(np.allclose(c, 1000, rtol=1e-2))
+
+        with self.assertRaises(ValueError):
+            Remesher2dIso.metric_from_indicator(msh, -eta, 1000, 1e-3, 1.0)
+        with self.assertRaises(ValueError):
+            Remesher2dIso.metric_from_indicator(msh, eta[1:, :], 1000, 1e-3, 1.0)
+        with self.assertRaises(ValueError):
+            Remesher2dIso.metric_from_indicator(msh, eta, 1000, 1e-3, 1.0, 0.0)
+
+    def test_3d_metric_from_indicator(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+
+        # larger errors for x < 0.5
+        centers = msh.get_coords()[msh.get_elems()].mean(axis=1)
+        eta = np.where(centers[:, 0] < 0.5, 10.0, 1.0).reshape((-1, 1))
+        m = Remesher3dIso.metric_from_indicator(msh, eta, 5000, 1e-3, 1.0, p=1.0)
+        self.assertEqual(m.shape, (msh.n_verts(), 1))
+        _, c = Remesher3dIso.complexity_per_tag(msh, m)
+        self.assertTrue(np.allclose(c, 5000, rtol=1e-2))
+
+        x = msh.get_coords()[:, 0]
+        self.assertLess(m[x < 0.2, 0].max(), m[x > 0.8, 0].min())
+
+    def test_3d_metric_from_direction(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+
+        n = msh.n_verts()
+        d = np.random.rand(n, 3) - 0.5
+        d[0, :] = 0.0
+        h_perp = 0.01 + 0.01 * np.random.rand(n)
+        m = Remesher3dAniso.metric_from_direction(msh, 10.0 * d, 0.1, h_perp)
+        self.assertEqual(m.shape, (n, 6))
+
+        eigvals, eigvecs = np.linalg.eigh(sym2mat(m))
+        self.assertTrue(np.allclose(eigvals[:, 1:], 1.0 / h_perp[:, None] ** 2))
+        self.assertTrue(np.allclose(eigvals[1:, 0], 1.0 / 0.1**2))
+        self.assertTrue(np.allclose(eigvals[0, 0], 1.0 / h_perp[0] ** 2))
+
+        d /= np.linalg.norm(d, axis=1)[:, None] + 1e-16
+        dots = np.abs(np.einsum("ij,ij->i", eigvecs[1:, :, 0], d[1:, :]))
+        self.assertTrue(np.allclose(dots, 1.0))
+
+    def test_metric_intersection_union(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        n = msh.n_verts()
+
+        h1 = 0.1 + 0.1 * np.random.rand(n, 1)
+        h2 = 0.1 + 0.1 * np.random.rand(n, 1)
+        m = Remesher3dIso.intersect_metrics(msh, h1, h2)
+        self.assertTrue(np.allclose(m, np.minimum(h1, h2)))
+        m = Remesher3dIso.union_metrics(msh, h1, h2)
+        self.assertTrue(np.allclose(m, np.maximum(h1, h2)))
+
+        ex, ey = np.zeros((n, 3)), np.zeros((n, 3))
+        ex[:, 0] = 1.0
+        ey[:, 1] = 1.0
+        m1 = Remesher3dAniso.metric_from_direction(msh, ex, 0.1, 0.5)
+        m2 = Remesher3dAniso.metric_from_direction(msh, ey, 0.1, 0.5)
+        a, b = 1.0 / 0.1**2, 1.0 / 0.5**2
+        m = Remesher3dAniso.intersect_metrics(msh, m1, m2)
+        self.assertTrue(np.allclose(m, [a, a, b, 0, 0, 0]))
+        m = Remesher3dAniso.union_metrics(msh, m1, m2)
+        self.assertTrue(np.allclose(m, [b, b, b, 0, 0, 0]))
+
+        # the unit ball of the intersection (resp. union) is contained in (resp.
+        # contains) the unit balls of both metrics
+        d = np.random.rand(n, 3) - 0.5
+        m2 = Remesher3dAniso.metric_from_direction(msh, d, 0.05, 0.2)
+        mi = sym2mat(Remesher3dAniso.intersect_metrics(msh, m1, m2))
+        mu = sym2mat(Remesher3dAniso.union_metrics(msh, m1, m2))
+        for mm in [sym2mat(m1), sym2mat(m2)]:
+            self.assertTrue(np.all(np.linalg.eigvalsh(mi - mm) > -1e-8 * mi.max()))
+            self.assertTrue(np.all(np.linalg.eigvalsh(mm - mu) > -1e-8 * mm.max()))
+
+        with self.assertRaises(ValueError):
+            Remesher3dAniso.intersect_metrics(msh, m1, m2[1:, :])
+        with self.assertRaises(ValueError):
+            Remesher3dAniso.union_metrics(msh, m1, m2[:, :3])
+
+    def test_3d_background_metric(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        bg_msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        x = bg_msh.get_coords()
+        n = bg_msh.n_verts()
+        d = np.stack([np.ones(n), x[:, 0], x[:, 1]], axis=-1)
+        m = Remesher3dAniso.metric_from_direction(bg_msh, d, 0.3, 0.1 + 0.1 * x[:, 2])
+        bg = BackgroundMetric3d(bg_msh, m)
+        self.assertEqual(bg.n(), 6)
+
+        self.assertTrue(np.allclose(bg.eval(x), m))
+
+        # log-Euclidean interpolation: the determinant is interpolated geometrically
+        i, j = bg_msh.get_elems()[0, :2]
+        m_mid = bg.eval(0.5 * (x[i : i + 1] + x[j : j + 1]))
+        det = np.linalg.det(sym2mat(np.vstack([m[[i, j]], m_mid])))
+        self.assertTrue(np.allclose(det[2], (det[0] * det[1]) ** 0.5))
+
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+        res = []
+        for metric in [bg, bg.eval(msh.get_coords())]:
+            remesher = Remesher3dAniso(msh, geom, metric)
+            remesher.remesh(geom, num_iter=2)
+            res.append(remesher.to_mesh())
+        self.assertEqual(res[0].n_elems(), res[1].n_elems())
+        self.assertTrue(np.allclose(res[0].get_coords(), res[1].get_coords()))
+
+        with self.assertRaises(ValueError):
+            Remesher3dIso(msh, geom, bg)
+        with self.assertRaises(ValueError):
+            BackgroundMetric3d(bg_msh, -np.ones((n, 1)))
+
+    def test_2d_metric_from_direction(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+
+        n = msh.n_verts()
+        theta = 2.0 * np.pi * np.random.rand(n)
+        d = np.stack([np.cos(theta), np.sin(theta)], axis=-1)
+        h_parallel = 0.1 + 0.1 * np.random.rand(n)
+        m = Remesher2dAniso.metric_from_direction(msh, d, h_parallel, 0.01)
+        self.assertEqual(m.shape, (n, 3))
+
+        eigvals, eigvecs = np.linalg.eigh(sym2mat(m))
+        self.assertTrue(np.allclose(eigvals[:, 0], 1.0 / h_parallel**2))
+        self.assertTrue(np.allclose(eigvals[:, 1], 1.0 / 0.01**2))
+        dots = np.abs(np.einsum("ij,ij->i", eigvecs[:, :, 0], d))
+        self.assertTrue(np.allclose(dots, 1.0))
+
+    def test_3d_mesh_metric_methods(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        x, y, z = msh.get_coords().T
+
+        zero = np.zeros(msh.n_verts())
+        h = np.stack([x**2 + 1.0, 2.0 + y * z, 1.0 + z**2, zero, zero, zero], axis=-1)
+        m = Remesher3dAniso.hessian_to_metric(msh, h, 2)
+        self.assertTrue(np.array_equal(msh.metric_from_hessian(h, 2), m))
+
+        m_iso = Remesher3dIso.hessian_to_metric(msh, h[:, :1])
+        self.assertTrue(np.array_equal(msh.metric_from_hessian(h[:, :1]), m_iso))
+
+        args = (0.01, 1.0, 1000)
+        self.assertTrue(
+            np.array_equal(
+                msh.scale_metric(m, *args)[0],
+                Remesher3dAniso.scale_metric(msh, m, *args)[0],
+            )
+        )
+        self.assertTrue(
+            np.array_equal(
+                msh.scale_metric(m_iso, *args)[0],
+                Remesher3dIso.scale_metric(msh, m_iso, *args)[0],
+            )
+        )
+
+        self.assertTrue(
+            np.array_equal(
+                msh.apply_gradation(m, 1.5, 10),
+                Remesher3dAniso.apply_metric_gradation(msh, m, 1.5, 10),
+            )
+        )
+
+        with self.assertRaises(ValueError):
+            msh.metric_from_hessian(h[:, :3])
+
+    def test_2d_mesh_metric_methods(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+
+        m = np.ones((msh.n_verts(), 3))
+        m[:, 2] = 0.0
+        self.assertTrue(
+            np.array_equal(
+                msh.apply_gradation(m, 1.5, 10),
+                Remesher2dAniso.apply_metric_gradation(msh, m, 1.5, 10),
+            )
+        )
+        m_iso = np.ones((msh.n_verts(), 1))
+        self.assertTrue(
+            np.array_equal(
+                msh.scale_metric(m_iso, 0.01, 1.0, 100)[0],
+                Remesher2dIso.scale_metric(msh, m_iso, 0.01, 1.0, 100)[0],
+            )
+        )
+
+    def test_2d_metric_mesh_mismatch(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+        h = 0.1 * np.ones((msh.n_verts(), 1))
+
+        fine = msh.split()
+        fine.compute_topology()
+        msg = (
+            f"Invalid dimension 0 for m: {msh.n_verts()} values, but the mesh "
+            f"\\(version {fine.version()}\\) has {fine.n_verts()} vertices"
+        )
+        with self.assertRaisesRegex(ValueError, msg):
+            Remesher2dIso(fine, geom, h)
+        with self.assertRaisesRegex(ValueError, msg):
+            Remesher2dIso.scale_metric(fine, h, 0.01, 1.0, 100)
+        with self.assertRaisesRegex(ValueError, msg):
+            fine.apply_gradation(h, 1.5, 10)
+
+    def test_check_gradation(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split().split()
+        msh.compute_topology()
+
+        # sizes varying much faster than beta allows
+        x = msh.get_coords()
+        h = 0.001 + 0.2 * (x[:, 0] > 0.5).astype(np.float64).reshape((-1, 1))
+        max_beta, edges = Remesher2dIso.check_gradation(msh, h, 1.5)
+        self.assertGreater(max_beta, 1.5)
+        self.assertGreater(edges.shape[0], 0)
+        self.assertEqual(edges.shape[1], 2)
+        self.assertTrue(np.all(x[edges[:, 0], 0] != x[edges[:, 1], 0]))
+
+        h = Remesher2dIso.apply_metric_gradation(msh, h, 1.5, 100)
+        max_beta, edges = Remesher2dIso.check_gradation(msh, h, 1.5)
+        self.assertLess(max_beta, 1.6)
+        self.assertEqual(edges.shape, (0, 2))
+
+        # same sizes as an anisotropic metric
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, 0] = 1.0 / h[:, 0] ** 2
+        m[:, 1] = 1.0 / h[:, 0] ** 2
+        max_beta, edges = Remesher2dAniso.check_gradation(msh, m, 1.5)
+        self.assertEqual(edges.shape, (0, 2))
+
+        m[:, 1] = np.where(x[:, 1] > 0.5, 1.0, 1e6)
+        max_beta, edges = Remesher2dAniso.check_gradation(msh, m, 1.5)
+        self.assertGreater(max_beta, 1.5)
+        self.assertTrue(np.all(x[edges[:, 0], 1] != x[edges[:, 1], 1]))
+
+        with self.assertRaises(ValueError):
+            Remesher2dIso.check_gradation(msh, h, 0.5)
+
+    def test_2d_remesh_sweep(self):
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split().split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+
+        metrics = [h * np.ones((msh.n_verts(), 1)) for h in [0.1, 0.05]]
+        params = {"num_iter": 2}
+        res = remesh_sweep(msh, geom, metrics, params, n_threads=2)
+        self.assertEqual(len(res), 2)
+        for new_msh, stats in res:
+            new_msh.check()
+            self.assertAlmostEqual(new_msh.vol(), 1.0)
+            self.assertGreater(len(json.loads(stats)), 0)
+        self.assertGreater(res[1][0].n_elems(), 2 * res[0][0].n_elems())
+
+        # same results with a callback
+        n_elems = {}
+
+        def callback(i, new_msh, stats):
+            n_elems[i] = new_msh.n_elems()
+
+        self.assertIsNone(remesh_sweep(msh, geom, metrics, params, callback=callback))
+        self.assertEqual(n_elems, {i: m.n_elems() for i, (m, _) in enumerate(res)})
+
+        with self.assertRaises(ValueError):
+            remesh_sweep(msh, geom, metrics, {"num_iters": 2})
+
+    def test_3d_remesh_group(self):
+        meshes, geoms, metrics = [], [], []
+        for shift, h in [(0.0, 0.2), (0.5, 0.3)]:
+            coords, elems, etags, faces, ftags = get_cube()
+            coords += shift
+            msh = Mesh33(coords, elems, etags, faces, ftags).split().split()
+            msh.compute_topology()
+            meshes.append(msh)
+            geoms.append(LinearGeometry3d(msh))
+            metrics.append(h * np.ones((msh.n_verts(), 1)))
+
+        params = {"num_iter": 2}
+        res, stats, c = remesh_group(meshes, geoms, metrics, params, n_threads=2)
+        self.assertEqual(len(res), 2)
+        self.assertEqual(len(stats), 2)
+        for new, msh, m, ci in zip(res, meshes, metrics, c):
+            new.check()
+            self.assertAlmostEqual(new.vol(), 1.0)
+            self.assertAlmostEqual(ci, Remesher3dIso.complexity_per_tag(msh, m)[1])
+        self.assertGreater(res[0].n_elems(), res[1].n_elems())
+
+        # the budget is split proportionally to the complexities
+        res, _, scaled = remesh_group(meshes, geoms, metrics, params, n_elems=2000)
+        self.assertAlmostEqual(sum(scaled), 2000)
+        self.assertAlmostEqual(scaled[0] / scaled[1], c[0] / c[1])
+        for new, ci in zip(res, scaled):
+            new.check()
+            self.assertGreater(new.n_elems(), 0.5 * ci)
+            self.assertLess(new.n_elems(), 2.0 * ci)
+
+        with self.assertRaises(ValueError):
+            remesh_group(meshes, geoms[:1], metrics)
+
+    def test_wrong_remesher_class(self):
+        coords, elems, etags, faces, ftags = get_cube()
+        msh = Mesh33(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry3d(msh)
+        h = 0.2 * np.ones((msh.n_verts(), 1))
+        m = np.zeros((msh.n_verts(), 6))
+        m[:, :3] = 1.0 / 0.2**2
+
+        with self.assertRaisesRegex(ValueError, "use Remesher3dAniso instead"):
+            Remesher3dIso(msh, geom, m)
+        with self.assertRaisesRegex(ValueError, "use Remesher3dIso instead"):
+            Remesher3dAniso(msh, geom, h)
+        with self.assertRaisesRegex(ValueError, "Invalid dimension 1"):
+            Remesher3dAniso(msh, geom, m[:, :3])
+
+        self.assertIsInstance(make_remesher(msh, geom, h), Remesher3dIso)
+        self.assertIsInstance(make_remesher(msh, geom, m), Remesher3dAniso)
+
+        coords, elems, etags, faces, ftags = get_square()
+        msh = Mesh22(coords, elems, etags, faces, ftags).split()
+        msh.compute_topology()
+        geom = LinearGeometry2d(msh)
+        h = 0.2 * np.ones((msh.n_verts(), 1))
+        m = np.zeros((msh.n_verts(), 3))
+        m[:, :2] = 1.0 / 0.2**2
+
+        with self.assertRaisesRegex(ValueError, "use Remesher2dAniso instead"):
+            Remesher2dIso(msh, geom, m)
+        self.assertIsInstance(make_remesher(msh, geom, h), Remesher2dIso)
+        remesher = make_remesher(msh, geom, m)
+        self.assertIsInstance(remesher, Remesher2dAniso)
+        remesher.remesh(geom, num_iter=1)
+        remesher.to_mesh().check()
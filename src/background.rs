@@ -0,0 +1,104 @@
+use crate::{
+    mesh::{Mesh22, Mesh33},
+    metric::sym_map,
+    to_numpy_2d,
+};
+use numpy::{PyArray2, PyReadonlyArray2, PyUntypedArrayMethods};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    pyclass, pymethods, Bound, PyResult, Python,
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    spatialindex::ObjectIndex,
+    topo_elems::{Tetrahedron, Triangle},
+};
+
+macro_rules! create_background_metric {
+    ($name: ident, $mesh: ident, $dim: expr, $etype: ident, $n_aniso: expr) => {
+        #[doc = concat!("Metric field defined at the vertices of a ", stringify!($mesh), " (the background mesh), that can be evaluated")]
+        /// at arbitrary points. The points are located in the background mesh and the metric is
+        /// interpolated with the log-Euclidean framework, i.e. the matrix logarithms of the
+        /// metrics are interpolated linearly
+        #[pyclass]
+        pub struct $name {
+            mesh: SimplexMesh<$dim, $etype>,
+            tree: ObjectIndex<$dim>,
+            /// Logarithm of the metric at the vertices
+            log_m: Vec<f64>,
+            /// Number of components of the metric (1 for isotropic metrics)
+            n: usize,
+        }
+
+        impl $name {
+            /// Evaluate the metric at the vertices of `mesh`, as a flat array
+            pub fn eval_at(&self, mesh: &SimplexMesh<$dim, $etype>, tol: Option<f64>) -> PyResult<Vec<f64>> {
+                let mut res = self
+                    .mesh
+                    .interpolate_linear(&self.tree, mesh, &self.log_m, tol)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                sym_map(&mut res, self.n, f64::exp);
+                Ok(res)
+            }
+        }
+
+        #[pymethods]
+        impl $name {
+            #[doc = concat!("Create a background metric from a ", stringify!($mesh), " and a metric `m` defined at its vertices, either")]
+            #[doc = concat!("isotropic (sizes, shape (# of vertices, 1)) or anisotropic (shape (# of vertices, ", stringify!($n_aniso), ")).")]
+            /// The mesh and metric are copied
+            #[new]
+            pub fn new(mesh: &$mesh, m: PyReadonlyArray2<f64>) -> PyResult<Self> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                let n = m.shape()[1];
+                if n != 1 && n != $n_aniso {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+                if mesh.mesh.n_elems() == 0 {
+                    return Err(PyValueError::new_err("The background mesh is empty"));
+                }
+                let mut log_m = m.as_slice()?.to_vec();
+                sym_map(&mut log_m, n, f64::ln);
+                if log_m.iter().any(|x| !x.is_finite()) {
+                    return Err(PyValueError::new_err("The metric must be positive definite"));
+                }
+                Ok(Self {
+                    mesh: mesh.mesh.clone(),
+                    tree: mesh.mesh.compute_elem_tree(),
+                    log_m,
+                    n,
+                })
+            }
+
+            /// Get the number of components of the metric (1 for isotropic metrics)
+            #[must_use]
+            pub const fn n(&self) -> usize {
+                self.n
+            }
+
+            #[doc = concat!("Evaluate the metric at `points`, an array of shape (# of points, ", stringify!($dim), "), `tol` being used")]
+            #[doc = concat!("to locate the points as in `", stringify!($mesh), ".interpolate_linear`")]
+            pub fn eval<'py>(
+                &self,
+                py: Python<'py>,
+                points: PyReadonlyArray2<f64>,
+                tol: Option<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                if points.shape()[1] != $dim {
+                    return Err(PyValueError::new_err("Invalid dimension 1 for points"));
+                }
+                let verts = points
+                    .as_slice()?
+                    .chunks($dim)
+                    .map(Point::<$dim>::from_column_slice)
+                    .collect();
+                let points =
+                    SimplexMesh::<$dim, $etype>::new(verts, Vec::new(), Vec::new(), Vec::new(), Vec::new());
+                Ok(to_numpy_2d(py, self.eval_at(&points, tol)?, self.n))
+            }
+        }
+    };
+}
+
+create_background_metric!(BackgroundMetric3d, Mesh33, 3, Tetrahedron, 6);
+create_background_metric!(BackgroundMetric2d, Mesh22, 2, Triangle, 3);
@@ -0,0 +1,240 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Seek, Write},
+    mem::size_of,
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::Elem,
+    Idx, Tag,
+};
+
+/// Header of the cache files
+const MAGIC: &[u8; 8] = b"TUCACHE1";
+
+/// 64-bit FNV-1a hash, which is stable across platforms and versions
+struct Fnv(u64);
+
+impl Fnv {
+    const fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Hash of the coordinates, elements, faces and tags of a mesh
+pub fn content_hash<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> u64 {
+    let mut hasher = Fnv::new();
+    for p in mesh.verts() {
+        for x in &p {
+            hasher.write(&x.to_le_bytes());
+        }
+    }
+    for e in mesh.elems() {
+        for i in e {
+            hasher.write(&i.to_le_bytes());
+        }
+    }
+    for t in mesh.etags() {
+        hasher.write(&t.to_le_bytes());
+    }
+    for f in mesh.faces() {
+        for i in f {
+            hasher.write(&i.to_le_bytes());
+        }
+    }
+    for t in mesh.ftags() {
+        hasher.write(&t.to_le_bytes());
+    }
+    hasher.0
+}
+
+fn write_u64(w: &mut impl Write, x: u64) -> Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+fn read_u64(r: &mut impl Read) -> Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f64(r: &mut impl Read) -> Result<f64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn read_idx(r: &mut impl Read) -> Result<Idx> {
+    let mut buf = [0; size_of::<Idx>()];
+    r.read_exact(&mut buf)?;
+    Ok(Idx::from_le_bytes(buf))
+}
+
+fn read_tag(r: &mut impl Read) -> Result<Tag> {
+    let mut buf = [0; size_of::<Tag>()];
+    r.read_exact(&mut buf)?;
+    Ok(Tag::from_le_bytes(buf))
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Check that `n` items of `size` bytes fit in the `len - pos` bytes left in the file, so that
+/// an invalid count does not cause a huge allocation
+fn check_count(r: &mut impl Seek, len: u64, n: u64, size: usize) -> Result<usize> {
+    let left = len.saturating_sub(r.stream_position()?);
+    if !n.checked_mul(size as u64).is_some_and(|x| x <= left) {
+        return Err(invalid_data("truncated cache file"));
+    }
+    Ok(n as usize)
+}
+
+/// Read the vertex indices of an element or face, checking that they are `< n_verts`
+fn read_ids(r: &mut impl Read, ids: &mut [Idx], n_verts: usize) -> Result<()> {
+    for i in ids {
+        *i = read_idx(r)?;
+        if *i as usize >= n_verts {
+            return Err(invalid_data("invalid vertex index"));
+        }
+    }
+    Ok(())
+}
+
+/// Write a mesh, its content hash and named arrays of shape (n, m), given as `(name, m, data)`,
+/// to a binary file
+pub fn write_cache<const D: usize, E: Elem>(
+    fname: &str,
+    mesh: &SimplexMesh<D, E>,
+    arrays: &[(String, usize, &[f64])],
+) -> Result<()> {
+    let mut w = BufWriter::new(File::create(fname)?);
+    w.write_all(MAGIC)?;
+    write_u64(&mut w, content_hash(mesh))?;
+    write_u64(&mut w, D as u64)?;
+    write_u64(&mut w, u64::from(E::N_VERTS))?;
+
+    write_u64(&mut w, u64::from(mesh.n_verts()))?;
+    for p in mesh.verts() {
+        for x in &p {
+            w.write_all(&x.to_le_bytes())?;
+        }
+    }
+    write_u64(&mut w, u64::from(mesh.n_elems()))?;
+    for (e, t) in mesh.elems().zip(mesh.etags()) {
+        for i in e {
+            w.write_all(&i.to_le_bytes())?;
+        }
+        w.write_all(&t.to_le_bytes())?;
+    }
+    write_u64(&mut w, u64::from(mesh.n_faces()))?;
+    for (f, t) in mesh.faces().zip(mesh.ftags()) {
+        for i in f {
+            w.write_all(&i.to_le_bytes())?;
+        }
+        w.write_all(&t.to_le_bytes())?;
+    }
+
+    write_u64(&mut w, arrays.len() as u64)?;
+    for (name, m, data) in arrays {
+        write_u64(&mut w, name.len() as u64)?;
+        w.write_all(name.as_bytes())?;
+        write_u64(&mut w, *m as u64)?;
+        write_u64(&mut w, data.len() as u64)?;
+        for x in *data {
+            w.write_all(&x.to_le_bytes())?;
+        }
+    }
+
+    w.flush()
+}
+
+/// Read a file written by `write_cache`, and return the content hash that was stored, the mesh
+/// and the named arrays
+#[allow(clippy::type_complexity)]
+pub fn read_cache<const D: usize, E: Elem>(
+    fname: &str,
+) -> Result<(u64, SimplexMesh<D, E>, Vec<(String, usize, Vec<f64>)>)> {
+    let file = File::open(fname)?;
+    let len = file.metadata()?.len();
+    let mut r = BufReader::new(file);
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a mesh cache file"));
+    }
+    let hash = read_u64(&mut r)?;
+    if read_u64(&mut r)? != D as u64 || read_u64(&mut r)? != u64::from(E::N_VERTS) {
+        return Err(invalid_data("invalid mesh type"));
+    }
+
+    let n = read_u64(&mut r)?;
+    let n_verts = check_count(&mut r, len, n, D * size_of::<f64>())?;
+    let mut verts = Vec::with_capacity(n_verts);
+    for _ in 0..n_verts {
+        let mut p = Point::<D>::zeros();
+        for x in p.iter_mut() {
+            *x = read_f64(&mut r)?;
+        }
+        verts.push(p);
+    }
+
+    let n = read_u64(&mut r)?;
+    let size = E::N_VERTS as usize * size_of::<Idx>() + size_of::<Tag>();
+    let n_elems = check_count(&mut r, len, n, size)?;
+    let mut elems = Vec::with_capacity(n_elems);
+    let mut etags = Vec::with_capacity(n_elems);
+    let mut ids = vec![0; E::N_VERTS as usize];
+    for _ in 0..n_elems {
+        read_ids(&mut r, &mut ids, n_verts)?;
+        elems.push(E::from_slice(&ids));
+        etags.push(read_tag(&mut r)?);
+    }
+
+    let n = read_u64(&mut r)?;
+    let size = E::Face::N_VERTS as usize * size_of::<Idx>() + size_of::<Tag>();
+    let n_faces = check_count(&mut r, len, n, size)?;
+    let mut faces = Vec::with_capacity(n_faces);
+    let mut ftags = Vec::with_capacity(n_faces);
+    let mut ids = vec![0; E::Face::N_VERTS as usize];
+    for _ in 0..n_faces {
+        read_ids(&mut r, &mut ids, n_verts)?;
+        faces.push(E::Face::from_slice(&ids));
+        ftags.push(read_tag(&mut r)?);
+    }
+
+    // each array has at least its name length, width and size
+    let n = read_u64(&mut r)?;
+    let n_arrays = check_count(&mut r, len, n, 3 * size_of::<u64>())?;
+    let mut arrays = Vec::with_capacity(n_arrays);
+    for _ in 0..n_arrays {
+        let n = read_u64(&mut r)?;
+        let mut name = vec![0; check_count(&mut r, len, n, 1)?];
+        r.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| invalid_data("invalid array name"))?;
+        let m = read_u64(&mut r)? as usize;
+        let n = read_u64(&mut r)?;
+        let n = check_count(&mut r, len, n, size_of::<f64>())?;
+        if m == 0 || n % m != 0 {
+            return Err(invalid_data("invalid array shape"));
+        }
+        let mut data = Vec::with_capacity(n);
+        for _ in 0..n {
+            data.push(read_f64(&mut r)?);
+        }
+        arrays.push((name, m, data));
+    }
+
+    Ok((
+        hash,
+        SimplexMesh::new(verts, elems, etags, faces, ftags),
+        arrays,
+    ))
+}
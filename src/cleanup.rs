@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::{Edge, Elem, Triangle},
+    Idx, Tag,
+};
+
+/// Sorted vertex pair used as an edge key
+const fn edge_key(i: Idx, j: Idx) -> [Idx; 2] {
+    if i < j {
+        [i, j]
+    } else {
+        [j, i]
+    }
+}
+
+/// Working copy of a surface mesh on which the edges are collapsed
+struct Surface {
+    verts: Vec<Point<3>>,
+    tris: Vec<[Idx; 3]>,
+    etags: Vec<Tag>,
+    alive: Vec<bool>,
+    v2t: Vec<Vec<usize>>,
+    faces: Vec<[Idx; 2]>,
+    ftags: Vec<Tag>,
+    /// Edges along which the vertices may slide but that can't be crossed
+    features: HashSet<[Idx; 2]>,
+}
+
+impl Surface {
+    fn new(mesh: &SimplexMesh<3, Triangle>, preserve_tags: bool) -> Self {
+        let verts: Vec<_> = mesh.verts().collect();
+        let tris: Vec<[Idx; 3]> = mesh
+            .elems()
+            .map(|e| {
+                let e: Vec<_> = e.into_iter().collect();
+                [e[0], e[1], e[2]]
+            })
+            .collect();
+        let etags: Vec<_> = mesh.etags().collect();
+        let faces: Vec<[Idx; 2]> = mesh
+            .faces()
+            .map(|f| {
+                let f: Vec<_> = f.into_iter().collect();
+                [f[0], f[1]]
+            })
+            .collect();
+        let ftags: Vec<_> = mesh.ftags().collect();
+
+        let mut v2t = vec![Vec::new(); verts.len()];
+        let mut e2t: HashMap<[Idx; 2], Vec<usize>> = HashMap::new();
+        for (i_tri, tri) in tris.iter().enumerate() {
+            for j in 0..3 {
+                v2t[tri[j] as usize].push(i_tri);
+                e2t.entry(edge_key(tri[j], tri[(j + 1) % 3]))
+                    .or_default()
+                    .push(i_tri);
+            }
+        }
+
+        // The face edges, the open boundary and non-manifold edges, and optionally the edges
+        // between different tags are features
+        let mut features: HashSet<_> = faces.iter().map(|f| edge_key(f[0], f[1])).collect();
+        for (edge, ts) in &e2t {
+            if ts.len() != 2 || (preserve_tags && etags[ts[0]] != etags[ts[1]]) {
+                features.insert(*edge);
+            }
+        }
+
+        let alive = vec![true; tris.len()];
+        Self {
+            verts,
+            tris,
+            etags,
+            alive,
+            v2t,
+            faces,
+            ftags,
+            features,
+        }
+    }
+
+    /// Vertices connected to `i` by an edge
+    fn neighbors(&self, i: Idx) -> HashSet<Idx> {
+        self.v2t[i as usize]
+            .iter()
+            .flat_map(|&t| self.tris[t])
+            .filter(|&j| j != i)
+            .collect()
+    }
+
+    fn length(&self, i: Idx, j: Idx) -> f64 {
+        (self.verts[i as usize] - self.verts[j as usize]).norm()
+    }
+
+    fn normal(&self, tri: &[Idx; 3]) -> Point<3> {
+        let p0 = self.verts[tri[0] as usize];
+        let p1 = self.verts[tri[1] as usize];
+        let p2 = self.verts[tri[2] as usize];
+        (p1 - p0).cross(&(p2 - p0))
+    }
+
+    /// Check if vertex `i` can be merged into vertex `j`
+    fn can_collapse(&self, i: Idx, j: Idx) -> bool {
+        // i may only slide along a feature curve
+        let n_i = self.neighbors(i);
+        let n_features = n_i
+            .iter()
+            .filter(|&&k| self.features.contains(&edge_key(i, k)))
+            .count();
+        match n_features {
+            0 => {}
+            2 if self.features.contains(&edge_key(i, j)) => {}
+            _ => return false,
+        }
+
+        // link condition: the only vertices connected to both i and j are those of the
+        // triangles sharing edge (i, j)
+        let n_shared = self.v2t[i as usize]
+            .iter()
+            .filter(|&&t| self.tris[t].contains(&j))
+            .count();
+        if n_shared == 0 {
+            return false;
+        }
+        let n_common = n_i.intersection(&self.neighbors(j)).count();
+        if n_common != n_shared {
+            return false;
+        }
+
+        // the remaining triangles around i must not be inverted
+        self.v2t[i as usize]
+            .iter()
+            .filter(|&&t| !self.tris[t].contains(&j))
+            .all(|&t| {
+                let old = self.tris[t];
+                let new = old.map(|k| if k == i { j } else { k });
+                self.normal(&old).dot(&self.normal(&new)) > 0.0
+            })
+    }
+
+    /// Merge vertex `i` into vertex `j`
+    fn collapse(&mut self, i: Idx, j: Idx) {
+        for k in self.neighbors(i) {
+            if self.features.remove(&edge_key(i, k)) && k != j {
+                self.features.insert(edge_key(j, k));
+            }
+        }
+
+        for t in std::mem::take(&mut self.v2t[i as usize]) {
+            if self.tris[t].contains(&j) {
+                self.alive[t] = false;
+                for k in self.tris[t] {
+                    if k != i {
+                        self.v2t[k as usize].retain(|&t2| t2 != t);
+                    }
+                }
+            } else {
+                for k in &mut self.tris[t] {
+                    if *k == i {
+                        *k = j;
+                    }
+                }
+                self.v2t[j as usize].push(t);
+            }
+        }
+
+        for f in &mut self.faces {
+            for k in f {
+                if *k == i {
+                    *k = j;
+                }
+            }
+        }
+    }
+
+    /// Try to collapse edge (i, j) in either direction
+    fn try_collapse(&mut self, i: Idx, j: Idx) -> bool {
+        if self.can_collapse(i, j) {
+            self.collapse(i, j);
+            true
+        } else if self.can_collapse(j, i) {
+            self.collapse(j, i);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Build the mesh from the remaining vertices and triangles, and get the indices of the
+    /// vertices in the original mesh
+    fn to_mesh(&self) -> (SimplexMesh<3, Triangle>, Vec<Idx>) {
+        let mut new_idx = vec![Idx::MAX; self.verts.len()];
+        let mut ids = Vec::new();
+        for (tri, _) in self.tris.iter().zip(&self.alive).filter(|(_, a)| **a) {
+            for &k in tri {
+                if new_idx[k as usize] == Idx::MAX {
+                    new_idx[k as usize] = ids.len() as Idx;
+                    ids.push(k);
+                }
+            }
+        }
+
+        let verts = ids.iter().map(|&k| self.verts[k as usize]).collect();
+        let (tris, etags): (Vec<_>, Vec<_>) = self
+            .tris
+            .iter()
+            .zip(&self.etags)
+            .zip(&self.alive)
+            .filter(|(_, a)| **a)
+            .map(|((tri, &tag), _)| {
+                let tri = tri.map(|k| new_idx[k as usize]);
+                (Triangle::from_slice(&tri), tag)
+            })
+            .unzip();
+
+        let mut added = HashSet::new();
+        let (faces, ftags): (Vec<_>, Vec<_>) = self
+            .faces
+            .iter()
+            .zip(&self.ftags)
+            .filter(|(f, _)| f[0] != f[1] && added.insert(edge_key(f[0], f[1])))
+            .map(|(f, &tag)| {
+                let f = f.map(|k| new_idx[k as usize]);
+                (Edge::from_slice(&f), tag)
+            })
+            .unzip();
+
+        (SimplexMesh::new(verts, tris, etags, faces, ftags), ids)
+    }
+}
+
+/// Collapse the edges of a surface mesh shorter than `l_min`
+///
+/// The face edges, the open boundary edges and, if `preserve_tags` is set, the edges between
+/// triangles with different tags are features: a vertex on a feature curve can only be merged
+/// into a neighbor along the curve, and the vertices where the curves meet are kept. Collapses
+/// that would change the topology of the surface or invert a triangle are skipped.
+/// Return the new mesh, the indices of its vertices in the original mesh and the number of
+/// collapses
+pub fn collapse_small_edges(
+    mesh: &SimplexMesh<3, Triangle>,
+    l_min: f64,
+    preserve_tags: bool,
+) -> (SimplexMesh<3, Triangle>, Vec<Idx>, Idx) {
+    let mut surf = Surface::new(mesh, preserve_tags);
+
+    let mut n_collapses = 0;
+    loop {
+        let mut edges: Vec<_> = surf
+            .tris
+            .iter()
+            .zip(&surf.alive)
+            .filter(|(_, a)| **a)
+            .flat_map(|(tri, _)| (0..3).map(move |j| edge_key(tri[j], tri[(j + 1) % 3])))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|[i, j]| (surf.length(i, j), i, j))
+            .filter(|(l, _, _)| *l < l_min)
+            .collect();
+        edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut modified = false;
+        for (_, i, j) in edges {
+            // the edge may have been removed or stretched by a previous collapse
+            if surf.v2t[i as usize].is_empty()
+                || surf.v2t[j as usize].is_empty()
+                || !surf.neighbors(i).contains(&j)
+                || surf.length(i, j) >= l_min
+            {
+                continue;
+            }
+            if surf.try_collapse(i, j) {
+                n_collapses += 1;
+                modified = true;
+            }
+        }
+        if !modified {
+            break;
+        }
+    }
+
+    let (res, ids) = surf.to_mesh();
+    (res, ids, n_collapses)
+}
+
+/// Triangulate a polygon with vertices `loop_verts` with the minimum total area, by dynamic
+/// programming over the sub-polygons. The triangles follow the order of the polygon vertices
+fn min_area_triangulation(verts: &[Point<3>], loop_verts: &[Idx]) -> Vec<[Idx; 3]> {
+    let n = loop_verts.len();
+    let p = |i: usize| verts[loop_verts[i] as usize];
+    let area = |i: usize, k: usize, j: usize| 0.5 * (p(k) - p(i)).cross(&(p(j) - p(i))).norm();
+
+    // cost[i][j]: min area of the triangulation of the sub-polygon i..=j, best[i][j]: its apex
+    let mut cost = vec![vec![0.0; n]; n];
+    let mut best = vec![vec![0; n]; n];
+    for len in 2..n {
+        for i in 0..n - len {
+            let j = i + len;
+            cost[i][j] = f64::INFINITY;
+            for k in i + 1..j {
+                let c = cost[i][k] + cost[k][j] + area(i, k, j);
+                if c < cost[i][j] {
+                    cost[i][j] = c;
+                    best[i][j] = k;
+                }
+            }
+        }
+    }
+
+    let mut res = Vec::with_capacity(n - 2);
+    let mut stack = vec![(0, n - 1)];
+    while let Some((i, j)) = stack.pop() {
+        if j < i + 2 {
+            continue;
+        }
+        let k = best[i][j];
+        res.push([loop_verts[i], loop_verts[k], loop_verts[j]]);
+        stack.push((i, k));
+        stack.push((k, j));
+    }
+    res
+}
+
+/// Fill the holes of a surface mesh, i.e. the loops of edges that belong to a single triangle,
+/// with at most `max_edges` edges. The loops are triangulated with the minimum area, the new
+/// triangles being oriented consistently with their neighbors and tagged `tag`. The loops
+/// through vertices with several open boundary edges are left alone.
+/// Return the new mesh, and the number of holes filled and left
+pub fn fill_holes(
+    mesh: &SimplexMesh<3, Triangle>,
+    max_edges: usize,
+    tag: Tag,
+) -> (SimplexMesh<3, Triangle>, Idx, Idx) {
+    let verts: Vec<Point<3>> = mesh.verts().collect();
+    let mut elems: Vec<Triangle> = mesh.elems().collect();
+    let mut etags: Vec<Tag> = mesh.etags().collect();
+
+    let mut edge_count: HashMap<[Idx; 2], usize> = HashMap::new();
+    for e in mesh.elems() {
+        let e: Vec<_> = e.into_iter().collect();
+        for j in 0..3 {
+            *edge_count
+                .entry(edge_key(e[j], e[(j + 1) % 3]))
+                .or_insert(0) += 1;
+        }
+    }
+    // the holes are bounded by the open edges in the reverse direction
+    let mut next: HashMap<Idx, Vec<Idx>> = HashMap::new();
+    for e in mesh.elems() {
+        let e: Vec<_> = e.into_iter().collect();
+        for j in 0..3 {
+            let (a, b) = (e[j], e[(j + 1) % 3]);
+            if edge_count[&edge_key(a, b)] == 1 {
+                next.entry(b).or_default().push(a);
+            }
+        }
+    }
+
+    let mut starts: Vec<Idx> = next.keys().copied().collect();
+    starts.sort_unstable();
+    let mut visited = HashSet::new();
+    let (mut n_filled, mut n_left) = (0, 0);
+    for start in starts {
+        if visited.contains(&start) {
+            continue;
+        }
+        let mut loop_verts = vec![start];
+        let mut manifold = true;
+        visited.insert(start);
+        let mut i = start;
+        loop {
+            let Some(nexts) = next.get(&i) else {
+                manifold = false;
+                break;
+            };
+            if nexts.len() != 1 {
+                manifold = false;
+            }
+            i = nexts[0];
+            if i == start {
+                break;
+            }
+            if !visited.insert(i) {
+                manifold = false;
+                break;
+            }
+            loop_verts.push(i);
+        }
+        if manifold && loop_verts.len() >= 3 && loop_verts.len() <= max_edges {
+            for tri in min_area_triangulation(&verts, &loop_verts) {
+                elems.push(Triangle::from_slice(&tri));
+                etags.push(tag);
+            }
+            n_filled += 1;
+        } else {
+            n_left += 1;
+        }
+    }
+
+    let faces: Vec<Edge> = mesh.faces().collect();
+    let ftags: Vec<Tag> = mesh.ftags().collect();
+    (
+        SimplexMesh::new(verts, elems, etags, faces, ftags),
+        n_filled,
+        n_left,
+    )
+}
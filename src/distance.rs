@@ -0,0 +1,103 @@
+use crate::{mesh::Mesh32, to_numpy_1d};
+use numpy::PyArray1;
+use pyo3::{exceptions::PyValueError, pyfunction, Bound, PyResult, Python};
+use rayon::prelude::*;
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    spatialindex::ObjectIndex,
+    topo_elems::Triangle,
+};
+
+/// Irrational steps of the R2 low discrepancy sequence used to sample the triangles
+const R2_STEPS: [f64; 2] = [0.754_877_666_246_692_7, 0.569_840_290_998_053_3];
+
+/// Sample points of a triangle mesh: its vertices and `n` points per triangle, the first one
+/// being the triangle center and the next ones following a low discrepancy sequence
+fn sample_points(mesh: &SimplexMesh<3, Triangle>, n: usize) -> Vec<Point<3>> {
+    let mut res: Vec<_> = mesh.verts().collect();
+    for e in mesh.elems() {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vert(e[i]));
+        for j in 0..n {
+            let mut u = (1.0 / 3.0 + j as f64 * R2_STEPS[0]).fract();
+            let mut v = (1.0 / 3.0 + j as f64 * R2_STEPS[1]).fract();
+            if u + v > 1.0 {
+                (u, v) = (1.0 - u, 1.0 - v);
+            }
+            res.push(a + u * (b - a) + v * (c - a));
+        }
+    }
+    res
+}
+
+/// Max distance from the sample points of `mesh_a` to `mesh_b` (using its element tree), and the
+/// sample point where it is attained
+fn one_sided_distance(
+    mesh_a: &SimplexMesh<3, Triangle>,
+    tree_b: &ObjectIndex<3>,
+    n: usize,
+) -> (f64, Point<3>) {
+    sample_points(mesh_a, n)
+        .into_par_iter()
+        .map(|p| (tree_b.project(&p).0, p))
+        .reduce(
+            || (0.0, Point::<3>::zeros()),
+            |a, b| if b.0 > a.0 { b } else { a },
+        )
+}
+
+/// Compute an approximation of the Hausdorff distance between two surface meshes: the
+/// distance from the vertices of `mesh_a` and `sample_per_tri` (default: 1) points per triangle
+/// to the surface of `mesh_b`, and the other way around if `symmetric` is True (default). The
+/// element trees stored by `compute_octree()` are used if valid, and built otherwise.
+/// Return the distance and the sampled point where it is attained, as an array of shape (3,)
+#[pyfunction]
+pub fn hausdorff_distance<'py>(
+    py: Python<'py>,
+    mesh_a: &Mesh32,
+    mesh_b: &Mesh32,
+    symmetric: Option<bool>,
+    sample_per_tri: Option<usize>,
+) -> PyResult<(f64, Bound<'py, PyArray1<f64>>)> {
+    if mesh_a.mesh.n_elems() == 0 || mesh_b.mesh.n_elems() == 0 {
+        return Err(PyValueError::new_err("The meshes must not be empty"));
+    }
+    let n = sample_per_tri.unwrap_or(1);
+    let symmetric = symmetric.unwrap_or(true);
+    let tree_a = mesh_a
+        .cached_octree()
+        .map(|tree| tree.get::<3>(mesh_a.version))
+        .transpose()?;
+    let tree_b = mesh_b
+        .cached_octree()
+        .map(|tree| tree.get::<3>(mesh_b.version))
+        .transpose()?;
+
+    let (d, p) = py.allow_threads(|| {
+        let owned;
+        let tree_b = if let Some(tree) = tree_b {
+            tree
+        } else {
+            owned = mesh_b.mesh.compute_elem_tree();
+            &owned
+        };
+        let res = one_sided_distance(&mesh_a.mesh, tree_b, n);
+        if !symmetric {
+            return res;
+        }
+        let owned;
+        let tree_a = if let Some(tree) = tree_a {
+            tree
+        } else {
+            owned = mesh_a.mesh.compute_elem_tree();
+            &owned
+        };
+        let other = one_sided_distance(&mesh_b.mesh, tree_a, n);
+        if other.0 > res.0 {
+            other
+        } else {
+            res
+        }
+    });
+
+    Ok((d, to_numpy_1d(py, p.iter().copied().collect())))
+}
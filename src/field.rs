@@ -0,0 +1,207 @@
+use crate::{
+    mesh::{Mesh21, Mesh22, Mesh31, Mesh32, Mesh33},
+    solb::{read_solb_at, solb_location},
+    to_numpy_2d,
+};
+use numpy::{
+    PyArray, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray2, PyReadonlyArray3,
+    PyUntypedArrayMethods,
+};
+use pyo3::{
+    exceptions::{PyDeprecationWarning, PyRuntimeError, PyValueError},
+    prelude::PyAnyMethods,
+    pyfunction,
+    types::{IntoPyDict, PyAny},
+    Bound, IntoPy, PyErr, PyObject, PyResult, Python,
+};
+use tucanos::{
+    mesh::SimplexMesh,
+    topo_elems::{Tetrahedron, Triangle},
+};
+
+/// Kinds of fields handled by the meshes and remeshers
+pub const FIELD_KINDS: [&str; 3] = ["scalar", "vector", "symtensor"];
+
+/// Order of the components of the 2D symmetric tensors (hessians, metrics)
+pub const SYMTENSOR_2D: [&str; 3] = ["xx", "yy", "xy"];
+
+/// Order of the components of the 3D symmetric tensors (hessians, metrics)
+pub const SYMTENSOR_3D: [&str; 6] = ["xx", "yy", "zz", "xy", "yz", "xz"];
+
+/// Number of components of a field of kind `kind` in dimension `dim`
+pub fn n_comps(dim: usize, kind: &str) -> PyResult<usize> {
+    match kind {
+        "scalar" => Ok(1),
+        "vector" => Ok(dim),
+        "symtensor" => Ok(dim * (dim + 1) / 2),
+        _ => Err(PyValueError::new_err(format!(
+            "Invalid field kind {kind}, expected one of {FIELD_KINDS:?}"
+        ))),
+    }
+}
+
+/// Index of component `(i, j)` of a symmetric tensor of dimension `dim`, in the order given by
+/// `SYMTENSOR_2D` / `SYMTENSOR_3D`
+pub const fn sym_index(dim: usize, i: usize, j: usize) -> usize {
+    if i == j {
+        i
+    } else if dim == 2 {
+        2
+    } else {
+        match i + j {
+            1 => 3,
+            3 => 4,
+            _ => 5,
+        }
+    }
+}
+
+/// Dimension of a symmetric tensor from its number of components
+fn sym_dim(n: usize) -> PyResult<usize> {
+    match n {
+        3 => Ok(2),
+        6 => Ok(3),
+        _ => Err(PyValueError::new_err("Invalid dimension 1")),
+    }
+}
+
+/// Get the number of columns expected for a field of kind `kind` ("scalar", "vector" or
+/// "symtensor") on a mesh. The components of symmetric tensors are ordered as
+/// `SYMTENSOR_2D` = (xx, yy, xy) in 2D and `SYMTENSOR_3D` = (xx, yy, zz, xy, yz, xz) in 3D
+#[pyfunction]
+pub fn field_shape(mesh: &Bound<'_, PyAny>, kind: &str) -> PyResult<usize> {
+    let dim = if mesh.is_instance_of::<Mesh33>()
+        || mesh.is_instance_of::<Mesh32>()
+        || mesh.is_instance_of::<Mesh31>()
+    {
+        3
+    } else if mesh.is_instance_of::<Mesh22>() || mesh.is_instance_of::<Mesh21>() {
+        2
+    } else {
+        return Err(PyValueError::new_err("Invalid mesh"));
+    };
+    n_comps(dim, kind)
+}
+
+/// Read a solution stored in a .sol(b) file, defined at the vertices or at the elements.
+/// If `mesh` is given, the solution is read as with `mesh.read_solb()` and its number of rows is
+/// checked against the number of vertices or elements of `mesh`; otherwise only .solb files can
+/// be read. Return `(arr, location)`, `location` being "vertex" or "elem".
+///
+/// Deprecated: use the `read_solb` classmethod of the mesh classes instead
+#[pyfunction]
+pub fn read_solb<'py>(
+    py: Python<'py>,
+    fname: &str,
+    mesh: Option<&Bound<'py, PyAny>>,
+) -> PyResult<(PyObject, &'static str)> {
+    PyErr::warn_bound(
+        py,
+        py.get_type_bound::<PyDeprecationWarning>().as_any(),
+        "read_solb() is deprecated, use the read_solb classmethod of the mesh classes instead",
+        1,
+    )?;
+
+    let location = if fname.ends_with(".solb") {
+        Some(solb_location(fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))?)
+    } else {
+        None
+    };
+    let at_elems = location.as_ref().is_some_and(|l| l.elem_kwd.is_some());
+    let name = if at_elems { "elem" } else { "vertex" };
+
+    if let Some(mesh) = mesh {
+        if !(mesh.is_instance_of::<Mesh33>()
+            || mesh.is_instance_of::<Mesh32>()
+            || mesh.is_instance_of::<Mesh31>()
+            || mesh.is_instance_of::<Mesh22>()
+            || mesh.is_instance_of::<Mesh21>())
+        {
+            return Err(PyValueError::new_err("Invalid mesh"));
+        }
+        let kwargs = [("location", name)].into_py_dict_bound(py);
+        let arr = mesh.call_method("read_solb", (fname,), Some(&kwargs))?;
+        let n = arr.extract::<PyReadonlyArray2<f64>>()?.shape()[0];
+        let expected: usize = mesh
+            .call_method0(if at_elems { "n_elems" } else { "n_verts" })?
+            .extract()?;
+        if n != expected {
+            return Err(PyValueError::new_err(format!(
+                "Invalid dimension 0: the solution has {n} rows, expected {expected}"
+            )));
+        }
+        return Ok((arr.unbind(), name));
+    }
+
+    let Some(location) = location else {
+        return Err(PyValueError::new_err("mesh is required to read .sol files"));
+    };
+    let (sol, m) = match (location.elem_kwd, location.dim) {
+        (Some(kwd), _) => read_solb_at(fname, kwd).map_err(|e| e.to_string()),
+        (None, 2) => SimplexMesh::<2, Triangle>::read_solb(fname).map_err(|e| e.to_string()),
+        (None, 3) => SimplexMesh::<3, Tetrahedron>::read_solb(fname).map_err(|e| e.to_string()),
+        (None, dim) => Err(format!("Invalid dimension {dim} in {fname}")),
+    }
+    .map_err(PyRuntimeError::new_err)?;
+    Ok((to_numpy_2d(py, sol, m).into_py(py), name))
+}
+
+/// Convert an array of symmetric tensors of shape (n, 3) or (n, 6), with the components ordered
+/// as `SYMTENSOR_2D` or `SYMTENSOR_3D`, to an array of full matrices of shape (n, dim, dim)
+#[pyfunction]
+pub fn symtensor_to_full<'py>(
+    py: Python<'py>,
+    arr: PyReadonlyArray2<f64>,
+) -> PyResult<Bound<'py, PyArray3<f64>>> {
+    let n = arr.shape()[0];
+    let dim = sym_dim(arr.shape()[1])?;
+
+    let arr = arr.as_slice()?;
+    let mut res = Vec::with_capacity(n * dim * dim);
+    for m in arr.chunks(dim * (dim + 1) / 2) {
+        for i in 0..dim {
+            for j in 0..dim {
+                res.push(m[sym_index(dim, i, j)]);
+            }
+        }
+    }
+
+    Ok(PyArray::from_vec_bound(py, res).reshape([n, dim, dim])?)
+}
+
+/// Convert an array of symmetric matrices of shape (n, dim, dim) to an array of symmetric tensors
+/// of shape (n, 3) or (n, 6), with the components ordered as `SYMTENSOR_2D` or `SYMTENSOR_3D`.
+/// The off-diagonal components are averaged
+#[pyfunction]
+pub fn full_to_symtensor<'py>(
+    py: Python<'py>,
+    arr: PyReadonlyArray3<f64>,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let dim = arr.shape()[1];
+    if dim != 2 && dim != 3 {
+        return Err(PyValueError::new_err("Invalid dimension 1"));
+    }
+    if arr.shape()[2] != dim {
+        return Err(PyValueError::new_err("Invalid dimension 2"));
+    }
+    let n_sym = dim * (dim + 1) / 2;
+
+    let arr = arr.as_slice()?;
+    let mut res = Vec::with_capacity(arr.len() / (dim * dim) * n_sym);
+    for m in arr.chunks(dim * dim) {
+        let mut t = vec![0.0; n_sym];
+        for i in 0..dim {
+            for j in 0..dim {
+                let k = sym_index(dim, i, j);
+                t[k] += if i == j {
+                    m[dim * i + j]
+                } else {
+                    0.5 * m[dim * i + j]
+                };
+            }
+        }
+        res.extend(t);
+    }
+
+    Ok(to_numpy_2d(py, res, n_sym))
+}
@@ -1,53 +1,507 @@
 use crate::{
     mesh::{Mesh21, Mesh22, Mesh32, Mesh33},
-    to_numpy_2d,
+    to_numpy_1d, to_numpy_2d,
 };
-use numpy::PyArray2;
-use pyo3::{exceptions::PyRuntimeError, pyclass, pymethods, Bound, PyResult, Python};
+use numpy::{PyArray1, PyArray2};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::{PyAnyMethods, PyDictMethods},
+    pyclass, pymethods,
+    types::{PyAny, PyDict},
+    Bound, IntoPy, PyObject, PyRef, PyResult, Python,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use tucanos::{
+    geom_elems::GElem,
     geometry::{Geometry, LinearGeometry},
+    mesh::{Point, SimplexMesh},
     mesh_stl::orient_stl,
-    topo_elems::{Edge, Triangle},
+    topo_elems::{Edge, Elem, Tetrahedron, Triangle},
+    Idx, Tag,
 };
+
+/// Relative tolerance used to weld the vertices when several surface meshes are merged
+const WELD_TOL: f64 = 1e-10;
+
+/// Compute the principal curvature directions of a geometry, scaled by the curvatures, at the
+/// vertices of a mesh that lie on boundary faces (i.e. not on boundary edges or corners), after
+/// projection onto the geometry. The directions are sorted by decreasing curvature
+#[allow(clippy::type_complexity)]
+fn vertex_curvature<const D: usize, E: Elem, F: Elem>(
+    geom: &LinearGeometry<D, F>,
+    mesh: &SimplexMesh<D, E>,
+) -> PyResult<Vec<(Idx, Point<D>, Option<Point<D>>)>> {
+    let vtags = mesh
+        .get_vertex_tags()
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    let mut res = Vec::new();
+    for (i_vert, (mut pt, tag)) in mesh.verts().zip(vtags.iter()).enumerate() {
+        if usize::try_from(tag.0) != Ok(D - 1) {
+            continue;
+        }
+        geom.project(&mut pt, tag);
+        let (u, v) = geom
+            .curvature(&pt, tag.1)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let (u, v) = match v {
+            Some(v) if v.norm() > u.norm() => (v, Some(u)),
+            _ => (u, v),
+        };
+        res.push((i_vert as Idx, u, v));
+    }
+
+    Ok(res)
+}
+/// Merge surface meshes with the given element tags, welding the vertices closer than
+/// `WELD_TOL` times the size of the bounding box and removing the duplicate faces
+pub fn merge_surfaces<const D: usize, E: Elem>(
+    parts: &[(&SimplexMesh<D, E>, &[Tag])],
+) -> SimplexMesh<D, E> {
+    let mut lo = Point::<D>::repeat(f64::MAX);
+    let mut hi = Point::<D>::repeat(f64::MIN);
+    for (part, _) in parts {
+        for p in part.verts() {
+            lo = lo.inf(&p);
+            hi = hi.sup(&p);
+        }
+    }
+    let tol = WELD_TOL * (hi - lo).norm();
+    let cell = |p: &Point<D>| -> [i64; D] { std::array::from_fn(|d| (p[d] / tol).floor() as i64) };
+
+    let mut grid: HashMap<[i64; D], Vec<Idx>> = HashMap::new();
+    let mut verts: Vec<Point<D>> = Vec::new();
+    let mut elems = Vec::new();
+    let mut etags = Vec::new();
+    let mut faces = Vec::new();
+    let mut ftags = Vec::new();
+    let mut added = HashSet::new();
+    for (part, tags) in parts {
+        let mut new_idx = Vec::with_capacity(part.n_verts() as usize);
+        for p in part.verts() {
+            // look for a vertex within tol in the neighboring cells
+            let c = cell(&p);
+            let mut found = None;
+            for offset in 0..3_i64.pow(D as u32) {
+                let mut o = offset;
+                let mut c = c;
+                for x in &mut c {
+                    *x += o % 3 - 1;
+                    o /= 3;
+                }
+                found = grid.get(&c).and_then(|ids| {
+                    ids.iter()
+                        .copied()
+                        .find(|&i| (verts[i as usize] - p).norm() <= tol)
+                });
+                if found.is_some() {
+                    break;
+                }
+            }
+            let i = found.unwrap_or_else(|| {
+                let i = verts.len() as Idx;
+                verts.push(p);
+                grid.entry(c).or_default().push(i);
+                i
+            });
+            new_idx.push(i);
+        }
+
+        for (e, &tag) in part.elems().zip(tags.iter()) {
+            let e: Vec<_> = e.into_iter().map(|i| new_idx[i as usize]).collect();
+            elems.push(E::from_slice(&e));
+            etags.push(tag);
+        }
+        for (f, tag) in part.faces().zip(part.ftags()) {
+            let f: Vec<_> = f.into_iter().map(|i| new_idx[i as usize]).collect();
+            let mut key = f.clone();
+            key.sort_unstable();
+            if added.insert(key) {
+                faces.push(E::Face::from_slice(&f));
+                ftags.push(tag);
+            }
+        }
+    }
+
+    SimplexMesh::new(verts, elems, etags, faces, ftags)
+}
+
+/// Split once the triangles of a surface mesh that have edges longer than `h_max` or, for the
+/// edges within a tag, a chordal error too large for `r_h`, i.e. such that `l * k > r_h` where
+/// `l` is the edge length and `k` the curvature along the edge, estimated from the vertex normals.
+/// The new mid-edge vertices of the edges within a tag are moved off the chord using this curvature
+/// estimate, while those of the edges between tags (or on the boundary of the surface) are kept
+/// on the chord. Return `None` if no edge needs to be split
+fn refine_surface(
+    gmesh: &SimplexMesh<3, Triangle>,
+    h_max: Option<f64>,
+    r_h: Option<f64>,
+) -> Option<SimplexMesh<3, Triangle>> {
+    let edge_key = |i: Idx, j: Idx| if i < j { [i, j] } else { [j, i] };
+
+    let mut verts: Vec<Point<3>> = gmesh.verts().collect();
+    let elems: Vec<[Idx; 3]> = gmesh
+        .elems()
+        .map(|e| {
+            let e: Vec<_> = e.into_iter().collect();
+            [e[0], e[1], e[2]]
+        })
+        .collect();
+    let etags: Vec<Tag> = gmesh.etags().collect();
+
+    // area weighted vertex normals, computed separately for each tag
+    let mut normals: HashMap<(Idx, Tag), Point<3>> = HashMap::new();
+    let mut edge_tags: BTreeMap<[Idx; 2], Vec<Tag>> = BTreeMap::new();
+    for (e, &tag) in elems.iter().zip(etags.iter()) {
+        let p0 = verts[e[0] as usize];
+        let n = (verts[e[1] as usize] - p0).cross(&(verts[e[2] as usize] - p0));
+        for j in 0..3 {
+            *normals.entry((e[j], tag)).or_insert_with(Point::<3>::zeros) += n;
+            edge_tags
+                .entry(edge_key(e[j], e[(j + 1) % 3]))
+                .or_default()
+                .push(tag);
+        }
+    }
+    normals.values_mut().for_each(|n| *n = n.normalize());
+
+    let mut mid_edge = HashMap::new();
+    for (&[i, j], tags) in &edge_tags {
+        let (p0, p1) = (verts[i as usize], verts[j as usize]);
+        let l = (p1 - p0).norm();
+        let smooth = tags.len() == 2 && tags[0] == tags[1];
+        let (n0, n1) = (normals[&(i, tags[0])], normals[&(j, tags[0])]);
+        let dn = (p1 - p0).dot(&(n1 - n0));
+        let split =
+            h_max.is_some_and(|h| l > h) || (smooth && r_h.is_some_and(|r| dn.abs() > r * l));
+        if split {
+            let mut p = 0.5 * (p0 + p1);
+            if smooth {
+                p += 0.125 * dn * (n0 + n1).normalize();
+            }
+            verts.push(p);
+            mid_edge.insert([i, j], verts.len() as Idx - 1);
+        }
+    }
+    if mid_edge.is_empty() {
+        return None;
+    }
+
+    let mut new_elems = Vec::with_capacity(4 * elems.len());
+    let mut new_etags = Vec::with_capacity(4 * elems.len());
+    for (e, &tag) in elems.iter().zip(etags.iter()) {
+        let m: Vec<_> = (0..3)
+            .map(|j| mid_edge.get(&edge_key(e[j], e[(j + 1) % 3])).copied())
+            .collect();
+        let new: Vec<[Idx; 3]> = match m.iter().filter(|x| x.is_some()).count() {
+            0 => vec![*e],
+            1 => {
+                let r = m.iter().position(Option::is_some).unwrap();
+                let (a, b, c) = (e[r], e[(r + 1) % 3], e[(r + 2) % 3]);
+                let ab = m[r].unwrap();
+                vec![[a, ab, c], [ab, b, c]]
+            }
+            2 => {
+                let r = (m.iter().position(Option::is_none).unwrap() + 1) % 3;
+                let (a, b, c) = (e[r], e[(r + 1) % 3], e[(r + 2) % 3]);
+                let (ab, bc) = (m[r].unwrap(), m[(r + 1) % 3].unwrap());
+                vec![[a, ab, c], [ab, b, bc], [ab, bc, c]]
+            }
+            _ => {
+                let (ab, bc, ca) = (m[0].unwrap(), m[1].unwrap(), m[2].unwrap());
+                vec![[e[0], ab, ca], [ab, e[1], bc], [ca, bc, e[2]], [ab, bc, ca]]
+            }
+        };
+        for x in new {
+            new_elems.push(Triangle::from_slice(&x));
+            new_etags.push(tag);
+        }
+    }
+
+    let mut new_faces = Vec::with_capacity(gmesh.n_faces() as usize);
+    let mut new_ftags = Vec::with_capacity(gmesh.n_faces() as usize);
+    for (f, tag) in gmesh.faces().zip(gmesh.ftags()) {
+        let f: Vec<_> = f.into_iter().collect();
+        if let Some(&m) = mid_edge.get(&edge_key(f[0], f[1])) {
+            new_faces.push(Edge::from_slice(&[f[0], m]));
+            new_faces.push(Edge::from_slice(&[m, f[1]]));
+            new_ftags.extend([tag, tag]);
+        } else {
+            new_faces.push(Edge::from_slice(&f));
+            new_ftags.push(tag);
+        }
+    }
+
+    Some(SimplexMesh::new(
+        verts, new_elems, new_etags, new_faces, new_ftags,
+    ))
+}
+
+/// Check that all the boundary tags of `mesh` are element tags of the geometry mesh `gmesh`
+fn check_tags<const D: usize, E: Elem, F: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    gmesh: &SimplexMesh<D, F>,
+) -> PyResult<()> {
+    let gtags: HashSet<Tag> = gmesh.etags().collect();
+    let missing: BTreeSet<Tag> = mesh.ftags().filter(|t| !gtags.contains(t)).collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "The boundary tags {missing:?} are not covered by the geometry"
+        )))
+    }
+}
+
+/// Distance between the face centers of `mesh` and their projection onto the geometry or, if
+/// `angle` is set, angle (in degrees) between the face normals and the geometry normals. The
+/// faces whose tag is not in `tags` (if given) get NaN
+fn face_deviations<const D: usize, E: Elem, G: Geometry<D>>(
+    geom: &G,
+    mesh: &SimplexMesh<D, E>,
+    tags: Option<&[Tag]>,
+    angle: bool,
+) -> Vec<f64> {
+    let dim = (E::N_VERTS - 2).try_into().unwrap();
+    mesh.gfaces()
+        .zip(mesh.ftags())
+        .map(|(gf, tag)| {
+            if tags.is_some_and(|tags| !tags.contains(&tag)) {
+                return f64::NAN;
+            }
+            let c = gf.center();
+            if angle {
+                geom.angle(&c, &gf.normal(), &(dim, tag))
+            } else {
+                let mut p = c;
+                geom.project(&mut p, &(dim, tag));
+                (p - c).norm()
+            }
+        })
+        .collect()
+}
+
+/// Max of the face values computed by `face_deviations`, as a float or, if `per_tag` is set, as
+/// a dict mapping the face tags to the max over the faces with this tag
+fn max_deviation<const D: usize, E: Elem>(
+    py: Python<'_>,
+    mesh: &SimplexMesh<D, E>,
+    values: &[f64],
+    per_tag: bool,
+) -> PyResult<PyObject> {
+    if !per_tag {
+        let res = values
+            .iter()
+            .filter(|x| !x.is_nan())
+            .fold(0.0, |a: f64, &b| a.max(b));
+        return Ok(res.into_py(py));
+    }
+    let mut res = BTreeMap::new();
+    for (&x, tag) in values.iter().zip(mesh.ftags()) {
+        if !x.is_nan() {
+            let v = res.entry(tag).or_insert(0.0_f64);
+            *v = v.max(x);
+        }
+    }
+    let dict = PyDict::new_bound(py);
+    for (tag, x) in res {
+        dict.set_item(tag, x)?;
+    }
+    Ok(dict.into_py(py))
+}
+
+/// Mesh of the unit simplex (the origin and the unit vectors), with all its faces tagged 1
+fn unit_simplex<const D: usize, E: Elem>() -> SimplexMesh<D, E> {
+    let verts = (0..=D)
+        .map(|i| {
+            let mut p = Point::<D>::zeros();
+            if i > 0 {
+                p[i - 1] = 1.0;
+            }
+            p
+        })
+        .collect();
+    let e = E::from_slice(&(0..=D as Idx).collect::<Vec<_>>());
+    let faces: Vec<_> = (0..E::N_FACES).map(|i| e.face(i)).collect();
+    let ftags = vec![1; faces.len()];
+    SimplexMesh::new(verts, vec![e], vec![1], faces, ftags)
+}
+
 macro_rules! create_geometry {
-    ($name: ident, $dim: expr, $etype: ident, $mesh: ident, $geom: ident) => {
+    ($name: ident, $dim: expr, $etype: ident, $mesh: ident, $vtype: ident, $geom: ident) => {
         #[doc = concat!("Piecewise linear geometry consisting of ", stringify!($etype), " in ", stringify!($dim), "D")]
-        #[pyclass]
+        #[pyclass(module = "pytucanos._pytucanos")]
         // #[derive(Clone)]
         pub struct $name {
             pub geom: LinearGeometry<$dim, $etype>,
+            /// Copy of the mesh used to build the geometry
+            pub gmesh: SimplexMesh<$dim, $etype>,
         }
         #[pymethods]
         impl $name {
-            /// Create a new geometry
+            #[doc = concat!("Create a new geometry, from the boundary of `mesh` or from `geom` which may be a ", stringify!($geom), ",")]
+            #[doc = concat!("a list of `(", stringify!($geom), ", tag_offset)` pairs (the offset being added to the element tags) or a dict")]
+            #[doc = concat!("mapping tags to ", stringify!($geom), " (all the elements getting the tag). Several meshes are merged,")]
+            /// the duplicate vertices along the shared edges being welded, and must cover all the
+            /// boundary tags of `mesh`.
+            /// The geometry builds its own spatial index of its surface mesh: the element tree
+            /// stored in `mesh` by `compute_octree()`, if any, is not used
             #[new]
-            #[must_use]
             pub fn new(
                 mesh: &$mesh,
-                geom: Option<&$geom>,
-            ) -> Self {
+                geom: Option<&Bound<'_, PyAny>>,
+            ) -> PyResult<Self> {
 
                 let mut gmesh = if let Some(geom) = geom {
-                    geom.mesh.clone()
+                    if let Ok(geom) = geom.extract::<PyRef<$geom>>() {
+                        geom.mesh.clone()
+                    } else {
+                        let parts = if let Ok(parts) = geom.extract::<BTreeMap<Tag, PyRef<$geom>>>() {
+                            parts
+                                .into_iter()
+                                .map(|(tag, part)| {
+                                    let tags = vec![tag; part.mesh.n_elems() as usize];
+                                    (part, tags)
+                                })
+                                .collect::<Vec<_>>()
+                        } else {
+                            let parts = geom.extract::<Vec<(PyRef<$geom>, Tag)>>()?;
+                            let mut res = Vec::with_capacity(parts.len());
+                            for (part, offset) in parts {
+                                let tags = part
+                                    .mesh
+                                    .etags()
+                                    .map(|t| t.checked_add(offset))
+                                    .collect::<Option<Vec<_>>>()
+                                    .ok_or_else(|| PyValueError::new_err("Invalid tag offset"))?;
+                                res.push((part, tags));
+                            }
+                            res
+                        };
+                        let parts: Vec<_> = parts.iter().map(|(part, tags)| (&part.mesh, tags.as_slice())).collect();
+                        let gmesh = merge_surfaces(&parts);
+                        check_tags(&mesh.mesh, &gmesh)?;
+                        gmesh
+                    }
                 } else {
                     mesh.mesh.boundary().0
                 };
                 orient_stl(&mesh.mesh, &mut gmesh);
-                let geom = LinearGeometry::new(&mesh.mesh, gmesh).unwrap();
+                let geom = LinearGeometry::new(&mesh.mesh, gmesh.clone())
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
 
-                Self { geom }
+                Ok(Self { geom, gmesh })
+            }
+
+            /// Arguments of `new()` used by pickle to create the object before `__setstate__()` is
+            #[doc = concat!("called: a ", stringify!($mesh), " of the unit simplex, the actual geometry being in the state")]
+            #[must_use]
+            pub fn __getnewargs__(&self) -> ($mesh,) {
+                (unit_simplex::<$dim, $vtype>().into(),)
             }
 
-            /// Compute the max distance between the face centers and the geometry normals
+            #[doc = concat!("Get the state used by pickle and `copy.deepcopy()`: the ", stringify!($geom), " used to build the geometry")]
             #[must_use]
-            pub fn max_distance(&self, mesh: &$mesh) -> f64 {
-                self.geom.max_distance(&mesh.mesh)
+            pub fn __getstate__(&self) -> ($geom,) {
+                (self.gmesh.clone().into(),)
+            }
+
+            /// Restore the state returned by `__getstate__()`. The surface mesh is used as is,
+            /// since it was already oriented when the geometry was first built
+            pub fn __setstate__(&mut self, state: (PyRef<'_, $geom>,)) -> PyResult<()> {
+                let gmesh = state.0.mesh.clone();
+                let bdy = SimplexMesh::<$dim, $vtype>::new(
+                    gmesh.verts().collect(),
+                    Vec::new(),
+                    Vec::new(),
+                    gmesh.elems().collect(),
+                    gmesh.etags().collect(),
+                );
+                self.geom = LinearGeometry::new(&bdy, gmesh.clone())
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                self.gmesh = gmesh;
+                Ok(())
+            }
+
+            /// Compute the max distance between the face centers and the geometry normals, over
+            /// the faces with a tag in `tags` if given. If `per_tag` is True, a dict mapping the
+            /// face tags to the max distance over the faces with this tag is returned
+            pub fn max_distance(
+                &self,
+                py: Python<'_>,
+                mesh: &$mesh,
+                tags: Option<Vec<Tag>>,
+                per_tag: Option<bool>,
+            ) -> PyResult<PyObject> {
+                let per_tag = per_tag.unwrap_or(false);
+                if tags.is_none() && !per_tag {
+                    return Ok(self.geom.max_distance(&mesh.mesh).into_py(py));
+                }
+                let d = face_deviations(&self.geom, &mesh.mesh, tags.as_deref(), false);
+                max_deviation(py, &mesh.mesh, &d, per_tag)
+            }
+
+            /// Compute the max angle between the face normals and the geometry normals, over the
+            /// faces with a tag in `tags` if given. If `per_tag` is True, a dict mapping the face
+            /// tags to the max angle over the faces with this tag is returned
+            pub fn max_normal_angle(
+                &self,
+                py: Python<'_>,
+                mesh: &$mesh,
+                tags: Option<Vec<Tag>>,
+                per_tag: Option<bool>,
+            ) -> PyResult<PyObject> {
+                let per_tag = per_tag.unwrap_or(false);
+                if tags.is_none() && !per_tag {
+                    return Ok(self.geom.max_normal_angle(&mesh.mesh).into_py(py));
+                }
+                let a = face_deviations(&self.geom, &mesh.mesh, tags.as_deref(), true);
+                max_deviation(py, &mesh.mesh, &a, per_tag)
+            }
+
+            /// Get the distance between each face center and the geometry, as an array of shape
+            /// (# of faces). If `tags` is given, the faces with other tags get NaN
+            pub fn face_distances<'py>(
+                &self,
+                py: Python<'py>,
+                mesh: &$mesh,
+                tags: Option<Vec<Tag>>,
+            ) -> Bound<'py, PyArray1<f64>> {
+                to_numpy_1d(py, face_deviations(&self.geom, &mesh.mesh, tags.as_deref(), false))
             }
 
-            /// Compute the max angle between the face normals and the geometry normals
+            /// Get the angle (in degrees) between each face normal and the geometry normal, as an
+            /// array of shape (# of faces). If `tags` is given, the faces with other tags get NaN
+            pub fn face_normal_angles<'py>(
+                &self,
+                py: Python<'py>,
+                mesh: &$mesh,
+                tags: Option<Vec<Tag>>,
+            ) -> Bound<'py, PyArray1<f64>> {
+                to_numpy_1d(py, face_deviations(&self.geom, &mesh.mesh, tags.as_deref(), true))
+            }
+
+            #[doc = concat!("Get a copy of the mesh used to build the geometry (after orientation) as a ", stringify!($geom))]
             #[must_use]
-            pub fn max_normal_angle(&self, mesh: &$mesh) -> f64 {
-                self.geom.max_normal_angle(&mesh.mesh)
+            pub fn mesh(&self) -> $geom {
+                self.gmesh.clone().into()
+            }
+
+            /// Write a vtk file containing the mesh used to build the geometry, with the element
+            /// tags and unit normals as element data
+            pub fn write_vtk(&self, fname: &str) -> PyResult<()> {
+                let tags: Vec<f64> = self.gmesh.etags().map(f64::from).collect();
+                let mut normals = Vec::with_capacity(self.gmesh.n_elems() as usize * $dim);
+                for ge in self.gmesh.gelems() {
+                    normals.extend(ge.normal().normalize().iter().copied());
+                }
+
+                let mut edata = HashMap::new();
+                edata.insert("tag".to_string(), tags.as_slice());
+                edata.insert("normal".to_string(), normals.as_slice());
+                self.gmesh
+                    .write_vtk(fname, None, Some(edata))
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
             }
 
             /// Compute the curvature
@@ -84,5 +538,120 @@ macro_rules! create_geometry {
     }
 }
 
-create_geometry!(LinearGeometry3d, 3, Triangle, Mesh33, Mesh32);
-create_geometry!(LinearGeometry2d, 2, Edge, Mesh22, Mesh21);
+create_geometry!(LinearGeometry3d, 3, Triangle, Mesh33, Tetrahedron, Mesh32);
+create_geometry!(LinearGeometry2d, 2, Edge, Mesh22, Triangle, Mesh21);
+
+#[pymethods]
+impl LinearGeometry3d {
+    /// Refine the mesh used to build the geometry, `mesh` being the mesh the geometry was
+    /// created for, until its edges are shorter than `h_max` and / or, within each tag, until
+    /// `l * k < r_h` where `k` is the curvature along an edge of length `l` (so that the chordal
+    /// error is about `r_h^2 / (8 k)`), or `max_iter` passes (default: 10) have been made.
+    /// The new vertices are moved off the chords using the curvature estimated from the vertex
+    /// normals. The geometry is then rebuilt (so `compute_curvature()` needs to be called again)
+    /// Return the new number of triangles
+    pub fn refine_to(
+        &mut self,
+        mesh: &Mesh33,
+        h_max: Option<f64>,
+        r_h: Option<f64>,
+        max_iter: Option<u32>,
+    ) -> PyResult<Idx> {
+        if h_max.is_none() && r_h.is_none() {
+            return Err(PyValueError::new_err("h_max or r_h must be given"));
+        }
+        if h_max.is_some_and(|h| h <= 0.0) || r_h.is_some_and(|r| r <= 0.0) {
+            return Err(PyValueError::new_err("h_max and r_h must be positive"));
+        }
+
+        for _ in 0..max_iter.unwrap_or(10) {
+            match refine_surface(&self.gmesh, h_max, r_h) {
+                Some(gmesh) => self.gmesh = gmesh,
+                None => break,
+            }
+        }
+        self.geom = LinearGeometry::new(&mesh.mesh, self.gmesh.clone())
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        Ok(self.gmesh.n_elems())
+    }
+
+    /// Get the principal curvatures and directions of the geometry at the vertices of `mesh` that
+    /// lie on the boundary faces (not on the boundary edges or corners), after projection onto
+    /// the geometry. `compute_curvature()` must have been called before.
+    /// Return `(ids, k1, k2, d1, d2)` with the vertex indices, the curvatures (k1 >= k2) and the
+    /// corresponding unit directions
+    #[allow(clippy::type_complexity)]
+    pub fn vertex_curvature<'py>(
+        &self,
+        py: Python<'py>,
+        mesh: &Mesh33,
+    ) -> PyResult<(
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray2<f64>>,
+        Bound<'py, PyArray2<f64>>,
+    )> {
+        let res = vertex_curvature(&self.geom, &mesh.mesh)?;
+
+        let ids = res.iter().map(|x| x.0).collect();
+        let k1 = res.iter().map(|x| x.1.norm()).collect();
+        let k2 = res.iter().map(|x| x.2.map_or(0.0, |v| v.norm())).collect();
+        let d1 = res
+            .iter()
+            .flat_map(|x| x.1.normalize().iter().copied().collect::<Vec<_>>())
+            .collect();
+        let d2 = res
+            .iter()
+            .flat_map(|x| {
+                x.2.map_or(Point::<3>::zeros(), |v| v.normalize())
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok((
+            to_numpy_1d(py, ids),
+            to_numpy_1d(py, k1),
+            to_numpy_1d(py, k2),
+            to_numpy_2d(py, d1, 3),
+            to_numpy_2d(py, d2, 3),
+        ))
+    }
+}
+
+#[pymethods]
+impl LinearGeometry2d {
+    /// Get the curvature of the geometry at the vertices of `mesh` that lie on the boundary
+    /// edges (not on the corners), after projection onto the geometry.
+    /// `compute_curvature()` must have been called before.
+    /// Return `(ids, k, d)` with the vertex indices, the curvatures and the corresponding unit
+    /// directions
+    #[allow(clippy::type_complexity)]
+    pub fn vertex_curvature<'py>(
+        &self,
+        py: Python<'py>,
+        mesh: &Mesh22,
+    ) -> PyResult<(
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray2<f64>>,
+    )> {
+        let res = vertex_curvature(&self.geom, &mesh.mesh)?;
+
+        let ids = res.iter().map(|x| x.0).collect();
+        let k = res.iter().map(|x| x.1.norm()).collect();
+        let d = res
+            .iter()
+            .flat_map(|x| x.1.normalize().iter().copied().collect::<Vec<_>>())
+            .collect();
+
+        Ok((
+            to_numpy_1d(py, ids),
+            to_numpy_1d(py, k),
+            to_numpy_2d(py, d, 2),
+        ))
+    }
+}
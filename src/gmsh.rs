@@ -0,0 +1,383 @@
+use log::warn;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::{BufWriter, Error, ErrorKind, Result, Write},
+    str::FromStr,
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::Elem,
+    Idx, Tag,
+};
+
+/// Gmsh element type of the simplices with `n_verts` vertices
+const fn gmsh_type(n_verts: usize) -> usize {
+    match n_verts {
+        1 => 15,
+        2 => 1,
+        3 => 2,
+        4 => 4,
+        _ => unreachable!(),
+    }
+}
+
+/// Lines of a .msh file, with the line numbers used in the error messages
+struct Lines<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+    line: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            lines: data.lines().enumerate(),
+            line: 0,
+        }
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid .msh file at line {}: {msg}", self.line),
+        )
+    }
+
+    /// Next non empty line, or None at the end of the file
+    fn next_line(&mut self) -> Option<&'a str> {
+        for (i, line) in self.lines.by_ref() {
+            self.line = i + 1;
+            let line = line.trim();
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    /// Values of the next non empty line
+    fn values<T: FromStr>(&mut self) -> Result<Vec<T>> {
+        let line = self
+            .next_line()
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        line.split_whitespace()
+            .map(str::parse)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| self.error(&format!("invalid values: {line}")))
+    }
+
+    /// Values of the next non empty line, that must contain at least `n` values
+    fn values_n<T: FromStr>(&mut self, n: usize) -> Result<Vec<T>> {
+        let res = self.values()?;
+        if res.len() < n {
+            return Err(self.error(&format!("expected {n} values, found {}", res.len())));
+        }
+        Ok(res)
+    }
+
+    /// Skip the lines up to the end of section `name`
+    fn skip_section(&mut self, name: &str) -> Result<()> {
+        let end = format!("$End{name}");
+        while let Some(line) = self.next_line() {
+            if line == end {
+                return Ok(());
+            }
+        }
+        Err(self.error(&format!("missing {end}")))
+    }
+}
+
+/// Physical tag of each entity, indexed by `(dim, entity tag)`: its first physical tag if any,
+/// and the entity tag otherwise
+fn read_entities(lines: &mut Lines<'_>) -> Result<HashMap<(usize, i64), i64>> {
+    let counts = lines.values_n::<usize>(4)?;
+    let mut res = HashMap::new();
+    for (dim, &n) in counts.iter().enumerate().take(4) {
+        for _ in 0..n {
+            let values = lines.values::<f64>()?;
+            // points: tag, x, y, z; others: tag and bounding box
+            let offset = if dim == 0 { 4 } else { 7 };
+            if values.len() <= offset {
+                return Err(lines.error("truncated entity"));
+            }
+            let tag = values[0] as i64;
+            let n_phys = values[offset] as usize;
+            let phys = if n_phys > 0 && values.len() > offset + 1 {
+                values[offset + 1] as i64
+            } else {
+                tag
+            };
+            res.insert((dim, tag), phys);
+        }
+    }
+    lines.skip_section("Entities")?;
+    Ok(res)
+}
+
+/// Read the vertices, indexed by their gmsh tags
+fn read_nodes<const D: usize>(
+    lines: &mut Lines<'_>,
+    verts: &mut Vec<Point<D>>,
+    ids: &mut HashMap<usize, Idx>,
+) -> Result<()> {
+    let header = lines.values_n::<usize>(4)?;
+    verts.reserve(header[1]);
+    for _ in 0..header[0] {
+        let block = lines.values_n::<usize>(4)?;
+        let (entity_dim, parametric, n) = (block[0], block[2], block[3]);
+        let n_values = 3 + if parametric == 1 { entity_dim } else { 0 };
+        let mut tags = Vec::with_capacity(n);
+        for _ in 0..n {
+            tags.push(lines.values_n::<usize>(1)?[0]);
+        }
+        for tag in tags {
+            let coords = lines.values_n::<f64>(n_values)?;
+            if D < 3 && coords[D..3].iter().any(|&x| x != 0.0) {
+                return Err(lines.error(&format!("vertex {tag} is not in the {D}D space")));
+            }
+            ids.insert(tag, verts.len() as Idx);
+            verts.push(Point::<D>::from_column_slice(&coords[..D]));
+        }
+    }
+    lines.skip_section("Nodes")
+}
+
+/// Elements and faces of a mesh, with their tags
+struct Elems {
+    elems: Vec<Idx>,
+    etags: Vec<Tag>,
+    faces: Vec<Idx>,
+    ftags: Vec<Tag>,
+}
+
+/// Read the elements of type `E` and `E::Face`, the other ones being ignored
+fn read_elements<E: Elem>(
+    lines: &mut Lines<'_>,
+    entities: &HashMap<(usize, i64), i64>,
+    ids: &HashMap<usize, Idx>,
+    res: &mut Elems,
+) -> Result<()> {
+    let elem_type = gmsh_type(E::N_VERTS as usize);
+    let face_type = gmsh_type(E::Face::N_VERTS as usize);
+    let header = lines.values_n::<usize>(4)?;
+    let mut n_ignored = 0;
+    for _ in 0..header[0] {
+        let block = lines.values_n::<i64>(4)?;
+        let (dim, entity, etype, n) = (block[0] as usize, block[1], block[2], block[3]);
+        let (conn, tags, n_verts) = if etype == elem_type as i64 {
+            (&mut res.elems, &mut res.etags, E::N_VERTS as usize)
+        } else if etype == face_type as i64 {
+            (&mut res.faces, &mut res.ftags, E::Face::N_VERTS as usize)
+        } else {
+            for _ in 0..n {
+                lines.values::<usize>()?;
+            }
+            n_ignored += n;
+            continue;
+        };
+        let tag = entities.get(&(dim, entity)).copied().unwrap_or(entity);
+        let tag = Tag::try_from(tag).map_err(|_| lines.error(&format!("invalid tag {tag}")))?;
+        for _ in 0..n {
+            let values = lines.values_n::<usize>(n_verts + 1)?;
+            for v in &values[1..=n_verts] {
+                let i = ids
+                    .get(v)
+                    .ok_or_else(|| lines.error(&format!("unknown vertex {v}")))?;
+                conn.push(*i);
+            }
+            tags.push(tag);
+        }
+    }
+    if n_ignored > 0 {
+        warn!("{n_ignored} elements of other types ignored");
+    }
+    lines.skip_section("Elements")
+}
+
+/// Read a mesh from a .msh file (ASCII, format 4.1). The elements of type `E` and `E::Face`
+/// are read, with the first physical tag of their entity as tag (or the entity tag if it has no
+/// physical tag); the other elements are ignored
+pub fn read_gmsh<const D: usize, E: Elem>(fname: &str) -> Result<SimplexMesh<D, E>> {
+    let data = fs::read(fname)?;
+    let data = std::str::from_utf8(&data).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "binary .msh files are not supported",
+        )
+    })?;
+    let mut lines = Lines::new(data);
+
+    let mut entities = HashMap::new();
+    let mut verts = Vec::new();
+    let mut ids = HashMap::new();
+    let mut elems = Elems {
+        elems: Vec::new(),
+        etags: Vec::new(),
+        faces: Vec::new(),
+        ftags: Vec::new(),
+    };
+    let mut has_format = false;
+    while let Some(line) = lines.next_line() {
+        match line {
+            "$MeshFormat" => {
+                let format = lines.values::<String>()?;
+                if format.len() < 3 || format[0] != "4.1" {
+                    return Err(lines.error("only the format 4.1 is supported"));
+                }
+                if format[1] != "0" {
+                    return Err(lines.error("binary .msh files are not supported"));
+                }
+                lines.skip_section("MeshFormat")?;
+                has_format = true;
+            }
+            "$Entities" => entities = read_entities(&mut lines)?,
+            "$Nodes" => read_nodes(&mut lines, &mut verts, &mut ids)?,
+            "$Elements" => read_elements::<E>(&mut lines, &entities, &ids, &mut elems)?,
+            _ => {
+                let Some(name) = line.strip_prefix('$') else {
+                    return Err(lines.error(&format!("unexpected line {line}")));
+                };
+                lines.skip_section(name)?;
+            }
+        }
+    }
+    if !has_format {
+        return Err(lines.error("missing $MeshFormat"));
+    }
+
+    let elems_conn = elems
+        .elems
+        .chunks(E::N_VERTS as usize)
+        .map(E::from_slice)
+        .collect();
+    let faces_conn = elems
+        .faces
+        .chunks(E::Face::N_VERTS as usize)
+        .map(E::Face::from_slice)
+        .collect();
+    Ok(SimplexMesh::new(
+        verts,
+        elems_conn,
+        elems.etags,
+        faces_conn,
+        elems.ftags,
+    ))
+}
+
+/// Write the entities of the elements of dimension `dim`, one per tag, with the tag as physical
+/// tag, and return the entity tag of each tag
+fn write_entities<W: Write>(
+    w: &mut W,
+    dim: usize,
+    tags: &BTreeSet<Tag>,
+    bbox: &[f64; 6],
+) -> Result<HashMap<Tag, usize>> {
+    let mut res = HashMap::new();
+    for (i, &tag) in tags.iter().enumerate() {
+        if dim == 0 {
+            write!(w, "{} {} {} {}", i + 1, bbox[0], bbox[1], bbox[2])?;
+            writeln!(w, " 1 {tag}")?;
+        } else {
+            write!(w, "{}", i + 1)?;
+            for x in bbox {
+                write!(w, " {x}")?;
+            }
+            writeln!(w, " 1 {tag} 0")?;
+        }
+        res.insert(tag, i + 1);
+    }
+    Ok(res)
+}
+
+/// Write the element blocks of dimension `dim`, one per tag
+fn write_blocks<W: Write, F: Elem>(
+    w: &mut W,
+    dim: usize,
+    conn: &[(F, Tag)],
+    entities: &HashMap<Tag, usize>,
+    next_id: &mut usize,
+) -> Result<()> {
+    let mut tags: Vec<_> = entities.iter().collect();
+    tags.sort_unstable();
+    for (&tag, &entity) in tags {
+        let n = conn.iter().filter(|(_, t)| *t == tag).count();
+        writeln!(w, "{dim} {entity} {} {n}", gmsh_type(F::N_VERTS as usize))?;
+        for (e, _) in conn.iter().filter(|(_, t)| *t == tag) {
+            write!(w, "{next_id}")?;
+            for i in *e {
+                write!(w, " {}", i + 1)?;
+            }
+            writeln!(w)?;
+            *next_id += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Write a mesh to an ASCII .msh file (format 4.1), with one entity per element or face tag,
+/// the tags being stored as physical tags
+pub fn write_gmsh<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>, fname: &str) -> Result<()> {
+    let mut w = BufWriter::new(File::create(fname)?);
+    let elem_dim = E::N_VERTS as usize - 1;
+    let face_dim = E::Face::N_VERTS as usize - 1;
+
+    let mut bbox = [0.0; 6];
+    for (i, p) in mesh.verts().enumerate() {
+        for (d, &x) in p.iter().enumerate() {
+            if i == 0 || x < bbox[d] {
+                bbox[d] = x;
+            }
+            if i == 0 || x > bbox[3 + d] {
+                bbox[3 + d] = x;
+            }
+        }
+    }
+
+    writeln!(w, "$MeshFormat\n4.1 0 8\n$EndMeshFormat")?;
+
+    // the vertices are stored in the first element entity, that is created if there is none
+    let mut etags: BTreeSet<Tag> = mesh.etags().collect();
+    if etags.is_empty() {
+        etags.insert(1);
+    }
+    let ftags: BTreeSet<Tag> = mesh.ftags().collect();
+    let mut counts = [0; 4];
+    counts[elem_dim] += etags.len();
+    counts[face_dim] += ftags.len();
+    writeln!(w, "$Entities")?;
+    writeln!(w, "{} {} {} {}", counts[0], counts[1], counts[2], counts[3])?;
+    let face_entities = write_entities(&mut w, face_dim, &ftags, &bbox)?;
+    let elem_entities = write_entities(&mut w, elem_dim, &etags, &bbox)?;
+    writeln!(w, "$EndEntities")?;
+
+    let n_verts = mesh.n_verts() as usize;
+    writeln!(w, "$Nodes")?;
+    writeln!(w, "1 {n_verts} 1 {n_verts}")?;
+    writeln!(w, "{elem_dim} 1 0 {n_verts}")?;
+    for i in 0..n_verts {
+        writeln!(w, "{}", i + 1)?;
+    }
+    for p in mesh.verts() {
+        let mut xyz = [0.0; 3];
+        xyz[..D].copy_from_slice(p.as_slice());
+        writeln!(w, "{} {} {}", xyz[0], xyz[1], xyz[2])?;
+    }
+    writeln!(w, "$EndNodes")?;
+
+    let n_elems = (mesh.n_elems() + mesh.n_faces()) as usize;
+    let used_etags: BTreeSet<Tag> = mesh.etags().collect();
+    let n_blocks = used_etags.len() + ftags.len();
+    writeln!(w, "$Elements")?;
+    writeln!(w, "{n_blocks} {n_elems} 1 {n_elems}")?;
+    let mut next_id = 1;
+    let faces: Vec<_> = mesh.faces().zip(mesh.ftags()).collect();
+    write_blocks(&mut w, face_dim, &faces, &face_entities, &mut next_id)?;
+    let elem_entities = elem_entities
+        .into_iter()
+        .filter(|(tag, _)| used_etags.contains(tag))
+        .collect();
+    let elems: Vec<_> = mesh.elems().zip(mesh.etags()).collect();
+    write_blocks(&mut w, elem_dim, &elems, &elem_entities, &mut next_id)?;
+    writeln!(w, "$EndElements")?;
+    w.flush()
+}
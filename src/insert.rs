@@ -0,0 +1,317 @@
+use crate::smooth::tet_vol;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::{Elem, Tetrahedron, Triangle},
+    Idx, Tag,
+};
+
+/// Max number of swaps tried per inserted vertex
+const MAX_SWAPS: usize = 1000;
+
+/// Sorted vertices used as a face key
+fn face_key(f: &[Idx; 3]) -> [Idx; 3] {
+    let mut res = *f;
+    res.sort_unstable();
+    res
+}
+
+/// Quality of a tetrahedron, between 0 (degenerate or inverted) and 1 (regular)
+fn tet_quality(verts: &[Point<3>], e: &[Idx; 4]) -> f64 {
+    let vol = tet_vol(verts, e);
+    if vol <= 0.0 {
+        return 0.0;
+    }
+    let mut l2 = 0.0;
+    for i in 0..4 {
+        for j in i + 1..4 {
+            l2 += (verts[e[j] as usize] - verts[e[i] as usize]).norm_squared();
+        }
+    }
+    12.0 * (3.0 * vol).powf(2.0 / 3.0) / l2
+}
+
+/// Working copy of a tetrahedral mesh in which vertices are inserted
+struct Inserter {
+    verts: Vec<Point<3>>,
+    elems: Vec<[Idx; 4]>,
+    etags: Vec<Tag>,
+    faces: Vec<[Idx; 3]>,
+    ftags: Vec<Tag>,
+    v2e: Vec<Vec<usize>>,
+    /// Index of the tagged faces, by sorted vertices
+    tagged: HashMap<[Idx; 3], usize>,
+}
+
+impl Inserter {
+    fn new(mesh: &SimplexMesh<3, Tetrahedron>) -> Self {
+        let verts: Vec<_> = mesh.verts().collect();
+        let elems: Vec<[Idx; 4]> = mesh
+            .elems()
+            .map(|e| {
+                let e: Vec<_> = e.into_iter().collect();
+                [e[0], e[1], e[2], e[3]]
+            })
+            .collect();
+        let faces: Vec<[Idx; 3]> = mesh
+            .faces()
+            .map(|f| {
+                let f: Vec<_> = f.into_iter().collect();
+                [f[0], f[1], f[2]]
+            })
+            .collect();
+        let mut v2e = vec![Vec::new(); verts.len()];
+        for (i_elem, e) in elems.iter().enumerate() {
+            for &i in e {
+                v2e[i as usize].push(i_elem);
+            }
+        }
+        let tagged = faces
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (face_key(f), i))
+            .collect();
+        Self {
+            verts,
+            elems,
+            etags: mesh.etags().collect(),
+            faces,
+            ftags: mesh.ftags().collect(),
+            v2e,
+            tagged,
+        }
+    }
+
+    /// Replace element `i` (or add a new element if `i` is the number of elements)
+    fn set_elem(&mut self, i: usize, e: [Idx; 4], tag: Tag) {
+        if i == self.elems.len() {
+            self.elems.push(e);
+            self.etags.push(tag);
+        } else {
+            for &k in &self.elems[i] {
+                self.v2e[k as usize].retain(|&j| j != i);
+            }
+            self.elems[i] = e;
+            self.etags[i] = tag;
+        }
+        for &k in &e {
+            self.v2e[k as usize].push(i);
+        }
+    }
+
+    /// Replace face `i` (or add a new face if `i` is the number of faces)
+    fn set_face(&mut self, i: usize, f: [Idx; 3], tag: Tag) {
+        if i == self.faces.len() {
+            self.faces.push(f);
+            self.ftags.push(tag);
+        } else {
+            self.tagged.remove(&face_key(&self.faces[i]));
+            self.faces[i] = f;
+            self.ftags[i] = tag;
+        }
+        self.tagged.insert(face_key(&f), i);
+    }
+
+    /// Elements that contain all the vertices `verts`
+    fn elems_around(&self, verts: &[Idx]) -> Vec<usize> {
+        self.v2e[verts[0] as usize]
+            .iter()
+            .copied()
+            .filter(|&i| verts.iter().all(|k| self.elems[i].contains(k)))
+            .collect()
+    }
+
+    /// Signed volume of element `e` with its vertex `j` replaced by `p`
+    fn sub_vol(&self, e: &[Idx; 4], j: usize, p: &Point<3>) -> f64 {
+        let verts: [Point<3>; 4] = std::array::from_fn(|k| {
+            if k == j {
+                *p
+            } else {
+                self.verts[e[k] as usize]
+            }
+        });
+        tet_vol(&verts, &[0, 1, 2, 3])
+    }
+
+    /// Find the element that contains `p`, up to a distance `tol` to its faces, and the signed
+    /// distances of `p` to its faces (the face `j` being opposite to the vertex `j`)
+    fn locate(&self, p: &Point<3>, tol: f64) -> Option<(usize, [f64; 4])> {
+        self.elems
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let dists: [f64; 4] = std::array::from_fn(|j| {
+                    let [a, b, c] = [1, 2, 3].map(|k| self.verts[e[(j + k) % 4] as usize]);
+                    let area = 0.5 * (b - a).cross(&(c - a)).norm();
+                    3.0 * self.sub_vol(e, j, p) / area
+                });
+                let d_min = dists.iter().copied().fold(f64::INFINITY, f64::min);
+                (d_min >= -tol).then_some((i, dists, d_min))
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(i, dists, _)| (i, dists))
+    }
+
+    /// Split the elements (and tagged faces) that contain the simplex `simplex` by inserting a
+    /// new vertex at `p`, that must lie inside it: each vertex of the simplex is replaced in
+    /// turn by the new vertex
+    fn split(&mut self, simplex: &[Idx], p: Point<3>) -> Idx {
+        let elems = self.elems_around(simplex);
+        let candidates: Vec<[Idx; 3]> = match simplex.len() {
+            3 => vec![[simplex[0], simplex[1], simplex[2]]],
+            2 => elems
+                .iter()
+                .flat_map(|&i| self.elems[i])
+                .filter(|k| !simplex.contains(k))
+                .map(|k| [simplex[0], simplex[1], k])
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut faces: Vec<usize> = candidates
+            .iter()
+            .filter_map(|f| self.tagged.get(&face_key(f)).copied())
+            .collect();
+        faces.sort_unstable();
+        faces.dedup();
+
+        let new = self.verts.len() as Idx;
+        self.verts.push(p);
+        self.v2e.push(Vec::new());
+        for i in elems {
+            let (e, tag) = (self.elems[i], self.etags[i]);
+            for (n, v) in simplex.iter().enumerate() {
+                let sub = e.map(|k| if k == *v { new } else { k });
+                self.set_elem(if n == 0 { i } else { self.elems.len() }, sub, tag);
+            }
+        }
+        for i in faces {
+            let (f, tag) = (self.faces[i], self.ftags[i]);
+            for (n, v) in simplex.iter().enumerate() {
+                let sub = f.map(|k| if k == *v { new } else { k });
+                self.set_face(if n == 0 { i } else { self.faces.len() }, sub, tag);
+            }
+        }
+        new
+    }
+
+    /// Try to swap the face of element `i` opposite to its vertex `v` (2-3 swap), if it is not
+    /// tagged and if the min quality is improved. Return the modified elements
+    fn try_swap(&mut self, i: usize, v: Idx) -> Option<[usize; 3]> {
+        let e = self.elems[i];
+        let j = e.iter().position(|&k| k == v)?;
+        let f = [e[(j + 1) % 4], e[(j + 2) % 4], e[(j + 3) % 4]];
+        if self.tagged.contains_key(&face_key(&f)) {
+            return None;
+        }
+        let other = self.elems_around(&f).into_iter().find(|&k| k != i)?;
+        if self.etags[other] != self.etags[i] {
+            return None;
+        }
+        let q = self.elems[other].into_iter().find(|k| !f.contains(k))?;
+
+        let new = f.map(|x| e.map(|k| if k == x { q } else { k }));
+        let q_old = tet_quality(&self.verts, &e).min(tet_quality(&self.verts, &self.elems[other]));
+        let q_new = new
+            .iter()
+            .map(|e| tet_quality(&self.verts, e))
+            .fold(f64::INFINITY, f64::min);
+        if q_new <= q_old + 1e-12 {
+            return None;
+        }
+
+        let tag = self.etags[i];
+        let added = self.elems.len();
+        self.set_elem(i, new[0], tag);
+        self.set_elem(other, new[1], tag);
+        self.set_elem(added, new[2], tag);
+        Some([i, other, added])
+    }
+
+    /// Improve the quality of the elements around vertex `v` with 2-3 swaps of their faces
+    /// opposite to `v`
+    fn swap_around(&mut self, v: Idx) {
+        let mut stack = self.v2e[v as usize].clone();
+        let mut n_swaps = 0;
+        while let Some(i) = stack.pop() {
+            if n_swaps == MAX_SWAPS {
+                break;
+            }
+            if let Some(modified) = self.try_swap(i, v) {
+                stack.extend(modified);
+                n_swaps += 1;
+            }
+        }
+    }
+
+    /// Insert a vertex at `p`, or use the nearest vertex if it is closer than `tol`. Points at a
+    /// distance smaller than `tol` to a face or an edge are projected onto it.
+    /// Return `None` if `p` is outside of the mesh
+    fn insert(&mut self, p: &Point<3>, tol: f64) -> Option<Idx> {
+        let nearest = self
+            .verts
+            .par_iter()
+            .enumerate()
+            .map(|(i, q)| ((q - p).norm(), i))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((d, i)) = nearest {
+            if d <= tol {
+                return Some(i as Idx);
+            }
+        }
+
+        let (i, dists) = self.locate(p, tol)?;
+        let e = self.elems[i];
+        let simplex: Vec<Idx> = (0..4).filter(|&j| dists[j] > tol).map(|j| e[j]).collect();
+        if simplex.len() <= 1 {
+            return nearest.map(|(_, i)| i as Idx);
+        }
+        let p = if simplex.len() == 4 {
+            *p
+        } else {
+            // projection using the barycentric coordinates of the vertices of the simplex
+            let mut res = Point::<3>::zeros();
+            let mut w_sum = 0.0;
+            for j in (0..4).filter(|&j| dists[j] > tol) {
+                let w = self.sub_vol(&e, j, p);
+                res += w * self.verts[e[j] as usize];
+                w_sum += w;
+            }
+            res / w_sum
+        };
+
+        let new = self.split(&simplex, p);
+        self.swap_around(new);
+        Some(new)
+    }
+
+    /// Build the mesh
+    fn into_mesh(self) -> SimplexMesh<3, Tetrahedron> {
+        SimplexMesh::new(
+            self.verts,
+            self.elems
+                .iter()
+                .map(|e| Tetrahedron::from_slice(e))
+                .collect(),
+            self.etags,
+            self.faces.iter().map(|f| Triangle::from_slice(f)).collect(),
+            self.ftags,
+        )
+    }
+}
+
+/// Insert vertices at the locations `points` of a tetrahedral mesh, one at a time: the element,
+/// face or edge that contains a point is split (points closer than `tol` to a face or an edge
+/// being projected onto it), and the quality around the new vertex is then improved with 2-3
+/// face swaps. Points closer than `tol` to an existing vertex are snapped to it.
+/// Return the new mesh (the existing vertices keeping their indices) and the index of the vertex
+/// at each point, or `None` for the points outside of the mesh
+pub fn insert_points(
+    mesh: &SimplexMesh<3, Tetrahedron>,
+    points: &[Point<3>],
+    tol: f64,
+) -> (SimplexMesh<3, Tetrahedron>, Vec<Option<Idx>>) {
+    let mut inserter = Inserter::new(mesh);
+    let ids = points.iter().map(|p| inserter.insert(p, tol)).collect();
+    (inserter.into_mesh(), ids)
+}
@@ -1,9 +1,32 @@
+mod background;
+mod cache;
+mod cleanup;
+mod distance;
+mod field;
 mod geometry;
+#[cfg(feature = "gmsh")]
+mod gmsh;
+mod insert;
 mod mesh;
+mod metric;
+mod p2;
 mod parallel;
+mod params;
+mod refine;
 mod remesher;
-use numpy::{PyArray, PyArray1, PyArray2, PyArrayMethods};
-use pyo3::{pymodule, types::PyModule, Bound, PyResult, Python};
+mod sdf;
+mod smooth;
+mod solb;
+mod stl;
+mod tags;
+mod tree;
+mod view;
+mod vtu;
+#[cfg(feature = "xdmf")]
+mod xdmf;
+use numpy::{dtype_bound, PyArray, PyArray1, PyArray2, PyArrayMethods};
+use pyo3::{pymodule, types::PyModule, wrap_pyfunction, Bound, PyResult, Python};
+use tucanos::{Idx, Tag};
 
 fn to_numpy_1d<T: numpy::Element>(py: Python<'_>, vec: Vec<T>) -> Bound<'_, PyArray1<T>> {
     PyArray::from_vec_bound(py, vec)
@@ -19,23 +42,50 @@ fn to_numpy_2d<T: numpy::Element>(py: Python<'_>, vec: Vec<T>, m: usize) -> Boun
 /// Python bindings for pytucanos
 #[pymodule]
 #[pyo3(name = "_pytucanos")]
-pub fn pytucanos(_py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
+pub fn pytucanos(py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
     pyo3_log::init();
     m.add_class::<crate::mesh::Mesh33>()?;
     m.add_class::<crate::mesh::Mesh32>()?;
     m.add_class::<crate::mesh::Mesh31>()?;
     m.add_class::<crate::mesh::Mesh22>()?;
     m.add_class::<crate::mesh::Mesh21>()?;
+    m.add_class::<crate::view::MeshView33>()?;
+    m.add_class::<crate::view::MeshView22>()?;
+    m.add_class::<crate::tree::ElemTree>()?;
+    m.add_class::<crate::tree::VertTree>()?;
     m.add_class::<crate::geometry::LinearGeometry2d>()?;
     m.add_class::<crate::geometry::LinearGeometry3d>()?;
     m.add_class::<crate::remesher::Remesher2dIso>()?;
     m.add_class::<crate::remesher::Remesher2dAniso>()?;
     m.add_class::<crate::remesher::Remesher3dIso>()?;
     m.add_class::<crate::remesher::Remesher3dAniso>()?;
+    m.add_class::<crate::background::BackgroundMetric2d>()?;
+    m.add_class::<crate::background::BackgroundMetric3d>()?;
     m.add_class::<crate::parallel::ParallelRemesher2dIso>()?;
     m.add_class::<crate::parallel::ParallelRemesher2dAniso>()?;
     m.add_class::<crate::parallel::ParallelRemesher3dIso>()?;
     m.add_class::<crate::parallel::ParallelRemesher3dAniso>()?;
+    m.add_class::<crate::params::PyRemesherParams>()?;
+    m.add_class::<crate::params::PyParallelRemeshingParams>()?;
+    m.add_function(wrap_pyfunction!(crate::field::field_shape, m)?)?;
+    m.add_function(wrap_pyfunction!(crate::field::symtensor_to_full, m)?)?;
+    m.add_function(wrap_pyfunction!(crate::field::full_to_symtensor, m)?)?;
+    m.add_function(wrap_pyfunction!(crate::field::read_solb, m)?)?;
+    m.add_function(wrap_pyfunction!(crate::tags::compare_tags, m)?)?;
+    m.add_function(wrap_pyfunction!(crate::distance::hausdorff_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        crate::parallel::available_partitioners,
+        m
+    )?)?;
+    m.add("FIELD_KINDS", crate::field::FIELD_KINDS)?;
+    m.add("SYMTENSOR_2D", crate::field::SYMTENSOR_2D)?;
+    m.add("SYMTENSOR_3D", crate::field::SYMTENSOR_3D)?;
+    m.add("IDX_DTYPE", dtype_bound::<Idx>(py))?;
+    m.add("TAG_DTYPE", dtype_bound::<Tag>(py))?;
+    m.add("TAG_MIN", crate::tags::TAG_MIN)?;
+    m.add("TAG_MAX", crate::tags::TAG_MAX)?;
+    m.add("VERSION", env!("CARGO_PKG_VERSION"))?;
+    m.add("TUCANOS_VERSION", env!("TUCANOS_VERSION"))?;
     #[cfg(not(feature = "metis"))]
     m.add("HAVE_METIS", false)?;
     #[cfg(feature = "metis")]
@@ -44,6 +94,14 @@ pub fn pytucanos(_py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
     m.add("HAVE_SCOTCH", false)?;
     #[cfg(feature = "scotch")]
     m.add("HAVE_SCOTCH", true)?;
+    #[cfg(not(feature = "gmsh"))]
+    m.add("HAVE_GMSH", false)?;
+    #[cfg(feature = "gmsh")]
+    m.add("HAVE_GMSH", true)?;
+    #[cfg(not(feature = "xdmf"))]
+    m.add("HAVE_XDMF", false)?;
+    #[cfg(feature = "xdmf")]
+    m.add("HAVE_XDMF", true)?;
     #[cfg(not(feature = "libmeshb"))]
     m.add("HAVE_LIBMESHB", false)?;
     #[cfg(feature = "libmeshb")]
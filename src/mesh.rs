@@ -1,46 +1,1448 @@
+#[cfg(feature = "gmsh")]
+use crate::gmsh::{read_gmsh, write_gmsh};
+#[cfg(feature = "xdmf")]
+use crate::xdmf::{read_xdmf, write_xdmf};
 use crate::{
+    cache::{content_hash, read_cache, write_cache},
+    cleanup::{collapse_small_edges, fill_holes},
+    field::sym_index,
     geometry::{LinearGeometry2d, LinearGeometry3d},
+    insert::insert_points,
+    p2::P2Mesh,
+    refine::refine_boundary,
+    remesher::{Remesher2dAniso, Remesher2dIso, Remesher3dAniso, Remesher3dIso},
+    sdf::mesh_from_sdf,
+    smooth::{smooth_boundary, untangle},
+    solb::{
+        read_elem_solb, read_solb_metadata, solb_at_elems, write_elem_solb, write_meshb_f32,
+        write_solb_metadata, SolbMetadata,
+    },
+    stl::read_stl,
+    tags::{tag_values, TagArray},
     to_numpy_1d, to_numpy_2d,
+    tree::{ElemTree, VertTree},
+    vtu::write_vtu_f32,
 };
+use log::warn;
 use numpy::{
     PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2, PyUntypedArrayMethods,
 };
 use pyo3::{
     exceptions::{PyRuntimeError, PyValueError},
-    prelude::PyDictMethods,
+    prelude::{PyAnyMethods, PyDictMethods},
     pyclass, pymethods,
-    types::{PyDict, PyType},
-    Bound, PyResult, Python,
+    types::{PyAny, PyDict, PyType},
+    Bound, IntoPy, Py, PyObject, PyResult, Python,
+};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering},
 };
-use std::collections::HashMap;
 use tucanos::{
     geom_elems::GElem,
     mesh::Point,
     mesh::SimplexMesh,
-    mesh_stl::read_stl,
-    metric::Metric,
+    metric::{AnisoMetric2d, AnisoMetric3d, IsoMetric, Metric},
+    spatialindex::ObjectIndex,
     topo_elems::{Edge, Elem, Tetrahedron, Triangle},
     Idx, Tag,
 };
 
+/// Check that `start..end` is a valid range of the `n` entities called `name`
+fn check_range(name: &str, start: Idx, end: Idx, n: Idx) -> PyResult<()> {
+    if start > end || end > n {
+        return Err(PyValueError::new_err(format!(
+            "Invalid range {start}..{end} for {n} {name}"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that `ids` are valid indices of the `n` entities called `name`
+fn check_indices(name: &str, ids: &[Idx], n: Idx) -> PyResult<()> {
+    if let Some(i) = ids.iter().find(|&&i| i >= n) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid index {i} for {n} {name}"
+        )));
+    }
+    Ok(())
+}
+
+/// Compute the inverse of a permutation
+fn inverse_permutation(perm: &[Idx]) -> Vec<Idx> {
+    let mut res = vec![0; perm.len()];
+    for (i, &j) in perm.iter().enumerate() {
+        res[j as usize] = i as Idx;
+    }
+    res
+}
+
+/// Counter used to give a unique version to the meshes
+static MESH_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Get a new mesh version
+fn next_mesh_version() -> u64 {
+    MESH_VERSION.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// Select entities from the (n, dim) array of their centers. `selector` is either a callable, called
+/// once on the whole array and returning a boolean array of shape (n,), or an axis-aligned box
+/// given as a pair `(min, max)` of coordinates
+fn select_where(
+    py: Python<'_>,
+    centers: Vec<f64>,
+    dim: usize,
+    selector: &Bound<'_, PyAny>,
+) -> PyResult<Vec<bool>> {
+    let n = centers.len() / dim;
+    if selector.is_callable() {
+        let res = selector.call1((to_numpy_2d(py, centers, dim),))?;
+        let res = res.extract::<PyReadonlyArray1<bool>>()?;
+        if res.shape()[0] != n {
+            return Err(PyValueError::new_err(
+                "Invalid dimension 0 for the selection",
+            ));
+        }
+        Ok(res.to_vec()?)
+    } else {
+        let (lo, hi) = selector.extract::<(Vec<f64>, Vec<f64>)>()?;
+        if lo.len() != dim || hi.len() != dim {
+            return Err(PyValueError::new_err("Invalid dimension for the box"));
+        }
+        Ok(centers
+            .chunks(dim)
+            .map(|c| {
+                c.iter()
+                    .zip(lo.iter().zip(hi.iter()))
+                    .all(|(x, (l, h))| l <= x && x <= h)
+            })
+            .collect())
+    }
+}
+
+/// Faces of a given tag, renumbered
+struct BoundaryGroup<F> {
+    /// Local index of the vertices of the parent mesh
+    new_idx: HashMap<Idx, Idx>,
+    /// Indices of the vertices in the parent mesh
+    vert_ids: Vec<Idx>,
+    /// Renumbered faces
+    elems: Vec<F>,
+    /// Indices of the faces in the parent mesh
+    face_ids: Vec<Idx>,
+}
+
+/// Extract the boundary faces of each tag (optionally only for the tags in `tags`) into a mesh in a
+/// single pass over the faces, and return the indices of its vertices and faces in the parent mesh
+#[allow(clippy::type_complexity)]
+pub fn boundary_per_tag<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    tags: Option<&[Tag]>,
+) -> Vec<(Tag, SimplexMesh<D, E::Face>, Vec<Idx>, Vec<Idx>)> {
+    let mut groups = BTreeMap::new();
+    for (i_face, (f, tag)) in mesh.faces().zip(mesh.ftags()).enumerate() {
+        if tags.is_some_and(|tags| !tags.contains(&tag)) {
+            continue;
+        }
+        let group = groups.entry(tag).or_insert_with(|| BoundaryGroup {
+            new_idx: HashMap::new(),
+            vert_ids: Vec::new(),
+            elems: Vec::new(),
+            face_ids: Vec::new(),
+        });
+        let ids: Vec<Idx> = f
+            .into_iter()
+            .map(|i| {
+                *group.new_idx.entry(i).or_insert_with(|| {
+                    group.vert_ids.push(i);
+                    group.vert_ids.len() as Idx - 1
+                })
+            })
+            .collect();
+        group.elems.push(E::Face::from_slice(&ids));
+        group.face_ids.push(i_face as Idx);
+    }
+
+    let verts: Vec<Point<D>> = mesh.verts().collect();
+    groups
+        .into_iter()
+        .map(|(tag, group)| {
+            let n = group.elems.len();
+            let bdy = SimplexMesh::new(
+                group.vert_ids.iter().map(|&i| verts[i as usize]).collect(),
+                group.elems,
+                vec![tag; n],
+                Vec::new(),
+                Vec::new(),
+            );
+            (tag, bdy, group.vert_ids, group.face_ids)
+        })
+        .collect()
+}
+
+/// Compute the `p`-th percentile (`0 <= p <= 100`) of `values` using a selection algorithm, with
+/// the same linear interpolation as `numpy.percentile`, or NaN if `values` is empty. `values` is
+/// reordered in the process
+#[allow(clippy::cast_sign_loss)]
+fn compute_percentile(values: &mut [f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p / 100.0 * (values.len() - 1) as f64;
+    let i = rank.floor() as usize;
+    let (_, &mut v_lo, upper) = values.select_nth_unstable_by(i, f64::total_cmp);
+    if upper.is_empty() {
+        v_lo
+    } else {
+        let v_hi = upper.iter().copied().fold(f64::INFINITY, f64::min);
+        v_lo + (rank - i as f64) * (v_hi - v_lo)
+    }
+}
+
+/// Count and volume of the entities per tag
+fn tag_stats<'py>(
+    py: Python<'py>,
+    tags: impl Iterator<Item = Tag>,
+    vols: impl Iterator<Item = f64>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let mut stats = BTreeMap::new();
+    for (tag, vol) in tags.zip(vols) {
+        let e = stats.entry(tag).or_insert((0, 0.0));
+        e.0 += 1;
+        e.1 += vol;
+    }
+    let dict = PyDict::new_bound(py);
+    for (tag, (count, vol)) in stats {
+        let d = PyDict::new_bound(py);
+        d.set_item("count", count)?;
+        d.set_item("volume", vol)?;
+        dict.set_item(tag, d)?;
+    }
+    Ok(dict)
+}
+
+/// Bounding box of the mesh vertices, computed in parallel
+fn bounding_box<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> ([f64; D], [f64; D]) {
+    let verts: Vec<Point<D>> = mesh.verts().collect();
+    verts
+        .par_iter()
+        .fold(
+            || ([f64::INFINITY; D], [f64::NEG_INFINITY; D]),
+            |(mut lo, mut hi), p| {
+                for d in 0..D {
+                    lo[d] = lo[d].min(p[d]);
+                    hi[d] = hi[d].max(p[d]);
+                }
+                (lo, hi)
+            },
+        )
+        .reduce(
+            || ([f64::INFINITY; D], [f64::NEG_INFINITY; D]),
+            |(lo0, hi0), (lo1, hi1)| {
+                (
+                    std::array::from_fn(|d| lo0[d].min(lo1[d])),
+                    std::array::from_fn(|d| hi0[d].max(hi1[d])),
+                )
+            },
+        )
+}
+
+/// Summary of a mesh as a dict, see `info()`
+fn mesh_info<'py, const D: usize, E: Elem>(
+    py: Python<'py>,
+    mesh: &SimplexMesh<D, E>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("dim", D)?;
+    dict.set_item("n_verts", mesh.n_verts())?;
+    dict.set_item("n_elems", mesh.n_elems())?;
+    dict.set_item("n_faces", mesh.n_faces())?;
+
+    let (lo, hi) = bounding_box(mesh);
+    dict.set_item("bounding_box", (lo.to_vec(), hi.to_vec()))?;
+
+    let mut vols = Vec::with_capacity(mesh.n_elems() as usize);
+    let (mut q_min, mut q_max, mut q_sum) = (f64::INFINITY, f64::NEG_INFINITY, 0.0);
+    for ge in mesh.gelems() {
+        vols.push(ge.vol());
+        let q = ge.quality();
+        q_min = q_min.min(q);
+        q_max = q_max.max(q);
+        q_sum += q;
+    }
+    dict.set_item("volume", vols.iter().sum::<f64>())?;
+    dict.set_item("etags", tag_stats(py, mesh.etags(), vols.into_iter())?)?;
+    dict.set_item(
+        "ftags",
+        tag_stats(py, mesh.ftags(), mesh.gfaces().map(|gf| gf.vol()))?,
+    )?;
+
+    let quality = PyDict::new_bound(py);
+    quality.set_item("min", q_min)?;
+    quality.set_item("max", q_max)?;
+    quality.set_item("mean", q_sum / f64::from(mesh.n_elems()))?;
+    dict.set_item("quality", quality)?;
+
+    let cache = PyDict::new_bound(py);
+    cache.set_item("vertex_to_elems", mesh.get_vertex_to_elems().is_ok())?;
+    cache.set_item("face_to_elems", mesh.get_face_to_elems().is_ok())?;
+    cache.set_item("elem_to_elems", mesh.get_elem_to_elems().is_ok())?;
+    cache.set_item("edges", mesh.get_edges().is_ok())?;
+    cache.set_item("vertex_to_vertices", mesh.get_vertex_to_vertices().is_ok())?;
+    cache.set_item("volumes", mesh.get_vol().is_ok())?;
+    cache.set_item("topology", mesh.get_vertex_tags().is_ok())?;
+    dict.set_item("cache", cache)?;
+
+    Ok(dict)
+}
+
+/// Element tags from a bin index per element: bin `i` gets tag `i + 1` or, if `split_components`
+/// is true, each connected component (through the element faces) of the elements of a bin gets
+/// its own tag, numbered from 1 in the order of the elements
+fn tags_from_bins<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    bins: &[usize],
+    split_components: bool,
+) -> PyResult<Vec<Tag>> {
+    let to_tag =
+        |i: usize| Tag::try_from(i + 1).map_err(|_| PyValueError::new_err("Too many tags"));
+    if !split_components {
+        return bins.iter().map(|&b| to_tag(b)).collect();
+    }
+
+    let mut face_to_elems: HashMap<Vec<Idx>, Vec<usize>> = HashMap::new();
+    for (i_elem, e) in mesh.elems().enumerate() {
+        for i_face in 0..E::N_FACES {
+            let mut f: Vec<_> = e.face(i_face).into_iter().collect();
+            f.sort_unstable();
+            face_to_elems.entry(f).or_default().push(i_elem);
+        }
+    }
+    let mut neighbors = vec![Vec::new(); bins.len()];
+    for elems in face_to_elems.values() {
+        if let [i, j] = elems[..] {
+            if bins[i] == bins[j] {
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+    }
+
+    let mut tags: Vec<Option<Tag>> = vec![None; bins.len()];
+    let mut n_components = 0;
+    for start in 0..bins.len() {
+        if tags[start].is_some() {
+            continue;
+        }
+        let tag = to_tag(n_components)?;
+        n_components += 1;
+        tags[start] = Some(tag);
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            for &j in &neighbors[i] {
+                if tags[j].is_none() {
+                    tags[j] = Some(tag);
+                    stack.push(j);
+                }
+            }
+        }
+    }
+
+    Ok(tags.into_iter().flatten().collect())
+}
+
+/// For the faces of a mesh adjacent to a single element, get the index of the face, of the
+/// element and of the face within the element (in the order given by `Elem::face`)
+fn boundary_face_elems<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+) -> (Vec<Idx>, Vec<Idx>, Vec<Idx>) {
+    let face_to_elems = face_to_elems(mesh);
+
+    let mut res = (Vec::new(), Vec::new(), Vec::new());
+    for (i_face, f) in mesh.faces().enumerate() {
+        let mut f: Vec<_> = f.into_iter().collect();
+        f.sort_unstable();
+        if let Some(&[(i_elem, i_local)]) = face_to_elems.get(&f).map(Vec::as_slice) {
+            res.0.push(i_face as Idx);
+            res.1.push(i_elem);
+            res.2.push(i_local);
+        }
+    }
+    res
+}
+
+/// Map the sorted vertices of the faces of all the elements to the `(element, local face)` pairs
+/// that contain them
+fn face_to_elems<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+) -> HashMap<Vec<Idx>, Vec<(Idx, Idx)>> {
+    let mut res: HashMap<Vec<Idx>, Vec<(Idx, Idx)>> = HashMap::new();
+    for (i_elem, e) in mesh.elems().enumerate() {
+        for i_face in 0..E::N_FACES {
+            let mut f: Vec<_> = e.face(i_face).into_iter().collect();
+            f.sort_unstable();
+            res.entry(f).or_default().push((i_elem as Idx, i_face));
+        }
+    }
+    res
+}
+
+/// Flag the vertices of the faces adjacent to a single element. The face to element connectivity
+/// of the mesh is used if it has been computed
+fn boundary_verts<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> Vec<bool> {
+    let mut res = vec![false; mesh.n_verts() as usize];
+    if let Ok(f2e) = mesh.get_face_to_elems() {
+        for (f, elems) in f2e.iter().filter(|(_, elems)| elems.len() == 1) {
+            for i in *f {
+                res[i as usize] = true;
+            }
+        }
+    } else {
+        for (f, elems) in face_to_elems(mesh) {
+            if elems.len() == 1 {
+                for i in f {
+                    res[i as usize] = true;
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Area weighted normals of the faces of a mesh of dimension `D` (2 or 3), and flags of the
+/// boundary faces (adjacent to a single element). The normals follow the orientation of the
+/// faces, except for the boundary faces whose normals point outwards
+fn face_normals<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+) -> PyResult<(Vec<Point<D>>, Vec<bool>)> {
+    if mesh.n_faces() == 0 {
+        return Err(PyRuntimeError::new_err("The faces have not been added"));
+    }
+    let face_to_elems = face_to_elems(mesh);
+    Ok(mesh
+        .faces()
+        .map(|f| {
+            let mut key: Vec<_> = f.into_iter().collect();
+            key.sort_unstable();
+            let (f, is_bdy) = match face_to_elems.get(&key).map(Vec::as_slice) {
+                Some(&[(i_elem, i_face)]) => (mesh.elem(i_elem).face(i_face), true),
+                _ => (f, false),
+            };
+            let p: Vec<Point<D>> = f.into_iter().map(|i| mesh.vert(i)).collect();
+            let u = p[1] - p[0];
+            let mut n = Point::<D>::zeros();
+            if D == 2 {
+                n[0] = u[1];
+                n[1] = -u[0];
+            } else {
+                let v = p[2] - p[0];
+                n[0] = 0.5 * (u[1] * v[2] - u[2] * v[1]);
+                n[1] = 0.5 * (u[2] * v[0] - u[0] * v[2]);
+                n[2] = 0.5 * (u[0] * v[1] - u[1] * v[0]);
+            }
+            (n, is_bdy)
+        })
+        .unzip())
+}
+
+/// Unit normals at the vertices of a mesh: area weighted average of the outward normals of the
+/// adjacent boundary faces, and zero for the other vertices
+fn vertex_normals<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> PyResult<Vec<Point<D>>> {
+    let (normals, is_bdy) = face_normals(mesh)?;
+    let mut res = vec![Point::<D>::zeros(); mesh.n_verts() as usize];
+    for ((f, n), _) in mesh
+        .faces()
+        .zip(normals.iter())
+        .zip(is_bdy.iter())
+        .filter(|(_, &b)| b)
+    {
+        for i in f {
+            res[i as usize] += n;
+        }
+    }
+    res.iter_mut()
+        .filter(|n| n.norm() > 0.0)
+        .for_each(|n| *n = n.normalize());
+    Ok(res)
+}
+
+/// Remove the elements `ids` from a mesh, as well as the faces that are no longer adjacent to
+/// any element. If `repair_boundary` is set, the faces of the removed elements that become
+/// boundary faces are added, oriented outwards and tagged `tag`. If `compact` is set, the
+/// vertices no longer used are removed.
+/// Return the new mesh, the indices in `mesh` of its vertices and elements, and the number of
+/// faces added
+fn remove_elems<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    ids: &[Idx],
+    repair_boundary: bool,
+    tag: Tag,
+    compact: bool,
+) -> (SimplexMesh<D, E>, Vec<Idx>, Vec<Idx>, Idx) {
+    let mut removed = vec![false; mesh.n_elems() as usize];
+    ids.iter().for_each(|&i| removed[i as usize] = true);
+
+    let key = |f: E::Face| {
+        let mut f: Vec<_> = f.into_iter().collect();
+        f.sort_unstable();
+        f
+    };
+    let mut elems = Vec::new();
+    let mut etags = Vec::new();
+    let mut elem_ids = Vec::new();
+    let mut count: HashMap<Vec<Idx>, (usize, E::Face)> = HashMap::new();
+    let mut exposed = HashSet::new();
+    for (i_elem, (e, t)) in mesh.elems().zip(mesh.etags()).enumerate() {
+        if removed[i_elem] {
+            exposed.extend((0..E::N_FACES).map(|i| key(e.face(i))));
+            continue;
+        }
+        for i_face in 0..E::N_FACES {
+            let f = e.face(i_face);
+            count.entry(key(f)).or_insert((0, f)).0 += 1;
+        }
+        elems.push(e);
+        etags.push(t);
+        elem_ids.push(i_elem as Idx);
+    }
+
+    let mut faces = Vec::new();
+    let mut ftags = Vec::new();
+    let mut existing = HashSet::new();
+    for (f, t) in mesh.faces().zip(mesh.ftags()) {
+        let k = key(f);
+        if count.contains_key(&k) {
+            faces.push(f);
+            ftags.push(t);
+        }
+        existing.insert(k);
+    }
+    let mut n_added = 0;
+    if repair_boundary {
+        let mut new_faces: Vec<_> = count
+            .iter()
+            .filter(|(k, &(n, _))| n == 1 && exposed.contains(*k) && !existing.contains(*k))
+            .map(|(k, &(_, f))| (k.clone(), f))
+            .collect();
+        new_faces.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, f) in new_faces {
+            faces.push(f);
+            ftags.push(tag);
+            n_added += 1;
+        }
+    }
+
+    let verts: Vec<_> = mesh.verts().collect();
+    let vert_ids: Vec<Idx> = if compact {
+        let used: BTreeSet<Idx> = elems.iter().flat_map(|&e| e).collect();
+        used.into_iter().collect()
+    } else {
+        (0..mesh.n_verts()).collect()
+    };
+    let mut new_idx = vec![Idx::MAX; verts.len()];
+    for (i, &j) in vert_ids.iter().enumerate() {
+        new_idx[j as usize] = i as Idx;
+    }
+    let renumber = |ids: Vec<Idx>| {
+        ids.into_iter()
+            .map(|i| new_idx[i as usize])
+            .collect::<Vec<_>>()
+    };
+    let elems = elems
+        .into_iter()
+        .map(|e| E::from_slice(&renumber(e.into_iter().collect())))
+        .collect();
+    let faces = faces
+        .into_iter()
+        .map(|f| E::Face::from_slice(&renumber(f.into_iter().collect())))
+        .collect();
+
+    let res = SimplexMesh::new(
+        vert_ids.iter().map(|&i| verts[i as usize]).collect(),
+        elems,
+        etags,
+        faces,
+        ftags,
+    );
+    (res, vert_ids, elem_ids, n_added)
+}
+
+/// Check if `a` and `b`, that contain the same vertices, are an even permutation of each other
+fn same_orientation(a: &[Idx], b: &[Idx]) -> bool {
+    let p: Vec<_> = b
+        .iter()
+        .map(|j| a.iter().position(|i| i == j).unwrap())
+        .collect();
+    let n_inversions = (0..p.len())
+        .flat_map(|i| (i + 1..p.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| p[i] > p[j])
+        .count();
+    n_inversions % 2 == 0
+}
+
+/// Sorted vertices of a face with at most 3 vertices, padded with `Idx::MAX`
+fn face_key(f: impl IntoIterator<Item = Idx>) -> [Idx; 3] {
+    let mut key = [Idx::MAX; 3];
+    key.iter_mut().zip(f).for_each(|(k, i)| *k = i);
+    key.sort_unstable();
+    key
+}
+
+/// For each face of `mesh` between two elements with different tags, the lowest and highest of
+/// these tags, and whether the face is oriented as the face of the element with the lowest tag,
+/// i.e. with its normal pointing from the lowest to the highest tag.
+/// The faces are matched in parallel by sorting the faces of all the elements
+fn interface_orientations<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+) -> Vec<Option<([Tag; 2], bool)>> {
+    let elems: Vec<E> = mesh.elems().collect();
+    let etags: Vec<Tag> = mesh.etags().collect();
+    let mut elem_faces: Vec<([Idx; 3], Idx, Idx)> = elems
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i_elem, e)| {
+            (0..E::N_FACES).map(move |i_face| (face_key(e.face(i_face)), i_elem as Idx, i_face))
+        })
+        .collect();
+    elem_faces.par_sort_unstable();
+
+    let faces: Vec<E::Face> = mesh.faces().collect();
+    faces
+        .par_iter()
+        .map(|&f| {
+            let key = face_key(f);
+            let start = elem_faces.partition_point(|x| x.0 < key);
+            let neighbors = &elem_faces[start..];
+            if neighbors.iter().take(3).take_while(|x| x.0 == key).count() != 2 {
+                return None;
+            }
+            let ((_, e0, i0), (_, e1, i1)) = (neighbors[0], neighbors[1]);
+            let f: Vec<_> = f.into_iter().collect();
+            let (t0, t1) = (etags[e0 as usize], etags[e1 as usize]);
+            if t0 == t1 {
+                return None;
+            }
+            let (e, i_face) = if t0 < t1 { (e0, i0) } else { (e1, i1) };
+            let g: Vec<_> = elems[e as usize].face(i_face).into_iter().collect();
+            Some(([t0.min(t1), t0.max(t1)], same_orientation(&g, &f)))
+        })
+        .collect()
+}
+
+/// Orient the faces between elements with different tags so that their normal points from the
+/// lowest to the highest element tag, and return the number of faces flipped
+fn orient_interfaces<const D: usize, E: Elem>(mesh: &mut SimplexMesh<D, E>) -> Idx {
+    let orientations = interface_orientations(mesh);
+    if !orientations.iter().any(|o| o.is_some_and(|(_, ok)| !ok)) {
+        return 0;
+    }
+    let mut n_flipped = 0;
+    let faces: Vec<E::Face> = mesh
+        .faces()
+        .zip(&orientations)
+        .map(|(f, o)| {
+            let mut f: Vec<_> = f.into_iter().collect();
+            if o.is_some_and(|(_, ok)| !ok) {
+                f.swap(0, 1);
+                n_flipped += 1;
+            }
+            E::Face::from_slice(&f)
+        })
+        .collect();
+    *mesh = SimplexMesh::new(
+        mesh.verts().collect(),
+        mesh.elems().collect(),
+        mesh.etags().collect(),
+        faces,
+        mesh.ftags().collect(),
+    );
+    n_flipped
+}
+
+/// Set the tags of the faces of `mesh` to the element tags of a boundary mesh `bdy`, whose vertex
+/// `i` is vertex `vert_ids[i]` of `mesh` (as returned by `boundary()`). The faces are matched by
+/// their vertices, and those that do not appear in `bdy` keep their tag.
+/// Return the number of faces retagged
+fn ftags_from_boundary<const D: usize, E: Elem>(
+    mesh: &mut SimplexMesh<D, E>,
+    bdy: &SimplexMesh<D, E::Face>,
+    vert_ids: &[Idx],
+) -> PyResult<Idx> {
+    if vert_ids.len() != bdy.n_verts() as usize {
+        return Err(PyValueError::new_err(format!(
+            "Invalid dimension 0 for vert_ids: {} values, but the boundary mesh has {} vertices",
+            vert_ids.len(),
+            bdy.n_verts()
+        )));
+    }
+    if vert_ids.iter().any(|&i| i >= mesh.n_verts()) {
+        return Err(PyValueError::new_err(
+            "vert_ids contains indices larger than the number of vertices",
+        ));
+    }
+
+    let mut tags = HashMap::with_capacity(bdy.n_elems() as usize);
+    for (e, tag) in bdy.elems().zip(bdy.etags()) {
+        let mut f: Vec<_> = e.into_iter().map(|i| vert_ids[i as usize]).collect();
+        f.sort_unstable();
+        tags.insert(f, tag);
+    }
+
+    let faces: Vec<Vec<Idx>> = mesh
+        .faces()
+        .map(|f| {
+            let mut f: Vec<_> = f.into_iter().collect();
+            f.sort_unstable();
+            f
+        })
+        .collect();
+    let mut count = 0;
+    for (f, t) in faces.iter().zip(mesh.mut_ftags()) {
+        if let Some(&tag) = tags.get(f) {
+            *t = tag;
+            count += 1;
+        }
+    }
+    if (count as usize) < tags.len() {
+        return Err(PyValueError::new_err(format!(
+            "{} elements of the boundary mesh do not match a face of the mesh",
+            tags.len() - count as usize
+        )));
+    }
+    Ok(count)
+}
+
+/// Extract the elements with a tag in `tags` into a mesh without faces, with the vertices
+/// compacted, and return the indices of its vertices and elements in the parent mesh
+fn extract_elems_with_tags<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    tags: &[Tag],
+) -> (SimplexMesh<D, E>, Vec<Idx>, Vec<Idx>) {
+    let mut new_idx = HashMap::new();
+    let mut vert_ids = Vec::new();
+    let mut elem_ids = Vec::new();
+    let mut elems = Vec::new();
+    let mut etags = Vec::new();
+    for (i_elem, (e, tag)) in mesh.elems().zip(mesh.etags()).enumerate() {
+        if !tags.contains(&tag) {
+            continue;
+        }
+        let ids: Vec<Idx> = e
+            .into_iter()
+            .map(|i| {
+                *new_idx.entry(i).or_insert_with(|| {
+                    vert_ids.push(i);
+                    vert_ids.len() as Idx - 1
+                })
+            })
+            .collect();
+        elems.push(E::from_slice(&ids));
+        etags.push(tag);
+        elem_ids.push(i_elem as Idx);
+    }
+
+    let verts: Vec<Point<D>> = mesh.verts().collect();
+    let res = SimplexMesh::new(
+        vert_ids.iter().map(|&i| verts[i as usize]).collect(),
+        elems,
+        etags,
+        Vec::new(),
+        Vec::new(),
+    );
+    (res, vert_ids, elem_ids)
+}
+
+/// Distance from the vertices of a tetrahedral mesh to its boundary faces with a tag in `tags`
+/// (None if there are no such faces)
+fn wall_distance(mesh: &SimplexMesh<3, Tetrahedron>, tags: &[Tag]) -> Option<Vec<f64>> {
+    let (bdy, _) = mesh.boundary();
+    let (wall, _, _) = extract_elems_with_tags(&bdy, tags);
+    if wall.n_elems() == 0 {
+        return None;
+    }
+    let tree = wall.compute_elem_tree();
+    let verts: Vec<_> = mesh.verts().collect();
+    Some(verts.par_iter().map(|p| tree.project(p).0).collect())
+}
+
+/// Signed volume of the simplex with vertices `p` in dimension `D` (2 or 3)
+fn signed_vol<const D: usize>(p: &[Point<D>]) -> f64 {
+    let u = p[1] - p[0];
+    let v = p[2] - p[0];
+    if D == 2 {
+        return 0.5 * (u[0] * v[1] - u[1] * v[0]);
+    }
+    let w = p[3] - p[0];
+    (u[1] * v[2] - u[2] * v[1]) * w[0]
+        + (u[2] * v[0] - u[0] * v[2]) * w[1]
+        + (u[0] * v[1] - u[1] * v[0]) * w[2]
+}
+
+/// Locate the points `coords` (flat array) in a mesh of simplices of dimension `D`, using the
+/// element tree `tree`: return the indices of the elements that contain them (-1 if none)
+/// and their barycentric coordinates in these elements (NaN if none), as flat arrays. A point
+/// is in an element if its barycentric coordinates are >= -`tol`
+fn locate_points<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    tree: &ObjectIndex<D>,
+    coords: &[f64],
+    tol: f64,
+) -> (Vec<i32>, Vec<f64>) {
+    let n = E::N_VERTS as usize;
+    let res: Vec<_> = coords
+        .par_chunks(D)
+        .map(|x| {
+            let p = Point::<D>::from_column_slice(x);
+            let i = tree.nearest_elem(&p);
+            let verts: Vec<_> = mesh.elem(i).into_iter().map(|k| mesh.vert(k)).collect();
+            let vol = signed_vol(&verts);
+            let bary: Vec<_> = (0..n)
+                .map(|j| {
+                    let mut sub = verts.clone();
+                    sub[j] = p;
+                    signed_vol(&sub) / vol
+                })
+                .collect();
+            if bary.iter().all(|&b| b >= -tol) {
+                (i as i32, bary)
+            } else {
+                (-1, vec![f64::NAN; n])
+            }
+        })
+        .collect();
+    let ids = res.iter().map(|x| x.0).collect();
+    let bary = res.into_iter().flat_map(|x| x.1).collect();
+    (ids, bary)
+}
+
+/// Get the arrays of `data` as `(array, # of components)`, checking that they have `n` rows
+fn data_arrays<'a>(
+    data: Option<&'a HashMap<String, PyReadonlyArray2<'_, f64>>>,
+    n: Idx,
+) -> PyResult<HashMap<String, (&'a [f64], usize)>> {
+    let mut res = HashMap::new();
+    for (name, arr) in data.into_iter().flatten() {
+        if arr.shape()[0] != n as usize {
+            return Err(PyValueError::new_err(format!(
+                "Invalid dimension 0 for {name}"
+            )));
+        }
+        res.insert(name.to_string(), (arr.as_slice()?, arr.shape()[1]));
+    }
+    Ok(res)
+}
+
+/// Parse the `precision` argument of the write methods: "f64" (default) or "f32"; return true
+/// for "f32"
+fn parse_precision(precision: Option<&str>) -> PyResult<bool> {
+    match precision.unwrap_or("f64") {
+        "f64" => Ok(false),
+        "f32" => Ok(true),
+        p => Err(PyValueError::new_err(format!(
+            "Invalid precision {p}: expected \"f64\" or \"f32\""
+        ))),
+    }
+}
+
+/// Check that the finite, non-zero values of `name` are in the range of the normal f32 values,
+/// i.e. that writing them as f32 neither overflows nor underflows
+fn check_f32_range(name: &str, values: impl Iterator<Item = f64>) -> PyResult<()> {
+    let range = f64::from(f32::MIN_POSITIVE)..=f64::from(f32::MAX);
+    for x in values {
+        if x.is_finite() && x != 0.0 && !range.contains(&x.abs()) {
+            return Err(PyValueError::new_err(format!(
+                "{name} contains {x:e}, outside of the range of f32 values: use force=True to write it anyway"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Write a mesh to a vtk file with vertex and element data given as `(array, # of components)`,
+/// optionally only the elements with a tag in `tags` (with the vertices compacted) and, if
+/// `parent_ids` is set, the indices of the elements in `mesh` as element data `parent_id`. If
+/// `f32` is set, a binary .vtu file with the reals stored as f32 is written
+fn write_vtk_with_tags<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    file_name: &str,
+    vert_data: &HashMap<String, (&[f64], usize)>,
+    elem_data: &HashMap<String, (&[f64], usize)>,
+    tags: Option<&[Tag]>,
+    parent_ids: bool,
+    f32: bool,
+) -> PyResult<()> {
+    let extracted = tags.map(|tags| extract_elems_with_tags(mesh, tags));
+    let select = |arr: &[f64], m: usize, ids: Option<&Vec<Idx>>| -> Vec<f64> {
+        ids.map_or_else(
+            || arr.to_vec(),
+            |ids| {
+                ids.iter()
+                    .flat_map(|&i| &arr[m * i as usize..m * (i as usize + 1)])
+                    .copied()
+                    .collect()
+            },
+        )
+    };
+    let vert_ids = extracted.as_ref().map(|x| &x.1);
+    let elem_ids = extracted.as_ref().map(|x| &x.2);
+
+    let vdata: HashMap<String, Vec<f64>> = vert_data
+        .iter()
+        .map(|(name, &(arr, m))| (name.clone(), select(arr, m, vert_ids)))
+        .collect();
+    let mut edata: HashMap<String, Vec<f64>> = elem_data
+        .iter()
+        .map(|(name, &(arr, m))| (name.clone(), select(arr, m, elem_ids)))
+        .collect();
+    if parent_ids {
+        let ids = elem_ids.map_or_else(
+            || (0..mesh.n_elems()).map(f64::from).collect(),
+            |ids| ids.iter().map(|&i| f64::from(i)).collect(),
+        );
+        edata.insert("parent_id".to_string(), ids);
+    }
+
+    let mesh = extracted.as_ref().map_or(mesh, |x| &x.0);
+    if mesh.n_elems() == 0 {
+        warn!("no element to write: {file_name} is not written");
+        return Ok(());
+    }
+    let vdata = vdata
+        .iter()
+        .map(|(k, v)| (k.clone(), v.as_slice()))
+        .collect();
+    let edata = edata
+        .iter()
+        .map(|(k, v)| (k.clone(), v.as_slice()))
+        .collect();
+    if f32 {
+        write_vtu_f32(mesh, file_name, &vdata, &edata)
+    } else {
+        mesh.write_vtk(file_name, Some(vdata), Some(edata))
+    }
+    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+/// Extract the tagged faces selected by `which` into a mesh, and return the indices of its
+/// vertices in the parent mesh. `which` is "all", "exterior" (faces that belong to a single
+/// element) or "internal" (faces shared by two elements, e.g. interfaces between element tags)
+fn extract_boundary<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    which: Option<&str>,
+) -> PyResult<(SimplexMesh<D, E::Face>, Vec<Idx>)> {
+    let exterior = match which.unwrap_or("all") {
+        "all" => return Ok(mesh.boundary()),
+        "exterior" => true,
+        "internal" => false,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid value {other} for which, expected \"all\", \"exterior\" or \"internal\""
+            )))
+        }
+    };
+
+    let face_to_elems = face_to_elems(mesh);
+    let mut new_idx = HashMap::new();
+    let mut vert_ids = Vec::new();
+    let mut elems = Vec::new();
+    let mut etags = Vec::new();
+    for (f, tag) in mesh.faces().zip(mesh.ftags()) {
+        let mut key: Vec<_> = f.into_iter().collect();
+        key.sort_unstable();
+        let n = face_to_elems.get(&key).map_or(0, Vec::len);
+        if (n == 1) != exterior {
+            continue;
+        }
+        let ids: Vec<Idx> = f
+            .into_iter()
+            .map(|i| {
+                *new_idx.entry(i).or_insert_with(|| {
+                    vert_ids.push(i);
+                    vert_ids.len() as Idx - 1
+                })
+            })
+            .collect();
+        elems.push(E::Face::from_slice(&ids));
+        etags.push(tag);
+    }
+
+    let verts: Vec<Point<D>> = mesh.verts().collect();
+    let bdy = SimplexMesh::new(
+        vert_ids.iter().map(|&i| verts[i as usize]).collect(),
+        elems,
+        etags,
+        Vec::new(),
+        Vec::new(),
+    );
+    Ok((bdy, vert_ids))
+}
+
+/// Normal sizes per face tag used by `curvature_metric`, given as arrays `h_n` and `h_n_tags`
+/// where tag 0 stands for all the face tags that are not listed. Check that the tags exist in the
+/// mesh, and return the sizes and tags with the wildcard expanded, and the normal size of each
+/// face tag of the mesh (None if the minimum tangential size is used)
+#[allow(clippy::type_complexity)]
+fn normal_sizes<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    h_n: Option<&[f64]>,
+    h_n_tags: Option<&[Tag]>,
+) -> PyResult<(Vec<f64>, Vec<Tag>, BTreeMap<Tag, Option<f64>>)> {
+    let mut res: BTreeMap<Tag, Option<f64>> = mesh.ftags().map(|t| (t, None)).collect();
+    let (h_n, h_n_tags) = match (h_n, h_n_tags) {
+        (None, None) => return Ok((Vec::new(), Vec::new(), res)),
+        (Some(_), None) => return Err(PyValueError::new_err("h_n_tags not given")),
+        (None, Some(_)) => return Err(PyValueError::new_err("h_n not given")),
+        (Some(h_n), Some(h_n_tags)) => (h_n, h_n_tags),
+    };
+    if h_n.len() != h_n_tags.len() {
+        return Err(PyValueError::new_err(
+            "h_n and h_n_tags must have the same length",
+        ));
+    }
+    let unknown: Vec<_> = h_n_tags
+        .iter()
+        .filter(|&&t| t != 0 && !res.contains_key(&t))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "Unknown face tags in h_n_tags: {unknown:?}"
+        )));
+    }
+
+    let mut wildcard = None;
+    for (&tag, &h) in h_n_tags.iter().zip(h_n) {
+        let prev = if tag == 0 {
+            wildcard.replace(h)
+        } else {
+            res.insert(tag, Some(h)).flatten()
+        };
+        if prev.is_some() {
+            return Err(PyValueError::new_err(format!(
+                "Duplicate tag {tag} in h_n_tags"
+            )));
+        }
+    }
+    if let Some(h) = wildcard {
+        res.values_mut()
+            .filter(|x| x.is_none())
+            .for_each(|x| *x = Some(h));
+    }
+
+    let (tags, sizes) = res.iter().filter_map(|(&t, &h)| h.map(|h| (t, h))).unzip();
+    Ok((sizes, tags, res))
+}
+
+/// Add `n` to `acc`, flipping it if needed so that the normals with opposite orientations do
+/// not cancel out
+fn add_normal(acc: &mut Point<3>, n: &Point<3>) {
+    if acc.dot(n) < 0.0 {
+        *acc -= n;
+    } else {
+        *acc += n;
+    }
+}
+
+/// Unit normals at the vertices of the boundary faces with a tag in `tags`: for each of these
+/// tags, the area weighted average of the normals of the faces with this tag or, if `gmesh` is
+/// given, the normal of its nearest element with this tag, and then the average over the tags.
+/// Return the vertex indices (sorted) and the normals, whose orientation is arbitrary
+fn boundary_vertex_normals(
+    mesh: &SimplexMesh<3, Tetrahedron>,
+    tags: &[Tag],
+    gmesh: Option<&SimplexMesh<3, Triangle>>,
+) -> PyResult<(Vec<Idx>, Vec<Point<3>>)> {
+    let mut normals: BTreeMap<Idx, BTreeMap<Tag, Point<3>>> = BTreeMap::new();
+    for ((f, gf), tag) in mesh.faces().zip(mesh.gfaces()).zip(mesh.ftags()) {
+        if !tags.contains(&tag) {
+            continue;
+        }
+        let n = gf.normal();
+        for i in f {
+            add_normal(normals.entry(i).or_default().entry(tag).or_default(), &n);
+        }
+    }
+    let unknown: Vec<_> = tags
+        .iter()
+        .filter(|&t| !normals.values().any(|x| x.contains_key(t)))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "No boundary face with tags {unknown:?}"
+        )));
+    }
+
+    if let Some(gmesh) = gmesh {
+        let verts: Vec<Point<3>> = mesh.verts().collect();
+        for &tag in tags {
+            let part = extract_elems_with_tags(gmesh, &[tag]).0;
+            if part.n_elems() == 0 {
+                return Err(PyValueError::new_err(format!(
+                    "Tag {tag} is not in the geometry"
+                )));
+            }
+            let tree = part.compute_elem_tree();
+            let part_normals: Vec<Point<3>> = part.gelems().map(|ge| ge.normal()).collect();
+            for (&i, x) in &mut normals {
+                if let Some(n) = x.get_mut(&tag) {
+                    let mut gn = part_normals[tree.nearest_elem(&verts[i as usize]) as usize];
+                    if gn.dot(n) < 0.0 {
+                        gn = -gn;
+                    }
+                    *n = gn;
+                }
+            }
+        }
+    }
+
+    Ok(normals
+        .into_iter()
+        .map(|(i, x)| {
+            let mut n = Point::<3>::zeros();
+            for t in x.values() {
+                add_normal(&mut n, &t.normalize());
+            }
+            (i, n.normalize())
+        })
+        .unzip())
+}
+
+/// Values at the boundary vertices (i.e. the vertices of the faces) of a field `f` with `n`
+/// components defined at the vertices. Return the values and the sorted vertex indices
+fn boundary_values<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    f: &[f64],
+    n: usize,
+) -> (Vec<f64>, Vec<Idx>) {
+    let ids: BTreeSet<Idx> = mesh.faces().flatten().collect();
+    let values = ids
+        .iter()
+        .flat_map(|&i| &f[n * i as usize..n * (i as usize + 1)])
+        .copied()
+        .collect();
+    (values, ids.into_iter().collect())
+}
+
+/// Min / max sizes, max anisotropy and complexity of a metric stored as a slice
+fn metric_info<const D: usize, E: Elem, M: Metric<D>>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[f64],
+) -> (f64, f64, f64, f64) {
+    let m: Vec<_> = m.chunks(M::N).map(M::from_slice).collect();
+    mesh.metric_info(&m)
+}
+
+/// Weights used to convert a field defined at the elements (P0) to the vertices (P1), for
+/// `weighting` = "uniform" (arithmetic average) or "inverse_distance" (inverse of the distance
+/// between the vertex and the element centers). For each vertex, the list of `(element, weight)`
+/// is returned, with weights that sum to 1.
+/// `None` is returned for "volume" (the default) which is the weighting used by tucanos
+pub fn p0_to_p1_weights<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    weighting: Option<&str>,
+) -> PyResult<Option<Vec<Vec<(usize, f64)>>>> {
+    let inverse_distance = match weighting.unwrap_or("volume") {
+        "volume" => return Ok(None),
+        "uniform" => false,
+        "inverse_distance" => true,
+        w => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid weighting {w}, expected \"volume\", \"uniform\" or \"inverse_distance\""
+            )))
+        }
+    };
+
+    let verts: Vec<Point<D>> = mesh.verts().collect();
+    let mut weights = vec![Vec::new(); verts.len()];
+    for (i_elem, e) in mesh.elems().enumerate() {
+        let ids: Vec<_> = e.into_iter().map(|i| i as usize).collect();
+        let center = ids.iter().map(|&i| verts[i]).sum::<Point<D>>() / ids.len() as f64;
+        for i in ids {
+            let w = if inverse_distance {
+                1.0 / (verts[i] - center).norm()
+            } else {
+                1.0
+            };
+            weights[i].push((i_elem, w));
+        }
+    }
+    for w in &mut weights {
+        let sum: f64 = w.iter().map(|(_, x)| x).sum();
+        for (_, x) in w.iter_mut() {
+            *x /= sum;
+        }
+    }
+
+    Ok(Some(weights))
+}
+
+/// Permute the rows of a (n, m) array stored as a slice, row `i` being moved to row `old_to_new[i]`
+fn permute_rows<T: Copy + Default>(arr: &[T], m: usize, old_to_new: &[Idx]) -> Vec<T> {
+    let mut res = vec![T::default(); arr.len()];
+    for (row, &i_new) in arr.chunks(m).zip(old_to_new.iter()) {
+        let i_new = i_new as usize;
+        res[m * i_new..m * (i_new + 1)].copy_from_slice(row);
+    }
+    res
+}
+
+/// Check that `perm` is a permutation of `0..n`
+fn check_permutation(name: &str, perm: &[Idx], n: usize) -> PyResult<()> {
+    if perm.len() != n {
+        return Err(PyValueError::new_err(format!(
+            "Invalid dimension 0 for {name}: {} values, expected {n}",
+            perm.len()
+        )));
+    }
+    let mut found = vec![false; n];
+    for &i in perm {
+        if (i as usize) >= n || found[i as usize] {
+            return Err(PyValueError::new_err(format!(
+                "{name} is not a permutation of 0..{n}"
+            )));
+        }
+        found[i as usize] = true;
+    }
+    Ok(())
+}
+
+/// Renumber the vertices, elements and faces of a mesh, entity `i` becoming entity `perm[i]`
+/// (the entities are unchanged if the permutation is None)
+pub fn permute_mesh<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    vert_perm: Option<&[Idx]>,
+    elem_perm: Option<&[Idx]>,
+    face_perm: Option<&[Idx]>,
+) -> SimplexMesh<D, E> {
+    let renumber = |i: Idx| vert_perm.map_or(i, |perm| perm[i as usize]);
+
+    let mut verts: Vec<Point<D>> = mesh.verts().collect();
+    if let Some(perm) = vert_perm {
+        for (p, &i) in mesh.verts().zip(perm) {
+            verts[i as usize] = p;
+        }
+    }
+
+    let mut elems: Vec<E> = mesh
+        .elems()
+        .map(|e| E::from_slice(&e.into_iter().map(renumber).collect::<Vec<_>>()))
+        .collect();
+    let mut etags: Vec<Tag> = mesh.etags().collect();
+    if let Some(perm) = elem_perm {
+        for ((e, tag), &i) in mesh.elems().zip(mesh.etags()).zip(perm) {
+            elems[i as usize] = E::from_slice(&e.into_iter().map(renumber).collect::<Vec<_>>());
+            etags[i as usize] = tag;
+        }
+    }
+
+    let mut faces: Vec<E::Face> = mesh
+        .faces()
+        .map(|f| E::Face::from_slice(&f.into_iter().map(renumber).collect::<Vec<_>>()))
+        .collect();
+    let mut ftags: Vec<Tag> = mesh.ftags().collect();
+    if let Some(perm) = face_perm {
+        for ((f, tag), &i) in mesh.faces().zip(mesh.ftags()).zip(perm) {
+            faces[i as usize] =
+                E::Face::from_slice(&f.into_iter().map(renumber).collect::<Vec<_>>());
+            ftags[i as usize] = tag;
+        }
+    }
+
+    SimplexMesh::new(verts, elems, etags, faces, ftags)
+}
+
+/// Eigenvalues of a symmetric 3x3 matrix `[[a00, a01, a02], [a01, a11, a12], [a02, a12, a22]]`,
+/// given as `[a00, a11, a22, a01, a12, a02]`, in decreasing order
+fn sym_eigenvalues_3d(a: [f64; 6]) -> [f64; 3] {
+    let [a00, a11, a22, a01, a12, a02] = a;
+    let q = (a00 + a11 + a22) / 3.0;
+    let p1 = a01 * a01 + a12 * a12 + a02 * a02;
+    let p2 = (a00 - q).powi(2) + (a11 - q).powi(2) + (a22 - q).powi(2) + 2.0 * p1;
+    let p = (p2 / 6.0).sqrt();
+    if p < f64::EPSILON * q.abs() {
+        return [q; 3];
+    }
+    let (b00, b11, b22) = ((a00 - q) / p, (a11 - q) / p, (a22 - q) / p);
+    let (b01, b12, b02) = (a01 / p, a12 / p, a02 / p);
+    let det = b00 * (b11 * b22 - b12 * b12) - b01 * (b01 * b22 - b12 * b02)
+        + b02 * (b01 * b12 - b11 * b02);
+    let phi = (0.5 * det).clamp(-1.0, 1.0).acos() / 3.0;
+    let l0 = 2.0f64.mul_add(p * phi.cos(), q);
+    let l2 = 2.0f64.mul_add(p * (phi + 2.0 * std::f64::consts::FRAC_PI_3).cos(), q);
+    [l0, 3.0f64.mul_add(q, -l0 - l2), l2]
+}
+
+/// Anisotropy of the elements of a 3D mesh: ratio of the largest to the smallest singular value
+/// of the affine map from the regular tetrahedron to the element, i.e. the square root of the
+/// eigenvalue ratio of the element-implied metric. It is 1 for regular elements, and infinite for
+/// degenerate elements
+pub fn elem_anisotropy(mesh: &SimplexMesh<3, Tetrahedron>) -> Vec<f64> {
+    // Inverse of the jacobian of the regular tetrahedron with unit edges (by rows)
+    let s3 = 3.0f64.sqrt();
+    let s6 = 6.0f64.sqrt();
+    let inv_j0 = [
+        Point::<3>::new(1.0, -1.0 / s3, -1.0 / s6),
+        Point::<3>::new(0.0, 2.0 / s3, -1.0 / s6),
+        Point::<3>::new(0.0, 0.0, 3.0 / s6),
+    ];
+
+    let verts: Vec<Point<3>> = mesh.verts().collect();
+    let elems: Vec<Vec<Idx>> = mesh.elems().map(|e| e.into_iter().collect()).collect();
+    elems
+        .par_iter()
+        .map(|e| {
+            let p0 = verts[e[0] as usize];
+            let edgs: Vec<_> = e[1..].iter().map(|&i| verts[i as usize] - p0).collect();
+            // Columns of the jacobian of the affine map
+            let cols: Vec<Point<3>> = (0..3)
+                .map(|j| (0..3).map(|k| edgs[k] * inv_j0[k][j]).sum())
+                .collect();
+            let [l0, _, l2] = sym_eigenvalues_3d([
+                cols[0].norm_squared(),
+                cols[1].norm_squared(),
+                cols[2].norm_squared(),
+                cols[0].dot(&cols[1]),
+                cols[1].dot(&cols[2]),
+                cols[0].dot(&cols[2]),
+            ]);
+            if l2 > 0.0 {
+                (l0 / l2).sqrt()
+            } else {
+                f64::INFINITY
+            }
+        })
+        .collect()
+}
+
 macro_rules! create_mesh {
     ($name: ident, $dim: expr, $etype: ident) => {
         #[doc = concat!("Mesh consisting of ", stringify!($etype), " in ", stringify!($dim), "D")]
-        #[pyclass]
+        #[pyclass(module = "pytucanos._pytucanos")]
         pub struct $name {
             pub mesh: SimplexMesh<$dim, $etype>,
+            /// Global ids of the vertices, if any
+            pub global_ids: Option<Vec<i64>>,
+            /// Version of the mesh, unique among all meshes and updated by the operations that
+            /// modify the topology in place
+            pub version: u64,
+            /// Element tree stored by `compute_octree()`, used when it matches the mesh version
+            pub octree: Option<ElemTree>,
+        }
+
+        impl From<SimplexMesh<$dim, $etype>> for $name {
+            fn from(mesh: SimplexMesh<$dim, $etype>) -> Self {
+                Self {
+                    mesh,
+                    global_ids: None,
+                    version: next_mesh_version(),
+                    octree: None,
+                }
+            }
+        }
+
+        impl $name {
+            /// Get the element tree stored by `compute_octree()` if it is valid for the current
+            /// version of the mesh
+            #[must_use]
+            pub fn cached_octree(&self) -> Option<&ElemTree> {
+                self.octree.as_ref().filter(|tree| tree.version() == self.version)
+            }
+
+            /// Check the size of an array defined at the mesh vertices
+            pub fn check_vertex_array(&self, name: &str, n: usize) -> PyResult<()> {
+                if n != self.mesh.n_verts() as usize {
+                    return Err(PyValueError::new_err(format!(
+                        "Invalid dimension 0 for {name}: {n} values, but the mesh (version {}) has {} vertices",
+                        self.version,
+                        self.mesh.n_verts()
+                    )));
+                }
+                Ok(())
+            }
+
+            /// Check the size of an array defined at the mesh elements
+            pub fn check_elem_array(&self, name: &str, n: usize) -> PyResult<()> {
+                if n != self.mesh.n_elems() as usize {
+                    return Err(PyValueError::new_err(format!(
+                        "Invalid dimension 0 for {name}: {n} values, but the mesh (version {}) has {} elements",
+                        self.version,
+                        self.mesh.n_elems()
+                    )));
+                }
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "gmsh")]
+        #[pymethods]
+        impl $name {
+            #[doc = concat!("Read a ", stringify!($name), " from an ASCII .msh file (gmsh format 4.1), keeping only the")]
+            #[doc = concat!(stringify!($etype), " elements and their faces. The tags are the first physical tags of")]
+            /// their entities (or the entity tags if they have no physical tag)
+            #[classmethod]
+            pub fn from_gmsh(_cls: &Bound<'_, PyType>, fname: &str) -> PyResult<Self> {
+                read_gmsh::<$dim, $etype>(fname)
+                    .map(Into::into)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
+
+            /// Write the mesh to an ASCII .msh file (gmsh format 4.1), with one entity per element
+            /// or face tag, the tags being stored as physical tags
+            /// The GIL is released while writing
+            pub fn write_gmsh(&self, py: Python<'_>, fname: &str) -> PyResult<()> {
+                py.allow_threads(|| write_gmsh(&self.mesh, fname))
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
+        }
+
+        #[cfg(feature = "xdmf")]
+        #[pymethods]
+        impl $name {
+            #[doc = concat!("Read a ", stringify!($name), " from a .xdmf file with its data in HDF5 files. The elements")]
+            /// and faces are read from the first grids with the corresponding topology types, and
+            /// their tags from the cell attributes named `tag`, if any (otherwise the tags are 1)
+            #[classmethod]
+            pub fn from_xdmf(_cls: &Bound<'_, PyType>, fname: &str) -> PyResult<Self> {
+                read_xdmf::<$dim, $etype>(fname)
+                    .map(Into::into)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
+
+            /// Write the mesh to a .xdmf file, with the heavy data (coordinates, elements, faces,
+            /// tags and the vertex and element data, given as in `write_vtk`) stored in an HDF5
+            /// file with the same base name and the extension .h5. The elements and faces are
+            /// written as two grids named "mesh" and "boundary"
+            /// The GIL is released while writing
+            pub fn write_xdmf(
+                &self,
+                py: Python<'_>,
+                fname: &str,
+                vert_data: Option<HashMap<String, PyReadonlyArray2<f64>>>,
+                elem_data: Option<HashMap<String, PyReadonlyArray2<f64>>>,
+            ) -> PyResult<()> {
+                let vdata = data_arrays(vert_data.as_ref(), self.mesh.n_verts())?;
+                let edata = data_arrays(elem_data.as_ref(), self.mesh.n_elems())?;
+                if let Some(name) = vdata.keys().chain(edata.keys()).find(|name| name.contains('/')) {
+                    return Err(PyValueError::new_err(format!("Invalid field name {name}")));
+                }
+                py.allow_threads(|| write_xdmf(&self.mesh, fname, &vdata, &edata))
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
         }
+
         #[pymethods]
         impl $name {
             /// Create a new mesh from numpy arrays
-            /// The data is copied
+            /// The data is copied. The tags may be given with any integer dtype, but their values
+            /// must be within `TAG_MIN` and `TAG_MAX` (`TAG_DTYPE` being signed, negative tags are
+            /// valid)
             #[new]
             pub fn new(
                 coords: PyReadonlyArray2<f64>,
                 elems: PyReadonlyArray2<Idx>,
-                etags: PyReadonlyArray1<Tag>,
+                etags: TagArray,
                 faces: PyReadonlyArray2<Idx>,
-                ftags: PyReadonlyArray1<Tag>,
+                ftags: TagArray,
             ) -> PyResult<Self> {
                 if coords.shape()[1] != $dim {
                     return Err(PyValueError::new_err("Invalid dimension 1 for coords"));
@@ -49,7 +1451,7 @@ macro_rules! create_mesh {
                 if elems.shape()[1] != <$etype as Elem>::N_VERTS as usize {
                     return Err(PyValueError::new_err("Invalid dimension 1 for elems"));
                 }
-                if etags.shape()[0] != n {
+                if etags.len() != n {
                     return Err(PyValueError::new_err("Invalid dimension 0 for etags"));
                 }
                 let n = faces.shape()[0];
@@ -57,7 +1459,7 @@ macro_rules! create_mesh {
                 if faces.shape()[1] != <$etype as Elem>::Face::N_VERTS as usize {
                     return Err(PyValueError::new_err("Invalid dimension 1 for faces"));
                 }
-                if ftags.shape()[0] != n {
+                if ftags.len() != n {
                     return Err(PyValueError::new_err("Invalid dimension 0 for ftags"));
                 }
 
@@ -75,15 +1477,89 @@ macro_rules! create_mesh {
                 let faces = faces.as_slice()?;
                 let faces = faces.chunks(<$etype as Elem>::Face::N_VERTS as usize).map(|e| <$etype as Elem>::Face::from_slice(e)).collect();
 
-                Ok(Self {
-                    mesh: SimplexMesh::<$dim, $etype>::new(
-                        coords,
-                        elems,
-                        etags.to_vec().unwrap(),
-                        faces,
-                        ftags.to_vec().unwrap(),
-                    ),
-                })
+                Ok(SimplexMesh::<$dim, $etype>::new(
+                    coords,
+                    elems,
+                    etags.into(),
+                    faces,
+                    ftags.into(),
+                ).into())
+            }
+
+            #[doc = concat!("Create an empty ", stringify!($name), " (no vertices, elements or faces)")]
+            #[classmethod]
+            pub fn empty(_cls: &Bound<'_, PyType>) -> Self {
+                SimplexMesh::<$dim, $etype>::empty().into()
+            }
+
+            /// Arguments of `new()` used by pickle to create the object before `__setstate__()` is
+            /// called: empty arrays, the actual data being in the state
+            #[must_use]
+            #[allow(clippy::type_complexity)]
+            pub fn __getnewargs__<'py>(
+                &self,
+                py: Python<'py>,
+            ) -> (
+                Bound<'py, PyArray2<f64>>,
+                Bound<'py, PyArray2<Idx>>,
+                Bound<'py, PyArray1<Tag>>,
+                Bound<'py, PyArray2<Idx>>,
+                Bound<'py, PyArray1<Tag>>,
+            ) {
+                (
+                    to_numpy_2d(py, Vec::new(), $dim),
+                    to_numpy_2d(py, Vec::new(), <$etype as Elem>::N_VERTS as usize),
+                    to_numpy_1d(py, Vec::new()),
+                    to_numpy_2d(py, Vec::new(), <$etype as Elem>::Face::N_VERTS as usize),
+                    to_numpy_1d(py, Vec::new()),
+                )
+            }
+
+            /// Get the state used by pickle and `copy.deepcopy()`: the coordinates, elements,
+            /// element tags, faces, face tags and global vertex ids (or None)
+            #[must_use]
+            #[allow(clippy::type_complexity)]
+            pub fn __getstate__<'py>(
+                &self,
+                py: Python<'py>,
+            ) -> (
+                Bound<'py, PyArray2<f64>>,
+                Bound<'py, PyArray2<Idx>>,
+                Bound<'py, PyArray1<Tag>>,
+                Bound<'py, PyArray2<Idx>>,
+                Bound<'py, PyArray1<Tag>>,
+                Option<Bound<'py, PyArray1<i64>>>,
+            ) {
+                (
+                    self.get_coords(py),
+                    self.get_elems(py),
+                    self.get_etags(py),
+                    self.get_faces(py),
+                    self.get_ftags(py),
+                    self.get_global_ids(py),
+                )
+            }
+
+            /// Restore the state returned by `__getstate__()`, the mesh being built by `new()`.
+            /// The element tree stored by `compute_octree()` is not restored
+            #[allow(clippy::type_complexity)]
+            pub fn __setstate__(
+                &mut self,
+                state: (
+                    PyReadonlyArray2<f64>,
+                    PyReadonlyArray2<Idx>,
+                    TagArray,
+                    PyReadonlyArray2<Idx>,
+                    TagArray,
+                    Option<PyReadonlyArray1<i64>>,
+                ),
+            ) -> PyResult<()> {
+                let (coords, elems, etags, faces, ftags, global_ids) = state;
+                *self = Self::new(coords, elems, etags, faces, ftags)?;
+                if let Some(ids) = global_ids {
+                    self.set_global_ids(ids)?;
+                }
+                Ok(())
             }
 
             #[doc = concat!("Read a ", stringify!($name), " from a .mesh(b) file")]
@@ -91,36 +1567,224 @@ macro_rules! create_mesh {
             pub fn from_meshb(_cls: &Bound<'_, PyType>, fname: &str) -> PyResult<Self> {
                 let res = SimplexMesh::<$dim, $etype>::read_meshb(fname);
                 match res {
-                    Ok(mesh) => Ok(Self{mesh}),
+                    Ok(mesh) => Ok(mesh.into()),
                     Err(err) => Err(PyRuntimeError::new_err(err.to_string())),
                 }
             }
 
             /// Write the mesh to a .mesh(b) file
-            pub fn write_meshb(&self, fname: &str) -> PyResult<()> {
-                self.mesh.write_meshb(fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            /// If `precision` is "f32" (default: "f64"), the coordinates are written as f32 to a
+            /// .meshb file, e.g. for visualization-only outputs; coordinates outside of the range
+            /// of f32 values are rejected unless `force` is True
+            /// The GIL is released while writing
+            pub fn write_meshb(
+                &self,
+                py: Python<'_>,
+                fname: &str,
+                precision: Option<&str>,
+                force: Option<bool>,
+            ) -> PyResult<()> {
+                if !parse_precision(precision)? {
+                    return py.allow_threads(|| self.mesh.write_meshb(fname))
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()));
+                }
+                if !fname.ends_with(".meshb") {
+                    return Err(PyValueError::new_err("precision f32 is only available for .meshb files"));
+                }
+                if !force.unwrap_or(false) {
+                    check_f32_range("coords", self.mesh.verts().flat_map(|p| (0..$dim).map(move |i| p[i])))?;
+                }
+                py.allow_threads(|| write_meshb_f32(&self.mesh, fname))
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
             }
 
-            /// Write a solution to a .sol(b) file
-            pub fn write_solb(&self, fname: &str, arr: PyReadonlyArray2<f64>) -> PyResult<()> {
-                self.mesh.write_solb(&arr.to_vec().unwrap(), fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            /// Write a solution to a .sol(b) file. The solution is defined at the vertices, or at
+            /// the elements if `location` is "elem" (only for .solb files)
+            /// Arrays containing NaN or infinite values are rejected unless `allow_nan` is True
+            /// An iteration number and / or a time may be given, e.g. for unsteady solutions; they
+            /// are stored as the `Iterations` and `Time` keywords (only for .solb files)
+            pub fn write_solb(
+                &self,
+                fname: &str,
+                arr: PyReadonlyArray2<f64>,
+                location: Option<&str>,
+                allow_nan: Option<bool>,
+                iteration: Option<i32>,
+                time: Option<f64>,
+            ) -> PyResult<()> {
+                let at_elems = match location.unwrap_or("vertex") {
+                    "vertex" => false,
+                    "elem" => true,
+                    location => {
+                        return Err(PyValueError::new_err(format!(
+                            "Invalid location {location}, expected \"vertex\" or \"elem\""
+                        )))
+                    }
+                };
+                let n = if at_elems { self.mesh.n_elems() } else { self.mesh.n_verts() };
+                if arr.shape()[0] != n as usize {
+                    return Err(PyValueError::new_err(format!(
+                        "Invalid dimension 0: {} values, but the mesh has {} vertices and {} elements",
+                        arr.shape()[0],
+                        self.mesh.n_verts(),
+                        self.mesh.n_elems()
+                    )));
+                }
+                let m = arr.shape()[1];
+                let arr = arr.to_vec()?;
+                if !allow_nan.unwrap_or(false) && arr.iter().any(|x| !x.is_finite()) {
+                    return Err(PyValueError::new_err("The solution contains NaN or infinite values"));
+                }
+
+                let metadata = SolbMetadata { iteration, time };
+                let has_metadata = metadata.iteration.is_some() || metadata.time.is_some();
+                if has_metadata && !fname.ends_with(".solb") {
+                    return Err(PyValueError::new_err("The iteration and time can only be written to .solb files"));
+                }
+
+                if at_elems {
+                    if !fname.ends_with(".solb") {
+                        return Err(PyValueError::new_err("Solutions defined at the elements can only be written to .solb files"));
+                    }
+                    write_elem_solb::<$etype>(fname, $dim, &arr, m)
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                } else {
+                    self.mesh.write_solb(&arr, fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                }
+                if has_metadata {
+                    write_solb_metadata(fname, &metadata).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                }
+                Ok(())
             }
 
 
-            /// Read a solution stored in a .sol(b) file
+            /// Read a solution stored in a .sol(b) file. The solution must be defined at the
+            /// vertices, or at the elements if `location` is "elem" (only for .solb files)
+            /// If `metadata` is True, return `(arr, metadata)` where `metadata` is a dict with
+            /// the entries "iteration" and / or "time" if they are stored in the file (always empty
+            /// for .sol files)
             #[classmethod]
-            pub fn read_solb<'py>(
+            pub fn read_solb(
                 _cls: &Bound<'_, PyType>,
-                py: Python<'py>,
-                fname: &str
-            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                use pyo3::exceptions::PyRuntimeError;
+                py: Python<'_>,
+                fname: &str,
+                location: Option<&str>,
+                metadata: Option<bool>,
+            ) -> PyResult<PyObject> {
+                let at_elems = match location.unwrap_or("vertex") {
+                    "vertex" => false,
+                    "elem" => true,
+                    location => {
+                        return Err(PyValueError::new_err(format!(
+                            "Invalid location {location}, expected \"vertex\" or \"elem\""
+                        )))
+                    }
+                };
+                if fname.ends_with(".solb") {
+                    let found = solb_at_elems::<$etype>(fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    if found.is_some_and(|x| x != at_elems) {
+                        let found = if at_elems { "vertices" } else { "elements" };
+                        return Err(PyValueError::new_err(format!("{fname} contains a solution defined at the {found}")));
+                    }
+                } else if at_elems {
+                    return Err(PyValueError::new_err("Solutions defined at the elements can only be read from .solb files"));
+                }
 
-                let res = SimplexMesh::<$dim, $etype>::read_solb(fname);
-                match res {
-                    Ok((sol, m)) => Ok(to_numpy_2d(py, sol, m)),
-                    Err(err) => Err(PyRuntimeError::new_err(err.to_string())),
+                let res = if at_elems {
+                    read_elem_solb::<$etype>(fname)
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+                } else {
+                    SimplexMesh::<$dim, $etype>::read_solb(fname)
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+                };
+                let (sol, m) = res?;
+                let arr = to_numpy_2d(py, sol, m);
+                if !metadata.unwrap_or(false) {
+                    return Ok(arr.into_py(py));
                 }
+
+                let dict = PyDict::new_bound(py);
+                if fname.ends_with(".solb") {
+                    let metadata = read_solb_metadata(fname).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    if let Some(iteration) = metadata.iteration {
+                        dict.set_item("iteration", iteration)?;
+                    }
+                    if let Some(time) = metadata.time {
+                        dict.set_item("time", time)?;
+                    }
+                }
+                Ok((arr, dict).into_py(py))
+            }
+
+            /// Set the global ids of the vertices
+            /// These are preserved by `reorder_hilbert` and `boundary`, but lost when the mesh is
+            /// split or remeshed
+            pub fn set_global_ids(&mut self, ids: PyReadonlyArray1<i64>) -> PyResult<()> {
+                if ids.shape()[0] != self.mesh.n_verts() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0 for ids"));
+                }
+                self.global_ids = Some(ids.to_vec()?);
+                Ok(())
+            }
+
+            /// Get a copy of the global ids of the vertices, or None if they are not set
+            #[must_use]
+            pub fn get_global_ids<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<i64>>> {
+                self.global_ids.as_ref().map(|ids| to_numpy_1d(py, ids.clone()))
+            }
+
+            /// Get the version of the mesh. Each mesh gets a unique version, which is updated by
+            /// the operations that modify it in place (`reorder_hilbert`, `add_boundary_faces`,
+            /// `smooth_boundary`), so that fields and trees built for another mesh can be detected
+            #[must_use]
+            pub const fn version(&self) -> u64 {
+                self.version
+            }
+
+            /// Renumber the vertices, vertex `i` becoming vertex `perm[i]`, and update the elements
+            /// and faces accordingly. `perm` must be a permutation; the `new_to_old` vertex
+            /// indices returned by `reorder_hilbert` can be used to undo the reordering
+            pub fn apply_vertex_permutation(&mut self, perm: PyReadonlyArray1<Idx>) -> PyResult<()> {
+                let perm = perm.as_slice()?;
+                check_permutation("perm", perm, self.mesh.n_verts() as usize)?;
+                self.mesh = permute_mesh(&self.mesh, Some(perm), None, None);
+                self.version = next_mesh_version();
+                if let Some(ids) = self.global_ids.as_ref() {
+                    self.global_ids = Some(permute_rows(ids, 1, perm));
+                }
+                Ok(())
+            }
+
+            /// Renumber the elements, element `i` becoming element `perm[i]`. `perm` must be a
+            /// permutation
+            pub fn apply_elem_permutation(&mut self, perm: PyReadonlyArray1<Idx>) -> PyResult<()> {
+                let perm = perm.as_slice()?;
+                check_permutation("perm", perm, self.mesh.n_elems() as usize)?;
+                self.mesh = permute_mesh(&self.mesh, None, Some(perm), None);
+                self.version = next_mesh_version();
+                Ok(())
+            }
+
+            /// Renumber the faces, face `i` becoming face `perm[i]`. `perm` must be a permutation
+            pub fn apply_face_permutation(&mut self, perm: PyReadonlyArray1<Idx>) -> PyResult<()> {
+                let perm = perm.as_slice()?;
+                check_permutation("perm", perm, self.mesh.n_faces() as usize)?;
+                self.mesh = permute_mesh(&self.mesh, None, None, Some(perm));
+                self.version = next_mesh_version();
+                Ok(())
+            }
+
+            /// Check if two meshes have exactly the same vertices, elements, faces and tags
+            #[must_use]
+            pub fn __eq__(&self, other: &Self) -> bool {
+                self.mesh.n_verts() == other.mesh.n_verts()
+                    && self.mesh.n_elems() == other.mesh.n_elems()
+                    && self.mesh.n_faces() == other.mesh.n_faces()
+                    && self.mesh.verts().zip(other.mesh.verts()).all(|(p, q)| p == q)
+                    && self.mesh.elems().zip(other.mesh.elems()).all(|(e, f)| e.into_iter().eq(f))
+                    && self.mesh.etags().eq(other.mesh.etags())
+                    && self.mesh.faces().zip(other.mesh.faces()).all(|(e, f)| e.into_iter().eq(f))
+                    && self.mesh.ftags().eq(other.mesh.ftags())
             }
 
             /// Get the number of vertices in the mesh
@@ -147,12 +1811,109 @@ macro_rules! create_mesh {
                 self.mesh.gelems().map(|ge| ge.vol()).sum()
             }
 
+            #[doc = concat!("Get the bounding box of the mesh as `(pmin, pmax)`, two arrays of shape (", stringify!($dim), ")")]
+            pub fn bounding_box<'py>(
+                &self,
+                py: Python<'py>,
+            ) -> PyResult<(Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<f64>>)> {
+                if self.mesh.n_verts() == 0 {
+                    return Err(PyValueError::new_err("The mesh is empty"));
+                }
+                let (lo, hi) = bounding_box(&self.mesh);
+                Ok((to_numpy_1d(py, lo.to_vec()), to_numpy_1d(py, hi.to_vec())))
+            }
+
+            /// Get the diameter of the mesh, i.e. the length of the diagonal of its bounding box,
+            /// typically used to scale tolerances
+            pub fn diameter(&self) -> PyResult<f64> {
+                if self.mesh.n_verts() == 0 {
+                    return Err(PyValueError::new_err("The mesh is empty"));
+                }
+                let (lo, hi) = bounding_box(&self.mesh);
+                Ok(lo.iter().zip(&hi).map(|(a, b)| (b - a) * (b - a)).sum::<f64>().sqrt())
+            }
+
+            #[doc = concat!("Get the centroid of the elements, weighted by their volume, as an array of shape (", stringify!($dim), ")")]
+            pub fn centroid<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<f64>>> {
+                let mut vol = 0.0;
+                let mut res = Point::<$dim>::zeros();
+                for ge in self.mesh.gelems() {
+                    let v = ge.vol();
+                    vol += v;
+                    res += v * ge.center();
+                }
+                if vol <= 0.0 {
+                    return Err(PyValueError::new_err("The mesh has no positive volume"));
+                }
+                res /= vol;
+                Ok(to_numpy_1d(py, res.iter().copied().collect()))
+            }
+
             /// Get the volume of all the elements
             #[must_use]
             pub fn vols<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
 
-                let res : Vec<_> = self.mesh.gelems().map(|ge| ge.vol()).collect();
-                to_numpy_1d(py, res)
+                let res : Vec<_> = self.mesh.gelems().map(|ge| ge.vol()).collect();
+                to_numpy_1d(py, res)
+            }
+
+            /// Get the volume of each element tag as a dict
+            pub fn tag_volumes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+                let mut vols = HashMap::new();
+                for (tag, ge) in self.mesh.etags().zip(self.mesh.gelems()) {
+                    *vols.entry(tag).or_insert(0.0) += ge.vol();
+                }
+                let dict = PyDict::new_bound(py);
+                for (k, v) in &vols {
+                    dict.set_item(k, v)?;
+                }
+                Ok(dict)
+            }
+
+            /// Set the tag of the elements whose center lies in a box, given as a pair `(min, max)`
+            /// of coordinates, or for which a callable returns True. The callable is called once
+            /// with the array of element centers, of shape (# of elements, dim).
+            /// Return the number of elements retagged
+            pub fn set_etags_where(
+                &mut self,
+                py: Python<'_>,
+                selector: &Bound<'_, PyAny>,
+                tag: Tag,
+            ) -> PyResult<Idx> {
+                let mut centers = Vec::with_capacity(self.mesh.n_elems() as usize * $dim);
+                for ge in self.mesh.gelems() {
+                    centers.extend(ge.center().iter().copied());
+                }
+                let selection = select_where(py, centers, $dim, selector)?;
+                let mut count = 0;
+                for (t, _) in self.mesh.mut_etags().zip(selection).filter(|(_, s)| *s) {
+                    *t = tag;
+                    count += 1;
+                }
+                Ok(count)
+            }
+
+            /// Set the tag of the faces whose center lies in a box, given as a pair `(min, max)`
+            /// of coordinates, or for which a callable returns True. The callable is called once
+            /// with the array of face centers, of shape (# of faces, dim).
+            /// Return the number of faces retagged
+            pub fn set_ftags_where(
+                &mut self,
+                py: Python<'_>,
+                selector: &Bound<'_, PyAny>,
+                tag: Tag,
+            ) -> PyResult<Idx> {
+                let mut centers = Vec::with_capacity(self.mesh.n_faces() as usize * $dim);
+                for gf in self.mesh.gfaces() {
+                    centers.extend(gf.center().iter().copied());
+                }
+                let selection = select_where(py, centers, $dim, selector)?;
+                let mut count = 0;
+                for (t, _) in self.mesh.mut_ftags().zip(selection).filter(|(_, s)| *s) {
+                    *t = tag;
+                    count += 1;
+                }
+                Ok(count)
             }
 
             /// Compute the vertex-to-element connectivity
@@ -217,19 +1978,49 @@ macro_rules! create_mesh {
                 self.mesh.clear_volumes();
             }
 
+            /// Get the vertex volumes, i.e. the volumes of the barycentric (median) dual cells,
+            /// each element giving an equal share of its volume to each of its vertices.
+            /// The volumes are computed if needed
+            pub fn get_vertex_volumes<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<f64>>> {
+                if self.mesh.get_vert_vol().is_err() {
+                    self.mesh.compute_volumes();
+                }
+                let res = self.mesh.get_vert_vol().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(to_numpy_1d(py, res.to_vec()))
+            }
+
+            /// Get the total volume of the mesh from the element volumes, which are computed if
+            /// needed
+            pub fn get_total_volume(&mut self) -> PyResult<f64> {
+                if self.mesh.get_vol().is_err() {
+                    self.mesh.compute_volumes();
+                }
+                let res = self.mesh.get_vol().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(res.iter().sum())
+            }
+
             /// Split all the elements and faces uniformly
             /// NB: vertex and element data is lost
             #[must_use]
             pub fn split(&self) -> Self {
-                Self {
-                    mesh: self.mesh.split(),
-                }
+                self.mesh.split().into()
             }
 
             /// Add the missing boundary faces and make sure that boundary faces are oriented outwards
             /// If internal faces are present, these are keps
+            ///
+            /// The faces between elements with different tags (internal interfaces) are oriented
+            /// so that their normal points from the lowest to the highest element tag (see
+            /// `orient_interfaces()`)
+            /// The GIL is released while the faces are added
             pub fn add_boundary_faces<'py>(&mut self, py: Python<'py>) -> PyResult<(Bound<'py, PyDict>, Bound<'py, PyDict>)> {
-                let (bdy, ifc) = self.mesh.add_boundary_faces();
+                let mesh = &mut self.mesh;
+                let (bdy, ifc) = py.allow_threads(|| {
+                    let res = mesh.add_boundary_faces();
+                    orient_interfaces(mesh);
+                    res
+                });
+                self.version = next_mesh_version();
                 let  dict_bdy = PyDict::new_bound(py);
                 for (k, v) in bdy.iter() {
                     dict_bdy.set_item(k, v)?;
@@ -243,45 +2034,169 @@ macro_rules! create_mesh {
 
             }
 
-            /// Write a vtk file containing the mesh
+            /// Remove the elements `ids`, and the faces that are no longer adjacent to any element.
+            /// If `repair_boundary` is True (default), the faces of the removed elements that
+            /// become boundary faces are added, oriented outwards, with tag `tag` (default: the
+            /// largest face tag + 1). If `compact` is True (default), the vertices that are no
+            /// longer used are removed.
+            /// Return the indices in the previous mesh of the vertices and of the elements
+            pub fn remove_elems<'py>(
+                &mut self,
+                py: Python<'py>,
+                ids: PyReadonlyArray1<Idx>,
+                repair_boundary: Option<bool>,
+                tag: Option<Tag>,
+                compact: Option<bool>,
+            ) -> PyResult<(Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>)> {
+                let ids = ids.as_slice()?;
+                check_indices("elements", ids, self.mesh.n_elems())?;
+                let tag = match tag {
+                    Some(tag) => tag,
+                    None => {
+                        let max_tag = self.mesh.ftags().max().unwrap_or(0);
+                        max_tag.checked_add(1).ok_or_else(|| {
+                            PyValueError::new_err("No tag available for the new faces")
+                        })?
+                    }
+                };
+
+                let (mesh, vert_ids, elem_ids, _) = remove_elems(
+                    &self.mesh,
+                    ids,
+                    repair_boundary.unwrap_or(true),
+                    tag,
+                    compact.unwrap_or(true),
+                );
+                self.mesh = mesh;
+                self.global_ids = self
+                    .global_ids
+                    .as_ref()
+                    .map(|gids| vert_ids.iter().map(|&i| gids[i as usize]).collect());
+                self.version = next_mesh_version();
+                Ok((to_numpy_1d(py, vert_ids), to_numpy_1d(py, elem_ids)))
+            }
+
+            /// Orient the faces between elements with different tags (internal interfaces) so that
+            /// their normal points from the lowest to the highest element tag, i.e. they are
+            /// oriented as the faces of the elements with the lowest tag. This is the convention of
+            /// `add_boundary_faces()`, and can be applied to meshes read from files.
+            /// Return the number of faces flipped
+            pub fn orient_interfaces(&mut self) -> Idx {
+                let res = orient_interfaces(&mut self.mesh);
+                if res > 0 {
+                    self.version = next_mesh_version();
+                }
+                res
+            }
+
+            /// Get the orientation of the faces between the elements with tags `tags = (tag0, tag1)`:
+            /// 1 if their normals all point from `tag0` to `tag1`, -1 if they all point from
+            /// `tag1` to `tag0`, 0 if the orientation is not consistent, or None if there is no
+            /// such face
+            #[must_use]
+            pub fn interface_orientation(&self, tags: (Tag, Tag)) -> Option<i32> {
+                let (t0, t1) = tags;
+                let key = [t0.min(t1), t0.max(t1)];
+                let mut res = None;
+                for (pair, ok) in interface_orientations(&self.mesh).into_iter().flatten() {
+                    if pair != key {
+                        continue;
+                    }
+                    let sign = if ok == (t0 < t1) { 1 } else { -1 };
+                    if res.is_some_and(|x| x != sign) {
+                        return Some(0);
+                    }
+                    res = Some(sign);
+                }
+                res
+            }
+
+            /// Write a vtk file containing the mesh (nothing is written, with a warning, if the mesh is
+            /// empty)
+            /// If `tags` is given, only the elements with these tags are written, with the vertices
+            /// compacted, and the vertex and element data are restricted accordingly. If
+            /// `parent_ids` is True, the indices of the elements in the mesh are written as element
+            /// data `parent_id`
+            /// If `precision` is "f32" (default: "f64"), a binary .vtu file with the coordinates
+            /// and data stored as f32 is written, e.g. for visualization-only outputs; values
+            /// outside of the range of f32 values are rejected unless `force` is True
+            /// The GIL is released while writing
+            #[allow(clippy::too_many_arguments)]
             pub fn write_vtk(&self,
+                py: Python<'_>,
                 file_name: &str,
                 vert_data : Option<HashMap<String, PyReadonlyArray2<f64>>>,
-                elem_data : Option<HashMap<String, PyReadonlyArray2<f64>>> ) -> PyResult<()> {
-
-                let mut vdata = HashMap::new();
-                if let Some(data) = vert_data.as_ref() {
-                    for (name, arr) in data.iter() {
-                        vdata.insert(name.to_string(), arr.as_slice().unwrap());
-                    }
+                elem_data : Option<HashMap<String, PyReadonlyArray2<f64>>>,
+                tags: Option<Vec<Tag>>,
+                parent_ids: Option<bool>,
+                precision: Option<&str>,
+                force: Option<bool>,
+            ) -> PyResult<()> {
+                let f32 = parse_precision(precision)?;
+                if f32 && !file_name.ends_with(".vtu") {
+                    return Err(PyValueError::new_err("precision f32 is only available for .vtu files"));
                 }
 
-                let mut edata = HashMap::new();
-                if let Some(data) = elem_data.as_ref() {
-                    for (name, arr) in data.iter() {
-                        edata.insert(name.to_string(), arr.as_slice().unwrap());
-                    }
+                if self.mesh.n_elems() == 0 {
+                    warn!("the mesh is empty: {file_name} is not written");
+                    return Ok(());
                 }
 
-                let res = self.mesh.write_vtk(file_name, Some(vdata), Some(edata));
+                let vdata = data_arrays(vert_data.as_ref(), self.mesh.n_verts())?;
+                let edata = data_arrays(elem_data.as_ref(), self.mesh.n_elems())?;
 
-                if let Err(res) = res {
-                    return Err(PyRuntimeError::new_err(res.to_string()));
+                if f32 && !force.unwrap_or(false) {
+                    check_f32_range("coords", self.mesh.verts().flat_map(|p| (0..$dim).map(move |i| p[i])))?;
+                    for (name, &(arr, _)) in vdata.iter().chain(edata.iter()) {
+                        check_f32_range(name, arr.iter().copied())?;
+                    }
                 }
-                Ok(())
+
+                py.allow_threads(|| {
+                    write_vtk_with_tags(
+                        &self.mesh,
+                        file_name,
+                        &vdata,
+                        &edata,
+                        tags.as_deref(),
+                        parent_ids.unwrap_or(false),
+                        f32,
+                    )
+                })
             }
 
-            /// Write a vtk file containing the boundary
-            pub fn write_boundary_vtk(&self, file_name: &str) -> PyResult<()> {
-                let res = self.mesh.boundary().0.write_vtk(file_name, None, None);
-                if let Err(res) = res {
-                    return Err(PyRuntimeError::new_err(res.to_string()));
+            /// Write a vtk file containing the boundary (nothing is written, with a warning, if the
+            /// mesh has no faces)
+            /// If `tags` is given, only the faces with these tags are written, and if `parent_ids`
+            /// is True, the indices of the faces in the mesh are written as element data
+            /// `parent_id`
+            pub fn write_boundary_vtk(
+                &self,
+                py: Python<'_>,
+                file_name: &str,
+                tags: Option<Vec<Tag>>,
+                parent_ids: Option<bool>,
+            ) -> PyResult<()> {
+                if self.mesh.n_faces() == 0 {
+                    warn!("the mesh has no boundary faces: {file_name} is not written");
+                    return Ok(());
                 }
-                Ok(())
+                py.allow_threads(|| {
+                    write_vtk_with_tags(
+                        &self.mesh.boundary().0,
+                        file_name,
+                        &HashMap::new(),
+                        &HashMap::new(),
+                        tags.as_deref(),
+                        parent_ids.unwrap_or(false),
+                        false,
+                    )
+                })
             }
 
             #[doc = concat!("Get a copy of the mesh coordinates as a numpy array of shape (# of vertices, ", stringify!($dim), ")")]
-            pub fn get_coords<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+            #[must_use]
+            pub fn get_coords<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
                 let mut coords = Vec::with_capacity(self.mesh.n_verts() as usize * $dim);
                 for v in self.mesh.verts() {
                     coords.extend(v.iter().copied());
@@ -290,11 +2205,70 @@ macro_rules! create_mesh {
             }
 
             /// Get a copy of the element connectivity as a numpy array of shape (# of elements, m)
-            pub fn get_elems<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<Idx>> {
+            #[must_use]
+            pub fn get_elems<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Idx>> {
                 let elems = self.mesh.elems().flatten().collect();
                 to_numpy_2d(py, elems, <$etype as Elem>::N_VERTS as usize)
             }
 
+            /// Get a copy of the coordinates of the vertices `start..end` as a numpy array of shape
+            /// (end - start, dim), without copying the other vertices
+            pub fn get_coords_slice<'py>(
+                &self,
+                py: Python<'py>,
+                start: Idx,
+                end: Idx,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                check_range("vertices", start, end, self.mesh.n_verts())?;
+                let mut coords = Vec::with_capacity((end - start) as usize * $dim);
+                for i in start..end {
+                    coords.extend(self.mesh.vert(i).iter().copied());
+                }
+                Ok(to_numpy_2d(py, coords, $dim))
+            }
+
+            /// Get a copy of the coordinates of the vertices `ids` as a numpy array of shape
+            /// (# of ids, dim), without copying the other vertices
+            pub fn get_coords_at<'py>(
+                &self,
+                py: Python<'py>,
+                ids: PyReadonlyArray1<Idx>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                let ids = ids.as_slice()?;
+                check_indices("vertices", ids, self.mesh.n_verts())?;
+                let mut coords = Vec::with_capacity(ids.len() * $dim);
+                for &i in ids {
+                    coords.extend(self.mesh.vert(i).iter().copied());
+                }
+                Ok(to_numpy_2d(py, coords, $dim))
+            }
+
+            /// Get a copy of the connectivity of the elements `start..end` as a numpy array of shape
+            /// (end - start, m), without copying the other elements
+            pub fn get_elems_slice<'py>(
+                &self,
+                py: Python<'py>,
+                start: Idx,
+                end: Idx,
+            ) -> PyResult<Bound<'py, PyArray2<Idx>>> {
+                check_range("elements", start, end, self.mesh.n_elems())?;
+                let elems = (start..end).flat_map(|i| self.mesh.elem(i)).collect();
+                Ok(to_numpy_2d(py, elems, <$etype as Elem>::N_VERTS as usize))
+            }
+
+            /// Get a copy of the connectivity of the elements `ids` as a numpy array of shape
+            /// (# of ids, m), without copying the other elements
+            pub fn get_elems_at<'py>(
+                &self,
+                py: Python<'py>,
+                ids: PyReadonlyArray1<Idx>,
+            ) -> PyResult<Bound<'py, PyArray2<Idx>>> {
+                let ids = ids.as_slice()?;
+                check_indices("elements", ids, self.mesh.n_elems())?;
+                let elems = ids.iter().flat_map(|&i| self.mesh.elem(i)).collect();
+                Ok(to_numpy_2d(py, elems, <$etype as Elem>::N_VERTS as usize))
+            }
+
             /// Get a copy of the element tags as a numpy array of shape (# of elements)
             #[must_use]
             pub fn get_etags<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Tag>> {
@@ -313,6 +2287,32 @@ macro_rules! create_mesh {
                 )
             }
 
+            /// Get a copy of the connectivity of the faces `start..end` as a numpy array of shape
+            /// (end - start, m), without copying the other faces
+            pub fn get_faces_slice<'py>(
+                &self,
+                py: Python<'py>,
+                start: Idx,
+                end: Idx,
+            ) -> PyResult<Bound<'py, PyArray2<Idx>>> {
+                check_range("faces", start, end, self.mesh.n_faces())?;
+                let faces = (start..end).flat_map(|i| self.mesh.face(i)).collect();
+                Ok(to_numpy_2d(py, faces, <$etype as Elem>::Face::N_VERTS as usize))
+            }
+
+            /// Get a copy of the connectivity of the faces `ids` as a numpy array of shape
+            /// (# of ids, m), without copying the other faces
+            pub fn get_faces_at<'py>(
+                &self,
+                py: Python<'py>,
+                ids: PyReadonlyArray1<Idx>,
+            ) -> PyResult<Bound<'py, PyArray2<Idx>>> {
+                let ids = ids.as_slice()?;
+                check_indices("faces", ids, self.mesh.n_faces())?;
+                let faces = ids.iter().flat_map(|&i| self.mesh.face(i)).collect();
+                Ok(to_numpy_2d(py, faces, <$etype as Elem>::Face::N_VERTS as usize))
+            }
+
             /// Get a copy of the face tags as a numpy array of shape (# of faces)
             #[must_use]
             pub fn get_ftags<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Tag>> {
@@ -320,30 +2320,121 @@ macro_rules! create_mesh {
                 to_numpy_1d(py, ftags)
             }
 
+            /// Get the element tags as a dict mapping the sorted unique tags to the number of
+            /// elements with this tag
+            #[must_use]
+            pub fn etag_values(&self) -> BTreeMap<Tag, usize> {
+                tag_values(self.mesh.etags())
+            }
+
+            /// Get the face tags as a dict mapping the sorted unique tags to the number of faces
+            /// with this tag
+            #[must_use]
+            pub fn ftag_values(&self) -> BTreeMap<Tag, usize> {
+                tag_values(self.mesh.ftags())
+            }
+
+            /// Check if an element has tag `tag`
+            #[must_use]
+            pub fn has_etag(&self, tag: Tag) -> bool {
+                self.mesh.etags().any(|t| t == tag)
+            }
+
+            /// Check if a face has tag `tag`
+            #[must_use]
+            pub fn has_ftag(&self, tag: Tag) -> bool {
+                self.mesh.ftags().any(|t| t == tag)
+            }
+
             /// Reorder the vertices, element and faces using a Hilbert SFC
-            pub fn reorder_hilbert<'py>(&mut self, py: Python<'py>) -> PyResult<(Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>)>{
-                let (new_vertex_indices, new_elem_indices, new_face_indices) = self.mesh.reorder_hilbert();
+            ///
+            /// The permutations of the vertices, elements and faces are returned as pairs
+            /// `(old_to_new, new_to_old)`: entity `i` of the original mesh is entity `old_to_new[i]`
+            /// of the reordered mesh, and entity `i` of the reordered mesh is entity `new_to_old[i]`
+            /// of the original mesh.
+            /// The vertex fields given in `fields` are permuted accordingly and returned in a dict
+            #[allow(clippy::type_complexity)]
+            pub fn reorder_hilbert<'py>(
+                &mut self,
+                py: Python<'py>,
+                fields: Option<HashMap<String, PyReadonlyArray2<f64>>>,
+            ) -> PyResult<(
+                (Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>),
+                (Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>),
+                (Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>),
+                Bound<'py, PyDict>,
+            )>{
+                if let Some(fields) = fields.as_ref() {
+                    for (name, arr) in fields.iter() {
+                        if arr.shape()[0] != self.mesh.n_verts() as usize {
+                            return Err(PyValueError::new_err(format!("Invalid dimension 0 for {name}")));
+                        }
+                    }
+                }
+
+                // the Hilbert indices are computed from the bounding box, undefined for an empty mesh
+                let (new_vertex_indices, new_elem_indices, new_face_indices) = if self.mesh.n_verts() == 0 {
+                    (Vec::new(), Vec::new(), Vec::new())
+                } else {
+                    self.mesh.reorder_hilbert()
+                };
+                self.version = next_mesh_version();
+                if let Some(ids) = self.global_ids.as_ref() {
+                    self.global_ids = Some(permute_rows(ids, 1, &new_vertex_indices));
+                }
+
+                let dict = PyDict::new_bound(py);
+                if let Some(fields) = fields.as_ref() {
+                    for (name, arr) in fields.iter() {
+                        let m = arr.shape()[1];
+                        let res = permute_rows(arr.as_slice()?, m, &new_vertex_indices);
+                        dict.set_item(name, to_numpy_2d(py, res, m))?;
+                    }
+                }
+
+                let old_vertex_indices = inverse_permutation(&new_vertex_indices);
+                let old_elem_indices = inverse_permutation(&new_elem_indices);
+                let old_face_indices = inverse_permutation(&new_face_indices);
+
                 Ok(
                     (
-                        to_numpy_1d(py, new_vertex_indices),
-                        to_numpy_1d(py, new_elem_indices),
-                        to_numpy_1d(py, new_face_indices)
+                        (to_numpy_1d(py, new_vertex_indices), to_numpy_1d(py, old_vertex_indices)),
+                        (to_numpy_1d(py, new_elem_indices), to_numpy_1d(py, old_elem_indices)),
+                        (to_numpy_1d(py, new_face_indices), to_numpy_1d(py, old_face_indices)),
+                        dict,
                     )
                 )
-
             }
 
             /// Convert a (scalar or vector) field defined at the element centers (P0) to a field defined at the vertices (P1)
             /// using a weighted average.
+            /// `weighting` may be "volume" (default, requires `compute_vertex_to_elems()` and
+            /// `compute_volumes()`), "uniform" or "inverse_distance" (inverse of the distance
+            /// between the vertex and the element centers)
             pub fn elem_data_to_vertex_data<'py>(
-                &mut self,
+                &self,
                 py: Python<'py>,
                 arr: PyReadonlyArray2<f64>,
+                weighting: Option<&str>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
                 if arr.shape()[0] != self.mesh.n_elems() as usize {
                     return Err(PyValueError::new_err("Invalid dimension 0"));
                 }
 
+                let m = arr.shape()[1];
+                if let Some(weights) = p0_to_p1_weights(&self.mesh, weighting)? {
+                    let arr = arr.as_slice()?;
+                    let mut res = vec![0.0; weights.len() * m];
+                    for (row, w) in res.chunks_mut(m).zip(weights.iter()) {
+                        for &(i_elem, x) in w {
+                            for (r, v) in row.iter_mut().zip(&arr[m * i_elem..m * (i_elem + 1)]) {
+                                *r += x * v;
+                            }
+                        }
+                    }
+                    return Ok(to_numpy_2d(py, res, m));
+                }
+
                 let res = self.mesh.elem_data_to_vertex_data(arr.as_slice().unwrap());
 
                 if let Err(res) = res {
@@ -355,7 +2446,7 @@ macro_rules! create_mesh {
             /// Convert a field (scalar or vector) defined at the vertices (P1) to a field defined at the
             /// element centers (P0)
             pub fn vertex_data_to_elem_data<'py>(
-                &mut self,
+                &self,
                 py: Python<'py>,
                 arr: PyReadonlyArray2<f64>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
@@ -366,34 +2457,161 @@ macro_rules! create_mesh {
                 Ok(to_numpy_2d(py, res.unwrap(), arr.shape()[1]))
             }
 
+            /// Clip a field (scalar or vector) defined at the vertices or at the elements, each
+            /// component being clipped separately
+            /// The bounds are `min_val` / `max_val` if given, or otherwise the percentiles
+            /// `percentile = (p_lo, p_hi)` of the field (no bound if neither is given). The
+            /// percentiles of an empty field are NaN.
+            /// Return the clipped field and the lower and upper bounds used for each component
+            #[allow(clippy::type_complexity)]
+            pub fn clip_field<'py>(
+                &self,
+                py: Python<'py>,
+                arr: PyReadonlyArray2<f64>,
+                min_val: Option<f64>,
+                max_val: Option<f64>,
+                percentile: Option<(f64, f64)>,
+            ) -> PyResult<(Bound<'py, PyArray2<f64>>, Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<f64>>)> {
+                let n = arr.shape()[0];
+                if n != self.mesh.n_verts() as usize && n != self.mesh.n_elems() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0"));
+                }
+                if let Some((p_lo, p_hi)) = percentile {
+                    if !(0.0..=100.0).contains(&p_lo) || !(0.0..=100.0).contains(&p_hi) || p_lo > p_hi {
+                        return Err(PyValueError::new_err("Invalid percentiles"));
+                    }
+                }
+
+                let m = arr.shape()[1];
+                let arr = arr.as_slice()?;
+                let mut res = arr.to_vec();
+                let mut lo = Vec::with_capacity(m);
+                let mut hi = Vec::with_capacity(m);
+                for j in 0..m {
+                    let mut values: Vec<f64> = arr.iter().skip(j).step_by(m).copied().collect();
+                    let v_lo = min_val
+                        .or_else(|| percentile.map(|(p, _)| compute_percentile(&mut values, p)))
+                        .unwrap_or(f64::NEG_INFINITY);
+                    let v_hi = max_val
+                        .or_else(|| percentile.map(|(_, p)| compute_percentile(&mut values, p)))
+                        .unwrap_or(f64::INFINITY);
+                    for v in res.iter_mut().skip(j).step_by(m) {
+                        *v = v.max(v_lo).min(v_hi);
+                    }
+                    lo.push(v_lo);
+                    hi.push(v_hi);
+                }
+
+                Ok((to_numpy_2d(py, res, m), to_numpy_1d(py, lo), to_numpy_1d(py, hi)))
+            }
+
+            /// Build a spatial index of the elements, that can be passed to the methods that locate
+            /// points in the mesh (`interpolate_linear`, `transfer_tags_*`) to avoid building it for
+            /// each call. It is only valid for the current version of the mesh
+            #[must_use]
+            pub fn build_elem_tree(&self) -> ElemTree {
+                ElemTree::new(self.mesh.compute_elem_tree(), self.version)
+            }
+
+            /// Build the element tree and store it in the mesh, so that it is used by the methods
+            /// that locate points in the mesh (`interpolate_linear`, `transfer_tags_*`) when no
+            /// tree is given. It is discarded when the mesh is modified
+            pub fn compute_octree(&mut self) {
+                self.octree = Some(self.build_elem_tree());
+            }
+
+            /// Clear the element tree stored by `compute_octree()`
+            pub fn clear_octree(&mut self) {
+                self.octree = None;
+            }
+
+            /// Check if an element tree valid for the current version of the mesh has been stored
+            /// by `compute_octree()`
+            #[must_use]
+            pub fn has_octree(&self) -> bool {
+                self.cached_octree().is_some()
+            }
+
+            /// Build a spatial index of the vertices, that can be passed to `interpolate_nearest`
+            /// to avoid building it for each call. It is only valid for the current version of the
+            /// mesh
+            #[must_use]
+            pub fn build_vert_tree(&self) -> VertTree {
+                VertTree::new(self.mesh.compute_vert_tree(), self.version)
+            }
+
             /// Interpolate a field (scalar or vector) defined at the vertices (P1) to a different mesh using linear interpolation
+            /// The element tree is built unless given as `tree` or stored by `compute_octree()`
+            /// The GIL is released while building the tree and interpolating
             pub fn interpolate_linear<'py>(
-                &mut self,
+                &self,
                 py: Python<'py>,
                 other: &Self,
                 arr: PyReadonlyArray2<f64>,
                 tol: Option<f64>,
+                tree: Option<&ElemTree>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
                 if arr.shape()[0] != self.mesh.n_verts() as usize {
                     return Err(PyValueError::new_err("Invalid dimension 0"));
                 }
-                let tree = self.mesh.compute_elem_tree();
-                let res = self.mesh.interpolate_linear(&tree, &other.mesh, arr.as_slice().unwrap(), tol);
+                if other.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), arr.shape()[1]));
+                }
+                if self.mesh.n_elems() == 0 {
+                    return Err(PyValueError::new_err("Cannot interpolate from an empty mesh"));
+                }
+                let tree = match tree.or_else(|| self.cached_octree()) {
+                    Some(tree) => Some(tree.get::<$dim>(self.version)?),
+                    None => None,
+                };
+                let data = arr.as_slice()?;
+                let res = py.allow_threads(|| {
+                    let owned;
+                    let tree = if let Some(tree) = tree {
+                        tree
+                    } else {
+                        owned = self.mesh.compute_elem_tree();
+                        &owned
+                    };
+                    self.mesh.interpolate_linear(tree, &other.mesh, data, tol)
+                });
                 Ok(to_numpy_2d(py, res.unwrap(), arr.shape()[1]))
             }
 
             /// Interpolate a field (scalar or vector) defined at the vertices (P1) to a different mesh using nearest neighbor interpolation
+            /// The vertex tree is built unless given as `tree`
+            /// The GIL is released while building the tree and interpolating
             pub fn interpolate_nearest<'py>(
-                &mut self,
+                &self,
                 py: Python<'py>,
                 other: &Self,
                 arr: PyReadonlyArray2<f64>,
+                tree: Option<&VertTree>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
                 if arr.shape()[0] != self.mesh.n_verts() as usize {
                     return Err(PyValueError::new_err("Invalid dimension 0"));
                 }
-                let tree = self.mesh.compute_vert_tree();
-                let res = self.mesh.interpolate_nearest(&tree, &other.mesh, arr.as_slice().unwrap());
+                if other.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), arr.shape()[1]));
+                }
+                if self.mesh.n_verts() == 0 {
+                    return Err(PyValueError::new_err("Cannot interpolate from an empty mesh"));
+                }
+                let tree = match tree {
+                    Some(tree) => Some(tree.get::<$dim>(self.version)?),
+                    None => None,
+                };
+                let data = arr.as_slice()?;
+                let res = py.allow_threads(|| {
+                    let owned;
+                    let tree = if let Some(tree) = tree {
+                        tree
+                    } else {
+                        owned = self.mesh.compute_vert_tree();
+                        &owned
+                    };
+                    self.mesh.interpolate_nearest(tree, &other.mesh, data)
+                });
                 Ok(to_numpy_2d(py, res.unwrap(), arr.shape()[1]))
             }
 
@@ -410,6 +2628,9 @@ macro_rules! create_mesh {
                 if arr.shape()[1] != 1 {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
+                if self.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), 1));
+                }
 
                 let res = self
                     .mesh
@@ -433,6 +2654,9 @@ macro_rules! create_mesh {
                 if arr.shape()[1] != 1 {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
+                if self.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), $dim));
+                }
 
                 let res = self
                     .mesh
@@ -450,6 +2674,8 @@ macro_rules! create_mesh {
             /// Compute the hessian of a field defined at the mesh vertices using a 2nd order least-square approximation
             /// if `weight_exp` is `None`, the vertex has a weight 10, its first order neighbors have
             /// a weight 1 and the 2nd order neighbors (if used) have a weight of 0.1
+            /// The components are ordered as `SYMTENSOR_2D` = (xx, yy, xy) or
+            /// `SYMTENSOR_3D` = (xx, yy, zz, xy, yz, xz)
             pub fn compute_hessian<'py>(
                 &self,
                 py: Python<'py>,
@@ -463,6 +2689,9 @@ macro_rules! create_mesh {
                 if arr.shape()[1] != 1 {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
+                if self.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), $dim * ($dim + 1) / 2));
+                }
 
                 let res = self
                     .mesh
@@ -489,6 +2718,9 @@ macro_rules! create_mesh {
                 if arr.shape()[1] != 1 {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
+                if self.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), $dim * ($dim + 1) / 2));
+                }
 
                 let grad = self
                     .mesh
@@ -502,72 +2734,461 @@ macro_rules! create_mesh {
                     return Err(PyRuntimeError::new_err(res.to_string()));
                 }
 
-                Ok(to_numpy_2d(
-                    py,
-                    res.unwrap(),
-                    $dim * ($dim +1 ) / 2,
-                ))
+                Ok(to_numpy_2d(
+                    py,
+                    res.unwrap(),
+                    $dim * ($dim +1 ) / 2,
+                ))
+            }
+
+            /// Check that the mesh is valid (an empty mesh is valid)
+            ///  - all elements have a >0 volume
+            ///  - all boundary faces are tagged
+            ///  - all the faces between different element tags are tagged
+            ///  - no other face is tagged
+            pub fn check(&self) -> PyResult<()> {
+                if self.mesh.n_verts() == 0 && self.mesh.n_elems() == 0 && self.mesh.n_faces() == 0 {
+                    return Ok(());
+                }
+                self.mesh.check().map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
+
+            /// Compute the topology
+            pub fn compute_topology(&mut self) {
+                self.mesh.compute_topology();
+            }
+
+            /// Clear the topology
+            pub fn clear_topology(&mut self) {
+                self.mesh.clear_topology();
+            }
+
+            /// Automatically tag the elements based on a feature angle
+            pub fn autotag<'py>(&mut self, py: Python<'py>, angle_deg: f64) -> PyResult<Bound<'py, PyDict>> {
+                let res = self.mesh.autotag(angle_deg);
+                if let Err(res) = res {
+                     Err(PyRuntimeError::new_err(res.to_string()))
+                } else {
+                    let dict = PyDict::new_bound(py);
+                    for (k, v) in res.unwrap().iter() {
+                        dict.set_item(k, to_numpy_1d(py, v.to_vec()))?;
+                    }
+                    Ok(dict)
+                }
+            }
+
+            /// Automatically tag the faces based on a feature angle
+            pub fn autotag_bdy<'py>(&mut self, py: Python<'py>, angle_deg: f64) -> PyResult<Bound<'py, PyDict>> {
+                let res = self.mesh.autotag_bdy(angle_deg);
+                if let Err(res) = res {
+                     Err(PyRuntimeError::new_err(res.to_string()))
+                } else {
+                    let dict = PyDict::new_bound(py);
+                    for (k, v) in res.unwrap().iter() {
+                        dict.set_item(k, to_numpy_1d(py, v.to_vec()))?;
+                    }
+                    Ok(dict)
+                }
+            }
+        }
+    };
+}
+
+create_mesh!(Mesh33, 3, Tetrahedron);
+create_mesh!(Mesh32, 3, Triangle);
+create_mesh!(Mesh31, 3, Edge);
+create_mesh!(Mesh22, 2, Triangle);
+create_mesh!(Mesh21, 2, Edge);
+
+#[pymethods]
+impl Mesh33 {
+    /// Mesh the domain where a signed distance function is negative, within a box given as a
+    /// pair `(min, max)` of coordinates, with a structured mesh of size `h` whose boundary
+    /// vertices are projected onto the zero level set. The function is called with arrays of
+    /// points of shape (n, dim) and must return the distances as an array of shape (n,).
+    /// All the elements and boundary faces have tag 1
+    #[classmethod]
+    pub fn from_sdf(
+        _cls: &Bound<'_, PyType>,
+        py: Python<'_>,
+        sdf: &Bound<'_, PyAny>,
+        bounds: (Vec<f64>, Vec<f64>),
+        h: f64,
+    ) -> PyResult<Self> {
+        let mesh = mesh_from_sdf::<3, Tetrahedron>(py, sdf, &bounds.0, &bounds.1, h)?;
+        Ok(mesh.into())
+    }
+
+    /// Get, for the faces adjacent to a single element (i.e. excluding the internal faces), the
+    /// face indices (in the `get_faces()` ordering), the indices of the adjacent elements and
+    /// the indices of the faces within these elements.
+    /// Return `(face_ids, elem_ids, local_face_ids)`
+    #[allow(clippy::type_complexity)]
+    #[must_use]
+    pub fn boundary_face_elems<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> (
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<Idx>>,
+    ) {
+        let (face_ids, elem_ids, local_ids) = boundary_face_elems(&self.mesh);
+        (
+            to_numpy_1d(py, face_ids),
+            to_numpy_1d(py, elem_ids),
+            to_numpy_1d(py, local_ids),
+        )
+    }
+
+    /// Refine `n` times (default: 1) the boundary faces with a tag in `tags` by bisecting all their
+    /// edges, and split the adjacent elements to keep the mesh conforming. The other elements
+    /// are not modified. If a geometry is given, the new vertices inside the faces of a tag are
+    /// projected onto it (those on the edges between tags are kept on the straight edges).
+    /// Return `(mesh, elem_parents, face_parents)` with the refined mesh and the indices of the
+    /// parent of each element and face in this mesh
+    #[allow(clippy::type_complexity)]
+    #[must_use]
+    pub fn refine_boundary<'py>(
+        &self,
+        py: Python<'py>,
+        tags: Vec<Tag>,
+        n: Option<u32>,
+        geometry: Option<&LinearGeometry3d>,
+    ) -> (Self, Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>) {
+        let (mesh, elem_parents, face_parents) =
+            refine_boundary(&self.mesh, &tags, n.unwrap_or(1), geometry.map(|g| &g.geom));
+        (
+            mesh.into(),
+            to_numpy_1d(py, elem_parents),
+            to_numpy_1d(py, face_parents),
+        )
+    }
+
+    /// Smooth the vertices on the boundary faces with a tag in `tags` (except those on the
+    /// edges and corners of the geometry) with `n_iter` Laplacian passes, projecting them onto
+    /// `geometry`. The other vertices are not moved, and the displacements are reduced where
+    /// they would invert the adjacent elements.
+    /// Return `((q_min, q_mean), (q_min, q_mean))`, the min and mean quality of the boundary
+    /// faces with a tag in `tags` before and after smoothing
+    #[allow(clippy::type_complexity)]
+    pub fn smooth_boundary(
+        &mut self,
+        tags: Vec<Tag>,
+        geometry: &LinearGeometry3d,
+        n_iter: u32,
+    ) -> PyResult<((f64, f64), (f64, f64))> {
+        let res = smooth_boundary(&mut self.mesh, &tags, &geometry.geom, n_iter)?;
+        self.version = next_mesh_version();
+        Ok(res)
+    }
+
+    /// Untangle the mesh without changing its connectivity: the vertices of the inverted
+    /// elements are moved, with at most `max_iter` passes, to increase the min signed volume of
+    /// their adjacent elements. The vertices on the faces with a tag in `frozen_ftags` are not
+    /// moved.
+    /// Return the number of elements that are still inverted
+    pub fn untangle(&mut self, max_iter: u32, frozen_ftags: Option<Vec<Tag>>) -> Idx {
+        let (_, res) = untangle(&mut self.mesh, max_iter, &frozen_ftags.unwrap_or_default());
+        self.version = next_mesh_version();
+        res
+    }
+
+    /// Insert vertices at `points` (array of shape (# of points, 3)), one point at a time: the
+    /// element, face or edge containing the point is split (points closer than `tol` to a face or
+    /// an edge are projected onto it, and the tagged faces are split as well), and the quality
+    /// around the new vertex is then improved with face swaps. Points closer than `tol` to an
+    /// existing vertex are snapped to it. The existing vertices keep their indices, and the
+    /// global ids of the new vertices are -1. Points are located by a brute force search, so
+    /// this is intended for a moderate number of points.
+    /// Return `(ids, outside)`: the indices of the vertices at the points (the max value of
+    /// `IDX_DTYPE` for the points outside of the mesh), and the indices of the points outside of
+    /// the mesh
+    pub fn insert_points<'py>(
+        &mut self,
+        py: Python<'py>,
+        points: PyReadonlyArray2<f64>,
+        tol: f64,
+    ) -> PyResult<(Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<Idx>>)> {
+        if points.shape()[1] != 3 {
+            return Err(PyValueError::new_err("Invalid dimension 1 for points"));
+        }
+        if tol < 0.0 {
+            return Err(PyValueError::new_err("tol must be >= 0"));
+        }
+        let points: Vec<Point<3>> = points
+            .as_slice()?
+            .chunks(3)
+            .map(Point::<3>::from_column_slice)
+            .collect();
+        let (mesh, ids) = py.allow_threads(|| insert_points(&self.mesh, &points, tol));
+
+        let outside: Vec<Idx> = (0..ids.len() as Idx)
+            .filter(|&i| ids[i as usize].is_none())
+            .collect();
+        if !outside.is_empty() {
+            warn!("{} points are outside of the mesh", outside.len());
+        }
+        if let Some(gids) = self.global_ids.as_mut() {
+            gids.resize(mesh.n_verts() as usize, -1);
+        }
+        self.mesh = mesh;
+        self.version = next_mesh_version();
+        let ids = ids.into_iter().map(|i| i.unwrap_or(Idx::MAX)).collect();
+        Ok((to_numpy_1d(py, ids), to_numpy_1d(py, outside)))
+    }
+
+    /// Get the sorted indices of the boundary vertices, i.e. the vertices of the faces adjacent
+    /// to a single element, without extracting the boundary. The face to element connectivity
+    /// is used if it has been computed
+    #[must_use]
+    pub fn get_boundary_verts<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Idx>> {
+        let flags = boundary_verts(&self.mesh);
+        let ids = (0..self.mesh.n_verts())
+            .filter(|&i| flags[i as usize])
+            .collect();
+        to_numpy_1d(py, ids)
+    }
+
+    /// Flag the boundary vertices (see `get_boundary_verts`), as a boolean array of shape
+    /// (# of vertices,)
+    #[must_use]
+    pub fn is_boundary_vertex<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        to_numpy_1d(py, boundary_verts(&self.mesh))
+    }
+
+    /// Compute the unit normals of the faces, as an array of shape (# of faces, 3). They
+    /// point outwards for the boundary faces, and follow the face orientation for the internal
+    /// faces (see `orient_interfaces()`)
+    pub fn compute_face_normals<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        let (normals, _) = face_normals(&self.mesh)?;
+        Ok(to_numpy_2d(
+            py,
+            normals
+                .iter()
+                .flat_map(|n| n.normalize().iter().copied().collect::<Vec<_>>())
+                .collect(),
+            3,
+        ))
+    }
+
+    /// Compute the unit normals at the vertices, as an array of shape (# of vertices, 3): the
+    /// area weighted average of the outward normals of the adjacent boundary faces, and zero
+    /// for the interior vertices
+    pub fn compute_vertex_normals<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        let normals = vertex_normals(&self.mesh)?;
+        Ok(to_numpy_2d(
+            py,
+            normals
+                .iter()
+                .flat_map(|n| n.iter().copied().collect::<Vec<_>>())
+                .collect(),
+            3,
+        ))
+    }
+
+    /// Split the mesh into an inner part, made of the elements whose vertices are all within a
+    /// distance `d` of the boundary faces with a tag in `tags`, and an outer part with the other
+    /// elements. The faces between the two parts that are not already tagged are added to both
+    /// parts, oriented outwards, with tag `tag` (default: the largest face tag + 1), so that the
+    /// parts can be joined back by merging their common vertices. The global vertex ids, if any,
+    /// are transferred to the parts.
+    /// Return `(inner, outer, tag, inner_ids, outer_ids)`, where `inner_ids` and `outer_ids` are
+    /// the indices of the elements of the parts in the mesh
+    #[allow(clippy::type_complexity)]
+    pub fn split_by_wall_distance<'py>(
+        &self,
+        py: Python<'py>,
+        tags: PyReadonlyArray1<Tag>,
+        d: f64,
+        tag: Option<Tag>,
+    ) -> PyResult<(
+        Self,
+        Self,
+        Tag,
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<Idx>>,
+    )> {
+        if d.is_nan() || d < 0.0 {
+            return Err(PyValueError::new_err("d must be >= 0"));
+        }
+        let tags = tags.as_slice()?;
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                let max_tag = self.mesh.ftags().max().unwrap_or(0);
+                max_tag.checked_add(1).ok_or_else(|| {
+                    PyValueError::new_err("No tag available for the interface faces")
+                })?
             }
+        };
+        let dist = py
+            .allow_threads(|| wall_distance(&self.mesh, tags))
+            .ok_or_else(|| PyValueError::new_err("No boundary face with the given tags"))?;
 
-            /// Check that the mesh is valid
-            ///  - all elements have a >0 volume
-            ///  - all boundary faces are tagged
-            ///  - all the faces between different element tags are tagged
-            ///  - no other face is tagged
-            pub fn check(&self) -> PyResult<()> {
-                self.mesh.check().map_err(|e| PyRuntimeError::new_err(e.to_string()))
-            }
+        let (inner_ids, outer_ids): (Vec<Idx>, Vec<Idx>) = (0..self.mesh.n_elems())
+            .partition(|&i| self.mesh.elem(i).into_iter().all(|j| dist[j as usize] <= d));
+        let part = |removed: &[Idx]| {
+            let (mesh, vert_ids, _, _) = remove_elems(&self.mesh, removed, true, tag, true);
+            let mut res = Self::from(mesh);
+            res.global_ids = self
+                .global_ids
+                .as_ref()
+                .map(|gids| vert_ids.iter().map(|&i| gids[i as usize]).collect());
+            res
+        };
+        let inner = part(&outer_ids);
+        let outer = part(&inner_ids);
 
-            /// Compute the topology
-            pub fn compute_topology(&mut self) {
-                self.mesh.compute_topology();
-            }
+        Ok((
+            inner,
+            outer,
+            tag,
+            to_numpy_1d(py, inner_ids),
+            to_numpy_1d(py, outer_ids),
+        ))
+    }
 
-            /// Clear the topology
-            pub fn clear_topology(&mut self) {
-                self.mesh.clear_topology();
-            }
+    /// Locate the points `coords` (array of shape (# of points, 3)) in the mesh, using the
+    /// element tree stored by `compute_octree()` (that is called if needed). A point is in an
+    /// element if its barycentric coordinates are >= -`tol` (default: 1e-12)
+    /// Return `(elem_ids, bary)`: the indices of the elements containing the points (int32, -1
+    /// for the points that are not found) and the barycentric coordinates of the points in
+    /// these elements, of shape (# of points, 4) (NaN for the points that are not found)
+    /// The GIL is released while locating the points
+    pub fn locate_point<'py>(
+        &mut self,
+        py: Python<'py>,
+        coords: PyReadonlyArray2<f64>,
+        tol: Option<f64>,
+    ) -> PyResult<(Bound<'py, PyArray1<i32>>, Bound<'py, PyArray2<f64>>)> {
+        if coords.shape()[1] != 3 {
+            return Err(PyValueError::new_err("Invalid dimension 1 for coords"));
+        }
+        if self.mesh.n_elems() == 0 {
+            return Err(PyValueError::new_err(
+                "Cannot locate points in an empty mesh",
+            ));
+        }
+        if self.cached_octree().is_none() {
+            self.compute_octree();
+        }
+        let tree = self.cached_octree().unwrap().get::<3>(self.version)?;
+        let coords = coords.as_slice()?;
+        let (ids, bary) =
+            py.allow_threads(|| locate_points(&self.mesh, tree, coords, tol.unwrap_or(1e-12)));
+        Ok((to_numpy_1d(py, ids), to_numpy_2d(py, bary, 4)))
+    }
 
-            /// Automatically tag the elements based on a feature angle
-            pub fn autotag<'py>(&mut self, py: Python<'py>, angle_deg: f64) -> PyResult<Bound<'py, PyDict>> {
-                let res = self.mesh.autotag(angle_deg);
-                if let Err(res) = res {
-                     Err(PyRuntimeError::new_err(res.to_string()))
-                } else {
-                    let dict = PyDict::new_bound(py);
-                    for (k, v) in res.unwrap().iter() {
-                        dict.set_item(k, to_numpy_1d(py, v.to_vec()))?;
-                    }
-                    Ok(dict)
-                }
+    /// Get a quadratic (P2) version of the mesh for visualization, with mid-edge nodes that are
+    /// projected onto `geometry` (if given) for the boundary edges. Only the faces whose tag is
+    /// part of the geometry are projected.
+    /// Return `(coords, elems, faces)`, with the vertices followed by the mid-edge nodes, and the
+    /// element (resp. face) vertices followed by their mid-edge nodes in the order of the VTK
+    /// quadratic tetrahedra (resp. triangles)
+    #[allow(clippy::type_complexity)]
+    pub fn to_p2<'py>(
+        &self,
+        py: Python<'py>,
+        geometry: Option<&LinearGeometry3d>,
+    ) -> (
+        Bound<'py, PyArray2<f64>>,
+        Bound<'py, PyArray2<Idx>>,
+        Bound<'py, PyArray2<Idx>>,
+    ) {
+        let p2 = P2Mesh::new(&self.mesh, geometry.map(|g| (&g.geom, &g.gmesh)));
+        (
+            to_numpy_2d(
+                py,
+                p2.verts.iter().flat_map(|p| p.iter().copied()).collect(),
+                3,
+            ),
+            to_numpy_2d(py, p2.elems.concat(), 10),
+            to_numpy_2d(py, p2.faces.concat(), 6),
+        )
+    }
+
+    /// Write a quadratic (P2) version of the mesh (see `to_p2`) with the elements and faces as
+    /// quadratic cells to a .vtu file, for visualization
+    pub fn write_vtu_p2(&self, fname: &str, geometry: Option<&LinearGeometry3d>) -> PyResult<()> {
+        let p2 = P2Mesh::new(&self.mesh, geometry.map(|g| (&g.geom, &g.gmesh)));
+        p2.write_vtu(fname)?;
+        Ok(())
+    }
+
+    /// Get a hash of the coordinates, elements, faces and tags of the mesh, as an hexadecimal
+    /// string
+    #[must_use]
+    pub fn content_hash(&self) -> String {
+        format!("{:016x}", content_hash(&self.mesh))
+    }
+
+    /// Write the mesh, its content hash and derived arrays given as a dict of 2D arrays (e.g.
+    /// the implied metric or a wall distance) to a binary cache file, to be read by
+    /// `load_cached`
+    pub fn cache_to(
+        &self,
+        fname: &str,
+        arrays: Option<HashMap<String, PyReadonlyArray2<f64>>>,
+    ) -> PyResult<()> {
+        let arrays = arrays.unwrap_or_default();
+        let mut data = Vec::with_capacity(arrays.len());
+        for (name, arr) in &arrays {
+            if arr.shape()[1] == 0 {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid dimension 1 for {name}"
+                )));
             }
+            data.push((name.clone(), arr.shape()[1], arr.as_slice()?));
+        }
+        data.sort_by(|a, b| a.0.cmp(&b.0));
+        write_cache(fname, &self.mesh, &data)?;
+        Ok(())
+    }
 
-            /// Automatically tag the faces based on a feature angle
-            pub fn autotag_bdy<'py>(&mut self, py: Python<'py>, angle_deg: f64) -> PyResult<Bound<'py, PyDict>> {
-                let res = self.mesh.autotag_bdy(angle_deg);
-                if let Err(res) = res {
-                     Err(PyRuntimeError::new_err(res.to_string()))
-                } else {
-                    let dict = PyDict::new_bound(py);
-                    for (k, v) in res.unwrap().iter() {
-                        dict.set_item(k, to_numpy_1d(py, v.to_vec()))?;
-                    }
-                    Ok(dict)
+    /// Read a cache file written by `cache_to`, and return the mesh and the dict of derived
+    /// arrays. Loading fails if the content of the mesh does not match the hash stored in the
+    /// file or `expected_hash` (if given), unless `force` is set
+    #[classmethod]
+    pub fn load_cached<'py>(
+        _cls: &Bound<'_, PyType>,
+        py: Python<'py>,
+        fname: &str,
+        expected_hash: Option<&str>,
+        force: Option<bool>,
+    ) -> PyResult<(Self, Bound<'py, PyDict>)> {
+        let (hash, mesh, arrays) = read_cache::<3, Tetrahedron>(fname)?;
+        if !force.unwrap_or(false) {
+            let hash = format!("{hash:016x}");
+            if format!("{:016x}", content_hash(&mesh)) != hash {
+                return Err(PyValueError::new_err(format!(
+                    "The mesh read from {fname} does not match its hash {hash}"
+                )));
+            }
+            if let Some(expected_hash) = expected_hash {
+                if expected_hash != hash {
+                    return Err(PyValueError::new_err(format!(
+                        "The hash of the mesh in {fname} is {hash}, expected {expected_hash}"
+                    )));
                 }
             }
         }
-    };
-}
 
-create_mesh!(Mesh33, 3, Tetrahedron);
-create_mesh!(Mesh32, 3, Triangle);
-create_mesh!(Mesh31, 3, Edge);
-create_mesh!(Mesh22, 2, Triangle);
-create_mesh!(Mesh21, 2, Edge);
+        let res = PyDict::new_bound(py);
+        for (name, m, data) in arrays {
+            res.set_item(name, to_numpy_2d(py, data, m))?;
+        }
+        Ok((mesh.into(), res))
+    }
 
-#[pymethods]
-impl Mesh33 {
     /// Create a Mesh33 from basic elements
+    /// The tags may be given with any integer dtype, within `TAG_MIN` and `TAG_MAX`
     #[allow(clippy::too_many_arguments)]
     #[allow(clippy::too_many_lines)]
     #[classmethod]
@@ -575,17 +3196,17 @@ impl Mesh33 {
         _cls: &Bound<'_, PyType>,
         coords: PyReadonlyArray2<f64>,
         hexs: Option<PyReadonlyArray2<Idx>>,
-        hex_tags: Option<PyReadonlyArray1<Tag>>,
+        hex_tags: Option<TagArray>,
         pris: Option<PyReadonlyArray2<Idx>>,
-        pri_tags: Option<PyReadonlyArray1<Tag>>,
+        pri_tags: Option<TagArray>,
         pyrs: Option<PyReadonlyArray2<Idx>>,
-        pyr_tags: Option<PyReadonlyArray1<Tag>>,
+        pyr_tags: Option<TagArray>,
         tets: Option<PyReadonlyArray2<Idx>>,
-        tet_tags: Option<PyReadonlyArray1<Tag>>,
+        tet_tags: Option<TagArray>,
         quas: Option<PyReadonlyArray2<Idx>>,
-        qua_tags: Option<PyReadonlyArray1<Tag>>,
+        qua_tags: Option<TagArray>,
         tris: Option<PyReadonlyArray2<Idx>>,
-        tri_tags: Option<PyReadonlyArray1<Tag>>,
+        tri_tags: Option<TagArray>,
     ) -> PyResult<Self> {
         let mut res = SimplexMesh::<3, Tetrahedron>::empty();
 
@@ -600,15 +3221,12 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for hexs"));
             }
             if let Some(hex_tags) = hex_tags {
-                if hexs.shape()[0] != hex_tags.shape()[0] {
+                if hexs.shape()[0] != hex_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for hexs / hex_tags",
                     ));
                 }
-                res.add_hexs(
-                    hexs.as_slice()?.chunks(8),
-                    hex_tags.as_slice()?.iter().copied(),
-                );
+                res.add_hexs(hexs.as_slice()?.chunks(8), hex_tags.iter().copied());
             } else {
                 res.add_hexs(hexs.as_slice()?.chunks(8), (0..hexs.shape()[0]).map(|_| 1));
             };
@@ -619,15 +3237,12 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for pris"));
             }
             if let Some(pri_tags) = pri_tags {
-                if pris.shape()[0] != pri_tags.shape()[0] {
+                if pris.shape()[0] != pri_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for pris / pri_tags",
                     ));
                 }
-                res.add_pris(
-                    pris.as_slice()?.chunks(6),
-                    pri_tags.as_slice()?.iter().copied(),
-                );
+                res.add_pris(pris.as_slice()?.chunks(6), pri_tags.iter().copied());
             } else {
                 res.add_pris(pris.as_slice()?.chunks(6), (0..pris.shape()[0]).map(|_| 1));
             };
@@ -638,15 +3253,12 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for pyrs"));
             }
             if let Some(pyr_tags) = pyr_tags {
-                if pyrs.shape()[0] != pyr_tags.shape()[0] {
+                if pyrs.shape()[0] != pyr_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for pyrs / pyr_tags",
                     ));
                 }
-                res.add_pyrs(
-                    pyrs.as_slice()?.chunks(5),
-                    pyr_tags.as_slice()?.iter().copied(),
-                );
+                res.add_pyrs(pyrs.as_slice()?.chunks(5), pyr_tags.iter().copied());
             } else {
                 res.add_pyrs(pyrs.as_slice()?.chunks(5), (0..pyrs.shape()[0]).map(|_| 1));
             };
@@ -657,15 +3269,12 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for tets"));
             }
             if let Some(tet_tags) = tet_tags {
-                if tets.shape()[0] != tet_tags.shape()[0] {
+                if tets.shape()[0] != tet_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for tets / tet_tags",
                     ));
                 }
-                res.add_tets(
-                    tets.as_slice()?.chunks(4),
-                    tet_tags.as_slice()?.iter().copied(),
-                );
+                res.add_tets(tets.as_slice()?.chunks(4), tet_tags.iter().copied());
             } else {
                 res.add_tets(tets.as_slice()?.chunks(4), (0..tets.shape()[0]).map(|_| 1));
             };
@@ -676,15 +3285,12 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for quas"));
             }
             if let Some(qua_tags) = qua_tags {
-                if quas.shape()[0] != qua_tags.shape()[0] {
+                if quas.shape()[0] != qua_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for quas / qua_tags",
                     ));
                 }
-                res.add_quas(
-                    quas.as_slice()?.chunks(4),
-                    qua_tags.as_slice()?.iter().copied(),
-                );
+                res.add_quas(quas.as_slice()?.chunks(4), qua_tags.iter().copied());
             } else {
                 res.add_quas(quas.as_slice()?.chunks(4), (0..quas.shape()[0]).map(|_| 1));
             };
@@ -695,31 +3301,118 @@ impl Mesh33 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for tris"));
             }
             if let Some(tri_tags) = tri_tags {
-                if tris.shape()[0] != tri_tags.shape()[0] {
+                if tris.shape()[0] != tri_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for tris / tri_tags",
                     ));
                 }
-                res.add_tris(
-                    tris.as_slice()?.chunks(3),
-                    tri_tags.as_slice()?.iter().copied(),
-                );
+                res.add_tris(tris.as_slice()?.chunks(3), tri_tags.iter().copied());
             } else {
                 res.add_tris(tris.as_slice()?.chunks(3), (0..tris.shape()[0]).map(|_| 1));
             };
         }
 
-        Ok(Self { mesh: res })
+        Ok(res.into())
     }
 
     /// Extract the boundary faces into a Mesh, and return the indices of the vertices in the
-    /// parent mesh
+    /// parent mesh. `which` selects the tagged faces that are extracted: "all" (default),
+    /// "exterior" (faces that belong to a single element) or "internal" (faces shared by two
+    /// elements, e.g. the interfaces between element tags)
+    pub fn boundary<'py>(
+        &self,
+        py: Python<'py>,
+        which: Option<&str>,
+    ) -> PyResult<(Mesh32, Bound<'py, PyArray1<Idx>>)> {
+        let (bdy, ids) = extract_boundary(&self.mesh, which)?;
+        let mut bdy = Mesh32::from(bdy);
+        bdy.global_ids = self
+            .global_ids
+            .as_ref()
+            .map(|gids| ids.iter().map(|&i| gids[i as usize]).collect());
+        Ok((bdy, to_numpy_1d(py, ids)))
+    }
+
+    /// Set the face tags from the element tags of a boundary `Mesh32` `bdy`, e.g. after
+    /// `bdy.autotag()`, `vert_ids` being the indices of its vertices in the mesh as returned by
+    /// `boundary()`. The faces are matched by their vertices, and those that are not in `bdy`
+    /// keep their tag. A ValueError is raised if an element of `bdy` does not match a face.
+    /// Return the updated face tags
+    pub fn set_ftags_from_boundary<'py>(
+        &mut self,
+        py: Python<'py>,
+        bdy: &Mesh32,
+        vert_ids: PyReadonlyArray1<Idx>,
+    ) -> PyResult<Bound<'py, PyArray1<Tag>>> {
+        ftags_from_boundary(&mut self.mesh, &bdy.mesh, vert_ids.as_slice()?)?;
+        Ok(to_numpy_1d(py, self.mesh.ftags().collect()))
+    }
+
+    /// Extract the boundary faces of each tag into a Mesh, optionally only for the tags in `tags`
+    /// Return a dict mapping each tag to `(mesh, vert_ids, face_ids)`, with the indices of the
+    /// vertices and faces in the parent mesh
+    pub fn boundary_per_tag<'py>(
+        &self,
+        py: Python<'py>,
+        tags: Option<Vec<Tag>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        for (tag, bdy, vert_ids, face_ids) in boundary_per_tag(&self.mesh, tags.as_deref()) {
+            let mut bdy = Mesh32::from(bdy);
+            bdy.global_ids = self
+                .global_ids
+                .as_ref()
+                .map(|gids| vert_ids.iter().map(|&i| gids[i as usize]).collect());
+            dict.set_item(
+                tag,
+                (
+                    Py::new(py, bdy)?,
+                    to_numpy_1d(py, vert_ids),
+                    to_numpy_1d(py, face_ids),
+                ),
+            )?;
+        }
+        Ok(dict)
+    }
+
+    /// Get the anisotropy of the elements, i.e. the ratio of the largest to the smallest singular
+    /// value of the map from the regular tetrahedron to each element (the square root of the
+    /// eigenvalue ratio of the element-implied metric). It is 1 for regular elements
     #[must_use]
-    pub fn boundary<'py>(&self, py: Python<'py>) -> (Mesh32, Bound<'py, PyArray1<Idx>>) {
-        let (bdy, ids) = self.mesh.boundary();
-        (Mesh32 { mesh: bdy }, to_numpy_1d(py, ids))
+    pub fn elem_anisotropy<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        to_numpy_1d(py, elem_anisotropy(&self.mesh))
+    }
+
+    /// Get a summary of the element shapes as a dict with the number of elements, the min and
+    /// max element volumes and the min, max and mean element anisotropy (see `elem_anisotropy`)
+    pub fn quality_report<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let vols: Vec<f64> = self.mesh.gelems().map(|ge| ge.vol()).collect();
+        let aniso = elem_anisotropy(&self.mesh);
+        let n = aniso.len();
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("n_elems", n)?;
+        dict.set_item(
+            "vol_min",
+            vols.iter().copied().fold(f64::INFINITY, f64::min),
+        )?;
+        dict.set_item(
+            "vol_max",
+            vols.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        )?;
+        let stats = PyDict::new_bound(py);
+        stats.set_item("min", aniso.iter().copied().fold(f64::INFINITY, f64::min))?;
+        stats.set_item(
+            "max",
+            aniso.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        )?;
+        stats.set_item("mean", aniso.iter().sum::<f64>() / n.max(1) as f64)?;
+        dict.set_item("anisotropy", stats)?;
+        Ok(dict)
     }
 
+    /// Compute the element-implied metric at the vertices, as symmetric tensors whose components
+    /// are ordered as `SYMTENSOR_3D` = (xx, yy, zz, xy, yz, xz)
     pub fn implied_metric<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f64>>> {
         let res = self.mesh.implied_metric();
 
@@ -736,30 +3429,39 @@ impl Mesh33 {
     ///    and the sizes to curvature radius ratio is r_h
     ///  - the metric is entended into the volume with gradation beta
     ///  - if an implied metric is provided, the result is limited to (1/step,step) times the implied metric
-    ///  - if a normal size array is not provided, the minimum of the tangential sizes is used.
+    ///  - the normal sizes on the boundary are given per face tag by `h_n` and `h_n_tags`, where
+    ///    tag 0 stands for all the other face tags; the minimum of the tangential sizes is used
+    ///    for the tags without a normal size.
+    /// Return the metric and a dict with the normal size used for each face tag (None if the
+    /// minimum of the tangential sizes is used)
+    /// If `return_boundary` is True, also return the metric computed at the boundary vertices
+    /// before its extension into the volume, as an array of shape (# of boundary vertices, N),
+    /// and the (sorted) indices of the boundary vertices. The extension does not modify the
+    /// boundary vertices, so this is the restriction of the metric to these vertices
     #[allow(clippy::too_many_arguments)]
-    pub fn curvature_metric<'py>(
+    pub fn curvature_metric(
         &self,
-        py: Python<'py>,
+        py: Python<'_>,
         geom: &LinearGeometry3d,
         r_h: f64,
         beta: f64,
         h_min: Option<f64>,
         h_n: Option<PyReadonlyArray1<f64>>,
         h_n_tags: Option<PyReadonlyArray1<Tag>>,
-    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-        let res = if let Some(h_n) = h_n {
-            let h_n = h_n.as_slice()?;
-            if h_n_tags.is_none() {
-                return Err(PyRuntimeError::new_err("h_n_tags not given"));
-            }
-            let h_n_tags = h_n_tags.unwrap();
-            let h_n_tags = h_n_tags.as_slice()?;
+        return_boundary: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let h_n = h_n.as_ref().map(PyReadonlyArray1::as_slice).transpose()?;
+        let h_n_tags = h_n_tags
+            .as_ref()
+            .map(PyReadonlyArray1::as_slice)
+            .transpose()?;
+        let (h_n, h_n_tags, sizes) = normal_sizes(&self.mesh, h_n, h_n_tags)?;
+        let res = if h_n.is_empty() {
             self.mesh
-                .curvature_metric(&geom.geom, r_h, beta, Some(h_n), Some(h_n_tags))
+                .curvature_metric(&geom.geom, r_h, beta, None, None)
         } else {
             self.mesh
-                .curvature_metric(&geom.geom, r_h, beta, None, None)
+                .curvature_metric(&geom.geom, r_h, beta, Some(&h_n), Some(&h_n_tags))
         };
 
         if let Err(res) = res {
@@ -774,24 +3476,151 @@ impl Mesh33 {
 
         let m: Vec<f64> = m.iter().flat_map(|m| m.into_iter()).collect();
 
-        Ok(to_numpy_2d(py, m, 6))
+        let dict = PyDict::new_bound(py);
+        for (tag, h) in sizes {
+            dict.set_item(tag, h)?;
+        }
+        if !return_boundary.unwrap_or(false) {
+            return Ok((to_numpy_2d(py, m, 6), dict).into_py(py));
+        }
+        let (m_bdy, ids) = boundary_values(&self.mesh, &m, 6);
+        Ok((
+            to_numpy_2d(py, m, 6),
+            dict,
+            to_numpy_2d(py, m_bdy, 6),
+            to_numpy_1d(py, ids),
+        )
+            .into_py(py))
+    }
+
+    /// Compute the size `(n^T M n)^(-1/2)` of an anisotropic metric `m` defined at the vertices
+    /// in the direction `n` normal to the boundary, at the vertices of the boundary faces with
+    /// a tag in `tags`, e.g. to check the first layer height of a boundary layer metric.
+    /// The normal is that of the geometry if given and otherwise the area weighted average of
+    /// the face normals, averaged over the tags of the faces the vertex belongs to.
+    /// Return `(vert_ids, sizes)`
+    pub fn metric_normal_sizes<'py>(
+        &self,
+        py: Python<'py>,
+        m: PyReadonlyArray2<f64>,
+        tags: Vec<Tag>,
+        geometry: Option<&LinearGeometry3d>,
+    ) -> PyResult<(Bound<'py, PyArray1<Idx>>, Bound<'py, PyArray1<f64>>)> {
+        self.check_vertex_array("m", m.shape()[0])?;
+        if m.shape()[1] != 6 {
+            return Err(PyValueError::new_err("Invalid dimension 1"));
+        }
+        let m = m.as_slice()?;
+
+        let (ids, normals) =
+            boundary_vertex_normals(&self.mesh, &tags, geometry.map(|g| &g.gmesh))?;
+        let sizes = ids
+            .iter()
+            .zip(&normals)
+            .map(|(&i, n)| {
+                let m = &m[6 * i as usize..6 * (i as usize + 1)];
+                let l2 = (0..3)
+                    .flat_map(|k| (0..3).map(move |l| (k, l)))
+                    .map(|(k, l)| n[k] * m[sym_index(3, k, l)] * n[l])
+                    .sum::<f64>();
+                1.0 / l2.sqrt()
+            })
+            .collect();
+
+        Ok((to_numpy_1d(py, ids), to_numpy_1d(py, sizes)))
+    }
+
+    /// Set the element tags from a scalar field defined at the elements or at the vertices (then
+    /// averaged at the element centers), binned by the increasing values `thresholds`: the elements
+    /// with `thresholds[i-1] <= f < thresholds[i]` are in bin `i`.
+    /// If `split_components` is True (default), each connected component of a bin gets its own
+    /// tag, otherwise bin `i` gets tag `i + 1`. If `add_faces` is True (default: False), the
+    /// faces between the new tags are added with `add_boundary_faces()`.
+    /// Return a dict mapping the new tags to their number of elements
+    pub fn tag_from_field<'py>(
+        &mut self,
+        py: Python<'py>,
+        arr: PyReadonlyArray2<f64>,
+        thresholds: Vec<f64>,
+        split_components: Option<bool>,
+        add_faces: Option<bool>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        if arr.shape()[1] != 1 {
+            return Err(PyValueError::new_err("Invalid dimension 1"));
+        }
+        if thresholds.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(PyValueError::new_err("thresholds must be increasing"));
+        }
+
+        let arr = arr.as_slice()?;
+        let values: Vec<f64> = if arr.len() == self.mesh.n_elems() as usize {
+            arr.to_vec()
+        } else if arr.len() == self.mesh.n_verts() as usize {
+            self.mesh
+                .elems()
+                .map(|e| e.into_iter().map(|i| arr[i as usize]).sum::<f64>() / 4.0)
+                .collect()
+        } else {
+            return Err(PyValueError::new_err("Invalid dimension 0"));
+        };
+        let bins: Vec<usize> = values
+            .iter()
+            .map(|&v| thresholds.iter().filter(|&&t| t <= v).count())
+            .collect();
+
+        let tags = tags_from_bins(&self.mesh, &bins, split_components.unwrap_or(true))?;
+        for (t, tag) in self.mesh.mut_etags().zip(tags) {
+            *t = tag;
+        }
+        if add_faces.unwrap_or(false) {
+            let _ = self.mesh.add_boundary_faces();
+            self.version = next_mesh_version();
+        }
+
+        let mut counts = BTreeMap::new();
+        for tag in self.mesh.etags() {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+        let dict = PyDict::new_bound(py);
+        for (tag, n) in counts {
+            dict.set_item(tag, n)?;
+        }
+        Ok(dict)
+    }
+}
+
+impl Mesh32 {
+    /// Read a .stl file, and the names of the solids indexed by tag
+    fn read_stl(fname: &str, tol: Option<f64>) -> PyResult<(Self, BTreeMap<Tag, String>)> {
+        if tol.is_some_and(|tol| tol.is_nan() || tol <= 0.0) {
+            return Err(PyValueError::new_err("tol must be positive"));
+        }
+        let (mesh, names) =
+            read_stl(fname, tol).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let names = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (i as Tag + 1, name))
+            .collect();
+        Ok((mesh.into(), names))
     }
 }
 
 #[pymethods]
 impl Mesh32 {
     /// Create a Mesh32 from basic elements
+    /// The tags may be given with any integer dtype, within `TAG_MIN` and `TAG_MAX`
     #[classmethod]
     #[allow(clippy::too_many_arguments)]
     pub fn from_basic_elems(
         _cls: &Bound<'_, PyType>,
         coords: PyReadonlyArray2<f64>,
         quas: Option<PyReadonlyArray2<Idx>>,
-        qua_tags: Option<PyReadonlyArray1<Tag>>,
+        qua_tags: Option<TagArray>,
         tris: Option<PyReadonlyArray2<Idx>>,
-        tri_tags: Option<PyReadonlyArray1<Tag>>,
+        tri_tags: Option<TagArray>,
         edgs: Option<PyReadonlyArray2<Idx>>,
-        edg_tags: Option<PyReadonlyArray1<Tag>>,
+        edg_tags: Option<TagArray>,
     ) -> PyResult<Self> {
         let mut res = SimplexMesh::<3, Triangle>::empty();
 
@@ -806,15 +3635,12 @@ impl Mesh32 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for quas"));
             }
             if let Some(qua_tags) = qua_tags {
-                if quas.shape()[0] != qua_tags.shape()[0] {
+                if quas.shape()[0] != qua_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for quas / qua_tags",
                     ));
                 }
-                res.add_quas(
-                    quas.as_slice()?.chunks(4),
-                    qua_tags.as_slice()?.iter().copied(),
-                );
+                res.add_quas(quas.as_slice()?.chunks(4), qua_tags.iter().copied());
             } else {
                 res.add_quas(quas.as_slice()?.chunks(4), (0..quas.shape()[0]).map(|_| 1));
             };
@@ -825,15 +3651,12 @@ impl Mesh32 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for tris"));
             }
             if let Some(tri_tags) = tri_tags {
-                if tris.shape()[0] != tri_tags.shape()[0] {
+                if tris.shape()[0] != tri_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for tris / tri_tags",
                     ));
                 }
-                res.add_tris(
-                    tris.as_slice()?.chunks(3),
-                    tri_tags.as_slice()?.iter().copied(),
-                );
+                res.add_tris(tris.as_slice()?.chunks(3), tri_tags.iter().copied());
             } else {
                 res.add_tris(tris.as_slice()?.chunks(3), (0..tris.shape()[0]).map(|_| 1));
             };
@@ -844,62 +3667,242 @@ impl Mesh32 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for edgs"));
             }
             if let Some(edg_tags) = edg_tags {
-                if edgs.shape()[0] != edg_tags.shape()[0] {
+                if edgs.shape()[0] != edg_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for edgs / edg_tags",
                     ));
                 }
-                res.add_edgs(
-                    edgs.as_slice()?.chunks(2),
-                    edg_tags.as_slice()?.iter().copied(),
-                );
+                res.add_edgs(edgs.as_slice()?.chunks(2), edg_tags.iter().copied());
             } else {
                 res.add_edgs(edgs.as_slice()?.chunks(2), (0..edgs.shape()[0]).map(|_| 1));
             };
         }
 
-        Ok(Self { mesh: res })
+        Ok(res.into())
     }
 
-    #[doc = concat!("Read a ", stringify!($name), " from a .stl file")]
+    #[doc = concat!("Read a ", stringify!($name), " from a .stl file (ASCII or binary), merging the vertices closer")]
+    /// than `tol` (or with exactly the same coordinates if `tol` is None). In ASCII files, the
+    /// triangles of the i-th `solid` section are tagged `i + 1`; in binary files, all the triangles
+    /// are tagged 1 and the attribute bytes are ignored
     #[classmethod]
-    pub fn from_stl(_cls: &Bound<'_, PyType>, fname: &str) -> Self {
-        Self {
-            mesh: read_stl(fname),
-        }
+    pub fn from_stl(_cls: &Bound<'_, PyType>, fname: &str, tol: Option<f64>) -> PyResult<Self> {
+        Ok(Self::read_stl(fname, tol)?.0)
+    }
+
+    /// Read a mesh from a .stl file as `from_stl`, and return it with a dict mapping the element
+    /// tags to the names of the solids (the header for binary files)
+    #[classmethod]
+    pub fn from_stl_with_names(
+        _cls: &Bound<'_, PyType>,
+        fname: &str,
+        tol: Option<f64>,
+    ) -> PyResult<(Self, BTreeMap<Tag, String>)> {
+        Self::read_stl(fname, tol)
     }
 
     /// Reset the face tags of other to match those in self
-    pub fn transfer_tags_face(&self, other: &mut Mesh33) -> PyResult<()> {
-        let tree = self.mesh.compute_elem_tree();
+    /// The element tree of self is built unless given as `tree` or stored by `compute_octree()`
+    pub fn transfer_tags_face(&self, other: &mut Mesh33, tree: Option<&ElemTree>) -> PyResult<()> {
+        let owned;
+        let tree = if let Some(tree) = tree.or_else(|| self.cached_octree()) {
+            tree.get::<3>(self.version)?
+        } else {
+            owned = self.mesh.compute_elem_tree();
+            &owned
+        };
         self.mesh
-            .transfer_tags(&tree, &mut other.mesh)
+            .transfer_tags(tree, &mut other.mesh)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
     /// Reset the element tags of other to match those in self
-    pub fn transfer_tags_elem(&self, other: &mut Self) -> PyResult<()> {
-        let tree = self.mesh.compute_elem_tree();
+    /// The element tree of self is built unless given as `tree` or stored by `compute_octree()`
+    pub fn transfer_tags_elem(&self, other: &mut Self, tree: Option<&ElemTree>) -> PyResult<()> {
+        let owned;
+        let tree = if let Some(tree) = tree.or_else(|| self.cached_octree()) {
+            tree.get::<3>(self.version)?
+        } else {
+            owned = self.mesh.compute_elem_tree();
+            &owned
+        };
         self.mesh
-            .transfer_tags(&tree, &mut other.mesh)
+            .transfer_tags(tree, &mut other.mesh)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
+
+    /// Collapse the edges shorter than `l_min`, typically to clean up a mesh read from a .stl
+    /// file before building a geometry. The vertices on the face edges, the open boundaries and,
+    /// if `preserve_tags` is set (default), the curves between element tags only move along these
+    /// curves, and the collapses that would change the topology or invert a triangle are skipped.
+    /// Return the new mesh and the number of collapses
+    #[must_use]
+    pub fn collapse_small_edges(&self, l_min: f64, preserve_tags: Option<bool>) -> (Self, Idx) {
+        let (mesh, ids, n_collapses) =
+            collapse_small_edges(&self.mesh, l_min, preserve_tags.unwrap_or(true));
+        let mut res = Self::from(mesh);
+        res.global_ids = self
+            .global_ids
+            .as_ref()
+            .map(|gids| ids.iter().map(|&i| gids[i as usize]).collect());
+        (res, n_collapses)
+    }
+
+    /// Fill the holes of the surface, i.e. the loops of edges that belong to a single triangle,
+    /// with at most `max_edges` (default: 10) edges. Each loop is triangulated with the minimum
+    /// area, without adding vertices, and the new triangles are oriented consistently with their
+    /// neighbors and tagged `tag` (default: the largest element tag + 1). The larger holes and
+    /// the loops through vertices with several open boundary edges are left alone.
+    /// Return the number of holes filled and left
+    pub fn fill_holes(
+        &mut self,
+        max_edges: Option<usize>,
+        tag: Option<Tag>,
+    ) -> PyResult<(Idx, Idx)> {
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                let max_tag = self.mesh.etags().max().unwrap_or(0);
+                max_tag.checked_add(1).ok_or_else(|| {
+                    PyValueError::new_err("No tag available for the new triangles")
+                })?
+            }
+        };
+        let (mesh, n_filled, n_left) = fill_holes(&self.mesh, max_edges.unwrap_or(10), tag);
+        if n_filled > 0 {
+            self.mesh = mesh;
+            self.version = next_mesh_version();
+        }
+        Ok((n_filled, n_left))
+    }
 }
 
 #[pymethods]
 impl Mesh22 {
+    /// Get the sorted indices of the boundary vertices, i.e. the vertices of the faces adjacent
+    /// to a single element, without extracting the boundary. The face to element connectivity
+    /// is used if it has been computed
+    #[must_use]
+    pub fn get_boundary_verts<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Idx>> {
+        let flags = boundary_verts(&self.mesh);
+        let ids = (0..self.mesh.n_verts())
+            .filter(|&i| flags[i as usize])
+            .collect();
+        to_numpy_1d(py, ids)
+    }
+
+    /// Flag the boundary vertices (see `get_boundary_verts`), as a boolean array of shape
+    /// (# of vertices,)
+    #[must_use]
+    pub fn is_boundary_vertex<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        to_numpy_1d(py, boundary_verts(&self.mesh))
+    }
+
+    /// Compute the unit tangent vectors of the faces (edges), as an array of shape (# of faces,
+    /// 2). The boundary edges are oriented so that the domain is on their left, i.e. their
+    /// outward normal is the tangent rotated by -90 degrees; the internal edges follow the
+    /// face orientation (see `orient_interfaces()`)
+    pub fn compute_face_normals<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        let (normals, _) = face_normals(&self.mesh)?;
+        Ok(to_numpy_2d(
+            py,
+            normals
+                .iter()
+                .flat_map(|n| {
+                    let n = n.normalize();
+                    [-n[1], n[0]]
+                })
+                .collect(),
+            2,
+        ))
+    }
+
+    /// Locate the points `coords` (array of shape (# of points, 2)) in the mesh, using the
+    /// element tree stored by `compute_octree()` (that is called if needed). A point is in an
+    /// element if its barycentric coordinates are >= -`tol` (default: 1e-12)
+    /// Return `(elem_ids, bary)`: the indices of the elements containing the points (int32, -1
+    /// for the points that are not found) and the barycentric coordinates of the points in
+    /// these elements, of shape (# of points, 3) (NaN for the points that are not found)
+    /// The GIL is released while locating the points
+    pub fn locate_point<'py>(
+        &mut self,
+        py: Python<'py>,
+        coords: PyReadonlyArray2<f64>,
+        tol: Option<f64>,
+    ) -> PyResult<(Bound<'py, PyArray1<i32>>, Bound<'py, PyArray2<f64>>)> {
+        if coords.shape()[1] != 2 {
+            return Err(PyValueError::new_err("Invalid dimension 1 for coords"));
+        }
+        if self.mesh.n_elems() == 0 {
+            return Err(PyValueError::new_err(
+                "Cannot locate points in an empty mesh",
+            ));
+        }
+        if self.cached_octree().is_none() {
+            self.compute_octree();
+        }
+        let tree = self.cached_octree().unwrap().get::<2>(self.version)?;
+        let coords = coords.as_slice()?;
+        let (ids, bary) =
+            py.allow_threads(|| locate_points(&self.mesh, tree, coords, tol.unwrap_or(1e-12)));
+        Ok((to_numpy_1d(py, ids), to_numpy_2d(py, bary, 3)))
+    }
+
+    /// Mesh the domain where a signed distance function is negative, within a box given as a
+    /// pair `(min, max)` of coordinates, with a structured mesh of size `h` whose boundary
+    /// vertices are projected onto the zero level set. The function is called with arrays of
+    /// points of shape (n, dim) and must return the distances as an array of shape (n,).
+    /// All the elements and boundary faces have tag 1
+    #[classmethod]
+    pub fn from_sdf(
+        _cls: &Bound<'_, PyType>,
+        py: Python<'_>,
+        sdf: &Bound<'_, PyAny>,
+        bounds: (Vec<f64>, Vec<f64>),
+        h: f64,
+    ) -> PyResult<Self> {
+        let mesh = mesh_from_sdf::<2, Triangle>(py, sdf, &bounds.0, &bounds.1, h)?;
+        Ok(mesh.into())
+    }
+
+    /// Get, for the faces adjacent to a single element (i.e. excluding the internal faces), the
+    /// face indices (in the `get_faces()` ordering), the indices of the adjacent elements and
+    /// the indices of the faces within these elements.
+    /// Return `(face_ids, elem_ids, local_face_ids)`
+    #[allow(clippy::type_complexity)]
+    #[must_use]
+    pub fn boundary_face_elems<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> (
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<Idx>>,
+        Bound<'py, PyArray1<Idx>>,
+    ) {
+        let (face_ids, elem_ids, local_ids) = boundary_face_elems(&self.mesh);
+        (
+            to_numpy_1d(py, face_ids),
+            to_numpy_1d(py, elem_ids),
+            to_numpy_1d(py, local_ids),
+        )
+    }
+
     /// Create a Mesh22 from basic elements
+    /// The tags may be given with any integer dtype, within `TAG_MIN` and `TAG_MAX`
     #[allow(clippy::too_many_arguments)]
     #[classmethod]
     pub fn from_basic_elems(
         _cls: &Bound<'_, PyType>,
         coords: PyReadonlyArray2<f64>,
         quas: Option<PyReadonlyArray2<Idx>>,
-        qua_tags: Option<PyReadonlyArray1<Tag>>,
+        qua_tags: Option<TagArray>,
         tris: Option<PyReadonlyArray2<Idx>>,
-        tri_tags: Option<PyReadonlyArray1<Tag>>,
+        tri_tags: Option<TagArray>,
         edgs: Option<PyReadonlyArray2<Idx>>,
-        edg_tags: Option<PyReadonlyArray1<Tag>>,
+        edg_tags: Option<TagArray>,
     ) -> PyResult<Self> {
         let mut res = SimplexMesh::<2, Triangle>::empty();
 
@@ -914,15 +3917,12 @@ impl Mesh22 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for quas"));
             }
             if let Some(qua_tags) = qua_tags {
-                if quas.shape()[0] != qua_tags.shape()[0] {
+                if quas.shape()[0] != qua_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for quas / qua_tags",
                     ));
                 }
-                res.add_quas(
-                    quas.as_slice()?.chunks(4),
-                    qua_tags.as_slice()?.iter().copied(),
-                );
+                res.add_quas(quas.as_slice()?.chunks(4), qua_tags.iter().copied());
             } else {
                 res.add_quas(quas.as_slice()?.chunks(4), (0..quas.shape()[0]).map(|_| 1));
             };
@@ -933,15 +3933,12 @@ impl Mesh22 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for tris"));
             }
             if let Some(tri_tags) = tri_tags {
-                if tris.shape()[0] != tri_tags.shape()[0] {
+                if tris.shape()[0] != tri_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for tris / tri_tags",
                     ));
                 }
-                res.add_tris(
-                    tris.as_slice()?.chunks(3),
-                    tri_tags.as_slice()?.iter().copied(),
-                );
+                res.add_tris(tris.as_slice()?.chunks(3), tri_tags.iter().copied());
             } else {
                 res.add_tris(tris.as_slice()?.chunks(3), (0..tris.shape()[0]).map(|_| 1));
             };
@@ -952,30 +3949,81 @@ impl Mesh22 {
                 return Err(PyValueError::new_err("Invalid dimension 1 for edgs"));
             }
             if let Some(edg_tags) = edg_tags {
-                if edgs.shape()[0] != edg_tags.shape()[0] {
+                if edgs.shape()[0] != edg_tags.len() {
                     return Err(PyValueError::new_err(
                         "Invalid dimension 0 for edgs / edg_tags",
                     ));
                 }
-                res.add_edgs(
-                    edgs.as_slice()?.chunks(2),
-                    edg_tags.as_slice()?.iter().copied(),
-                );
+                res.add_edgs(edgs.as_slice()?.chunks(2), edg_tags.iter().copied());
             } else {
                 res.add_edgs(edgs.as_slice()?.chunks(2), (0..edgs.shape()[0]).map(|_| 1));
             };
         }
 
-        Ok(Self { mesh: res })
+        Ok(res.into())
     }
     /// Extract the boundary faces into a Mesh, and return the indices of the vertices in the
-    /// parent mesh
-    #[must_use]
-    pub fn boundary<'py>(&self, py: Python<'py>) -> (Mesh21, Bound<'py, PyArray1<Idx>>) {
-        let (bdy, ids) = self.mesh.boundary();
-        (Mesh21 { mesh: bdy }, to_numpy_1d(py, ids))
+    /// parent mesh. `which` selects the tagged faces that are extracted: "all" (default),
+    /// "exterior" (faces that belong to a single element) or "internal" (faces shared by two
+    /// elements, e.g. the interfaces between element tags)
+    pub fn boundary<'py>(
+        &self,
+        py: Python<'py>,
+        which: Option<&str>,
+    ) -> PyResult<(Mesh21, Bound<'py, PyArray1<Idx>>)> {
+        let (bdy, ids) = extract_boundary(&self.mesh, which)?;
+        let mut bdy = Mesh21::from(bdy);
+        bdy.global_ids = self
+            .global_ids
+            .as_ref()
+            .map(|gids| ids.iter().map(|&i| gids[i as usize]).collect());
+        Ok((bdy, to_numpy_1d(py, ids)))
+    }
+
+    /// Set the face tags from the element tags of a boundary `Mesh21` `bdy`, e.g. after
+    /// `bdy.autotag()`, `vert_ids` being the indices of its vertices in the mesh as returned by
+    /// `boundary()`. The faces are matched by their vertices, and those that are not in `bdy`
+    /// keep their tag. A ValueError is raised if an element of `bdy` does not match a face.
+    /// Return the updated face tags
+    pub fn set_ftags_from_boundary<'py>(
+        &mut self,
+        py: Python<'py>,
+        bdy: &Mesh21,
+        vert_ids: PyReadonlyArray1<Idx>,
+    ) -> PyResult<Bound<'py, PyArray1<Tag>>> {
+        ftags_from_boundary(&mut self.mesh, &bdy.mesh, vert_ids.as_slice()?)?;
+        Ok(to_numpy_1d(py, self.mesh.ftags().collect()))
+    }
+
+    /// Extract the boundary faces of each tag into a Mesh, optionally only for the tags in `tags`
+    /// Return a dict mapping each tag to `(mesh, vert_ids, face_ids)`, with the indices of the
+    /// vertices and faces in the parent mesh
+    pub fn boundary_per_tag<'py>(
+        &self,
+        py: Python<'py>,
+        tags: Option<Vec<Tag>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        for (tag, bdy, vert_ids, face_ids) in boundary_per_tag(&self.mesh, tags.as_deref()) {
+            let mut bdy = Mesh21::from(bdy);
+            bdy.global_ids = self
+                .global_ids
+                .as_ref()
+                .map(|gids| vert_ids.iter().map(|&i| gids[i as usize]).collect());
+            dict.set_item(
+                tag,
+                (
+                    Py::new(py, bdy)?,
+                    to_numpy_1d(py, vert_ids),
+                    to_numpy_1d(py, face_ids),
+                ),
+            )?;
+        }
+        Ok(dict)
     }
 
+    /// Compute the element-implied metric at the vertices, as symmetric tensors whose components
+    /// are ordered as `SYMTENSOR_2D` = (xx, yy, xy)
     pub fn implied_metric<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f64>>> {
         let res = self.mesh.implied_metric();
 
@@ -991,30 +4039,39 @@ impl Mesh22 {
     ///  - for boundary vertices, the principal directions are aligned with the principal curvature directions
     ///    and the sizes to curvature radius ratio is r_h
     ///  - the metric is entended into the volume with gradation beta
-    ///  - if a normal size array is not provided, the minimum of the tangential sizes is used.
+    ///  - the normal sizes on the boundary are given per face tag by `h_n` and `h_n_tags`, where
+    ///    tag 0 stands for all the other face tags; the minimum of the tangential sizes is used
+    ///    for the tags without a normal size.
+    /// Return the metric and a dict with the normal size used for each face tag (None if the
+    /// minimum of the tangential sizes is used)
+    /// If `return_boundary` is True, also return the metric computed at the boundary vertices
+    /// before its extension into the volume, as an array of shape (# of boundary vertices, N),
+    /// and the (sorted) indices of the boundary vertices. The extension does not modify the
+    /// boundary vertices, so this is the restriction of the metric to these vertices
     #[allow(clippy::too_many_arguments)]
-    pub fn curvature_metric<'py>(
+    pub fn curvature_metric(
         &self,
-        py: Python<'py>,
+        py: Python<'_>,
         geom: &LinearGeometry2d,
         r_h: f64,
         beta: f64,
         h_min: Option<f64>,
         h_n: Option<PyReadonlyArray1<f64>>,
         h_n_tags: Option<PyReadonlyArray1<Tag>>,
-    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-        let res = if let Some(h_n) = h_n {
-            let h_n = h_n.as_slice()?;
-            if h_n_tags.is_none() {
-                return Err(PyRuntimeError::new_err("h_n_tags not given"));
-            }
-            let h_n_tags = h_n_tags.unwrap();
-            let h_n_tags = h_n_tags.as_slice()?;
+        return_boundary: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let h_n = h_n.as_ref().map(PyReadonlyArray1::as_slice).transpose()?;
+        let h_n_tags = h_n_tags
+            .as_ref()
+            .map(PyReadonlyArray1::as_slice)
+            .transpose()?;
+        let (h_n, h_n_tags, sizes) = normal_sizes(&self.mesh, h_n, h_n_tags)?;
+        let res = if h_n.is_empty() {
             self.mesh
-                .curvature_metric(&geom.geom, r_h, beta, Some(h_n), Some(h_n_tags))
+                .curvature_metric(&geom.geom, r_h, beta, None, None)
         } else {
             self.mesh
-                .curvature_metric(&geom.geom, r_h, beta, None, None)
+                .curvature_metric(&geom.geom, r_h, beta, Some(&h_n), Some(&h_n_tags))
         };
 
         if let Err(res) = res {
@@ -1029,25 +4086,172 @@ impl Mesh22 {
 
         let m: Vec<f64> = m.iter().flat_map(|m| m.into_iter()).collect();
 
-        Ok(to_numpy_2d(py, m, 3))
+        let dict = PyDict::new_bound(py);
+        for (tag, h) in sizes {
+            dict.set_item(tag, h)?;
+        }
+        if !return_boundary.unwrap_or(false) {
+            return Ok((to_numpy_2d(py, m, 3), dict).into_py(py));
+        }
+        let (m_bdy, ids) = boundary_values(&self.mesh, &m, 3);
+        Ok((
+            to_numpy_2d(py, m, 3),
+            dict,
+            to_numpy_2d(py, m_bdy, 3),
+            to_numpy_1d(py, ids),
+        )
+            .into_py(py))
     }
 }
 
 #[pymethods]
 impl Mesh21 {
     /// Reset the face tags of other to match those in self
-    pub fn transfer_tags_face(&self, other: &mut Mesh22) -> PyResult<()> {
-        let tree = self.mesh.compute_elem_tree();
+    /// The element tree of self is built unless given as `tree` or stored by `compute_octree()`
+    pub fn transfer_tags_face(&self, other: &mut Mesh22, tree: Option<&ElemTree>) -> PyResult<()> {
+        let owned;
+        let tree = if let Some(tree) = tree.or_else(|| self.cached_octree()) {
+            tree.get::<2>(self.version)?
+        } else {
+            owned = self.mesh.compute_elem_tree();
+            &owned
+        };
         self.mesh
-            .transfer_tags(&tree, &mut other.mesh)
+            .transfer_tags(tree, &mut other.mesh)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
     /// Reset the element tags of other to match those in self
-    pub fn transfer_tags_elem(&self, other: &mut Self) -> PyResult<()> {
-        let tree = self.mesh.compute_elem_tree();
+    /// The element tree of self is built unless given as `tree` or stored by `compute_octree()`
+    pub fn transfer_tags_elem(&self, other: &mut Self, tree: Option<&ElemTree>) -> PyResult<()> {
+        let owned;
+        let tree = if let Some(tree) = tree.or_else(|| self.cached_octree()) {
+            tree.get::<2>(self.version)?
+        } else {
+            owned = self.mesh.compute_elem_tree();
+            &owned
+        };
         self.mesh
-            .transfer_tags(&tree, &mut other.mesh)
+            .transfer_tags(tree, &mut other.mesh)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 }
+
+macro_rules! create_mesh_metric_methods {
+    ($name: ident, $n_aniso: literal, $iso: ident, $aniso: ident) => {
+        #[pymethods]
+        impl $name {
+            #[doc = concat!("Convert a Hessian to the optimal metric for a Lp norm, using ", stringify!($iso), ".hessian_to_metric")]
+            #[doc = concat!("or ", stringify!($aniso), ".hessian_to_metric depending on the number of columns of `m` (1 or ", stringify!($n_aniso), ")")]
+            pub fn metric_from_hessian<'py>(
+                &self,
+                py: Python<'py>,
+                m: PyReadonlyArray2<f64>,
+                p: Option<Idx>,
+                rel_floor: Option<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                let n = m.shape()[1];
+                match n {
+                    1 => $iso::hessian_to_metric(&py.get_type_bound::<$iso>(), py, self, m, p, rel_floor),
+                    $n_aniso => $aniso::hessian_to_metric(&py.get_type_bound::<$aniso>(), py, self, m, p, rel_floor),
+                    _ => Err(PyValueError::new_err("Invalid dimension 1")),
+                }
+            }
+
+            #[doc = concat!("Scale a metric field to reach the desired (ideal) number of elements, using ", stringify!($iso), ".scale_metric")]
+            #[doc = concat!("or ", stringify!($aniso), ".scale_metric depending on the number of columns of `m` (1 or ", stringify!($n_aniso), ")")]
+            #[allow(clippy::too_many_arguments)]
+            pub fn scale_metric<'py>(
+                &self,
+                py: Python<'py>,
+                m: PyReadonlyArray2<f64>,
+                h_min: f64,
+                h_max: f64,
+                n_elems: Idx,
+                fixed_m: Option<PyReadonlyArray2<f64>>,
+                implied_m: Option<PyReadonlyArray2<f64>>,
+                step: Option<f64>,
+                max_iter: Option<Idx>,
+                fixed_etags: Option<Vec<Tag>>,
+            ) -> PyResult<(Bound<'py, PyArray2<f64>>, Bound<'py, PyDict>)> {
+                let n = m.shape()[1];
+                match n {
+                    1 => $iso::scale_metric(
+                        &py.get_type_bound::<$iso>(), py, self, m, h_min, h_max, n_elems, fixed_m, implied_m, step, max_iter, fixed_etags,
+                    ),
+                    $n_aniso => $aniso::scale_metric(
+                        &py.get_type_bound::<$aniso>(), py, self, m, h_min, h_max, n_elems, fixed_m, implied_m, step, max_iter, fixed_etags,
+                    ),
+                    _ => Err(PyValueError::new_err("Invalid dimension 1")),
+                }
+            }
+
+            #[doc = concat!("Apply a maximum gradation to a metric field, using ", stringify!($iso), ".apply_metric_gradation")]
+            #[doc = concat!("or ", stringify!($aniso), ".apply_metric_gradation depending on the number of columns of `m` (1 or ", stringify!($n_aniso), ")")]
+            pub fn apply_gradation<'py>(
+                &self,
+                py: Python<'py>,
+                m: PyReadonlyArray2<f64>,
+                beta: f64,
+                n_iter: Idx,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                let n = m.shape()[1];
+                match n {
+                    1 => $iso::apply_metric_gradation(&py.get_type_bound::<$iso>(), py, self, m, beta, n_iter),
+                    $n_aniso => $aniso::apply_metric_gradation(&py.get_type_bound::<$aniso>(), py, self, m, beta, n_iter),
+                    _ => Err(PyValueError::new_err("Invalid dimension 1")),
+                }
+            }
+        }
+    };
+}
+
+create_mesh_metric_methods!(Mesh33, 6, Remesher3dIso, Remesher3dAniso);
+create_mesh_metric_methods!(Mesh22, 3, Remesher2dIso, Remesher2dAniso);
+
+macro_rules! create_mesh_info {
+    ($name: ident, $dim: expr, $aniso: ident) => {
+        #[pymethods]
+        impl $name {
+            /// Get a summary of the mesh as a dict: entity counts, bounding box, element and face
+            /// counts and volumes per tag, element quality statistics, status of the connectivity
+            /// caches and, if a metric `m` defined at the vertices is given (isotropic or
+            /// anisotropic), its min / max sizes, max anisotropy and complexity
+            pub fn info<'py>(
+                &self,
+                py: Python<'py>,
+                m: Option<PyReadonlyArray2<f64>>,
+            ) -> PyResult<Bound<'py, PyDict>> {
+                let dict = mesh_info(py, &self.mesh)?;
+                dict.set_item("version", self.version)?;
+
+                if let Some(m) = m {
+                    self.check_vertex_array("m", m.shape()[0])?;
+                    let n = m.shape()[1];
+                    let m = m.as_slice()?;
+                    let (h_min, h_max, aniso, complexity) = if n == 1 {
+                        metric_info::<$dim, _, IsoMetric<$dim>>(&self.mesh, m)
+                    } else if n == <$aniso as Metric<$dim>>::N {
+                        metric_info::<$dim, _, $aniso>(&self.mesh, m)
+                    } else {
+                        return Err(PyValueError::new_err("Invalid dimension 1"));
+                    };
+                    let metric = PyDict::new_bound(py);
+                    metric.set_item("h_min", h_min)?;
+                    metric.set_item("h_max", h_max)?;
+                    metric.set_item("anisotropy", aniso)?;
+                    metric.set_item("complexity", complexity)?;
+                    dict.set_item("metric", metric)?;
+                }
+
+                Ok(dict)
+            }
+        }
+    };
+}
+
+create_mesh_info!(Mesh33, 3, AnisoMetric3d);
+create_mesh_info!(Mesh32, 3, AnisoMetric3d);
+create_mesh_info!(Mesh31, 3, AnisoMetric3d);
+create_mesh_info!(Mesh22, 2, AnisoMetric2d);
+create_mesh_info!(Mesh21, 2, AnisoMetric2d);
@@ -0,0 +1,558 @@
+use crate::field::sym_index;
+use numpy::{PyReadonlyArray1, PyUntypedArrayMethods};
+use pyo3::{exceptions::PyValueError, prelude::PyAnyMethods, Bound, PyAny, PyResult};
+use std::collections::{BTreeMap, BTreeSet};
+use tucanos::{geom_elems::GElem, mesh::SimplexMesh, metric::Metric, topo_elems::Elem, Idx, Tag};
+
+/// Volume of the ideal element (regular simplex with unit edges)
+fn ideal_vol<E: Elem>() -> f64 {
+    match E::N_VERTS {
+        2 => 1.0,
+        3 => f64::sqrt(3.0) / 4.0,
+        4 => 1.0 / (6.0 * f64::sqrt(2.0)),
+        _ => unreachable!(),
+    }
+}
+
+/// Contribution of each element to the complexity of a metric field defined at the mesh
+/// vertices, the metric density being averaged over the element vertices
+fn elem_complexities<'a, const D: usize, E: Elem, M: Metric<D>>(
+    mesh: &'a SimplexMesh<D, E>,
+    m: &'a [M],
+) -> impl Iterator<Item = f64> + 'a {
+    let ideal_vol = ideal_vol::<E>();
+    mesh.elems().zip(mesh.gelems()).map(move |(e, ge)| {
+        let density = e
+            .into_iter()
+            .map(|i| 1.0 / m[i as usize].vol())
+            .sum::<f64>()
+            / f64::from(E::N_VERTS);
+        ge.vol() * density / ideal_vol
+    })
+}
+
+/// Estimate the complexity (ideal number of elements) of a metric field defined at the mesh
+/// vertices, the metric density being averaged over the element vertices
+pub fn complexity<const D: usize, E: Elem, M: Metric<D>>(mesh: &SimplexMesh<D, E>, m: &[M]) -> f64 {
+    elem_complexities(mesh, m).sum()
+}
+
+/// Estimate the complexity of a metric field defined at the mesh vertices over the elements of
+/// each tag, the values summing to `complexity(mesh, m)`
+pub fn complexity_per_tag<const D: usize, E: Elem, M: Metric<D>>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[M],
+) -> BTreeMap<Tag, f64> {
+    let mut res = BTreeMap::new();
+    for (tag, c) in mesh.etags().zip(elem_complexities(mesh, m)) {
+        *res.entry(tag).or_insert(0.0) += c;
+    }
+    res
+}
+
+/// Split the complexity of a metric field defined at the mesh vertices between the vertices:
+/// the contribution of each element is shared equally between its vertices, weighted by their
+/// metric density. The values sum to `complexity(mesh, m)`
+fn vertex_complexities<const D: usize, E: Elem, M: Metric<D>>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[M],
+) -> Vec<f64> {
+    let ideal_vol = ideal_vol::<E>();
+    let n = f64::from(E::N_VERTS);
+    let mut res = vec![0.0; m.len()];
+    for (e, ge) in mesh.elems().zip(mesh.gelems()) {
+        let w = ge.vol() / ideal_vol / n;
+        for i in e {
+            res[i as usize] += w / m[i as usize].vol();
+        }
+    }
+    res
+}
+
+/// Scale a metric field defined at the mesh vertices to reach a complexity of `n_elems`, the
+/// metric being kept unchanged at the vertices where `fixed` is true. The complexity of these
+/// vertices (see `vertex_complexities`) is subtracted from `n_elems`, and the other vertices are
+/// scaled uniformly, with sizes bounded by `h_min` and `h_max`, to reach the remaining complexity.
+/// Return the complexities of the fixed and scaled vertices
+pub fn scale_metric_with_fixed<const D: usize, E: Elem, M: Metric<D>>(
+    mesh: &SimplexMesh<D, E>,
+    m: &mut [M],
+    fixed: &[bool],
+    h_min: f64,
+    h_max: f64,
+    n_elems: f64,
+    max_iter: Idx,
+) -> PyResult<(f64, f64)> {
+    let split = |m: &[M]| {
+        vertex_complexities(mesh, m).into_iter().zip(fixed).fold(
+            (0.0, 0.0),
+            |(f, s), (c, &is_fixed)| {
+                if is_fixed {
+                    (f + c, s)
+                } else {
+                    (f, s + c)
+                }
+            },
+        )
+    };
+
+    let (c_fixed, mut c_scaled) = split(m);
+    let target = n_elems - c_fixed;
+    if target <= 0.0 {
+        return Err(PyValueError::new_err(format!(
+            "The complexity of the fixed vertices ({c_fixed:.0}) exceeds the target ({n_elems:.0})"
+        )));
+    }
+    if c_scaled == 0.0 {
+        return Err(PyValueError::new_err("All the vertices are fixed"));
+    }
+
+    let orig: Vec<M> = m.to_vec();
+    let dim = f64::from(E::N_VERTS - 1);
+    let mut scale = 1.0;
+    for _ in 0..max_iter {
+        scale *= (target / c_scaled).powf(2.0 / dim);
+        for ((m_v, orig_v), &is_fixed) in m.iter_mut().zip(&orig).zip(fixed) {
+            if !is_fixed {
+                *m_v = *orig_v;
+                m_v.scale_with_bounds(scale, h_min, h_max);
+            }
+        }
+        c_scaled = split(m).1;
+        if (c_scaled - target).abs() < 1e-3 * target {
+            break;
+        }
+    }
+    Ok((c_fixed, c_scaled))
+}
+
+/// Lower bound of the error indicator relative to its maximum, to keep the sizes finite where
+/// it vanishes
+const MIN_INDICATOR_RATIO: f64 = 1e-12;
+
+/// Element sizes that equidistribute an error indicator `eta` defined at the elements, assuming
+/// that it behaves as `h^p`: the size of each element (the edge length of the ideal element with
+/// the same volume) is multiplied by `(eta / eta_max)^(-1/p)`. The result is defined up to a
+/// scaling factor, to be set by the target complexity
+pub fn indicator_sizes<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    eta: &[f64],
+    p: f64,
+) -> PyResult<Vec<f64>> {
+    if p <= 0.0 {
+        return Err(PyValueError::new_err("p must be positive"));
+    }
+    if eta.iter().any(|&x| !(x >= 0.0 && x.is_finite())) {
+        return Err(PyValueError::new_err(
+            "The indicator must be non negative and finite",
+        ));
+    }
+    let eta_max = eta.iter().copied().fold(0.0, f64::max);
+    if eta_max == 0.0 {
+        return Err(PyValueError::new_err("The indicator is zero"));
+    }
+
+    let ideal_vol = ideal_vol::<E>();
+    let dim = f64::from(E::N_VERTS - 1);
+    Ok(mesh
+        .gelems()
+        .zip(eta)
+        .map(|(ge, &x)| {
+            let h = (ge.vol() / ideal_vol).powf(1.0 / dim);
+            h * (x / eta_max).max(MIN_INDICATOR_RATIO).powf(-1.0 / p)
+        })
+        .collect())
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix of dimension `dim` <= 3 given
+/// as `(i, j) -> value`, computed with cyclic Jacobi rotations
+fn sym_eig(dim: usize, a: impl Fn(usize, usize) -> f64) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut m = [[0.0; 3]; 3];
+    let mut v = [[0.0; 3]; 3];
+    for i in 0..dim {
+        v[i][i] = 1.0;
+        for j in 0..dim {
+            m[i][j] = a(i, j);
+        }
+    }
+    for _ in 0..50 {
+        let off: f64 = (0..dim)
+            .flat_map(|i| (i + 1..dim).map(move |j| (i, j)))
+            .map(|(i, j)| m[i][j] * m[i][j])
+            .sum();
+        let diag: f64 = (0..dim).map(|i| m[i][i] * m[i][i]).sum();
+        if off <= f64::EPSILON * f64::EPSILON * diag || off == 0.0 {
+            break;
+        }
+        for p in 0..dim {
+            for q in p + 1..dim {
+                if m[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = 0.5 * (m[q][q] - m[p][p]) / m[p][q];
+                let t = theta.signum() / (theta.abs() + theta.hypot(1.0));
+                let c = 1.0 / t.hypot(1.0);
+                let s = t * c;
+                for k in 0..dim {
+                    let (mkp, mkq) = (m[k][p], m[k][q]);
+                    m[k][p] = c * mkp - s * mkq;
+                    m[k][q] = s * mkp + c * mkq;
+                }
+                for k in 0..dim {
+                    let (mpk, mqk) = (m[p][k], m[q][k]);
+                    m[p][k] = c * mpk - s * mqk;
+                    m[q][k] = s * mpk + c * mqk;
+                }
+                for row in v.iter_mut().take(dim) {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    ([m[0][0], m[1][1], m[2][2]], v)
+}
+
+/// Apply `f` to the eigenvalues of a field of symmetric tensors, in place. The field has `n` = 1
+/// (scalar), 3 or 6 (symmetric tensors ordered as `SYMTENSOR_2D` or `SYMTENSOR_3D`) components
+/// per vertex
+pub fn sym_map(x: &mut [f64], n: usize, f: impl Fn(f64) -> f64) {
+    let dim = match n {
+        1 => {
+            x.iter_mut().for_each(|x| *x = f(*x));
+            return;
+        }
+        3 => 2,
+        6 => 3,
+        _ => unreachable!(),
+    };
+    for x in x.chunks_mut(n) {
+        let (l, v) = sym_eig(dim, |i, j| x[sym_index(dim, i, j)]);
+        let l: Vec<_> = l[..dim].iter().map(|&l| f(l)).collect();
+        for i in 0..dim {
+            for j in i..dim {
+                x[sym_index(dim, i, j)] = (0..dim).map(|k| l[k] * v[i][k] * v[j][k]).sum();
+            }
+        }
+    }
+}
+
+/// Solve the linear system `a x = b` of size `n` <= 6 (rows of `a` stored contiguously) by Gaussian
+/// elimination with partial pivoting, or return None if it is singular
+fn solve(n: usize, mut a: [f64; 36], mut b: [f64; 6]) -> Option<[f64; 6]> {
+    let scale = a[..n * n].iter().fold(0.0, |m: f64, x| m.max(x.abs()));
+    for k in 0..n {
+        let p = (k..n).max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))?;
+        if a[p * n + k].abs() <= 1e-12 * scale {
+            return None;
+        }
+        for j in 0..n {
+            a.swap(k * n + j, p * n + j);
+        }
+        b.swap(k, p);
+        for i in k + 1..n {
+            let f = a[i * n + k] / a[k * n + k];
+            for j in k..n {
+                a[i * n + j] -= f * a[k * n + j];
+            }
+            b[i] -= f * b[k];
+        }
+    }
+    let mut x = [0.0; 6];
+    for k in (0..n).rev() {
+        let s: f64 = (k + 1..n).map(|j| a[k * n + j] * x[j]).sum();
+        x[k] = (b[k] - s) / a[k * n + k];
+    }
+    Some(x)
+}
+
+/// Deviation of the elements of a mesh from a metric field `m` defined at the vertices, with `n`
+/// = 1 (sizes) or `D (D + 1) / 2` (symmetric tensors) components per vertex. The metric of each
+/// element is the log-Euclidean mean of the metric at its vertices, and the element implied
+/// metric `M_e` is the metric in which all the element edges have unit length. With `l_k` the
+/// eigenvalues of `M_e^-1/2 M M_e^-1/2`, the deviation is `1 - exp(-max_k |ln(l_k)| / 2)`, i.e.
+/// `1 - r` where `r` <= 1 is the smallest ratio between the element sizes and the metric
+/// sizes, or its inverse, in the principal directions. It is 0 for elements that match the
+/// metric and tends to 1 when they do not (or are degenerate)
+pub fn conformity_error<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[f64],
+    n: usize,
+) -> Vec<f64> {
+    let nc = D * (D + 1) / 2;
+    let mut log_m: Vec<f64> = if n == 1 {
+        m.iter()
+            .flat_map(|h| (0..nc).map(move |i| if i < D { h.powi(-2) } else { 0.0 }))
+            .collect()
+    } else {
+        m.to_vec()
+    };
+    sym_map(&mut log_m, nc, f64::ln);
+
+    let verts: Vec<_> = mesh.verts().collect();
+    mesh.elems()
+        .map(|e| {
+            let e: Vec<_> = e.into_iter().map(|i| i as usize).collect();
+            let mut m_e = vec![0.0; nc];
+            for &i in &e {
+                for (x, y) in m_e.iter_mut().zip(&log_m[nc * i..nc * (i + 1)]) {
+                    *x += y / e.len() as f64;
+                }
+            }
+            sym_map(&mut m_e, nc, f64::exp);
+
+            // implied metric: e^T M e = 1 for all the edges e
+            let mut a = [0.0; 36];
+            let mut row = 0;
+            for i in 0..e.len() {
+                for j in i + 1..e.len() {
+                    let v = verts[e[j]] - verts[e[i]];
+                    for k in 0..D {
+                        for l in k..D {
+                            let f = if k == l { 1.0 } else { 2.0 };
+                            a[row * nc + sym_index(D, k, l)] = f * v[k] * v[l];
+                        }
+                    }
+                    row += 1;
+                }
+            }
+            let Some(implied) = solve(nc, a, [1.0; 6]) else {
+                return 1.0;
+            };
+            let (l, v) = sym_eig(D, |i, j| implied[sym_index(D, i, j)]);
+            if l[..D].iter().any(|&x| x <= 0.0) {
+                return 1.0;
+            }
+            // M_e^-1/2 M M_e^-1/2
+            let p = |i: usize, j: usize| -> f64 {
+                (0..D).map(|k| v[i][k] * v[j][k] / l[k].sqrt()).sum()
+            };
+            let mm = |i: usize, j: usize| m_e[sym_index(D, i, j)];
+            let s = |i: usize, j: usize| -> f64 {
+                (0..D)
+                    .flat_map(|k| (0..D).map(move |q| (k, q)))
+                    .map(|(k, q)| p(i, k) * mm(k, q) * p(q, j))
+                    .sum()
+            };
+            let (l, _) = sym_eig(D, s);
+            let dev = l[..D].iter().map(|x| x.ln().abs()).fold(0.0, f64::max);
+            1.0 - (-0.5 * dev).exp()
+        })
+        .collect()
+}
+
+/// Mean length of the edges of each element in the metric space of a field `m` defined at the
+/// vertices, with `n` = 1 (sizes) or `D (D + 1) / 2` (symmetric tensors) components per vertex.
+/// The length of an edge is the mean of its lengths with the metric at its two vertices
+pub fn mean_edge_lengths<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[f64],
+    n: usize,
+) -> Vec<f64> {
+    let verts: Vec<_> = mesh.verts().collect();
+    let length = |i: usize, v: &[f64]| -> f64 {
+        let m = &m[n * i..n * (i + 1)];
+        if n == 1 {
+            return v.iter().map(|x| x * x).sum::<f64>().sqrt() / m[0];
+        }
+        (0..D)
+            .flat_map(|k| (0..D).map(move |l| (k, l)))
+            .map(|(k, l)| v[k] * m[sym_index(D, k, l)] * v[l])
+            .sum::<f64>()
+            .sqrt()
+    };
+    mesh.elems()
+        .map(|e| {
+            let e: Vec<_> = e.into_iter().map(|i| i as usize).collect();
+            let mut sum = 0.0;
+            let mut count = 0;
+            for i in 0..e.len() {
+                for j in i + 1..e.len() {
+                    let v = verts[e[j]] - verts[e[i]];
+                    sum += 0.5 * (length(e[i], v.as_slice()) + length(e[j], v.as_slice()));
+                    count += 1;
+                }
+            }
+            sum / f64::from(count)
+        })
+        .collect()
+}
+
+/// Max ratio, over all directions, between the sizes of the metrics `m1` and `m0` with `n` = 1
+/// (sizes) or `dim (dim + 1) / 2` (symmetric tensors) components, i.e. for tensors
+/// `lambda_min(M0^-1/2 M1 M0^-1/2)^-1/2`
+fn max_size_ratio(dim: usize, n: usize, m0: &[f64], m1: &[f64]) -> f64 {
+    if n == 1 {
+        return m1[0] / m0[0];
+    }
+    let (l, v) = sym_eig(dim, |i, j| m0[sym_index(dim, i, j)]);
+    // M0^-1/2
+    let s = |i: usize, j: usize| -> f64 { (0..dim).map(|k| v[i][k] * v[j][k] / l[k].sqrt()).sum() };
+    let a = |i: usize, j: usize| -> f64 {
+        (0..dim)
+            .flat_map(|k| (0..dim).map(move |p| (k, p)))
+            .map(|(k, p)| s(i, k) * m1[sym_index(dim, k, p)] * s(p, j))
+            .sum()
+    };
+    let (l, _) = sym_eig(dim, a);
+    1.0 / l[..dim]
+        .iter()
+        .copied()
+        .fold(f64::INFINITY, f64::min)
+        .sqrt()
+}
+
+/// Check the gradation of a metric field `m` defined at the vertices, with `n` = 1 (sizes) or
+/// `D (D + 1) / 2` (symmetric tensors) components per vertex. Along an edge from vertex `i` to
+/// vertex `j`, with length `l` in the metric at `i`, the sizes at `j` may not exceed those at `i`
+/// by more than a factor `1 + l ln(beta)`, as for the metric span used by the gradation; the
+/// effective gradation of the edge is then `exp((r - 1) / l)` where `r` is the max size ratio.
+/// Return the max effective gradation over the edges (in both directions) and the edges whose
+/// size ratio exceeds the bound by more than a factor `1 + tol`
+pub fn check_gradation<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    m: &[f64],
+    n: usize,
+    beta: f64,
+    tol: f64,
+) -> (f64, Vec<[Idx; 2]>) {
+    let verts: Vec<_> = mesh.verts().collect();
+    let mut edges = BTreeSet::new();
+    for e in mesh.elems() {
+        let e: Vec<_> = e.into_iter().collect();
+        for i in 0..e.len() {
+            for j in i + 1..e.len() {
+                edges.insert([e[i].min(e[j]), e[i].max(e[j])]);
+            }
+        }
+    }
+
+    let metric = |i: Idx| &m[n * i as usize..n * (i as usize + 1)];
+    let length = |i: Idx, j: Idx| -> f64 {
+        let v = verts[j as usize] - verts[i as usize];
+        let m = metric(i);
+        if n == 1 {
+            return v.norm() / m[0];
+        }
+        (0..D)
+            .flat_map(|k| (0..D).map(move |l| (k, l)))
+            .map(|(k, l)| v[k] * m[sym_index(D, k, l)] * v[l])
+            .sum::<f64>()
+            .sqrt()
+    };
+
+    let mut max_beta: f64 = 1.0;
+    let mut violations = Vec::new();
+    for &[i0, i1] in &edges {
+        let mut violated = false;
+        for (i, j) in [(i0, i1), (i1, i0)] {
+            let l = length(i, j);
+            let r = max_size_ratio(D, n, metric(i), metric(j));
+            if l > 0.0 {
+                max_beta = max_beta.max(((r - 1.0) / l).exp());
+            }
+            violated |= r > (1.0 + l * beta.ln()) * (1.0 + tol);
+        }
+        if violated {
+            violations.push([i0, i1]);
+        }
+    }
+    (max_beta, violations)
+}
+
+/// Make a Hessian field suitable to build a metric, in place: the eigenvalues are replaced by
+/// their absolute values, floored by `rel_floor` times the largest one over the field. The
+/// field has `n` = 1 (scalar), 3 or 6 (symmetric tensors ordered as `SYMTENSOR_2D` or
+/// `SYMTENSOR_3D`) components per vertex.
+/// Return the number of vertices where eigenvalues were floored
+pub fn regularize_hessian(h: &mut [f64], n: usize, rel_floor: f64) -> PyResult<usize> {
+    if rel_floor.is_nan() || rel_floor <= 0.0 {
+        return Err(PyValueError::new_err("rel_floor must be positive"));
+    }
+    if h.iter().any(|x| !x.is_finite()) {
+        return Err(PyValueError::new_err(
+            "The Hessian contains NaN or infinite values",
+        ));
+    }
+    let dim = match n {
+        1 => 1,
+        3 => 2,
+        6 => 3,
+        _ => unreachable!(),
+    };
+
+    let eigs: Vec<_> = h
+        .chunks(n)
+        .map(|x| {
+            if dim == 1 {
+                ([x[0], 0.0, 0.0], [[1.0, 0.0, 0.0], [0.0; 3], [0.0; 3]])
+            } else {
+                sym_eig(dim, |i, j| x[sym_index(dim, i, j)])
+            }
+        })
+        .collect();
+    let max = eigs
+        .iter()
+        .flat_map(|(l, _)| l[..dim].iter().map(|x| x.abs()))
+        .fold(0.0, f64::max);
+    if max == 0.0 {
+        return Err(PyValueError::new_err("The Hessian is zero"));
+    }
+    let floor = rel_floor * max;
+
+    let mut n_floored = 0;
+    for (x, (l, v)) in h.chunks_mut(n).zip(eigs) {
+        let l: Vec<_> = l[..dim].iter().map(|x| x.abs()).collect();
+        if l.iter().any(|&x| x < floor) {
+            n_floored += 1;
+        }
+        let l: Vec<_> = l.iter().map(|&x| x.max(floor)).collect();
+        for i in 0..dim {
+            for j in i..dim {
+                x[sym_index(dim, i, j)] = (0..dim).map(|k| l[k] * v[i][k] * v[j][k]).sum();
+            }
+        }
+    }
+
+    Ok(n_floored)
+}
+
+/// Get the values of a scalar, or of a per-vertex array of size n
+pub fn scalar_or_array(x: &Bound<'_, PyAny>, n: usize) -> PyResult<Vec<f64>> {
+    if let Ok(x) = x.extract::<f64>() {
+        return Ok(vec![x; n]);
+    }
+    let x = x.extract::<PyReadonlyArray1<f64>>()?;
+    if x.len() != n {
+        return Err(PyValueError::new_err("Invalid dimension 0"));
+    }
+    Ok(x.to_vec()?)
+}
+
+/// Anisotropic metric with size `h_parallel` along direction `d` and `h_perp` across it, stored
+/// as the upper triangle of the tensor in the order used by tucanos ((xx, yy, xy) in 2D, (xx, yy,
+/// zz, xy, yz, xz) in 3D). If `d` is zero, the metric is isotropic with size `h_perp`
+pub fn direction_metric(d: &[f64], h_parallel: f64, h_perp: f64) -> Vec<f64> {
+    let indices: &[(usize, usize)] = match d.len() {
+        2 => &[(0, 0), (1, 1), (0, 1)],
+        3 => &[(0, 0), (1, 1), (2, 2), (0, 1), (1, 2), (0, 2)],
+        _ => unreachable!(),
+    };
+
+    let a = 1.0 / (h_parallel * h_parallel);
+    let b = 1.0 / (h_perp * h_perp);
+    let norm = d.iter().map(|x| x * x).sum::<f64>().sqrt();
+    indices
+        .iter()
+        .map(|&(i, j)| {
+            let delta = if i == j { 1.0 } else { 0.0 };
+            if norm < f64::EPSILON {
+                b * delta
+            } else {
+                let dd = d[i] * d[j] / (norm * norm);
+                a * dd + b * (delta - dd)
+            }
+        })
+        .collect()
+}
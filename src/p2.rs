@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Result, Write},
+};
+use tucanos::{
+    geometry::{Geometry, LinearGeometry},
+    mesh::{Point, SimplexMesh},
+    topo_elems::Elem,
+    Idx, Tag,
+};
+
+/// Local edges of the simplices, in the order of the VTK quadratic cells
+fn local_edges(n_verts: usize) -> &'static [[usize; 2]] {
+    match n_verts {
+        1 => &[],
+        2 => &[[0, 1]],
+        3 => &[[0, 1], [1, 2], [2, 0]],
+        4 => &[[0, 1], [1, 2], [2, 0], [0, 3], [1, 3], [2, 3]],
+        _ => unreachable!(),
+    }
+}
+
+/// VTK type of the quadratic cells with `n_nodes` nodes
+fn vtk_type(n_nodes: usize) -> u8 {
+    match n_nodes {
+        1 => 1,
+        3 => 21,
+        6 => 22,
+        10 => 24,
+        _ => unreachable!(),
+    }
+}
+
+/// Quadratic (P2) version of a mesh, for visualization only
+pub struct P2Mesh<const D: usize> {
+    /// The mesh vertices followed by the mid-edge nodes
+    pub verts: Vec<Point<D>>,
+    /// Element nodes: the vertices followed by the mid-edge nodes
+    pub elems: Vec<Vec<Idx>>,
+    pub etags: Vec<Tag>,
+    /// Face nodes: the vertices followed by the mid-edge nodes
+    pub faces: Vec<Vec<Idx>>,
+    pub ftags: Vec<Tag>,
+}
+
+impl<const D: usize> P2Mesh<D> {
+    /// Add mid-edge nodes to a mesh. If a geometry is given with the surface mesh it was built
+    /// from, the nodes on the boundary faces whose tag is an element tag of this surface mesh are
+    /// projected onto the geometry, the others being left at the middle of the edges
+    pub fn new<E: Elem>(
+        mesh: &SimplexMesh<D, E>,
+        geom: Option<(&LinearGeometry<D, E::Face>, &SimplexMesh<D, E::Face>)>,
+    ) -> Self {
+        let mut verts: Vec<Point<D>> = mesh.verts().collect();
+        let mut edges = HashMap::new();
+        let mut add_nodes = |ids: Vec<Idx>| -> Vec<Idx> {
+            let mut res = ids.clone();
+            for &[i, j] in local_edges(ids.len()) {
+                let key = if ids[i] < ids[j] {
+                    [ids[i], ids[j]]
+                } else {
+                    [ids[j], ids[i]]
+                };
+                let k = *edges.entry(key).or_insert_with(|| {
+                    let p = 0.5 * (verts[ids[i] as usize] + verts[ids[j] as usize]);
+                    verts.push(p);
+                    verts.len() as Idx - 1
+                });
+                res.push(k);
+            }
+            res
+        };
+
+        let elems: Vec<_> = mesh
+            .elems()
+            .map(|e| add_nodes(e.into_iter().collect()))
+            .collect();
+        let faces: Vec<_> = mesh
+            .faces()
+            .map(|f| add_nodes(f.into_iter().collect()))
+            .collect();
+
+        if let Some((geom, gmesh)) = geom {
+            let dim = (D - 1).try_into().unwrap();
+            let gtags: HashSet<Tag> = gmesh.etags().collect();
+            for (f, tag) in faces.iter().zip(mesh.ftags()) {
+                if !gtags.contains(&tag) {
+                    continue;
+                }
+                let n = E::Face::N_VERTS as usize;
+                for &k in &f[n..] {
+                    geom.project(&mut verts[k as usize], &(dim, tag));
+                }
+            }
+        }
+
+        Self {
+            verts,
+            elems,
+            etags: mesh.etags().collect(),
+            faces,
+            ftags: mesh.ftags().collect(),
+        }
+    }
+
+    /// Write the elements and faces as VTK quadratic cells to a .vtu file, with the tags as cell
+    /// data
+    pub fn write_vtu(&self, fname: &str) -> Result<()> {
+        let mut w = BufWriter::new(File::create(fname)?);
+        let cells = || self.elems.iter().chain(self.faces.iter());
+        let n_cells = self.elems.len() + self.faces.len();
+
+        writeln!(w, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            w,
+            "<VTKFile type=\"UnstructuredGrid\" version=\"0.1\" byte_order=\"LittleEndian\">"
+        )?;
+        writeln!(w, "<UnstructuredGrid>")?;
+        writeln!(
+            w,
+            "<Piece NumberOfPoints=\"{}\" NumberOfCells=\"{n_cells}\">",
+            self.verts.len()
+        )?;
+
+        writeln!(w, "<Points>")?;
+        writeln!(
+            w,
+            "<DataArray type=\"Float64\" NumberOfComponents=\"3\" format=\"ascii\">"
+        )?;
+        for p in &self.verts {
+            let mut x = [0.0; 3];
+            x[..D].copy_from_slice(p.as_slice());
+            writeln!(w, "{} {} {}", x[0], x[1], x[2])?;
+        }
+        writeln!(w, "</DataArray>")?;
+        writeln!(w, "</Points>")?;
+
+        writeln!(w, "<Cells>")?;
+        writeln!(
+            w,
+            "<DataArray type=\"UInt32\" Name=\"connectivity\" format=\"ascii\">"
+        )?;
+        for c in cells() {
+            for i in c {
+                write!(w, "{i} ")?;
+            }
+            writeln!(w)?;
+        }
+        writeln!(w, "</DataArray>")?;
+        writeln!(
+            w,
+            "<DataArray type=\"UInt64\" Name=\"offsets\" format=\"ascii\">"
+        )?;
+        let mut offset = 0;
+        for c in cells() {
+            offset += c.len();
+            writeln!(w, "{offset}")?;
+        }
+        writeln!(w, "</DataArray>")?;
+        writeln!(
+            w,
+            "<DataArray type=\"UInt8\" Name=\"types\" format=\"ascii\">"
+        )?;
+        for c in cells() {
+            writeln!(w, "{}", vtk_type(c.len()))?;
+        }
+        writeln!(w, "</DataArray>")?;
+        writeln!(w, "</Cells>")?;
+
+        writeln!(w, "<CellData>")?;
+        writeln!(
+            w,
+            "<DataArray type=\"Int16\" Name=\"tag\" format=\"ascii\">"
+        )?;
+        for tag in self.etags.iter().chain(self.ftags.iter()) {
+            writeln!(w, "{tag}")?;
+        }
+        writeln!(w, "</DataArray>")?;
+        writeln!(w, "</CellData>")?;
+
+        writeln!(w, "</Piece>")?;
+        writeln!(w, "</UnstructuredGrid>")?;
+        writeln!(w, "</VTKFile>")?;
+
+        w.flush()
+    }
+}
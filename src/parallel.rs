@@ -1,21 +1,288 @@
 use crate::{
     geometry::{LinearGeometry2d, LinearGeometry3d},
     mesh::{Mesh22, Mesh33},
+    metric::{complexity, sym_map},
+    params::{parse_smooth_type, PyParallelRemeshingParams, PyRemesherParams},
+    remesher::{IterationControls, LevelSummary, Operation, RemeshSummary},
+    tags::tag_values,
 };
+use log::warn;
 use numpy::{PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2, PyUntypedArrayMethods};
 use pyo3::{
-    exceptions::{PyRuntimeError, PyValueError},
-    pyclass, pymethods, PyResult, Python,
+    exceptions::{PyNotImplementedError, PyRuntimeError, PyValueError},
+    pyclass, pyfunction, pymethods,
+    types::PyType,
+    Bound, PyRef, PyResult, Python,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeSet,
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 use tucanos::{
+    geometry::LinearGeometry,
+    mesh::SimplexMesh,
     mesh_partition::PartitionType,
     metric::{AnisoMetric2d, AnisoMetric3d, IsoMetric, Metric},
     parallel::{ParallelRemesher, ParallelRemeshingParams},
-    remesher::{RemesherParams, SmoothingType},
-    topo_elems::{Tetrahedron, Triangle},
-    Idx,
+    remesher::RemesherParams,
+    topo_elems::{Elem, Tetrahedron, Triangle},
+    Idx, Tag,
 };
 
+/// Status of a partition in the progress of a parallel remeshing run
+struct PartitionProgress {
+    /// Number of elements of the partition before remeshing
+    n_elems_init: usize,
+    /// Remeshing time of the partition at the first level, in seconds, once known
+    time: Option<f64>,
+}
+
+/// Write the progress of a parallel remeshing run to a json file
+///
+/// The partitions are remeshed within tucanos, so their remeshing time is only known once the
+/// run is complete: until then, a partition is reported as "remeshing" with the number of
+/// elements it contains
+fn write_progress(
+    fname: &str,
+    n_levels: Idx,
+    phase: &str,
+    elapsed: f64,
+    partitions: &[PartitionProgress],
+) {
+    let partitions: Vec<_> = partitions
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            json!({
+                "partition": i,
+                "phase": if p.time.is_some() { "done" } else { phase },
+                "n_elems_init": p.n_elems_init,
+                "time": p.time,
+            })
+        })
+        .collect();
+    let progress = json!({
+        "n_partitions": partitions.len(),
+        "n_levels": n_levels,
+        "phase": phase,
+        "elapsed": elapsed,
+        "partitions": partitions,
+    });
+
+    // write to a temporary file first so that the file is never read while incomplete
+    let tmp = format!("{fname}.tmp");
+    if let Err(e) = fs::write(&tmp, progress.to_string()).and_then(|()| fs::rename(&tmp, fname)) {
+        warn!("Unable to write {fname}: {e}");
+    }
+}
+
+/// Keys of the number of elements of the interface mesh in the statistics returned by tucanos
+const N_ELEMS_KEYS: [&str; 2] = ["n_elems", "n_elems_init"];
+
+/// Get the timings of each level from the statistics of a parallel remeshing run returned by
+/// tucanos, where a level contains the statistics of its partitions (`partitions`, with their
+/// remeshing `time`) and of the interface mesh (`interface`), which is remeshed at the next level.
+/// The last level, where the interface is remeshed without partitioning, is seen as a level with
+/// a single partition
+fn level_summaries(info: &Value) -> Vec<LevelSummary> {
+    let time = |x: &Value| x.get("time").and_then(Value::as_f64);
+    let n_elems = |x: &Value| {
+        let res = N_ELEMS_KEYS
+            .iter()
+            .find_map(|k| x.get(k).and_then(Value::as_u64))
+            .map(|n| n as usize);
+        if res.is_none() {
+            warn!("No element count ({N_ELEMS_KEYS:?}) in the statistics of an interface");
+        }
+        res
+    };
+
+    let mut res = Vec::new();
+    let mut level = Some(info);
+    while let Some(info) = level.filter(|x| x.is_object()) {
+        let interface = info.get("interface").filter(|x| x.is_object());
+        let partition_times: Vec<_> = match info.get("partitions").and_then(Value::as_array) {
+            Some(partitions) => partitions.iter().filter_map(time).collect(),
+            None => time(info).into_iter().collect(),
+        };
+        if partition_times.is_empty() {
+            break;
+        }
+        res.push(LevelSummary {
+            max_partition_time: partition_times.iter().copied().fold(0.0, f64::max),
+            n_interface_elems: interface.and_then(n_elems),
+            partition_times,
+        });
+        level = interface;
+    }
+    res
+}
+
+/// Check the interval (in seconds) between two writes of the progress of a parallel remeshing
+/// run
+fn check_progress_interval(interval: f64) -> PyResult<f64> {
+    if interval.is_finite() && interval > 0.0 {
+        Ok(interval)
+    } else {
+        Err(PyValueError::new_err(format!(
+            "Invalid progress_interval {interval}: expected a positive number of seconds"
+        )))
+    }
+}
+
+/// Periodically write the progress of a parallel remeshing run to a json file from a
+/// separate thread (that does not require the GIL)
+///
+/// The thread is stopped by `finish()` once the run is complete, or when the monitor is dropped
+/// if the run failed, in which case the phase is "failed"
+struct ProgressMonitor {
+    fname: String,
+    n_levels: Idx,
+    start: Instant,
+    n_elems_init: Vec<usize>,
+    done: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ProgressMonitor {
+    fn start(fname: &str, n_elems_init: Vec<usize>, n_levels: Idx, interval: f64) -> Self {
+        let start = Instant::now();
+        let done = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let fname = fname.to_string();
+            let n_elems_init = n_elems_init.clone();
+            let interval = Duration::from_secs_f64(interval);
+            let done = done.clone();
+            thread::spawn(move || {
+                let partitions: Vec<_> = n_elems_init
+                    .into_iter()
+                    .map(|n_elems_init| PartitionProgress {
+                        n_elems_init,
+                        time: None,
+                    })
+                    .collect();
+                while !done.load(Ordering::Relaxed) {
+                    let elapsed = start.elapsed().as_secs_f64();
+                    write_progress(&fname, n_levels, "remeshing", elapsed, &partitions);
+                    thread::park_timeout(interval);
+                }
+            })
+        };
+        Self {
+            fname: fname.to_string(),
+            n_levels,
+            start,
+            n_elems_init,
+            done,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the thread and write the final progress, with the remeshing time of each partition
+    /// at the first level if known
+    fn stop(&mut self, phase: &str, times: &[f64]) {
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+        self.done.store(true, Ordering::Relaxed);
+        handle.thread().unpark();
+        if handle.join().is_err() {
+            warn!("The progress monitor of {} panicked", self.fname);
+        }
+        let partitions: Vec<_> = self
+            .n_elems_init
+            .iter()
+            .enumerate()
+            .map(|(i, &n_elems_init)| PartitionProgress {
+                n_elems_init,
+                time: times.get(i).copied(),
+            })
+            .collect();
+        let elapsed = self.start.elapsed().as_secs_f64();
+        write_progress(&self.fname, self.n_levels, phase, elapsed, &partitions);
+    }
+
+    fn finish(mut self, times: &[f64]) {
+        self.stop("done", times);
+    }
+}
+
+impl Drop for ProgressMonitor {
+    fn drop(&mut self) {
+        self.stop("failed", &[]);
+    }
+}
+
+/// Number of partitions of a mesh whose element tags are partition ids, checking that these are
+/// the consecutive integers `1..=n` used by `partitionned_mesh()`
+fn partition_count<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> PyResult<Idx> {
+    let tags: BTreeSet<Tag> = mesh.etags().collect();
+    let (Some(&first), Some(&last)) = (tags.first(), tags.last()) else {
+        return Err(PyValueError::new_err("The mesh is empty"));
+    };
+    if first != 1 {
+        return Err(PyValueError::new_err(format!(
+            "The element tags are not partition ids: they start at {first} instead of 1"
+        )));
+    }
+    if i64::from(last) != tags.len() as i64 {
+        let missing: Vec<_> = (first..=last).filter(|t| !tags.contains(t)).collect();
+        return Err(PyValueError::new_err(format!(
+            "The element tags are not consecutive partition ids: {missing:?} are missing"
+        )));
+    }
+    Ok(tags.len() as Idx)
+}
+
+/// Get the partitioners available in this build (scotch and metis are optional)
+#[pyfunction]
+#[must_use]
+pub fn available_partitioners() -> Vec<&'static str> {
+    let mut res = vec!["hilbert"];
+    #[cfg(feature = "scotch")]
+    res.push("scotch");
+    #[cfg(feature = "metis")]
+    res.extend(["metis_kway", "metis_recursive"]);
+    res
+}
+
+/// Get the partition type from its name (default: "hilbert"), checking that it is available in
+/// this build
+fn parse_partition_type(name: Option<&str>, n_partitions: Idx) -> PyResult<PartitionType> {
+    let name = name.unwrap_or("hilbert");
+    let available = available_partitioners();
+    let library = match name {
+        "hilbert" => "hilbert",
+        "scotch" => "scotch",
+        "metis_kway" | "metis_recursive" => "metis",
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid partition type {name}: allowed values are {}",
+                available.join(", ")
+            )))
+        }
+    };
+    if !available.contains(&name) {
+        return Err(PyValueError::new_err(format!(
+            "{library} not available in this build; available: {}",
+            available.join(", ")
+        )));
+    }
+    Ok(match name {
+        "scotch" => PartitionType::Scotch(n_partitions),
+        "metis_kway" => PartitionType::MetisKWay(n_partitions),
+        "metis_recursive" => PartitionType::MetisRecursive(n_partitions),
+        _ => PartitionType::Hilbert(n_partitions),
+    })
+}
+
 macro_rules! create_parallel_remesher {
     ($name: ident, $dim: expr, $etype: ident, $metric: ident, $mesh: ident, $geom: ident) => {
         #[doc = concat!("Parallel remesher for a meshes consisting of ", stringify!($etype), " in ", stringify!($dim), "D")]
@@ -23,36 +290,47 @@ macro_rules! create_parallel_remesher {
         #[pyclass]
         pub struct $name {
             dd: ParallelRemesher<$dim, $etype>,
+            n_partitions: Idx,
+            /// Partitioner used when the mesh is partitioned again between guarded iterations
+            partition_type: String,
         }
 
         #[doc = concat!("Create a parallel remesher from a ", stringify!($mesh), " and a ",stringify!($metric) ," metric defined at the mesh vertices")]
         #[doc = concat!("A piecewise linear representation of the geometry is used, either from the ", stringify!($geom), " given or otherwise from the mesh boundary.")]
         #[pymethods]
         impl $name {
+            /// Create a parallel remesher, partitioning the mesh into `n_partitions` parts with
+            /// `partition_type` ("hilbert", "scotch", "metis_kway" or "metis_recursive", the
+            /// latter ones only if available in this build, see `available_partitioners()`).
+            /// "hilbert" is always available and is used if `partition_type` is None
             #[new]
             pub fn new(
                 mesh: &$mesh,
-                partition_type: &str,
+                partition_type: Option<&str>,
                 n_partitions: Idx,
             ) -> PyResult<Self> {
-
-                let partition_type = if partition_type == "scotch" {
-                    PartitionType::Scotch(n_partitions)
-                } else if partition_type == "metis_kway" {
-                    PartitionType::MetisKWay(n_partitions)
-                } else if partition_type == "metis_recursive" {
-                    PartitionType::MetisRecursive(n_partitions)
-                } else if partition_type == "hilbert" {
-                    PartitionType::Hilbert(n_partitions)
-                } else {
-                    return Err(PyValueError::new_err("Invalid partition type: allowed values are scotch, metis_kway, metis_recursive"));
-                };
+                let name = partition_type.unwrap_or("hilbert").to_string();
+                let partition_type = parse_partition_type(partition_type, n_partitions)?;
 
                 let dd = ParallelRemesher::new(mesh.mesh.clone(), partition_type);
                 if let Err(res) = dd {
                     return Err(PyRuntimeError::new_err(res.to_string()));
                 }
-                Ok(Self {dd: dd.unwrap()})
+                Ok(Self {dd: dd.unwrap(), n_partitions, partition_type: name})
+            }
+
+            /// Create a parallel remesher from a mesh that is already partitioned, e.g. by
+            /// `partitionned_mesh()` of another parallel remesher: the element tags are used as
+            /// partition ids, and must be the consecutive integers `1..=n`. The mesh is not
+            /// partitioned again.
+            /// NB: the element tags of the partitionned mesh replace the physical ones, which are
+            /// therefore lost: the meshes returned by `remesh()` are tagged with the partition ids
+            #[classmethod]
+            pub fn from_partitioned(_cls: &Bound<'_, PyType>, mesh: &$mesh) -> PyResult<Self> {
+                let n_partitions = partition_count(&mesh.mesh)?;
+                let dd = ParallelRemesher::new(mesh.mesh.clone(), PartitionType::None)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(Self {dd, n_partitions, partition_type: "hilbert".to_string()})
             }
 
             pub fn set_debug(&mut self, debug: bool) {
@@ -60,11 +338,79 @@ macro_rules! create_parallel_remesher {
             }
 
             pub fn partitionned_mesh(&mut self) -> $mesh {
-                $mesh{
-                    mesh:self.dd.partitionned_mesh().clone()
+                self.dd.partitionned_mesh().clone().into()
+            }
+
+            /// Estimate the complexity (ideal number of elements) of a metric defined at the
+            /// vertices of the partitionned mesh, without remeshing
+            pub fn complexity(&mut self, m: PyReadonlyArray2<f64>) -> PyResult<f64> {
+                if m.shape()[0] != self.dd.n_verts() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0"));
                 }
+                if m.shape()[1] != $metric::N as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                let m = m.as_slice()?;
+                let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
+
+                Ok(complexity(self.dd.partitionned_mesh(), &m))
             }
 
+            /// Compute the min/max sizes, max anisotropy and complexity of a metric defined at the
+            /// vertices of the partitionned mesh, without remeshing
+            pub fn metric_info(&mut self, m: PyReadonlyArray2<f64>) -> PyResult<(f64, f64, f64, f64)> {
+                if m.shape()[0] != self.dd.n_verts() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0"));
+                }
+                if m.shape()[1] != $metric::N as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                let m = m.as_slice()?;
+                let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
+
+                Ok(self.dd.partitionned_mesh().metric_info(&m))
+            }
+
+            /// Remesh the partitionned mesh
+            ///
+            /// If `max_n_elems` or `max_geom_deviation` are given, the operations are run one
+            /// sweep at a time as for the serial remeshers (see their `remesh()`), each sweep being
+            /// a parallel remeshing run: the metric is interpolated from the partitionned mesh onto
+            /// the mesh after each sweep, which is partitioned again. A sweep is rolled back if the
+            /// number of elements exceeds `max_n_elems` (and increases), in which case the splits
+            /// are disabled for the remaining iterations and `truncated` is set, or if it moves
+            /// the boundary faces farther than `max_geom_deviation` from the geometry (and farther
+            /// than before the sweep). The statistics then include `geom_deviation`,
+            /// `geom_deviation_exceeded`, `rejected_ops` and `n_iter` as for the serial remeshers,
+            /// the tucanos statistics and `levels` being those of the last sweep.
+            ///
+            /// If `progress_json` is given, the status of the partitions is written to this file
+            /// every `progress_interval` seconds (default: 1, must be positive) while remeshing,
+            /// without the GIL: the overall `phase` ("remeshing", then "done" or "failed" if
+            /// remeshing fails), the `elapsed` time and, for each partition, its number of elements
+            /// before remeshing (`n_elems_init`) and, once the run is complete, its remeshing
+            /// time at the first level (`time`). NB: tucanos remeshes the partitions internally
+            /// and does not report their individual progress, nor which partition or level
+            /// emits a log record, so the number of elements processed is not updated while
+            /// remeshing and the log records are not prefixed with `[part {i}/{n} level {l}]`
+            ///
+            /// The statistics include the total wall time (`elapsed`) and, for each level
+            /// (`levels`), the max remeshing time of its partitions (`max_partition_time`, i.e.
+            /// its wall time without the partitioning, interface extraction and merge, which
+            /// tucanos does not time), the number of elements of the interface mesh remeshed at
+            /// the next level, the number of partitions and the min/max/mean partition times.
+            /// `parallel_level_stats()` returns them as a list of `LevelStats`
+            ///
+            /// If `params` (a `RemesherParams`) or `parallel_params` (a `ParallelRemeshingParams`)
+            /// are given, they replace the default values of the parameters that are not given as
+            /// keyword arguments, including `max_n_elems`
+            ///
+            /// `target_min_quality` and `target_mean_quality` (also in `params`) are not supported
+            /// and raise NotImplementedError: the element qualities are only computed by tucanos
+            /// within the partitions. `max_seconds` is not supported either (NotImplementedError):
+            /// the partitions are remeshed by tucanos in a single call that cannot be interrupted
             #[allow(clippy::too_many_arguments)]
             pub fn remesh(&mut self,
                 py: Python<'_>,
@@ -96,7 +442,26 @@ macro_rules! create_parallel_remesher {
                 n_layers: Option<Idx>,
                 n_levels: Option<Idx>,
                 min_verts: Option<Idx>,
+                max_n_elems: Option<Idx>,
+                max_geom_deviation: Option<f64>,
+                progress_json: Option<&str>,
+                progress_interval: Option<f64>,
+                params: Option<PyRef<'_, PyRemesherParams>>,
+                parallel_params: Option<PyRef<'_, PyParallelRemeshingParams>>,
+                target_min_quality: Option<f64>,
+                target_mean_quality: Option<f64>,
+                max_seconds: Option<f64>,
             ) -> PyResult<($mesh, String)> {
+                if target_min_quality.is_some() || target_mean_quality.is_some() {
+                    return Err(PyNotImplementedError::new_err(
+                        "target_min_quality and target_mean_quality are not supported by the parallel remeshers",
+                    ));
+                }
+                if max_seconds.is_some() {
+                    return Err(PyNotImplementedError::new_err(
+                        "max_seconds is not supported by the parallel remeshers",
+                    ));
+                }
 
                 if m.shape()[0] != self.dd.n_verts() as usize {
                     return Err(PyValueError::new_err("Invalid dimension 0"));
@@ -108,19 +473,24 @@ macro_rules! create_parallel_remesher {
                 let m = m.as_slice()?;
                 let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
 
-                let smooth_type = smooth_type.unwrap_or("laplacian");
+                let mut summary = RemeshSummary::default();
 
-                let smooth_type = if smooth_type == "laplacian" {
-                    SmoothingType::Laplacian
-                } else if smooth_type == "laplacian2" {
-                    SmoothingType::Laplacian2
-                } else if smooth_type == "nlopt" {
-                    unreachable!()
-                } else {
-                    SmoothingType::Avro
+                let (default_params, max_n_elems) = match params {
+                    Some(params) => {
+                        if params.target_min_quality.is_some() || params.target_mean_quality.is_some() {
+                            return Err(PyNotImplementedError::new_err(
+                                "target_min_quality and target_mean_quality are not supported by the parallel remeshers",
+                            ));
+                        }
+                        (params.to_params()?, max_n_elems.or(params.max_n_elems))
+                    }
+                    None => (RemesherParams::default(), max_n_elems),
                 };
 
-                let default_params = RemesherParams::default();
+                let smooth_type = match smooth_type {
+                    None => default_params.smooth_type.clone(),
+                    Some(name) => parse_smooth_type(name)?,
+                };
 
                 let params = RemesherParams {
                     num_iter: num_iter.unwrap_or(default_params.num_iter),
@@ -148,17 +518,156 @@ macro_rules! create_parallel_remesher {
                     debug: debug.unwrap_or(default_params.debug),
                 };
 
-                let dd_params = ParallelRemeshingParams::new(
-                    n_layers.unwrap_or(2),
-                    n_levels.unwrap_or(1),
-                    min_verts.unwrap_or(0)
+                let default_dd_params = parallel_params
+                    .map_or_else(|| PyParallelRemeshingParams::new(None, None, None), |p| (*p).clone());
+                let n_levels = n_levels.unwrap_or(default_dd_params.n_levels);
+                let dd_params = (
+                    n_layers.unwrap_or(default_dd_params.n_layers),
+                    n_levels,
+                    min_verts.unwrap_or(default_dd_params.min_verts),
                 );
+                let controls = IterationControls {
+                    max_n_elems,
+                    max_geom_deviation,
+                    ..IterationControls::default()
+                };
 
-                let (mesh, info) = py.allow_threads(|| self.dd.remesh(&m, &geometry.geom, params, dd_params).unwrap());
+                let progress_interval = check_progress_interval(progress_interval.unwrap_or(1.0))?;
+                let progress = progress_json.map(|fname| {
+                    let n_elems_init = tag_values(self.dd.partitionned_mesh().etags()).into_values().collect();
+                    ProgressMonitor::start(fname, n_elems_init, n_levels, progress_interval)
+                });
 
-                let mesh = $mesh{mesh};
-                Ok((mesh, info.to_json()))
+                let start = Instant::now();
+                let (mesh, info) = if controls.has_guards() {
+                    self.guarded_remesh(py, &m, &geometry.geom, &params, dd_params, &controls, &mut summary)?
+                } else {
+                    let dd_params = ParallelRemeshingParams::new(dd_params.0, dd_params.1, dd_params.2);
+                    let (mesh, info) = py
+                        .allow_threads(|| self.dd.remesh(&m, &geometry.geom, params, dd_params))
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    (mesh, info.to_json())
+                };
+                summary.elapsed = Some(start.elapsed().as_secs_f64());
+                let info_json: Value = serde_json::from_str(&info).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                summary.levels = level_summaries(&info_json);
+
+                if let Some(progress) = progress {
+                    let times = summary.levels.first().map_or(&[][..], |l| &l.partition_times[..]);
+                    progress.finish(times);
+                }
+
+                Ok((mesh.into(), summary.merge_json(&info)))
+
+            }
+        }
+
+        impl $name {
+            /// Run the remeshing iterations one operation sweep at a time, checking the guards of
+            /// `controls` after each sweep (see `remesh()`). `dd_params` are the `n_layers`,
+            /// `n_levels` and `min_verts` parallel parameters. Return the mesh and the statistics
+            /// of the last sweep
+            #[allow(clippy::too_many_arguments)]
+            fn guarded_remesh(
+                &mut self,
+                py: Python<'_>,
+                m: &[$metric],
+                geom: &LinearGeometry<$dim, <$etype as Elem>::Face>,
+                params: &RemesherParams,
+                dd_params: (Idx, Idx, Idx),
+                controls: &IterationControls,
+                summary: &mut RemeshSummary,
+            ) -> PyResult<(SimplexMesh<$dim, $etype>, String)> {
+                let n = <$metric as Metric<$dim>>::N;
+                let input = self.dd.partitionned_mesh().clone();
+                let tree = input.compute_elem_tree();
+                let mut log_m: Vec<f64> = m.iter().cloned().flatten().collect();
+                sym_map(&mut log_m, n, f64::ln);
+
+                let mut dd: Option<ParallelRemesher<$dim, $etype>> = None;
+                let mut metric = m.to_vec();
+                let mut res: Option<(SimplexMesh<$dim, $etype>, String)> = None;
+                let mut sizes = (input.n_verts(), input.n_elems());
+                let mut splits = true;
+                if controls.max_geom_deviation.is_some() {
+                    summary.geom_deviation = Some(geom.max_distance(&input));
+                }
+
+                for i in 0..params.num_iter {
+                    for op in Operation::ALL {
+                        if op == Operation::Split && !splits {
+                            continue;
+                        }
+                        for sweep in 0..op.n_sweeps(params) {
+                            let mut p = op.sweep_params(params);
+                            p.two_steps = params.two_steps && i == 0 && op == Operation::Split && sweep == 0;
+                            let dd_params = ParallelRemeshingParams::new(dd_params.0, dd_params.1, dd_params.2);
+                            let dd_ref = dd.as_mut().unwrap_or(&mut self.dd);
+                            let (mesh, info) = py
+                                .allow_threads(|| dd_ref.remesh(&metric, geom, p, dd_params))
+                                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+                            let mut violated = None;
+                            if let Some(max_n_elems) = controls.max_n_elems {
+                                if mesh.n_elems() > max_n_elems && mesh.n_elems() > sizes.1 {
+                                    summary.truncated = true;
+                                    violated = Some("max_n_elems");
+                                }
+                            }
+                            if let (None, Some(max_geom_deviation)) = (violated, controls.max_geom_deviation) {
+                                let d = geom.max_distance(&mesh);
+                                if d > max_geom_deviation && d > summary.geom_deviation.unwrap_or(0.0) {
+                                    summary.geom_deviation_exceeded = true;
+                                    violated = Some("max_geom_deviation");
+                                } else {
+                                    summary.geom_deviation = Some(d);
+                                }
+                            }
 
+                            if let Some(guard) = violated {
+                                warn!("{guard} exceeded by a {} sweep: the sweep is rolled back", op.name());
+                                *summary.rejected_ops.entry(guard).or_default() += 1;
+                                if guard == "max_n_elems" && op == Operation::Split {
+                                    splits = false;
+                                }
+                                break;
+                            }
+
+                            let new_sizes = (mesh.n_verts(), mesh.n_elems());
+                            let unchanged = new_sizes == sizes;
+                            sizes = new_sizes;
+                            let mut m = input
+                                .interpolate_linear(&tree, &mesh, &log_m, None)
+                                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                            sym_map(&mut m, n, f64::exp);
+                            metric = m.chunks(n).map(|x| $metric::from_slice(x)).collect();
+                            let partition_type = parse_partition_type(Some(&self.partition_type), self.n_partitions)?;
+                            let new_dd = ParallelRemesher::new(mesh.clone(), partition_type)
+                                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                            dd = Some(new_dd);
+                            res = Some((mesh, info.to_json()));
+                            if unchanged && matches!(op, Operation::Split | Operation::Collapse) {
+                                break;
+                            }
+                        }
+                    }
+                    summary.n_iter = Some(i + 1);
+                }
+
+                if let Some(res) = res {
+                    return Ok(res);
+                }
+
+                // no sweep was accepted: get the input mesh with its element tags (those of the
+                // partitionned mesh being the partition ids) from an iteration without any
+                // operation
+                let mut p = Operation::Smooth.sweep_params(params);
+                p.smooth_iter = 0;
+                let dd_params = ParallelRemeshingParams::new(dd_params.0, dd_params.1, dd_params.2);
+                let (mesh, info) = py
+                    .allow_threads(|| self.dd.remesh(m, geom, p, dd_params))
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok((mesh, info.to_json()))
             }
         }
     }
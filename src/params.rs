@@ -0,0 +1,339 @@
+use pyo3::{exceptions::PyValueError, pyclass, pymethods, PyResult};
+use tucanos::{
+    remesher::{RemesherParams, SmoothingType},
+    Idx,
+};
+
+/// Name of a smoothing type, as used in the parameter dicts
+pub const fn smooth_type_name(smooth_type: &SmoothingType) -> &'static str {
+    match smooth_type {
+        SmoothingType::Laplacian => "laplacian",
+        SmoothingType::Avro => "avro",
+        #[cfg(feature = "nlopt")]
+        SmoothingType::NLOpt => "nlopt",
+        SmoothingType::Laplacian2 => "laplacian2",
+    }
+}
+
+/// Parse a smoothing type from its name
+pub fn parse_smooth_type(name: &str) -> PyResult<SmoothingType> {
+    match name {
+        "laplacian" => Ok(SmoothingType::Laplacian),
+        "laplacian2" => Ok(SmoothingType::Laplacian2),
+        "avro" => Ok(SmoothingType::Avro),
+        #[cfg(feature = "nlopt")]
+        "nlopt" => Ok(SmoothingType::NLOpt),
+        #[cfg(not(feature = "nlopt"))]
+        "nlopt" => Err(PyValueError::new_err(
+            "Smoothing type nlopt not available in this build",
+        )),
+        smooth_type => Err(PyValueError::new_err(format!(
+            "Invalid smoothing type {smooth_type}"
+        ))),
+    }
+}
+
+/// Python representation of a bool
+const fn py_bool(x: bool) -> &'static str {
+    if x {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+/// Python representation of an optional value
+fn py_option<T: std::fmt::Debug>(x: Option<T>) -> String {
+    x.map_or_else(|| "None".to_string(), |x| format!("{x:?}"))
+}
+
+/// Parameters of the remeshing iterations, with the same fields and defaults as
+/// `default_params()`, the cap on the number of elements and the target qualities at which the
+/// iterations are stopped. They can be
+/// passed as the `params` argument of the `remesh()` methods, the other keyword arguments given
+/// to `remesh()` taking precedence
+#[pyclass(name = "RemesherParams", module = "pytucanos._pytucanos")]
+#[derive(Clone)]
+pub struct PyRemesherParams {
+    #[pyo3(get, set)]
+    pub num_iter: u32,
+    #[pyo3(get, set)]
+    pub two_steps: bool,
+    #[pyo3(get, set)]
+    pub split_max_iter: u32,
+    #[pyo3(get, set)]
+    pub split_min_l_rel: f64,
+    #[pyo3(get, set)]
+    pub split_min_l_abs: f64,
+    #[pyo3(get, set)]
+    pub split_min_q_rel: f64,
+    #[pyo3(get, set)]
+    pub split_min_q_abs: f64,
+    #[pyo3(get, set)]
+    pub collapse_max_iter: u32,
+    #[pyo3(get, set)]
+    pub collapse_max_l_rel: f64,
+    #[pyo3(get, set)]
+    pub collapse_max_l_abs: f64,
+    #[pyo3(get, set)]
+    pub collapse_min_q_rel: f64,
+    #[pyo3(get, set)]
+    pub collapse_min_q_abs: f64,
+    #[pyo3(get, set)]
+    pub swap_max_iter: u32,
+    #[pyo3(get, set)]
+    pub swap_max_l_rel: f64,
+    #[pyo3(get, set)]
+    pub swap_max_l_abs: f64,
+    #[pyo3(get, set)]
+    pub swap_min_l_rel: f64,
+    #[pyo3(get, set)]
+    pub swap_min_l_abs: f64,
+    #[pyo3(get, set)]
+    pub smooth_iter: u32,
+    /// Smoothing type: "laplacian", "laplacian2", "avro" or "nlopt" (if available)
+    #[pyo3(get)]
+    pub smooth_type: String,
+    #[pyo3(get, set)]
+    pub smooth_relax: Vec<f64>,
+    #[pyo3(get, set)]
+    pub smooth_keep_local_minima: bool,
+    #[pyo3(get, set)]
+    pub max_angle: f64,
+    #[pyo3(get, set)]
+    pub debug: bool,
+    /// Max number of elements, a guard checked after each operation sweep (not part of the
+    /// tucanos parameters, see `remesh()`)
+    #[pyo3(get, set)]
+    pub max_n_elems: Option<Idx>,
+    /// Min element quality above which the iterations are stopped (not part of the tucanos
+    /// parameters, see `remesh()`)
+    #[pyo3(get, set)]
+    pub target_min_quality: Option<f64>,
+    /// Mean element quality above which the iterations are stopped (not part of the tucanos
+    /// parameters, see `remesh()`)
+    #[pyo3(get, set)]
+    pub target_mean_quality: Option<f64>,
+}
+
+impl From<&RemesherParams> for PyRemesherParams {
+    fn from(params: &RemesherParams) -> Self {
+        Self {
+            num_iter: params.num_iter,
+            two_steps: params.two_steps,
+            split_max_iter: params.split_max_iter,
+            split_min_l_rel: params.split_min_l_rel,
+            split_min_l_abs: params.split_min_l_abs,
+            split_min_q_rel: params.split_min_q_rel,
+            split_min_q_abs: params.split_min_q_abs,
+            collapse_max_iter: params.collapse_max_iter,
+            collapse_max_l_rel: params.collapse_max_l_rel,
+            collapse_max_l_abs: params.collapse_max_l_abs,
+            collapse_min_q_rel: params.collapse_min_q_rel,
+            collapse_min_q_abs: params.collapse_min_q_abs,
+            swap_max_iter: params.swap_max_iter,
+            swap_max_l_rel: params.swap_max_l_rel,
+            swap_max_l_abs: params.swap_max_l_abs,
+            swap_min_l_rel: params.swap_min_l_rel,
+            swap_min_l_abs: params.swap_min_l_abs,
+            smooth_iter: params.smooth_iter,
+            smooth_type: smooth_type_name(&params.smooth_type).to_string(),
+            smooth_relax: params.smooth_relax.clone(),
+            smooth_keep_local_minima: params.smooth_keep_local_minima,
+            max_angle: params.max_angle,
+            debug: params.debug,
+            max_n_elems: None,
+            target_min_quality: None,
+            target_mean_quality: None,
+        }
+    }
+}
+
+impl PyRemesherParams {
+    /// Get the parameters used by tucanos
+    pub fn to_params(&self) -> PyResult<RemesherParams> {
+        Ok(RemesherParams {
+            num_iter: self.num_iter,
+            two_steps: self.two_steps,
+            split_max_iter: self.split_max_iter,
+            split_min_l_rel: self.split_min_l_rel,
+            split_min_l_abs: self.split_min_l_abs,
+            split_min_q_rel: self.split_min_q_rel,
+            split_min_q_abs: self.split_min_q_abs,
+            collapse_max_iter: self.collapse_max_iter,
+            collapse_max_l_rel: self.collapse_max_l_rel,
+            collapse_max_l_abs: self.collapse_max_l_abs,
+            collapse_min_q_rel: self.collapse_min_q_rel,
+            collapse_min_q_abs: self.collapse_min_q_abs,
+            swap_max_iter: self.swap_max_iter,
+            swap_max_l_rel: self.swap_max_l_rel,
+            swap_max_l_abs: self.swap_max_l_abs,
+            swap_min_l_rel: self.swap_min_l_rel,
+            swap_min_l_abs: self.swap_min_l_abs,
+            smooth_iter: self.smooth_iter,
+            smooth_type: parse_smooth_type(&self.smooth_type)?,
+            smooth_relax: self.smooth_relax.clone(),
+            smooth_keep_local_minima: self.smooth_keep_local_minima,
+            max_angle: self.max_angle,
+            debug: self.debug,
+        })
+    }
+}
+
+#[pymethods]
+impl PyRemesherParams {
+    /// Create the parameters, the values not given being the defaults
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        num_iter: Option<u32>,
+        two_steps: Option<bool>,
+        split_max_iter: Option<u32>,
+        split_min_l_rel: Option<f64>,
+        split_min_l_abs: Option<f64>,
+        split_min_q_rel: Option<f64>,
+        split_min_q_abs: Option<f64>,
+        collapse_max_iter: Option<u32>,
+        collapse_max_l_rel: Option<f64>,
+        collapse_max_l_abs: Option<f64>,
+        collapse_min_q_rel: Option<f64>,
+        collapse_min_q_abs: Option<f64>,
+        swap_max_iter: Option<u32>,
+        swap_max_l_rel: Option<f64>,
+        swap_max_l_abs: Option<f64>,
+        swap_min_l_rel: Option<f64>,
+        swap_min_l_abs: Option<f64>,
+        smooth_iter: Option<u32>,
+        smooth_type: Option<&str>,
+        smooth_relax: Option<Vec<f64>>,
+        smooth_keep_local_minima: Option<bool>,
+        max_angle: Option<f64>,
+        debug: Option<bool>,
+        max_n_elems: Option<Idx>,
+        target_min_quality: Option<f64>,
+        target_mean_quality: Option<f64>,
+    ) -> PyResult<Self> {
+        let default_params = Self::from(&RemesherParams::default());
+        let mut res = Self {
+            num_iter: num_iter.unwrap_or(default_params.num_iter),
+            two_steps: two_steps.unwrap_or(default_params.two_steps),
+            split_max_iter: split_max_iter.unwrap_or(default_params.split_max_iter),
+            split_min_l_rel: split_min_l_rel.unwrap_or(default_params.split_min_l_rel),
+            split_min_l_abs: split_min_l_abs.unwrap_or(default_params.split_min_l_abs),
+            split_min_q_rel: split_min_q_rel.unwrap_or(default_params.split_min_q_rel),
+            split_min_q_abs: split_min_q_abs.unwrap_or(default_params.split_min_q_abs),
+            collapse_max_iter: collapse_max_iter.unwrap_or(default_params.collapse_max_iter),
+            collapse_max_l_rel: collapse_max_l_rel.unwrap_or(default_params.collapse_max_l_rel),
+            collapse_max_l_abs: collapse_max_l_abs.unwrap_or(default_params.collapse_max_l_abs),
+            collapse_min_q_rel: collapse_min_q_rel.unwrap_or(default_params.collapse_min_q_rel),
+            collapse_min_q_abs: collapse_min_q_abs.unwrap_or(default_params.collapse_min_q_abs),
+            swap_max_iter: swap_max_iter.unwrap_or(default_params.swap_max_iter),
+            swap_max_l_rel: swap_max_l_rel.unwrap_or(default_params.swap_max_l_rel),
+            swap_max_l_abs: swap_max_l_abs.unwrap_or(default_params.swap_max_l_abs),
+            swap_min_l_rel: swap_min_l_rel.unwrap_or(default_params.swap_min_l_rel),
+            swap_min_l_abs: swap_min_l_abs.unwrap_or(default_params.swap_min_l_abs),
+            smooth_iter: smooth_iter.unwrap_or(default_params.smooth_iter),
+            smooth_type: default_params.smooth_type,
+            smooth_relax: smooth_relax.unwrap_or(default_params.smooth_relax),
+            smooth_keep_local_minima: smooth_keep_local_minima
+                .unwrap_or(default_params.smooth_keep_local_minima),
+            max_angle: max_angle.unwrap_or(default_params.max_angle),
+            debug: debug.unwrap_or(default_params.debug),
+            max_n_elems,
+            target_min_quality,
+            target_mean_quality,
+        };
+        if let Some(smooth_type) = smooth_type {
+            res.set_smooth_type(smooth_type)?;
+        }
+        Ok(res)
+    }
+
+    /// Set the smoothing type, checking that it is valid
+    #[setter]
+    pub fn set_smooth_type(&mut self, smooth_type: &str) -> PyResult<()> {
+        parse_smooth_type(smooth_type)?;
+        self.smooth_type = smooth_type.to_string();
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "RemesherParams(num_iter={}, two_steps={}, split_max_iter={}, split_min_l_rel={:?}, \
+             split_min_l_abs={:?}, split_min_q_rel={:?}, split_min_q_abs={:?}, \
+             collapse_max_iter={}, collapse_max_l_rel={:?}, collapse_max_l_abs={:?}, \
+             collapse_min_q_rel={:?}, collapse_min_q_abs={:?}, swap_max_iter={}, \
+             swap_max_l_rel={:?}, swap_max_l_abs={:?}, swap_min_l_rel={:?}, \
+             swap_min_l_abs={:?}, smooth_iter={}, smooth_type='{}', smooth_relax={:?}, \
+             smooth_keep_local_minima={}, max_angle={:?}, debug={}, max_n_elems={}, \
+             target_min_quality={}, target_mean_quality={})",
+            self.num_iter,
+            py_bool(self.two_steps),
+            self.split_max_iter,
+            self.split_min_l_rel,
+            self.split_min_l_abs,
+            self.split_min_q_rel,
+            self.split_min_q_abs,
+            self.collapse_max_iter,
+            self.collapse_max_l_rel,
+            self.collapse_max_l_abs,
+            self.collapse_min_q_rel,
+            self.collapse_min_q_abs,
+            self.swap_max_iter,
+            self.swap_max_l_rel,
+            self.swap_max_l_abs,
+            self.swap_min_l_rel,
+            self.swap_min_l_abs,
+            self.smooth_iter,
+            self.smooth_type,
+            self.smooth_relax,
+            py_bool(self.smooth_keep_local_minima),
+            self.max_angle,
+            py_bool(self.debug),
+            py_option(self.max_n_elems),
+            py_option(self.target_min_quality),
+            py_option(self.target_mean_quality),
+        )
+    }
+}
+
+/// Parameters of the domain decomposition used by the parallel remeshers. They can be passed
+/// as the `parallel_params` argument of their `remesh()` methods, the other keyword arguments
+/// given to `remesh()` taking precedence
+#[pyclass(name = "ParallelRemeshingParams", module = "pytucanos._pytucanos")]
+#[derive(Clone)]
+pub struct PyParallelRemeshingParams {
+    /// Number of element layers added around the partition interfaces
+    #[pyo3(get, set)]
+    pub n_layers: Idx,
+    /// Number of levels, the interfaces of each level being remeshed at the next one
+    #[pyo3(get, set)]
+    pub n_levels: Idx,
+    /// Minimum number of vertices of the interface mesh to partition it at the next level
+    #[pyo3(get, set)]
+    pub min_verts: Idx,
+}
+
+#[pymethods]
+impl PyParallelRemeshingParams {
+    /// Create the parameters, the default values being `n_layers=2`, `n_levels=1` and
+    /// `min_verts=0`
+    #[new]
+    #[must_use]
+    pub fn new(n_layers: Option<Idx>, n_levels: Option<Idx>, min_verts: Option<Idx>) -> Self {
+        Self {
+            n_layers: n_layers.unwrap_or(2),
+            n_levels: n_levels.unwrap_or(1),
+            min_verts: min_verts.unwrap_or(0),
+        }
+    }
+
+    #[must_use]
+    pub fn __repr__(&self) -> String {
+        format!(
+            "ParallelRemeshingParams(n_layers={}, n_levels={}, min_verts={})",
+            self.n_layers, self.n_levels, self.min_verts
+        )
+    }
+}
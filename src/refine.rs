@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use tucanos::{
+    geometry::{Geometry, LinearGeometry},
+    mesh::{Point, SimplexMesh},
+    topo_elems::{Elem, Tetrahedron, Triangle},
+    Idx, Tag,
+};
+
+const fn edge_key(i: Idx, j: Idx) -> [Idx; 2] {
+    if i < j {
+        [i, j]
+    } else {
+        [j, i]
+    }
+}
+
+/// Recursively bisect a simplex along its marked edges, always splitting first the edge with
+/// the lowest rank. As the ranks are global, the simplices sharing a face split it the same way
+/// and the result is conforming
+fn bisect<const N: usize>(
+    s: [Idx; N],
+    marked: &HashMap<[Idx; 2], (usize, Idx)>,
+    res: &mut Vec<[Idx; N]>,
+) {
+    let mut first: Option<(usize, usize, usize, Idx)> = None;
+    for i in 0..N {
+        for j in i + 1..N {
+            if let Some(&(rank, mid)) = marked.get(&edge_key(s[i], s[j])) {
+                if !first.is_some_and(|f| f.0 <= rank) {
+                    first = Some((rank, i, j, mid));
+                }
+            }
+        }
+    }
+    match first {
+        None => res.push(s),
+        Some((_, i, j, mid)) => {
+            let mut s0 = s;
+            s0[j] = mid;
+            bisect(s0, marked, res);
+            let mut s1 = s;
+            s1[i] = mid;
+            bisect(s1, marked, res);
+        }
+    }
+}
+
+/// Bisect once all the edges of the boundary faces with a tag in `tags`, and the elements and
+/// faces that contain them. The new vertices on the edges within a tag (i.e. not on a geometry
+/// edge) are projected onto `geom` if given.
+/// Return the new mesh, and the indices of the parent element of each element and of the parent
+/// face of each face
+fn refine_boundary_once(
+    mesh: &SimplexMesh<3, Tetrahedron>,
+    tags: &[Tag],
+    geom: Option<&LinearGeometry<3, Triangle>>,
+) -> (SimplexMesh<3, Tetrahedron>, Vec<Idx>, Vec<Idx>) {
+    let mut verts: Vec<Point<3>> = mesh.verts().collect();
+    let faces: Vec<[Idx; 3]> = mesh
+        .faces()
+        .map(|f| {
+            let f: Vec<_> = f.into_iter().collect();
+            [f[0], f[1], f[2]]
+        })
+        .collect();
+    let ftags: Vec<Tag> = mesh.ftags().collect();
+
+    // tags of the faces containing each boundary edge
+    let mut edge_tags: HashMap<[Idx; 2], Vec<Tag>> = HashMap::new();
+    for (f, &tag) in faces.iter().zip(ftags.iter()) {
+        for j in 0..3 {
+            edge_tags
+                .entry(edge_key(f[j], f[(j + 1) % 3]))
+                .or_default()
+                .push(tag);
+        }
+    }
+    let mut edges: Vec<[Idx; 2]> = edge_tags
+        .iter()
+        .filter(|(_, t)| t.iter().any(|t| tags.contains(t)))
+        .map(|(&e, _)| e)
+        .collect();
+    edges.sort_unstable();
+
+    let dim = 2_usize.try_into().unwrap();
+    let mut marked = HashMap::with_capacity(edges.len());
+    for (rank, e) in edges.into_iter().enumerate() {
+        let mut p = 0.5 * (verts[e[0] as usize] + verts[e[1] as usize]);
+        let t = &edge_tags[&e];
+        if let Some(geom) = geom {
+            if t.len() == 2 && t[0] == t[1] {
+                geom.project(&mut p, &(dim, t[0]));
+            }
+        }
+        verts.push(p);
+        marked.insert(e, (rank, verts.len() as Idx - 1));
+    }
+
+    let mut elems = Vec::with_capacity(mesh.n_elems() as usize);
+    let mut etags = Vec::with_capacity(mesh.n_elems() as usize);
+    let mut elem_parents = Vec::with_capacity(mesh.n_elems() as usize);
+    let mut children = Vec::new();
+    for (i_elem, (e, tag)) in mesh.elems().zip(mesh.etags()).enumerate() {
+        let e: Vec<_> = e.into_iter().collect();
+        children.clear();
+        bisect([e[0], e[1], e[2], e[3]], &marked, &mut children);
+        for c in &children {
+            elems.push(Tetrahedron::from_slice(c));
+            etags.push(tag);
+            elem_parents.push(i_elem as Idx);
+        }
+    }
+
+    let mut new_faces = Vec::with_capacity(faces.len());
+    let mut new_ftags = Vec::with_capacity(faces.len());
+    let mut face_parents = Vec::with_capacity(faces.len());
+    let mut children = Vec::new();
+    for (i_face, (f, &tag)) in faces.iter().zip(ftags.iter()).enumerate() {
+        children.clear();
+        bisect(*f, &marked, &mut children);
+        for c in &children {
+            new_faces.push(Triangle::from_slice(c));
+            new_ftags.push(tag);
+            face_parents.push(i_face as Idx);
+        }
+    }
+
+    (
+        SimplexMesh::new(verts, elems, etags, new_faces, new_ftags),
+        elem_parents,
+        face_parents,
+    )
+}
+
+/// Apply `n` times `refine_boundary_once`, and return the refined mesh with the indices of the
+/// parent element and parent face in the original mesh
+pub fn refine_boundary(
+    mesh: &SimplexMesh<3, Tetrahedron>,
+    tags: &[Tag],
+    n: u32,
+    geom: Option<&LinearGeometry<3, Triangle>>,
+) -> (SimplexMesh<3, Tetrahedron>, Vec<Idx>, Vec<Idx>) {
+    let mut res = mesh.clone();
+    let mut elem_parents: Vec<Idx> = (0..mesh.n_elems()).collect();
+    let mut face_parents: Vec<Idx> = (0..mesh.n_faces()).collect();
+    for _ in 0..n {
+        let (new, e, f) = refine_boundary_once(&res, tags, geom);
+        res = new;
+        elem_parents = e.iter().map(|&i| elem_parents[i as usize]).collect();
+        face_parents = f.iter().map(|&i| face_parents[i as usize]).collect();
+    }
+    (res, elem_parents, face_parents)
+}
@@ -1,63 +1,724 @@
 use crate::{
-    geometry::{LinearGeometry2d, LinearGeometry3d},
-    mesh::{Mesh22, Mesh33},
+    background::{BackgroundMetric2d, BackgroundMetric3d},
+    cache::content_hash,
+    geometry::{merge_surfaces, LinearGeometry2d, LinearGeometry3d},
+    mesh::{boundary_per_tag, p0_to_p1_weights, permute_mesh, Mesh22, Mesh33},
+    metric::{
+        check_gradation, complexity, complexity_per_tag, conformity_error, direction_metric,
+        indicator_sizes, mean_edge_lengths, regularize_hessian, scalar_or_array,
+        scale_metric_with_fixed, sym_map,
+    },
+    params::{parse_smooth_type, smooth_type_name, PyRemesherParams},
+    tags::tag_values,
     to_numpy_1d, to_numpy_2d,
 };
+use log::warn;
 use numpy::{
     PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2, PyUntypedArrayMethods,
 };
 use pyo3::{
     exceptions::{PyRuntimeError, PyValueError},
-    prelude::PyDictMethods,
+    prelude::{PyAnyMethods, PyDictMethods},
     pyclass, pymethods,
-    types::{PyDict, PyType},
-    Bound, PyResult, Python,
+    types::{PyAny, PyDict, PyType},
+    Bound, Py, PyRef, PyResult, Python,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Instant,
 };
 use tucanos::{
+    geometry::LinearGeometry,
+    mesh::{Point, SimplexMesh},
     metric::{AnisoMetric2d, AnisoMetric3d, IsoMetric, Metric},
-    remesher::{Remesher, RemesherParams, SmoothingType},
-    topo_elems::{Tetrahedron, Triangle},
-    Idx,
+    remesher::{Remesher, RemesherParams},
+    topo_elems::{Elem, Tetrahedron, Triangle},
+    Idx, Tag,
 };
 
+/// Information about a remeshing run collected by the wrappers, on top of the statistics
+/// gathered by tucanos
+#[derive(Default)]
+pub struct RemeshSummary {
+    /// The element count exceeded `max_n_elems`
+    pub truncated: bool,
+    /// Max distance between the boundary faces and the geometry
+    pub geom_deviation: Option<f64>,
+    /// Operations were rejected as the geometric deviation would exceed `max_geom_deviation`
+    pub geom_deviation_exceeded: bool,
+    /// Number of operation sweeps (or parallel iterations) rolled back for each guard
+    pub rejected_ops: BTreeMap<&'static str, usize>,
+    /// The adapted mesh did not meet the acceptance criteria and was rolled back
+    pub rejected: bool,
+    /// Number of elements of each tag before and after remeshing
+    pub tag_counts: BTreeMap<Tag, (usize, usize)>,
+    /// Number of iterations run, if they were run one at a time
+    pub n_iter: Option<u32>,
+    /// The iterations were stopped before `num_iter` as the target qualities were reached
+    pub early_exit: bool,
+    /// The iterations were stopped before `num_iter` as `max_seconds` was exceeded
+    pub timed_out: bool,
+    /// Wall time of a parallel remeshing run, in seconds
+    pub elapsed: Option<f64>,
+    /// Breakdown of a parallel remeshing run per level
+    pub levels: Vec<LevelSummary>,
+    /// Breakdown of a remeshing run with pre-passes per pass, the last one being the main pass
+    pub passes: Vec<PassSummary>,
+    /// Effective parameters, input mesh and metric, and versions of a remeshing run
+    pub provenance: Option<Value>,
+    /// Paths of the debug meshes written during a remeshing run
+    pub debug_files: Vec<String>,
+}
+
+/// Result of a remeshing pass
+pub struct PassSummary {
+    /// Number of iterations of the pass
+    pub num_iter: u32,
+    /// Wall time of the pass, in seconds
+    pub time: f64,
+    /// Number of elements after the pass
+    pub n_elems: Idx,
+    /// Min element quality after the pass
+    pub q_min: f64,
+    /// Mean element quality after the pass
+    pub q_mean: f64,
+}
+
+impl PassSummary {
+    fn to_json_value(&self, pass: usize) -> Value {
+        json!({
+            "pass": pass,
+            "num_iter": self.num_iter,
+            "time": self.time,
+            "n_elems": self.n_elems,
+            "q_min": self.q_min,
+            "q_mean": self.q_mean,
+        })
+    }
+}
+
+/// Timings of a level of a parallel remeshing run
+pub struct LevelSummary {
+    /// Max remeshing time of the partitions of the level, in seconds. The partitions being
+    /// remeshed concurrently, this is the wall time of the level without the partitioning, the
+    /// extraction of the interface and the merge, that are not timed by tucanos
+    pub max_partition_time: f64,
+    /// Number of elements of the interface mesh remeshed at the next level, if known
+    pub n_interface_elems: Option<usize>,
+    /// Remeshing time of each partition, in seconds
+    pub partition_times: Vec<f64>,
+}
+
+impl LevelSummary {
+    fn to_json_value(&self, level: usize) -> Value {
+        let n = self.partition_times.len();
+        let min = self
+            .partition_times
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        let max = self.partition_times.iter().copied().fold(0.0, f64::max);
+        let mean = self.partition_times.iter().sum::<f64>() / n.max(1) as f64;
+        json!({
+            "level": level,
+            "max_partition_time": self.max_partition_time,
+            "n_interface_elems": self.n_interface_elems,
+            "n_partitions": n,
+            "partition_time": {"min": min, "max": max, "mean": mean},
+        })
+    }
+}
+
+impl RemeshSummary {
+    fn to_json_value(&self) -> Value {
+        let mut res = json!({
+            "truncated": self.truncated,
+            "geom_deviation": self.geom_deviation,
+            "geom_deviation_exceeded": self.geom_deviation_exceeded,
+            "rejected_ops": self.rejected_ops,
+            "rejected": self.rejected,
+            "tag_counts": self.tag_counts,
+            "n_iter": self.n_iter,
+            "early_exit": self.early_exit,
+            "timed_out": self.timed_out,
+        });
+        if let Some(elapsed) = self.elapsed {
+            res["elapsed"] = json!(elapsed);
+        }
+        if !self.levels.is_empty() {
+            res["levels"] = self
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(i, level)| level.to_json_value(i))
+                .collect();
+        }
+        if !self.passes.is_empty() {
+            res["passes"] = self
+                .passes
+                .iter()
+                .enumerate()
+                .map(|(i, pass)| pass.to_json_value(i))
+                .collect();
+        }
+        if let Some(provenance) = &self.provenance {
+            res["provenance"] = provenance.clone();
+        }
+        if !self.debug_files.is_empty() {
+            res["debug_files"] = json!(self.debug_files);
+        }
+        res
+    }
+
+    /// Get the summary as a json string
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// Add the summary entries to a json object
+    #[must_use]
+    pub fn merge_json(&self, json: &str) -> String {
+        let mut res: Value = serde_json::from_str(json).unwrap();
+        if let (Value::Object(res), Value::Object(summary)) = (&mut res, self.to_json_value()) {
+            res.extend(summary);
+        }
+        res.to_string()
+    }
+}
+
+/// Controls applied between the remeshing iterations
+#[derive(Clone, Default)]
+pub struct IterationControls {
+    /// Max number of elements: the operations that would exceed it are rolled back
+    pub max_n_elems: Option<Idx>,
+    /// Max distance between the boundary faces and the geometry: the operations that would
+    /// exceed it are rolled back
+    pub max_geom_deviation: Option<f64>,
+    /// Element tags that may not be removed: they are checked after each pass, or after each
+    /// collapse sweep if the operations are run one sweep at a time, in which case the collapse
+    /// sweeps that remove all the elements of one of them are rolled back
+    pub protected_tags: Option<Vec<Tag>>,
+    /// Run the operations one sweep at a time even if no other guard is set, to roll back the
+    /// collapse sweeps that remove a protected tag
+    pub guard_tags: bool,
+    /// Min element quality above which the iterations are stopped
+    pub target_min_quality: Option<f64>,
+    /// Mean element quality above which the iterations are stopped
+    pub target_mean_quality: Option<f64>,
+    /// Wall time (in seconds) after which the iterations are stopped
+    pub max_seconds: Option<f64>,
+}
+
+impl IterationControls {
+    /// Check if any control is set, in which case the iterations need to be run one at a time
+    const fn is_active(&self) -> bool {
+        self.has_guards()
+            || self.target_min_quality.is_some()
+            || self.target_mean_quality.is_some()
+            || self.max_seconds.is_some()
+    }
+
+    /// Check if any guard is set, in which case the operations are run one sweep at a time
+    pub const fn has_guards(&self) -> bool {
+        self.max_n_elems.is_some() || self.max_geom_deviation.is_some() || self.guard_tags
+    }
+
+    /// Check if the target qualities (if any) are reached
+    fn targets_reached(&self, qualities: &[f64]) -> bool {
+        if self.target_min_quality.is_none() && self.target_mean_quality.is_none() {
+            return false;
+        }
+        self.target_min_quality
+            .iter()
+            .all(|&q| min_quality(qualities) >= q)
+            && self
+                .target_mean_quality
+                .iter()
+                .all(|&q| mean_quality(qualities) >= q)
+    }
+}
+
+/// Remeshing operations, that are run one sweep at a time when guards are checked
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Split,
+    Collapse,
+    Swap,
+    Smooth,
+}
+
+impl Operation {
+    /// The operations, in the order they are run within an iteration
+    pub const ALL: [Self; 4] = [Self::Split, Self::Collapse, Self::Swap, Self::Smooth];
+
+    /// Name of the operation
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Split => "split",
+            Self::Collapse => "collapse",
+            Self::Swap => "swap",
+            Self::Smooth => "smooth",
+        }
+    }
+
+    /// Number of sweeps of the operation in an iteration
+    pub const fn n_sweeps(self, params: &RemesherParams) -> u32 {
+        match self {
+            Self::Split => params.split_max_iter,
+            Self::Collapse => params.collapse_max_iter,
+            Self::Swap => params.swap_max_iter,
+            Self::Smooth => params.smooth_iter,
+        }
+    }
+
+    /// Parameters running all the sweeps of the operation in an iteration, the other ones being
+    /// disabled
+    pub fn op_params(self, params: &RemesherParams) -> RemesherParams {
+        let n = self.n_sweeps(params);
+        RemesherParams {
+            num_iter: 1,
+            two_steps: false,
+            split_max_iter: if self == Self::Split { n } else { 0 },
+            collapse_max_iter: if self == Self::Collapse { n } else { 0 },
+            swap_max_iter: if self == Self::Swap { n } else { 0 },
+            smooth_iter: if self == Self::Smooth { n } else { 0 },
+            ..params.clone()
+        }
+    }
+
+    /// Parameters running a single sweep of the operation, the other ones being disabled
+    pub fn sweep_params(self, params: &RemesherParams) -> RemesherParams {
+        RemesherParams {
+            num_iter: 1,
+            two_steps: false,
+            split_max_iter: u32::from(self == Self::Split),
+            collapse_max_iter: u32::from(self == Self::Collapse),
+            swap_max_iter: u32::from(self == Self::Swap),
+            smooth_iter: u32::from(self == Self::Smooth),
+            ..params.clone()
+        }
+    }
+}
+
+/// Criteria checked after remeshing to accept the adapted mesh
+pub struct AcceptCriteria {
+    /// The min element quality must not be lower than before remeshing
+    pub min_quality_not_worse: bool,
+    /// Max distance between the boundary faces and the geometry
+    pub max_distance: Option<f64>,
+}
+
+impl AcceptCriteria {
+    /// Read the criteria from a dict, the missing entries taking their default values
+    pub fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut res = Self {
+            min_quality_not_worse: true,
+            max_distance: None,
+        };
+        for (k, v) in dict.iter() {
+            let k: String = k.extract()?;
+            match k.as_str() {
+                "min_quality_not_worse" => res.min_quality_not_worse = v.extract()?,
+                "max_distance" => res.max_distance = v.extract()?,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Invalid acceptance criterion {k}"
+                    )))
+                }
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Get all the remesher parameters as a json object with the same keys as `default_params()`
+fn params_to_json(params: &RemesherParams) -> Value {
+    json!({
+        "num_iter": params.num_iter,
+        "two_steps": params.two_steps,
+        "split_max_iter": params.split_max_iter,
+        "split_min_l_rel": params.split_min_l_rel,
+        "split_min_l_abs": params.split_min_l_abs,
+        "split_min_q_rel": params.split_min_q_rel,
+        "split_min_q_abs": params.split_min_q_abs,
+        "collapse_max_iter": params.collapse_max_iter,
+        "collapse_max_l_rel": params.collapse_max_l_rel,
+        "collapse_max_l_abs": params.collapse_max_l_abs,
+        "collapse_min_q_rel": params.collapse_min_q_rel,
+        "collapse_min_q_abs": params.collapse_min_q_abs,
+        "swap_max_iter": params.swap_max_iter,
+        "swap_max_l_rel": params.swap_max_l_rel,
+        "swap_max_l_abs": params.swap_max_l_abs,
+        "swap_min_l_rel": params.swap_min_l_rel,
+        "swap_min_l_abs": params.swap_min_l_abs,
+        "smooth_iter": params.smooth_iter,
+        "smooth_type": smooth_type_name(&params.smooth_type),
+        "smooth_relax": params.smooth_relax,
+        "smooth_keep_local_minima": params.smooth_keep_local_minima,
+        "max_angle": params.max_angle,
+        "debug": params.debug,
+    })
+}
+
+/// Versions of the bindings and of tucanos, as a json object
+fn versions_json() -> Value {
+    json!({
+        "pytucanos": env!("CARGO_PKG_VERSION"),
+        "tucanos": env!("TUCANOS_VERSION"),
+    })
+}
+
+/// Build the remesher parameters from a dict with the same keys as `default_params()`, the
+/// missing entries taking their default values
+pub fn params_from_dict(dict: Option<&Bound<'_, PyDict>>) -> PyResult<RemesherParams> {
+    let mut params = RemesherParams::default();
+    if let Some(dict) = dict {
+        update_params(&mut params, dict)?;
+    }
+    Ok(params)
+}
+
+/// Update the remesher parameters with the entries of a dict with the same keys as
+/// `default_params()`
+fn update_params(params: &mut RemesherParams, dict: &Bound<'_, PyDict>) -> PyResult<()> {
+    for (k, v) in dict.iter() {
+        let k: String = k.extract()?;
+        match k.as_str() {
+            "num_iter" => params.num_iter = v.extract()?,
+            "two_steps" => params.two_steps = v.extract()?,
+            "split_max_iter" => params.split_max_iter = v.extract()?,
+            "split_min_l_rel" => params.split_min_l_rel = v.extract()?,
+            "split_min_l_abs" => params.split_min_l_abs = v.extract()?,
+            "split_min_q_rel" => params.split_min_q_rel = v.extract()?,
+            "split_min_q_abs" => params.split_min_q_abs = v.extract()?,
+            "collapse_max_iter" => params.collapse_max_iter = v.extract()?,
+            "collapse_max_l_rel" => params.collapse_max_l_rel = v.extract()?,
+            "collapse_max_l_abs" => params.collapse_max_l_abs = v.extract()?,
+            "collapse_min_q_rel" => params.collapse_min_q_rel = v.extract()?,
+            "collapse_min_q_abs" => params.collapse_min_q_abs = v.extract()?,
+            "swap_max_iter" => params.swap_max_iter = v.extract()?,
+            "swap_max_l_rel" => params.swap_max_l_rel = v.extract()?,
+            "swap_max_l_abs" => params.swap_max_l_abs = v.extract()?,
+            "swap_min_l_rel" => params.swap_min_l_rel = v.extract()?,
+            "swap_min_l_abs" => params.swap_min_l_abs = v.extract()?,
+            "smooth_iter" => params.smooth_iter = v.extract()?,
+            "smooth_type" => params.smooth_type = parse_smooth_type(&v.extract::<String>()?)?,
+            "smooth_relax" => params.smooth_relax = v.extract()?,
+            "smooth_keep_local_minima" => params.smooth_keep_local_minima = v.extract()?,
+            "max_angle" => params.max_angle = v.extract()?,
+            "debug" => params.debug = v.extract()?,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid remesher parameter {k}"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse the `internal_interfaces` option of the remesher constructors: `True` for all the
+/// interfaces between element tags, or a list of `(tag0, tag1)` pairs
+fn parse_interfaces(interfaces: Option<&Bound<'_, PyAny>>) -> PyResult<Option<Vec<[Tag; 2]>>> {
+    let Some(interfaces) = interfaces else {
+        return Ok(None);
+    };
+    if let Ok(all) = interfaces.extract::<bool>() {
+        return Ok(if all { Some(Vec::new()) } else { None });
+    }
+    let pairs = interfaces.extract::<Vec<(Tag, Tag)>>()?;
+    if pairs.is_empty() {
+        return Err(PyValueError::new_err("No interface given"));
+    }
+    Ok(Some(pairs.into_iter().map(|(t0, t1)| [t0, t1]).collect()))
+}
+
+/// Add the faces at the interfaces between the element tags in `pairs` (all of them if empty) to
+/// a copy of `mesh`, and build a geometry where these interfaces are represented by their initial
+/// surface in addition to the geometry mesh `gmesh`, so that they are preserved by the remesher
+fn add_interfaces<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    gmesh: &SimplexMesh<D, E::Face>,
+    pairs: &[[Tag; 2]],
+) -> PyResult<(SimplexMesh<D, E>, LinearGeometry<D, E::Face>)> {
+    let mut mesh = mesh.clone();
+    let (_, ifc) = mesh.add_boundary_faces();
+    let is_selected =
+        |tags: &[Tag]| pairs.is_empty() || pairs.iter().any(|p| p.iter().all(|t| tags.contains(t)));
+    let selected: Vec<Tag> = ifc
+        .iter()
+        .filter(|(_, tags)| is_selected(&tags[..]))
+        .map(|(&tag, _)| tag)
+        .collect();
+
+    // remove the interface faces that are not selected
+    if selected.len() < ifc.len() {
+        let removed: HashSet<Tag> = ifc
+            .keys()
+            .copied()
+            .filter(|tag| !selected.contains(tag))
+            .collect();
+        let (faces, ftags) = mesh
+            .faces()
+            .zip(mesh.ftags())
+            .filter(|(_, tag)| !removed.contains(tag))
+            .unzip();
+        mesh = SimplexMesh::new(
+            mesh.verts().collect(),
+            mesh.elems().collect(),
+            mesh.etags().collect(),
+            faces,
+            ftags,
+        );
+    }
+    // the topology is stale after add_boundary_faces() and missing after the rebuild
+    mesh.clear_topology();
+    mesh.compute_topology();
+
+    let gtags: Vec<Tag> = gmesh.etags().collect();
+    let patches = boundary_per_tag(&mesh, Some(selected.as_slice()));
+    let patch_tags: Vec<Vec<Tag>> = patches
+        .iter()
+        .map(|(tag, patch, _, _)| vec![*tag; patch.n_elems() as usize])
+        .collect();
+    let mut parts = vec![(gmesh, gtags.as_slice())];
+    parts.extend(
+        patches
+            .iter()
+            .zip(&patch_tags)
+            .map(|((_, patch, _, _), tags)| (patch, tags.as_slice())),
+    );
+    let geom = LinearGeometry::new(&mesh, merge_surfaces(&parts))
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok((mesh, geom))
+}
+
+/// Number of elements of each tag
+fn tag_counts<const D: usize, E: Elem>(mesh: &SimplexMesh<D, E>) -> BTreeMap<Tag, usize> {
+    tag_values(mesh.etags())
+}
+
+/// For each vertex of `mesh`, the index of the vertex of `input` with exactly the same
+/// coordinates, i.e. that was not moved by the remesher, or -1 for the new vertices
+fn input_vert_ids<const D: usize, E: Elem>(
+    input: &SimplexMesh<D, E>,
+    mesh: &SimplexMesh<D, E>,
+) -> Vec<i64> {
+    let key = |p: &Point<D>| -> [u64; D] { std::array::from_fn(|d| p[d].to_bits()) };
+    let mut ids = HashMap::with_capacity(input.n_verts() as usize);
+    for (i, p) in input.verts().enumerate() {
+        ids.entry(key(&p)).or_insert(i as i64);
+    }
+    mesh.verts()
+        .map(|p| ids.get(&key(&p)).copied().unwrap_or(-1))
+        .collect()
+}
+
+/// Check if the vertex order passed to `to_mesh()` is "input_first" (or "internal", the default)
+fn input_first(order: Option<&str>) -> PyResult<bool> {
+    match order.unwrap_or("internal") {
+        "internal" => Ok(false),
+        "input_first" => Ok(true),
+        o => Err(PyValueError::new_err(format!(
+            "Invalid vertex order {o}, expected \"internal\" or \"input_first\""
+        ))),
+    }
+}
+
+/// Vertex permutation (vertex `i` becoming vertex `perm[i]`) that moves the vertices of the
+/// input mesh first, in their input order, followed by the new vertices in their current order
+fn input_first_perm(ids: &[i64]) -> Vec<Idx> {
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+    order.sort_by_key(|&i| {
+        if ids[i] < 0 {
+            (1, i as i64)
+        } else {
+            (0, ids[i])
+        }
+    });
+    let mut perm = vec![0; ids.len()];
+    for (i_new, i) in order.into_iter().enumerate() {
+        perm[i] = i_new as Idx;
+    }
+    perm
+}
+
+/// Min value of the element qualities
+fn min_quality(qualities: &[f64]) -> f64 {
+    qualities.iter().copied().fold(f64::INFINITY, f64::min)
+}
+
+/// Mean value of the element qualities
+fn mean_quality(qualities: &[f64]) -> f64 {
+    qualities.iter().sum::<f64>() / qualities.len().max(1) as f64
+}
+
+/// Check the number of components `n` of the metric passed to remesher `name` in `dim`D, with
+/// a hint to use the other remesher if `n` is that of an isotropic / anisotropic metric
+fn check_metric_components(name: &str, dim: usize, n: usize, expected: usize) -> PyResult<()> {
+    if n == expected {
+        return Ok(());
+    }
+    let other = if n == 1 {
+        Some(format!("Remesher{dim}dIso"))
+    } else if n == dim * (dim + 1) / 2 {
+        Some(format!("Remesher{dim}dAniso"))
+    } else {
+        None
+    };
+    Err(PyValueError::new_err(other.map_or_else(
+        || format!("Invalid dimension 1: metric has {n} components (expected {expected})"),
+        |other| {
+            format!(
+                "metric has {n} components: use {other} instead of {name} (expected {expected})"
+            )
+        },
+    )))
+}
+
 macro_rules! create_remesher {
-    ($name: ident, $dim: expr, $etype: ident, $metric: ident, $mesh: ident, $geom: ident) => {
+    ($name: ident, $dim: expr, $etype: ident, $metric: ident, $mesh: ident, $geom: ident, $bg: ident) => {
         #[doc = concat!("Remesher for a meshes consisting of ", stringify!($etype), " in ", stringify!($dim), "D")]
         #[doc = concat!("using ", stringify!($metric), " as metric and a piecewise linear representation of the geometry")]
-        #[pyclass]
+        #[doc = ""]
+        #[doc = "The GIL is released by `remesh()` and by the metric class methods (`hessian_to_metric`,"]
+        #[doc = "`scale_metric`, `smooth_metric`, `apply_metric_gradation`, `check_gradation`, the P0 / P1"]
+        #[doc = "conversions, `control_step_metric`, `intersect_metrics`, `union_metrics`, `metric_info`,"]
+        #[doc = "`conformity_field` and `complexity_per_tag`) while computing. It is held while the numpy"]
+        #[doc = "arrays are checked and converted, and by the other methods"]
+        #[pyclass(module = "pytucanos._pytucanos")]
         pub struct $name {
             remesher: Remesher<$dim, $etype, $metric>,
+            /// Geometry given to `new()`, kept to pickle the remesher
+            geometry: Py<$geom>,
+            summary: RemeshSummary,
+            input_mesh: SimplexMesh<$dim, $etype>,
+            input_metric: Vec<$metric>,
+            /// Geometry including the internal interfaces, used instead of the geometry passed
+            /// to `remesh()` if set
+            interface_geom: Option<Arc<LinearGeometry<$dim, <$etype as Elem>::Face>>>,
         }
 
         #[doc = concat!("Create a remesher from a ", stringify!($mesh), " and a ",stringify!($metric) ," metric defined at the mesh vertices")]
         #[doc = concat!("A piecewise linear representation of the geometry is used, either from the ", stringify!($geom), " given or otherwise from the mesh boundary.")]
         #[pymethods]
         impl $name {
+            /// Create a remesher. The topology of the mesh is computed if needed
+            ///
+            /// If `internal_interfaces` is True (or a list of `(tag0, tag1)` pairs), the faces between
+            /// elements with different tags (or with the tags in the list) are added to the mesh and
+            /// treated like boundary faces: they are projected onto their initial surface, which is
+            /// added to the geometry, so that the interfaces remain conforming. This geometry is
+            /// then used by `remesh()`, which must be given the same `geometry` as `new()`
+            ///
+            #[doc = concat!("`m` may also be a ", stringify!($bg), ", which is evaluated at the mesh vertices. The remesher")]
+            /// then interpolates these values on the mesh being adapted, as for a metric array
             #[new]
             pub fn new(
                 mesh: &$mesh,
-                geometry: &$geom,
-                m: PyReadonlyArray2<f64>,
+                geometry: PyRef<'_, $geom>,
+                m: &Bound<'_, PyAny>,
+                internal_interfaces: Option<&Bound<'_, PyAny>>,
             ) -> PyResult<Self> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
-                if m.shape()[1] != $metric::N as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 1"));
-                }
-
-                let m = m.as_slice()?;
+                let m = if let Ok(bg) = m.extract::<PyRef<$bg>>() {
+                    check_metric_components(stringify!($name), $dim, bg.n(), $metric::N)?;
+                    bg.eval_at(&mesh.mesh, None)?
+                } else {
+                    let m = m.extract::<PyReadonlyArray2<f64>>()?;
+                    mesh.check_vertex_array("m", m.shape()[0])?;
+                    check_metric_components(stringify!($name), $dim, m.shape()[1], $metric::N)?;
+                    m.as_slice()?.to_vec()
+                };
                 let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
 
-                let remesher = Remesher::new(&mesh.mesh, &m, &geometry.geom);
+                let (mut input_mesh, interface_geom) = match parse_interfaces(internal_interfaces)? {
+                    Some(pairs) => {
+                        let (mesh, geom) = add_interfaces(&mesh.mesh, &geometry.gmesh, &pairs)?;
+                        (mesh, Some(Arc::new(geom)))
+                    }
+                    None => (mesh.mesh.clone(), None),
+                };
+                // the meshes rebuilt from their arrays, e.g. by pickle, have no topology
+                if input_mesh.get_vertex_tags().is_err() {
+                    input_mesh.compute_topology();
+                }
+
+                let geom = interface_geom.as_deref().unwrap_or(&geometry.geom);
+                let remesher = Remesher::new(&input_mesh, &m, geom);
                 if let Err(res) = remesher {
                     return Err(PyRuntimeError::new_err(res.to_string()));
                 }
-                Ok(Self {remesher: remesher.unwrap()})
+                Ok(Self {
+                    remesher: remesher.unwrap(),
+                    geometry: geometry.into(),
+                    summary: RemeshSummary::default(),
+                    input_mesh,
+                    input_metric: m,
+                    interface_geom,
+                })
+            }
+
+            /// Arguments of `new()` used by pickle to create the object: the current mesh, the
+            /// geometry and the current metric. Remeshers with internal interfaces cannot be
+            /// pickled
+            #[allow(clippy::type_complexity)]
+            pub fn __getnewargs__<'py>(
+                &self,
+                py: Python<'py>,
+            ) -> PyResult<($mesh, Py<$geom>, Bound<'py, PyArray2<f64>>)> {
+                if self.interface_geom.is_some() {
+                    return Err(PyValueError::new_err(
+                        "Remeshers with internal interfaces cannot be pickled",
+                    ));
+                }
+                Ok((
+                    self.remesher.to_mesh(false).into(),
+                    self.geometry.clone_ref(py),
+                    to_numpy_2d(py, self.ordered_metric(None)?, <$metric as Metric<$dim>>::N),
+                ))
+            }
+
+            /// Get the state used by pickle and `copy.deepcopy()`: the input mesh and metric, to
+            /// which the remesher is reset if an adapted mesh is rejected. The summary of the
+            /// last `remesh()` call is not part of the state
+            #[must_use]
+            pub fn __getstate__<'py>(&self, py: Python<'py>) -> ($mesh, Bound<'py, PyArray2<f64>>) {
+                let m = self.input_metric.iter().cloned().flatten().collect();
+                (
+                    self.input_mesh.clone().into(),
+                    to_numpy_2d(py, m, <$metric as Metric<$dim>>::N),
+                )
+            }
+
+            /// Restore the state returned by `__getstate__()`
+            pub fn __setstate__(
+                &mut self,
+                state: (PyRef<'_, $mesh>, PyReadonlyArray2<f64>),
+            ) -> PyResult<()> {
+                let (mesh, m) = state;
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                check_metric_components(stringify!($name), $dim, m.shape()[1], $metric::N)?;
+                self.input_metric = m.as_slice()?.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
+                self.input_mesh = mesh.mesh.clone();
+                self.input_mesh.compute_topology();
+                Ok(())
             }
 
             /// Convert a Hessian $H$ to the optimal metric for a Lp norm, i.e.
             ///  $$ m = det(|H|)^{-1/(2p+dim)}|H| $$
+            /// where the eigenvalues of $|H|$ are the absolute values of those of $H$, floored by
+            /// `rel_floor` (default: 1e-8) times the largest one over the field so that the metric
+            /// is definite where the Hessian vanishes. The number of vertices where the eigenvalues
+            /// are floored is logged
             #[classmethod]
             pub fn hessian_to_metric<'py>(
                 _cls: &Bound<'_, PyType>,
@@ -65,16 +726,19 @@ macro_rules! create_remesher {
                 mesh: &$mesh,
                 m: PyReadonlyArray2<f64>,
                 p: Option<Idx>,
+                rel_floor: Option<f64>,
             ) -> PyResult<Bound<'py,PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
                 let mut res = Vec::with_capacity(m.shape()[0] * m.shape()[1]);
-                let m = m.as_slice().unwrap();
+                let mut m = m.as_slice()?.to_vec();
+                let n_floored = regularize_hessian(&mut m, <$metric as Metric<$dim>>::N, rel_floor.unwrap_or(1e-8))?;
+                if n_floored > 0 {
+                    warn!("the Hessian eigenvalues have been floored at {n_floored} vertices");
+                }
                 let mut m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
 
                 let exponent = if let Some(p) = p {
@@ -83,18 +747,29 @@ macro_rules! create_remesher {
                     0.0
                 };
 
-                for m_v in m.iter_mut() {
-                    let scale = f64::powf(m_v.vol(), exponent);
-                    if !scale.is_nan() {
-                        m_v.scale(scale);
+                py.allow_threads(|| {
+                    for m_v in m.iter_mut() {
+                        m_v.scale(f64::powf(m_v.vol(), exponent));
+                        res.extend(m_v.into_iter());
                     }
-                    res.extend(m_v.into_iter());
-                }
+                });
 
                 return Ok(to_numpy_2d(py, res, <$metric as Metric<$dim>>::N));
             }
 
             /// Scale a metric field to reach the desired (ideal) number of elements using min / max bounds on the cell size
+            ///
+            /// If `fixed_m` is given, the scaled metric is intersected with it, i.e. it bounds the
+            /// sizes everywhere, and `implied_m` is the metric implied by the current mesh used to
+            /// limit the variations between remeshing iterations with `step`.
+            ///
+            /// If `fixed_etags` is given, the metric at the vertices of the elements with these tags
+            /// is kept exactly as given: the complexity of these vertices is subtracted from
+            /// `n_elems` and the metric at the other vertices is scaled to reach the remaining
+            /// complexity. It cannot be combined with `fixed_m`, `implied_m` or `step`.
+            ///
+            /// Return the scaled metric, and a dict with the complexity of the `"fixed"` and
+            /// `"scaled"` vertices, the complexity of each element being shared between its vertices
             #[classmethod]
             #[allow(clippy::too_many_arguments)]
             pub fn scale_metric<'py>(
@@ -109,10 +784,9 @@ macro_rules! create_remesher {
                 implied_m: Option<PyReadonlyArray2<f64>>,
                 step: Option<f64>,
                 max_iter: Option<Idx>,
-            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                fixed_etags: Option<Vec<Tag>>,
+            ) -> PyResult<(Bound<'py, PyArray2<f64>>, Bound<'py, PyDict>)> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
@@ -120,34 +794,49 @@ macro_rules! create_remesher {
                 let m = m.as_slice().unwrap();
                 let mut m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
 
-                let res =  if let Some(fixed_m) = fixed_m {
-                    let fixed_m = fixed_m.as_slice().unwrap();
-                    let fixed_m: Vec<_> = fixed_m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                    if let Some(implied_m) = implied_m {
-                        let implied_m = implied_m.as_slice().unwrap();
-                        let implied_m: Vec<_> = implied_m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                        mesh.mesh
-                            .scale_metric(&mut m, h_min, h_max, n_elems, Some(&fixed_m), Some(&implied_m), step, max_iter.unwrap_or(10))
-                    } else {
-                        mesh.mesh
-                            .scale_metric(&mut m, h_min, h_max, n_elems, Some(&fixed_m), None, step, max_iter.unwrap_or(10))
+                let split = PyDict::new_bound(py);
+                if let Some(fixed_etags) = fixed_etags {
+                    if fixed_m.is_some() || implied_m.is_some() || step.is_some() {
+                        return Err(PyValueError::new_err(
+                            "fixed_etags cannot be combined with fixed_m, implied_m or step",
+                        ));
                     }
-                } else if let Some(implied_m) = implied_m {
-                    let implied_m = implied_m.as_slice().unwrap();
-                    let implied_m: Vec<_> = implied_m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                    mesh.mesh
-                        .scale_metric(&mut m, h_min, h_max, n_elems, None, Some(&implied_m), step, max_iter.unwrap_or(10))
-                } else {
-                    mesh.mesh
-                    .scale_metric(&mut m, h_min, h_max, n_elems, None, None, None, max_iter.unwrap_or(10))
+                    let mut fixed = vec![false; m.len()];
+                    for (e, tag) in mesh.mesh.elems().zip(mesh.mesh.etags()) {
+                        if fixed_etags.contains(&tag) {
+                            e.into_iter().for_each(|i| fixed[i as usize] = true);
+                        }
+                    }
+                    let msh = &mesh.mesh;
+                    let (c_fixed, c_scaled) = py.allow_threads(|| scale_metric_with_fixed(
+                        msh, &mut m, &fixed, h_min, h_max, f64::from(n_elems), max_iter.unwrap_or(10),
+                    ))?;
+                    split.set_item("fixed", c_fixed)?;
+                    split.set_item("scaled", c_scaled)?;
+                    let m: Vec<_> = m.iter().cloned().flatten().collect();
+                    return Ok((to_numpy_2d(py, m, <$metric as Metric<$dim>>::N), split));
+                }
+
+                let to_metric = |x: PyReadonlyArray2<f64>| -> PyResult<Vec<$metric>> {
+                    Ok(x.as_slice()?.chunks($metric::N).map(|x| $metric::from_slice(x)).collect())
                 };
+                let fixed_m = fixed_m.map(to_metric).transpose()?;
+                let implied_m = implied_m.map(to_metric).transpose()?;
+                // the step is only used to limit the variations with respect to fixed_m / implied_m
+                let step = if fixed_m.is_some() || implied_m.is_some() { step } else { None };
 
-                if let Err(res) = res {
-                    return Err(PyRuntimeError::new_err(res.to_string()));
-                }
+                let msh = &mesh.mesh;
+                let c_scaled = py
+                    .allow_threads(|| {
+                        msh.scale_metric(&mut m, h_min, h_max, n_elems, fixed_m.as_deref(), implied_m.as_deref(), step, max_iter.unwrap_or(10))
+                            .map(|_| complexity(msh, &m))
+                    })
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
 
+                split.set_item("fixed", 0.0)?;
+                split.set_item("scaled", c_scaled)?;
                 let m: Vec<_> = m.iter().cloned().flatten().collect();
-                return Ok(to_numpy_2d(py, m, <$metric as Metric<$dim>>::N));
+                return Ok((to_numpy_2d(py, m, <$metric as Metric<$dim>>::N), split));
             }
 
             /// Smooth a metric field
@@ -158,16 +847,15 @@ macro_rules! create_remesher {
                 mesh: &$mesh,
                 m: PyReadonlyArray2<f64>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
                 let m = m.as_slice().unwrap();
                 let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                let m = mesh.mesh.smooth_metric(&m);
+                let msh = &mesh.mesh;
+                let m = py.allow_threads(|| msh.smooth_metric(&m));
                 if let Err(m) = m {
                     return Err(PyRuntimeError::new_err(m.to_string()));
                 }
@@ -187,16 +875,15 @@ macro_rules! create_remesher {
                 beta: f64,
                 n_iter: Idx,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
                 let m = m.as_slice().unwrap();
                 let mut m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                let res = mesh.mesh.apply_metric_gradation(&mut m, beta, n_iter);
+                let msh = &mesh.mesh;
+                let res = py.allow_threads(|| msh.apply_metric_gradation(&mut m, beta, n_iter));
                 match res {
                     Ok(_) => {
                         let m: Vec<_> = m.iter().cloned().flatten().collect();
@@ -209,25 +896,75 @@ macro_rules! create_remesher {
                 }
             }
 
+            /// Check that a metric field satisfies a maximum gradation `beta`, e.g. after
+            /// `apply_metric_gradation`: along an edge of length `l` in the metric at one of its
+            /// vertices, the sizes at the other vertex may not exceed those at the first one by
+            /// more than a factor `1 + l ln(beta)` (the span used by the gradation).
+            /// Return the max effective gradation `exp((r - 1) / l)` over the edges, where `r`
+            /// is the max size ratio between the two vertices, and the edges (as pairs of
+            /// vertices) that exceed the bound by more than a factor `1 + tol` (default: 1e-3)
+            #[classmethod]
+            pub fn check_gradation<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                m: PyReadonlyArray2<f64>,
+                beta: f64,
+                tol: Option<f64>,
+            ) -> PyResult<(f64, Bound<'py, PyArray2<Idx>>)> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                if m.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+                if beta.is_nan() || beta <= 1.0 {
+                    return Err(PyValueError::new_err("beta must be larger than 1"));
+                }
+
+                let msh = &mesh.mesh;
+                let m = m.as_slice()?;
+                let (max_beta, edges) = py.allow_threads(|| check_gradation(
+                    msh,
+                    m,
+                    <$metric as Metric<$dim>>::N,
+                    beta,
+                    tol.unwrap_or(1e-3),
+                ));
+                let edges: Vec<Idx> = edges.into_iter().flatten().collect();
+                Ok((max_beta, to_numpy_2d(py, edges, 2)))
+            }
+
             /// Convert a metic field defined at the element centers (P0) to a field defined at the vertices (P1)
-            /// using a weighted average.
+            /// using a weighted interpolation.
+            /// `weighting` may be "volume" (default, requires `compute_vertex_to_elems()` and
+            /// `compute_volumes()`), "uniform" or "inverse_distance" (see
+            /// `elem_data_to_vertex_data`)
             #[classmethod]
             pub fn elem_data_to_vertex_data_metric<'py>(
                 _cls: &Bound<'_, PyType>,
                 py: Python<'py>,
                 mesh: &$mesh,
                 m: PyReadonlyArray2<f64>,
+                weighting: Option<&str>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_elems() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_elem_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
                 let m = m.as_slice().unwrap();
                 let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                let res = mesh.mesh.elem_data_to_vertex_data_metric::<$metric>(&m);
+                let msh = &mesh.mesh;
+                let weights = py.allow_threads(|| p0_to_p1_weights(msh, weighting))?;
+                if let Some(weights) = weights {
+                    let res: Vec<_> = py.allow_threads(|| {
+                        weights
+                            .iter()
+                            .flat_map(|w| $metric::interpolate(&mut w.iter().map(|&(i, x)| (x, &m[i]))))
+                            .collect()
+                    });
+                    return Ok(to_numpy_2d(py, res, <$metric as Metric<$dim>>::N));
+                }
+                let res = py.allow_threads(|| msh.elem_data_to_vertex_data_metric::<$metric>(&m));
                 match res {
                     Ok(res) => {
                         let res: Vec<_> = res.iter().cloned().flatten().collect();
@@ -248,16 +985,15 @@ macro_rules! create_remesher {
                 mesh: &$mesh,
                 m: PyReadonlyArray2<f64>,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
                 let m = m.as_slice().unwrap();
                 let m: Vec<_> = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
-                let res = mesh.mesh.vertex_data_to_elem_data_metric::<$metric>(&m);
+                let msh = &mesh.mesh;
+                let res = py.allow_threads(|| msh.vertex_data_to_elem_data_metric::<$metric>(&m));
                 match res {
                     Ok(res) => {
                         let res: Vec<_> = res.iter().cloned().flatten().collect();
@@ -280,16 +1016,12 @@ macro_rules! create_remesher {
                 m_other: PyReadonlyArray2<f64>,
                 step: f64,
             ) -> PyResult<Bound<'py, PyArray2<f64>>> {
-                if m.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m", m.shape()[0])?;
                 if m.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
 
-                if m_other.shape()[0] != mesh.mesh.n_verts() as usize {
-                    return Err(PyValueError::new_err("Invalid dimension 0"));
-                }
+                mesh.check_vertex_array("m_other", m_other.shape()[0])?;
                 if m_other.shape()[1] != <$metric as Metric<$dim>>::N {
                     return Err(PyValueError::new_err("Invalid dimension 1"));
                 }
@@ -302,24 +1034,238 @@ macro_rules! create_remesher {
 
                 let mut res = Vec::with_capacity(mesh.mesh.n_verts() as usize * <$metric as Metric<$dim>>::N);
 
-                for (mut m_i, m_other_i) in m.zip(m_other) {
-                    m_i.control_step(&m_other_i, step);
-                    res.extend(m_i.into_iter());
-                }
+                py.allow_threads(|| {
+                    for (mut m_i, m_other_i) in m.zip(m_other) {
+                        m_i.control_step(&m_other_i, step);
+                        res.extend(m_i.into_iter());
+                    }
+                });
 
                 return Ok(to_numpy_2d(py, res, <$metric as Metric<$dim>>::N));
             }
 
+            /// Intersect two metrics, i.e. compute at each vertex the largest metric whose unit
+            /// ball is contained in the unit balls of `m1` and `m2` (the smallest sizes of both)
+            #[classmethod]
+            pub fn intersect_metrics<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                m1: PyReadonlyArray2<f64>,
+                m2: PyReadonlyArray2<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                mesh.check_vertex_array("m1", m1.shape()[0])?;
+                if m1.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                mesh.check_vertex_array("m2", m2.shape()[0])?;
+                if m2.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                let m1 = m1.as_slice()?.chunks($metric::N).map(|x| $metric::from_slice(x));
+                let m2 = m2.as_slice()?.chunks($metric::N).map(|x| $metric::from_slice(x));
+                let res: Vec<f64> = py.allow_threads(|| {
+                    m1.zip(m2).flat_map(|(m1_i, m2_i)| m1_i.intersect(&m2_i).into_iter()).collect()
+                });
+
+                Ok(to_numpy_2d(py, res, <$metric as Metric<$dim>>::N))
+            }
+
+            /// Compute the union of two metrics, i.e. at each vertex the smallest metric whose unit
+            /// ball contains the unit balls of `m1` and `m2` (the largest sizes of both). It is
+            /// computed as the inverse of the intersection of the inverse metrics
+            #[classmethod]
+            pub fn union_metrics<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                m1: PyReadonlyArray2<f64>,
+                m2: PyReadonlyArray2<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                mesh.check_vertex_array("m1", m1.shape()[0])?;
+                if m1.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                mesh.check_vertex_array("m2", m2.shape()[0])?;
+                if m2.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                let n = <$metric as Metric<$dim>>::N;
+                let mut inv_m1 = m1.to_vec()?;
+                let mut inv_m2 = m2.to_vec()?;
+                let res = py.allow_threads(|| {
+                    sym_map(&mut inv_m1, n, |x| 1.0 / x);
+                    sym_map(&mut inv_m2, n, |x| 1.0 / x);
+
+                    let inv_m1 = inv_m1.chunks(n).map(|x| $metric::from_slice(x));
+                    let inv_m2 = inv_m2.chunks(n).map(|x| $metric::from_slice(x));
+                    let mut res: Vec<f64> = inv_m1.zip(inv_m2).flat_map(|(m1_i, m2_i)| m1_i.intersect(&m2_i).into_iter()).collect();
+                    sym_map(&mut res, n, |x| 1.0 / x);
+                    res
+                });
+
+                Ok(to_numpy_2d(py, res, n))
+            }
+
             /// Compute the min/max sizes, max anisotropy and complexity of a metric
             #[classmethod]
             pub fn metric_info(
                 _cls: &Bound<'_, PyType>,
+                py: Python<'_>,
                 mesh: &$mesh,
                 m: PyReadonlyArray2<f64>,
-            ) -> (f64, f64, f64, f64) {
+            ) -> PyResult<(f64, f64, f64, f64)> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                if m.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
                 let m = m.as_slice().unwrap();
                 let m = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect::<Vec<_>>();
-                mesh.mesh.metric_info(&m)
+                let msh = &mesh.mesh;
+                Ok(py.allow_threads(|| msh.metric_info(&m)))
+            }
+
+            /// Get the deviation of each element of `mesh` from the metric `m` defined at the mesh
+            /// vertices, as an array of shape (# of elements) with values in [0, 1] that can be
+            /// written as element data with `write_vtk`. It is `1 - r`, where `r` is the smallest
+            /// ratio (or inverse ratio) between the sizes of the element (given by its implied
+            /// metric) and the metric sizes in the principal directions, so it is 0 for elements
+            /// that match the metric
+            #[classmethod]
+            pub fn conformity_field<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                m: PyReadonlyArray2<f64>,
+            ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                if m.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+                let msh = &mesh.mesh;
+                let m = m.as_slice()?;
+                let res = py.allow_threads(|| conformity_error(msh, m, <$metric as Metric<$dim>>::N));
+                Ok(to_numpy_1d(py, res))
+            }
+
+            /// Estimate the complexity (ideal number of elements) of a metric defined at the mesh
+            /// vertices over the elements of each tag, the metric density being averaged over the
+            /// element vertices.
+            /// Return a dict mapping the element tags to their complexity, and the total complexity
+            #[classmethod]
+            pub fn complexity_per_tag<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                m: PyReadonlyArray2<f64>,
+            ) -> PyResult<(Bound<'py, PyDict>, f64)> {
+                mesh.check_vertex_array("m", m.shape()[0])?;
+                if m.shape()[1] != <$metric as Metric<$dim>>::N {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+                let m = m.as_slice().unwrap();
+                let m = m.chunks($metric::N).map(|x| $metric::from_slice(x)).collect::<Vec<_>>();
+
+                let msh = &mesh.mesh;
+                let (per_tag, total) = py.allow_threads(|| (complexity_per_tag(msh, &m), complexity(msh, &m)));
+                let dict = PyDict::new_bound(py);
+                for (tag, c) in per_tag {
+                    dict.set_item(tag, c)?;
+                }
+                Ok((dict, total))
+            }
+
+            /// Remesh a mesh for each metric in `metrics`, sharing the mesh and the geometry
+            ///
+            /// The remesher parameters are given as a dict with the same keys as
+            /// `default_params()`. The remeshings are run on `n_threads` threads (default: 1),
+            /// each on its own remesher.
+            /// If `callback` is given, it is called as `callback(i, mesh, stats_json)` as soon as
+            /// remeshing for metric `i` is done, so that only the meshes being processed are kept
+            /// in memory, and None is returned. Otherwise the list of `(mesh, stats_json)` is
+            /// returned.
+            #[classmethod]
+            #[allow(clippy::too_many_arguments)]
+            #[allow(clippy::type_complexity)]
+            pub fn remesh_sweep(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'_>,
+                mesh: &$mesh,
+                geometry: &$geom,
+                metrics: Vec<PyReadonlyArray2<f64>>,
+                params: Option<&Bound<'_, PyDict>>,
+                n_threads: Option<usize>,
+                callback: Option<&Bound<'_, PyAny>>,
+            ) -> PyResult<Option<Vec<(Py<$mesh>, String)>>> {
+                let params = params_from_dict(params)?;
+                let mut ms = Vec::with_capacity(metrics.len());
+                for m in &metrics {
+                    mesh.check_vertex_array("m", m.shape()[0])?;
+                    if m.shape()[1] != <$metric as Metric<$dim>>::N {
+                        return Err(PyValueError::new_err("Invalid dimension 1"));
+                    }
+                    let m: Vec<_> = m.as_slice()?.chunks($metric::N).map(|x| $metric::from_slice(x)).collect();
+                    ms.push(m);
+                }
+                let n = ms.len();
+                let n_threads = n_threads.unwrap_or(1).clamp(1, n.max(1));
+                let callback = callback.map(|c| c.clone().unbind());
+
+                let next = AtomicUsize::new(0);
+                let (tx, rx) = mpsc::channel();
+                let mut results: Vec<Option<(Py<$mesh>, String)>> = (0..n).map(|_| None).collect();
+                let mut err = None;
+                py.allow_threads(|| {
+                    thread::scope(|s| {
+                        for _ in 0..n_threads {
+                            let tx = tx.clone();
+                            let (next, ms, params) = (&next, &ms, &params);
+                            s.spawn(move || loop {
+                                let i = next.fetch_add(1, Ordering::SeqCst);
+                                if i >= n {
+                                    break;
+                                }
+                                let res = Remesher::new(&mesh.mesh, &ms[i], &geometry.geom).and_then(|mut remesher| {
+                                    remesher.remesh(params.clone(), &geometry.geom)?;
+                                    Ok((remesher.to_mesh(false), remesher.stats_json()))
+                                });
+                                if tx.send((i, res.map_err(|e| e.to_string()))).is_err() {
+                                    break;
+                                }
+                            });
+                        }
+                        drop(tx);
+
+                        for (i, res) in rx {
+                            Python::with_gil(|py| {
+                                let res = res.map_err(PyRuntimeError::new_err).and_then(|(new_mesh, stats)| {
+                                    let new_mesh = Py::new(py, $mesh::from(new_mesh))?;
+                                    if let Some(callback) = callback.as_ref() {
+                                        callback.call1(py, (i, new_mesh, stats))?;
+                                    } else {
+                                        results[i] = Some((new_mesh, stats));
+                                    }
+                                    Ok(())
+                                });
+                                if let Err(e) = res {
+                                    err.get_or_insert(e);
+                                }
+                            });
+                        }
+                    });
+                });
+
+                if let Some(err) = err {
+                    return Err(err);
+                }
+                if callback.is_some() {
+                    return Ok(None);
+                }
+                Ok(Some(results.into_iter().flatten().collect()))
             }
 
             /// Check that the mesh is valid
@@ -339,11 +1285,51 @@ macro_rules! create_remesher {
             }
 
             #[doc = concat!("Get the mesh as a ", stringify!($mesh))]
-            #[must_use]
-            pub fn to_mesh(&self, only_bdy_faces: Option<bool>) -> $mesh {
-                $mesh {
-                    mesh: self.remesher.to_mesh(only_bdy_faces.unwrap_or(false)),
+            ///
+            /// With `order="internal"` (the default), the vertices are in the remesher internal
+            /// order, that is not specified and may change between versions. With
+            /// `order="input_first"`, the vertices of the input mesh that were not modified come
+            /// first, in their input order (see `input_vert_ids()`), followed by the new or moved
+            /// vertices
+            pub fn to_mesh(&self, only_bdy_faces: Option<bool>, order: Option<&str>) -> PyResult<$mesh> {
+                let mesh = self.remesher.to_mesh(only_bdy_faces.unwrap_or(false));
+                if !input_first(order)? {
+                    return Ok(mesh.into());
                 }
+                let perm = input_first_perm(&input_vert_ids(&self.input_mesh, &mesh));
+                Ok(permute_mesh(&mesh, Some(&perm), None, None).into())
+            }
+
+            /// Get, for each vertex of the mesh returned by `to_mesh(order=order)`, the index of
+            /// the input vertex with exactly the same coordinates, or -1 for the vertices created
+            /// or moved by the remesher. With `order="input_first"`, the ids are increasing up to
+            /// the first -1
+            pub fn input_vert_ids<'py>(
+                &self,
+                py: Python<'py>,
+                order: Option<&str>,
+            ) -> PyResult<Bound<'py, PyArray1<i64>>> {
+                let mut ids = input_vert_ids(&self.input_mesh, &self.remesher.to_mesh(false));
+                if input_first(order)? {
+                    let perm = input_first_perm(&ids);
+                    let mut res = vec![0; ids.len()];
+                    for (i, &j) in perm.iter().enumerate() {
+                        res[j as usize] = ids[i];
+                    }
+                    ids = res;
+                }
+                Ok(to_numpy_1d(py, ids))
+            }
+
+            #[doc = concat!("Get the metric used by the remesher at the vertices of the mesh returned by `to_mesh(order=order)`, as an array of shape (# of vertices, ", stringify!($metric), "::N).")]
+            /// It is interpolated by the remesher at the vertices it creates or moves, and is the
+            /// input metric at the unmodified input vertices
+            pub fn metric<'py>(
+                &self,
+                py: Python<'py>,
+                order: Option<&str>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                Ok(to_numpy_2d(py, self.ordered_metric(order)?, <$metric as Metric<$dim>>::N))
             }
 
             /// Get the number of vertices
@@ -386,24 +1372,96 @@ macro_rules! create_remesher {
                 dict.set_item("swap_min_l_rel", default_params.swap_min_l_rel).unwrap();
                 dict.set_item("swap_min_l_abs", default_params.swap_min_l_abs).unwrap();
                 dict.set_item("smooth_iter", default_params.smooth_iter).unwrap();
-                let smooth_type = match default_params.smooth_type {
-                    tucanos::remesher::SmoothingType::Laplacian => "laplacian",
-                    tucanos::remesher::SmoothingType::Avro => "avro",
-                    #[cfg(feature = "nlopt")]
-                    tucanos::remesher::SmoothingType::NLOpt => "nlopt",
-                    tucanos::remesher::SmoothingType::Laplacian2 => "laplacian2",
-                };
-                dict.set_item("smooth_type", smooth_type).unwrap();
+                dict.set_item("smooth_type", smooth_type_name(&default_params.smooth_type)).unwrap();
                 dict.set_item("smooth_relax", to_numpy_1d(py, default_params.smooth_relax)).unwrap();
+                dict.set_item("smooth_keep_local_minima", default_params.smooth_keep_local_minima).unwrap();
                 dict.set_item("max_angle", default_params.max_angle).unwrap();
+                dict.set_item("debug", default_params.debug).unwrap();
 
                 dict
             }
 
             /// Perform a remeshing iteration
+            /// The GIL is released while remeshing, so that independent remeshers can run
+            /// concurrently in Python threads
+            ///
+            /// If controls are given, the iterations are run one at a time and the controls are
+            /// checked between them:
+            ///  - `max_n_elems` and `max_geom_deviation` are guards: the operations are run one
+            ///    sweep at a time (`split`, `collapse`, `swap` then `smooth`, each up to its
+            ///    `*_max_iter` / `smooth_iter` times per iteration), the guards are checked after
+            ///    each sweep and a sweep that violates them is rolled back, the remaining sweeps
+            ///    of this operation being skipped for the iteration. The number of rejected sweeps
+            ///    is reported per guard as `rejected_ops` in `summary_json()`. NB: tucanos does not
+            ///    expose the individual operations, so a sweep is the finest granularity at which
+            ///    they can be rolled back,
+            ///  - `max_n_elems`: a sweep is rolled back if the number of elements exceeds
+            ///    `max_n_elems` (and increases), so the adapted mesh does not exceed the cap unless
+            ///    the input mesh does. The splits are then disabled for the remaining iterations
+            ///    and `truncated` is set in `summary_json()`,
+            ///  - `max_geom_deviation`: a sweep is rolled back if it moves the boundary faces
+            ///    farther than `max_geom_deviation` from the geometry (and farther than before the
+            ///    sweep). The final max distance is reported as `geom_deviation` in
+            ///    `summary_json()`, and `geom_deviation_exceeded` is set if any sweep was rejected,
+            ///  - `target_min_quality` / `target_mean_quality`: the iterations are stopped as soon
+            ///    as the min / mean element quality reach these values (both if both are given),
+            ///    and `early_exit` is set in `summary_json()`,
+            ///  - `max_seconds`: if the wall time since the start of `remesh()` exceeds
+            ///    `max_seconds` after an iteration, the remaining iterations are replaced by a
+            ///    single smoothing pass (no split, collapse or swap), so that a valid mesh is
+            ///    returned, and `timed_out` is set in `summary_json()`. It is also checked between
+            ///    the passes (see `pre_passes`), the remaining ones being skipped. As the
+            ///    iterations cannot be interrupted, the budget may be exceeded by the duration of
+            ///    an iteration (or of a pre-pass) and of the final smoothing.
+            ///
+            /// The number of iterations run is then reported as `n_iter` in `summary_json()`
+            ///
+            /// If `accept_if` is given, the adapted mesh is checked against the following criteria
+            /// and, if any is violated, the remesher is reset to the mesh and metric it was created
+            /// with and `rejected` is set in `summary_json()`:
+            ///  - `min_quality_not_worse` (default: True): the min element quality must not be
+            ///    lower than before remeshing,
+            ///  - `max_distance` (default: None): the max distance between the boundary faces and
+            ///    the geometry must not exceed this value
+            ///
+            /// Unless `allow_tag_removal` is True, the element tags of the input mesh are protected,
+            /// also in the pre-passes: if a pass removes all the elements of one of these tags, it
+            /// is run again from its start one sweep at a time (see the guards above), the collapse
+            /// sweeps that remove all the elements of one of these tags being rolled back and
+            /// counted as `rejected_ops["tag_removal"]`. If other guards are set, the tags are
+            /// checked after each collapse sweep instead. The number of elements of each tag before
+            /// and after remeshing is reported as `tag_counts` in `summary_json()` in both cases
+            ///
+            /// If `pre_passes` is given, each of its dicts (with the same keys as
+            /// `default_params()`, the missing entries taking the values of the main pass) is run in
+            /// sequence on the same remesher before the main pass, without the controls above,
+            /// e.g. `[{"split_max_iter": 0, "swap_max_iter": 0, "smooth_iter": 0}]` for a
+            /// collapse-only pre-pass. The number of iterations, wall time, number of elements and
+            /// min / mean quality after each pass (the main one being the last) are reported as
+            /// `passes` in `summary_json()`.
+            /// `two_steps=True` is a shortcut for a pre-pass `{"num_iter": 1, "two_steps": True}`
+            /// followed by `num_iter - 1` iterations of the main pass with `two_steps=False`
+            ///
+            /// If `params` (a `RemesherParams`) is given, it replaces the default values of the
+            /// parameters that are not given as keyword arguments, including `max_n_elems`,
+            /// `target_min_quality` and `target_mean_quality`
+            ///
+            /// If `debug` is True, tucanos checks the mesh after each operation (see
+            /// `RemesherParams`). If `debug_dir` is also given, the directory is created if needed
+            /// and the iterations are run one operation at a time, the mesh being written with the
+            /// metric (as vertex data `metric`) to:
+            ///  - `input.vtu` before remeshing,
+            ///  - `pass{p:02}_iter{i:03}_{op}.vtu` after operation `op` ("split", "collapse",
+            ///    "swap" or "smooth", unless it has no sweep) of iteration `i` of pass `p` (the
+            ///    main pass being the last one, see `pre_passes`),
+            ///  - `pass{p:02}_iter{i:03}_final_smooth.vtu` after the final smoothing if
+            ///    `max_seconds` is exceeded after iteration `i`,
+            ///  - `output.vtu` after remeshing (and the rollback, if any).
+            /// The paths of the files written are reported as `debug_files` in `summary_json()`
             #[allow(clippy::too_many_arguments)]
             pub fn remesh(
                 &mut self,
+                py: Python<'_>,
                 geometry: &$geom,
                 num_iter:Option< u32>,
                 two_steps: Option<bool>,
@@ -428,20 +1486,36 @@ macro_rules! create_remesher {
                 smooth_keep_local_minima: Option<bool>,
                 max_angle:Option< f64>,
                 debug: Option<bool>,
+                max_n_elems: Option<Idx>,
+                max_geom_deviation: Option<f64>,
+                accept_if: Option<&Bound<'_, PyDict>>,
+                allow_tag_removal: Option<bool>,
+                target_min_quality: Option<f64>,
+                target_mean_quality: Option<f64>,
+                max_seconds: Option<f64>,
+                pre_passes: Option<Vec<Bound<'_, PyDict>>>,
+                params: Option<PyRef<'_, PyRemesherParams>>,
+                debug_dir: Option<&str>,
             ) -> PyResult<()>{
-                let smooth_type = smooth_type.unwrap_or("laplacian");
-
-                let smooth_type = if smooth_type == "laplacian" {
-                    SmoothingType::Laplacian
-                } else if smooth_type == "laplacian2" {
-                    SmoothingType::Laplacian2
-                } else if smooth_type == "nlopt" {
-                    unreachable!()
-                } else {
-                    SmoothingType::Avro
+                let accept = accept_if.map(AcceptCriteria::from_dict).transpose()?;
+                if max_seconds.is_some_and(|x| x.is_nan() || x <= 0.0) {
+                    return Err(PyValueError::new_err("max_seconds must be positive"));
+                }
+
+                let (default_params, max_n_elems, target_min_quality, target_mean_quality) = match params {
+                    Some(params) => (
+                        params.to_params()?,
+                        max_n_elems.or(params.max_n_elems),
+                        target_min_quality.or(params.target_min_quality),
+                        target_mean_quality.or(params.target_mean_quality),
+                    ),
+                    None => (RemesherParams::default(), max_n_elems, target_min_quality, target_mean_quality),
                 };
 
-                let default_params = RemesherParams::default();
+                let smooth_type = match smooth_type {
+                    None => default_params.smooth_type.clone(),
+                    Some(name) => parse_smooth_type(name)?,
+                };
 
                 let params = RemesherParams {
                     num_iter: num_iter.unwrap_or(default_params.num_iter),
@@ -468,7 +1542,136 @@ macro_rules! create_remesher {
                     max_angle: max_angle.unwrap_or(default_params.max_angle),
                     debug: debug.unwrap_or(default_params.debug),
                 };
-                self.remesher.remesh(params, &geometry.geom).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+                if let Some(dir) = debug_dir {
+                    if !params.debug {
+                        return Err(PyValueError::new_err("debug_dir requires debug=True"));
+                    }
+                    std::fs::create_dir_all(dir).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                }
+                let pre_passes = pre_passes
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|dict| {
+                        let mut pass_params = params.clone();
+                        update_params(&mut pass_params, dict)?;
+                        Ok(pass_params)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+
+                let initial_counts = tag_counts(&self.remesher.to_mesh(false));
+                let protected_tags: Option<Vec<Tag>> = if allow_tag_removal.unwrap_or(false) {
+                    None
+                } else {
+                    Some(initial_counts.keys().copied().collect())
+                };
+                let pre_pass_controls = IterationControls {
+                    protected_tags: protected_tags.clone(),
+                    ..IterationControls::default()
+                };
+                let controls = IterationControls {
+                    max_n_elems,
+                    max_geom_deviation,
+                    protected_tags,
+                    target_min_quality,
+                    target_mean_quality,
+                    max_seconds,
+                };
+
+                if self.interface_geom.is_some() && !std::ptr::eq(geometry, &*self.geometry.borrow(py)) {
+                    return Err(PyValueError::new_err(
+                        "Remeshers with internal interfaces must be given the geometry they were created with",
+                    ));
+                }
+                let interface_geom = self.interface_geom.clone();
+                let geom = interface_geom.as_deref().unwrap_or(&geometry.geom);
+
+                let (h_min, h_max, max_aniso, complexity) = self.input_mesh.metric_info(&self.input_metric);
+                self.summary = RemeshSummary {
+                    provenance: Some(json!({
+                        "params": params_to_json(&params),
+                        "pre_passes": pre_passes.iter().map(params_to_json).collect::<Vec<_>>(),
+                        "controls": {
+                            "max_n_elems": controls.max_n_elems,
+                            "max_geom_deviation": controls.max_geom_deviation,
+                            "allow_tag_removal": controls.protected_tags.is_none(),
+                            "target_min_quality": controls.target_min_quality,
+                            "target_mean_quality": controls.target_mean_quality,
+                            "max_seconds": controls.max_seconds,
+                        },
+                        "input_hash": format!("{:016x}", content_hash(&self.input_mesh)),
+                        "metric_info": {
+                            "h_min": h_min,
+                            "h_max": h_max,
+                            "max_anisotropy": max_aniso,
+                            "complexity": complexity,
+                        },
+                        "versions": versions_json(),
+                    })),
+                    ..RemeshSummary::default()
+                };
+                if let Some(dir) = debug_dir {
+                    self.write_debug_mesh(dir, "input")?;
+                }
+                let q_min = min_quality(&self.remesher.qualities());
+                let n_passes = pre_passes.len() + 1;
+                let start = Instant::now();
+                for (i, pass_params) in pre_passes.into_iter().chain(Some(params)).enumerate() {
+                    if let (true, Some(max_seconds)) = (i > 0, controls.max_seconds) {
+                        let elapsed = start.elapsed().as_secs_f64();
+                        if elapsed > max_seconds {
+                            warn!("{elapsed:.2}s > {max_seconds:.2}s after {i} passes: the remaining passes are skipped");
+                            self.summary.timed_out = true;
+                            break;
+                        }
+                    }
+                    let pass_start = Instant::now();
+                    let num_iter = pass_params.num_iter;
+                    let pass_controls = if i + 1 == n_passes { &controls } else { &pre_pass_controls };
+                    let debug = debug_dir.map(|dir| (dir, i));
+                    let saved = (pass_controls.protected_tags.is_some() && !pass_controls.has_guards())
+                        .then(|| (self.remesher.to_mesh(false), self.remesher.metrics()));
+                    if pass_controls.is_active() || debug.is_some() {
+                        self.remesh_iterations(py, &pass_params, geom, pass_controls, start, debug)?;
+                    } else {
+                        let remesher = &mut self.remesher;
+                        py.allow_threads(|| remesher.remesh(pass_params.clone(), geom)).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    }
+                    if let (Some((mesh, metric)), Some(tags)) = (saved, &pass_controls.protected_tags) {
+                        let counts = tag_counts(&self.remesher.to_mesh(false));
+                        if let Some(tag) = tags.iter().find(|tag| !counts.contains_key(tag)) {
+                            warn!("all the elements with tag {tag} have been removed: the pass is run again one sweep at a time");
+                            self.restore(mesh, &metric, geom)?;
+                            let pass_controls = IterationControls {
+                                guard_tags: true,
+                                ..pass_controls.clone()
+                            };
+                            self.remesh_iterations(py, &pass_params, geom, &pass_controls, start, debug)?;
+                        }
+                    }
+                    if n_passes > 1 {
+                        let qualities = self.remesher.qualities();
+                        self.summary.passes.push(PassSummary {
+                            num_iter,
+                            time: pass_start.elapsed().as_secs_f64(),
+                            n_elems: self.remesher.n_elems(),
+                            q_min: min_quality(&qualities),
+                            q_mean: mean_quality(&qualities),
+                        });
+                    }
+                }
+
+                let final_counts = tag_counts(&self.remesher.to_mesh(false));
+                for (tag, n) in initial_counts {
+                    let n_final = final_counts.get(&tag).copied().unwrap_or(0);
+                    self.summary.tag_counts.insert(tag, (n, n_final));
+                }
+
+                self.accept_or_rollback(accept.as_ref(), q_min, geom)?;
+                if let Some(dir) = debug_dir {
+                    self.write_debug_mesh(dir, "output")?;
+                }
+
+                Ok(())
             }
 
             /// Get the element qualities as a numpy array of size (# or elements)
@@ -477,17 +1680,285 @@ macro_rules! create_remesher {
                 to_numpy_1d(py, self.remesher.qualities())
             }
 
+            /// Get the min element quality
+            #[must_use]
+            pub fn min_quality(&self) -> f64 {
+                min_quality(&self.remesher.qualities())
+            }
+
             /// Get the element lengths (in metric space) as a numpy array of size (# or edges)
             #[must_use]
             pub fn lengths<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
                 to_numpy_1d(py, self.remesher.lengths())
             }
 
+            /// Get, for each element of the adapted mesh (in the order of `to_mesh()`), the ratio
+            /// between the mean length of its edges in the metric space and the target length 1,
+            /// so that values above (resp. below) 1 mark under- (resp. over-) resolved regions. The
+            /// input metric is interpolated onto the adapted mesh in the log-Euclidean framework.
+            /// The array can be written as element data with `write_vtk`
+            pub fn size_achievement<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<f64>>> {
+                let n = <$metric as Metric<$dim>>::N;
+                let mesh = self.remesher.to_mesh(false);
+                let mut log_m: Vec<f64> = self.input_metric.iter().cloned().flatten().collect();
+                sym_map(&mut log_m, n, f64::ln);
+                let tree = self.input_mesh.compute_elem_tree();
+                let mut m = self
+                    .input_mesh
+                    .interpolate_linear(&tree, &mesh, &log_m, None)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                sym_map(&mut m, n, f64::exp);
+                Ok(to_numpy_1d(py, mean_edge_lengths(&mesh, &m, n)))
+            }
+
             /// Get the infomation about the remeshing steps performed in remesh() as a json string
             #[must_use]
             pub fn stats_json(&self) -> String {
                 self.remesher.stats_json()
             }
+
+            /// Get the summary of the last call to remesh() as a json string
+            ///
+            /// It includes, as `provenance`, the parameters of the main pass and of the pre-passes
+            /// with the defaults resolved, the controls, the content hash and the `metric_info()`
+            /// of the mesh and metric the remesher was created with, and the versions of the
+            /// bindings and of tucanos
+            #[must_use]
+            pub fn summary_json(&self) -> String {
+                self.summary.to_json()
+            }
+
+            /// Save the infomation about the remeshing steps (`stats_json()`) and the summary
+            /// (`summary_json()`) of the last call to remesh() to a json file, that can be read
+            /// with `load_stats()`
+            pub fn save_stats(&self, fname: &str) -> PyResult<()> {
+                let stats: Value = serde_json::from_str(&self.remesher.stats_json())
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                let res = json!({
+                    "stats": stats,
+                    "summary": self.summary.to_json_value(),
+                });
+                std::fs::write(fname, res.to_string())
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+            }
+        }
+
+        impl $name {
+            /// Metric at the vertices of the mesh returned by `to_mesh(order=order)`, as a flat
+            /// array
+            fn ordered_metric(&self, order: Option<&str>) -> PyResult<Vec<f64>> {
+                let n = <$metric as Metric<$dim>>::N;
+                let m: Vec<f64> = self.remesher.metrics().iter().cloned().flatten().collect();
+                if !input_first(order)? {
+                    return Ok(m);
+                }
+                let perm = input_first_perm(&input_vert_ids(&self.input_mesh, &self.remesher.to_mesh(false)));
+                let mut res = vec![0.0; m.len()];
+                for (i, &j) in perm.iter().enumerate() {
+                    res[n * j as usize..n * (j as usize + 1)].copy_from_slice(&m[n * i..n * (i + 1)]);
+                }
+                Ok(res)
+            }
+
+            /// Write the current mesh with the metric (as vertex data `metric`) to `dir/{name}.vtu`,
+            /// and add its path to the debug files of the summary
+            fn write_debug_mesh(&mut self, dir: &str, name: &str) -> PyResult<()> {
+                let fname = Path::new(dir).join(format!("{name}.vtu")).to_string_lossy().into_owned();
+                let m: Vec<f64> = self.remesher.metrics().iter().cloned().flatten().collect();
+                let mut vdata = HashMap::new();
+                vdata.insert("metric".to_string(), m.as_slice());
+                self.remesher
+                    .to_mesh(false)
+                    .write_vtk(&fname, Some(vdata), None)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                self.summary.debug_files.push(fname);
+                Ok(())
+            }
+
+            /// Reset the remesher to a mesh and metric saved before an operation sweep
+            fn restore(&mut self, mut mesh: SimplexMesh<$dim, $etype>, metric: &[$metric], geom: &LinearGeometry<$dim, <$etype as Elem>::Face>) -> PyResult<()> {
+                mesh.compute_topology();
+                self.remesher = Remesher::new(&mesh, metric, geom).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(())
+            }
+
+            /// Run a single sweep of `op` and check the guards of `controls` afterwards (the
+            /// protected tags only after a collapse sweep): if any is violated, the remesher is
+            /// reset to its state before the sweep, the rejection is counted in the summary and
+            /// the name of the guard is returned
+            fn guarded_sweep(&mut self, py: Python<'_>, op: Operation, params: &RemesherParams, two_steps: bool, geom: &LinearGeometry<$dim, <$etype as Elem>::Face>, controls: &IterationControls) -> PyResult<Option<&'static str>> {
+                let mesh = self.remesher.to_mesh(false);
+                let metric = self.remesher.metrics();
+                let n_elems = self.remesher.n_elems();
+                let mut sweep_params = op.sweep_params(params);
+                sweep_params.two_steps = two_steps;
+                let remesher = &mut self.remesher;
+                py.allow_threads(|| remesher.remesh(sweep_params, geom)).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+                let mut violated = None;
+                if let Some(max_n_elems) = controls.max_n_elems {
+                    if self.remesher.n_elems() > max_n_elems && self.remesher.n_elems() > n_elems {
+                        self.summary.truncated = true;
+                        violated = Some("max_n_elems");
+                    }
+                }
+                if let (None, Some(max_geom_deviation)) = (violated, controls.max_geom_deviation) {
+                    let d = geom.max_distance(&self.remesher.to_mesh(true));
+                    if d > max_geom_deviation && d > self.summary.geom_deviation.unwrap_or(0.0) {
+                        self.summary.geom_deviation_exceeded = true;
+                        violated = Some("max_geom_deviation");
+                    } else {
+                        self.summary.geom_deviation = Some(d);
+                    }
+                }
+                if let (None, Operation::Collapse, Some(tags)) = (violated, op, &controls.protected_tags) {
+                    let counts = tag_counts(&self.remesher.to_mesh(false));
+                    if let Some(tag) = tags.iter().find(|tag| !counts.contains_key(tag)) {
+                        warn!("all the elements with tag {tag} have been removed");
+                        violated = Some("tag_removal");
+                    }
+                }
+
+                if let Some(guard) = violated {
+                    warn!("{guard} exceeded by a {} sweep: the sweep is rolled back", op.name());
+                    self.restore(mesh, &metric, geom)?;
+                    *self.summary.rejected_ops.entry(guard).or_default() += 1;
+                }
+                Ok(violated)
+            }
+
+            /// Run an iteration one operation sweep at a time, checking the guards of `controls`
+            /// after each sweep. The sweeps of an operation are stopped for the iteration once one
+            /// of them is rejected, or once a split or collapse sweep leaves the mesh unchanged.
+            /// If `debug` is `(dir, prefix)`, the mesh is written to `dir/{prefix}_{op}.vtu` after
+            /// the sweeps of each operation.
+            /// Return false if a split sweep was rejected as it exceeded `max_n_elems`
+            fn guarded_iteration(&mut self, py: Python<'_>, params: &RemesherParams, two_steps: bool, geom: &LinearGeometry<$dim, <$etype as Elem>::Face>, controls: &IterationControls, debug: Option<(&str, &str)>) -> PyResult<bool> {
+                let mut splits = true;
+                for op in Operation::ALL {
+                    if op.n_sweeps(params) == 0 {
+                        continue;
+                    }
+                    for sweep in 0..op.n_sweeps(params) {
+                        let sizes = (self.remesher.n_verts(), self.remesher.n_elems());
+                        let two_steps = two_steps && op == Operation::Split && sweep == 0;
+                        if let Some(guard) = self.guarded_sweep(py, op, params, two_steps, geom, controls)? {
+                            splits &= !(guard == "max_n_elems" && op == Operation::Split);
+                            break;
+                        }
+                        let unchanged = sizes == (self.remesher.n_verts(), self.remesher.n_elems());
+                        if unchanged && matches!(op, Operation::Split | Operation::Collapse) {
+                            break;
+                        }
+                    }
+                    if let Some((dir, prefix)) = debug {
+                        self.write_debug_mesh(dir, &format!("{prefix}_{}", op.name()))?;
+                    }
+                }
+                Ok(splits)
+            }
+
+            /// Run the remeshing iterations one at a time, applying the controls between the
+            /// iterations, and one operation sweep at a time if `controls` has guards (see
+            /// `guarded_iteration()`). `max_seconds` is measured from `start`, the start of
+            /// `remesh()`. If `debug` is `(dir, pass)`, the iterations are run one operation at a
+            /// time and the mesh is written to `dir` after each operation (see `remesh()`)
+            fn remesh_iterations(&mut self, py: Python<'_>, params: &RemesherParams, geom: &LinearGeometry<$dim, <$etype as Elem>::Face>, controls: &IterationControls, start: Instant, debug: Option<(&str, usize)>) -> PyResult<()> {
+                let mut split_max_iter = params.split_max_iter;
+
+                if controls.max_geom_deviation.is_some() && self.summary.geom_deviation.is_none() {
+                    self.summary.geom_deviation = Some(geom.max_distance(&self.remesher.to_mesh(true)));
+                }
+
+                for i in 0..params.num_iter {
+                    let mut iter_params = params.clone();
+                    iter_params.num_iter = 1;
+                    iter_params.two_steps = params.two_steps && i == 0;
+                    iter_params.split_max_iter = split_max_iter;
+                    let prefix = debug.map(|(dir, pass)| (dir, format!("pass{pass:02}_iter{i:03}")));
+                    let prefix = prefix.as_ref().map(|(dir, prefix)| (*dir, prefix.as_str()));
+                    if controls.has_guards() {
+                        if !self.guarded_iteration(py, &iter_params, iter_params.two_steps, geom, controls, prefix)? {
+                            warn!("a split sweep would exceed max_n_elems: splits are disabled");
+                            split_max_iter = 0;
+                        }
+                    } else if let Some((dir, prefix)) = prefix {
+                        for op in Operation::ALL {
+                            if op.n_sweeps(&iter_params) == 0 {
+                                continue;
+                            }
+                            let mut op_params = op.op_params(&iter_params);
+                            op_params.two_steps = iter_params.two_steps && op == Operation::Split;
+                            let remesher = &mut self.remesher;
+                            py.allow_threads(|| remesher.remesh(op_params, geom)).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                            self.write_debug_mesh(dir, &format!("{prefix}_{}", op.name()))?;
+                        }
+                    } else {
+                        let remesher = &mut self.remesher;
+                        py.allow_threads(|| remesher.remesh(iter_params, geom)).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    }
+                    self.summary.n_iter = Some(i + 1);
+
+                    if i + 1 < params.num_iter && controls.targets_reached(&self.remesher.qualities()) {
+                        self.summary.early_exit = true;
+                        break;
+                    }
+
+                    if let Some(max_seconds) = controls.max_seconds {
+                        let elapsed = start.elapsed().as_secs_f64();
+                        if i + 1 < params.num_iter && elapsed > max_seconds {
+                            warn!("{elapsed:.2}s > {max_seconds:.2}s after {} iterations: final smoothing", i + 1);
+                            self.summary.timed_out = true;
+                            let smooth_params = Operation::Smooth.op_params(params);
+                            if controls.has_guards() {
+                                self.guarded_iteration(py, &smooth_params, false, geom, controls, None)?;
+                            } else {
+                                let remesher = &mut self.remesher;
+                                py.allow_threads(|| remesher.remesh(smooth_params, geom)).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                            }
+                            if let Some((dir, pass)) = debug {
+                                self.write_debug_mesh(dir, &format!("pass{pass:02}_iter{i:03}_final_smooth"))?;
+                            }
+                            break;
+                        }
+                    }
+                }
+
+                if let Some(max_n_elems) = controls.max_n_elems {
+                    if self.remesher.n_elems() > max_n_elems {
+                        warn!("{} elements > {max_n_elems} after remeshing", self.remesher.n_elems());
+                        self.summary.truncated = true;
+                    }
+                }
+
+                Ok(())
+            }
+
+            /// Check the adapted mesh against the acceptance criteria (if any), and reset the
+            /// remesher to its input mesh and metric if they are not met
+            fn accept_or_rollback(&mut self, accept: Option<&AcceptCriteria>, q_min: f64, geom: &LinearGeometry<$dim, <$etype as Elem>::Face>) -> PyResult<()> {
+                if let Some(accept) = accept {
+                    let new_q_min = min_quality(&self.remesher.qualities());
+                    if accept.min_quality_not_worse && new_q_min < q_min {
+                        warn!("min quality {new_q_min:.2e} < {q_min:.2e}: the adapted mesh is rejected");
+                        self.summary.rejected = true;
+                    }
+                    if let Some(max_distance) = accept.max_distance {
+                        let d = geom.max_distance(&self.remesher.to_mesh(true));
+                        if d > max_distance {
+                            warn!("geometric deviation {d:.2e} > {max_distance:.2e}: the adapted mesh is rejected");
+                            self.summary.rejected = true;
+                        }
+                    }
+                }
+
+                if self.summary.rejected {
+                    self.remesher = Remesher::new(&self.input_mesh, &self.input_metric, geom)
+                        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                }
+
+                Ok(())
+            }
         }
     };
 }
@@ -500,7 +1971,8 @@ create_remesher!(
     Triangle,
     IsoMetric2d,
     Mesh22,
-    LinearGeometry2d
+    LinearGeometry2d,
+    BackgroundMetric2d
 );
 create_remesher!(
     Remesher2dAniso,
@@ -508,7 +1980,8 @@ create_remesher!(
     Triangle,
     AnisoMetric2d,
     Mesh22,
-    LinearGeometry2d
+    LinearGeometry2d,
+    BackgroundMetric2d
 );
 create_remesher!(
     Remesher3dIso,
@@ -516,7 +1989,8 @@ create_remesher!(
     Tetrahedron,
     IsoMetric3d,
     Mesh33,
-    LinearGeometry3d
+    LinearGeometry3d,
+    BackgroundMetric3d
 );
 create_remesher!(
     Remesher3dAniso,
@@ -524,5 +1998,109 @@ create_remesher!(
     Tetrahedron,
     AnisoMetric3d,
     Mesh33,
-    LinearGeometry3d
+    LinearGeometry3d,
+    BackgroundMetric3d
 );
+
+macro_rules! create_iso_remesher_methods {
+    ($name: ident, $dim: expr, $metric: ident, $mesh: ident) => {
+        #[pymethods]
+        impl $name {
+            /// Get the sizes used by the remesher at the vertices of the mesh returned by
+            /// `to_mesh(order=order)`, as an array of shape (# of vertices), i.e. `metric(order)`
+            /// flattened
+            pub fn sizes<'py>(
+                &self,
+                py: Python<'py>,
+                order: Option<&str>,
+            ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+                Ok(to_numpy_1d(py, self.ordered_metric(order)?))
+            }
+
+            /// Build an isotropic metric that equidistributes an error indicator `eta` defined at
+            /// the elements, of shape (# of elements, 1), assuming that it behaves as `h^p`
+            /// (default: p = 2). The element sizes are multiplied by `(eta / max(eta))^(-1/p)`,
+            /// converted to the vertices and scaled with bounds `h_min` and `h_max` to get a
+            /// complexity of `n_elems` (see `scale_metric`)
+            #[classmethod]
+            #[allow(clippy::too_many_arguments)]
+            pub fn metric_from_indicator<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                eta: PyReadonlyArray2<f64>,
+                n_elems: Idx,
+                h_min: f64,
+                h_max: f64,
+                p: Option<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                mesh.check_elem_array("eta", eta.shape()[0])?;
+                if eta.shape()[1] != 1 {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+
+                let h = indicator_sizes(&mesh.mesh, eta.as_slice()?, p.unwrap_or(2.0))?;
+                let h: Vec<_> = h.iter().map(|&x| $metric::from_slice(&[x])).collect();
+                let weights = p0_to_p1_weights(&mesh.mesh, Some("uniform"))?.unwrap();
+                let mut m: Vec<_> = weights
+                    .iter()
+                    .map(|w| $metric::interpolate(&mut w.iter().map(|&(i, x)| (x, &h[i]))))
+                    .collect();
+
+                mesh.mesh
+                    .scale_metric(&mut m, h_min, h_max, n_elems, None, None, None, 10)
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+                let m: Vec<_> = m.iter().cloned().flatten().collect();
+                Ok(to_numpy_2d(py, m, 1))
+            }
+        }
+    };
+}
+
+create_iso_remesher_methods!(Remesher2dIso, 2, IsoMetric2d, Mesh22);
+create_iso_remesher_methods!(Remesher3dIso, 3, IsoMetric3d, Mesh33);
+
+macro_rules! create_aniso_remesher_methods {
+    ($name: ident, $dim: expr, $metric: ident, $mesh: ident) => {
+        #[pymethods]
+        impl $name {
+            /// Build an anisotropic metric aligned with a direction field of shape (# of vertices, dim)
+            /// with sizes `h_parallel` along the directions and `h_perp` across them. The sizes are
+            /// either scalars or arrays of shape (# of vertices). The directions are normalized, and
+            /// the metric is isotropic with size `h_perp` where they are zero.
+            #[classmethod]
+            pub fn metric_from_direction<'py>(
+                _cls: &Bound<'_, PyType>,
+                py: Python<'py>,
+                mesh: &$mesh,
+                directions: PyReadonlyArray2<f64>,
+                h_parallel: &Bound<'_, PyAny>,
+                h_perp: &Bound<'_, PyAny>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                let n = mesh.mesh.n_verts() as usize;
+                mesh.check_vertex_array("directions", directions.shape()[0])?;
+                if directions.shape()[1] != $dim {
+                    return Err(PyValueError::new_err("Invalid dimension 1"));
+                }
+                let h_parallel = scalar_or_array(h_parallel, n)?;
+                let h_perp = scalar_or_array(h_perp, n)?;
+
+                let mut res = Vec::with_capacity(n * <$metric as Metric<$dim>>::N);
+                for ((d, &h_parallel), &h_perp) in directions
+                    .as_slice()?
+                    .chunks($dim)
+                    .zip(h_parallel.iter())
+                    .zip(h_perp.iter())
+                {
+                    res.extend(direction_metric(d, h_parallel, h_perp));
+                }
+
+                Ok(to_numpy_2d(py, res, <$metric as Metric<$dim>>::N))
+            }
+        }
+    };
+}
+
+create_aniso_remesher_methods!(Remesher2dAniso, 2, AnisoMetric2d, Mesh22);
+create_aniso_remesher_methods!(Remesher3dAniso, 3, AnisoMetric3d, Mesh33);
@@ -0,0 +1,245 @@
+use crate::to_numpy_2d;
+use numpy::{PyReadonlyArray1, PyUntypedArrayMethods};
+use pyo3::{exceptions::PyValueError, prelude::PyAnyMethods, Bound, PyAny, PyResult, Python};
+use std::collections::HashMap;
+use tucanos::{mesh::Point, mesh::SimplexMesh, topo_elems::Elem, Idx};
+
+/// Number of iterations used to project the boundary vertices onto the zero level set
+const N_PROJECTION_ITER: usize = 3;
+
+/// Number of times the displacement of a vertex is halved if it inverts an element
+const N_BACKTRACK_ITER: usize = 10;
+
+/// Evaluate the signed distance function on an array of points in a single call
+fn eval_sdf<const D: usize>(
+    py: Python<'_>,
+    sdf: &Bound<'_, PyAny>,
+    pts: &[Point<D>],
+) -> PyResult<Vec<f64>> {
+    let coords = pts.iter().flat_map(|p| p.iter().copied()).collect();
+    let res = sdf.call1((to_numpy_2d(py, coords, D),))?;
+    let res = res.extract::<PyReadonlyArray1<f64>>()?;
+    if res.len() != pts.len() {
+        return Err(PyValueError::new_err(
+            "Invalid dimension 0 for the signed distance values",
+        ));
+    }
+    Ok(res.to_vec()?)
+}
+
+/// Signed volume (up to a constant factor) of a simplex
+fn signed_vol<const D: usize>(pts: &[Point<D>]) -> f64 {
+    let e0 = pts[1] - pts[0];
+    let e1 = pts[2] - pts[0];
+    match D {
+        2 => e0[0] * e1[1] - e0[1] * e1[0],
+        3 => {
+            let e2 = pts[3] - pts[0];
+            e0[0] * (e1[1] * e2[2] - e1[2] * e2[1]) - e0[1] * (e1[0] * e2[2] - e1[2] * e2[0])
+                + e0[2] * (e1[0] * e2[1] - e1[1] * e2[0])
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Structured simplex mesh of a box with size h, each cell being split into `D!` simplices
+/// (Kuhn decomposition) that are positively oriented
+#[allow(clippy::cast_sign_loss)]
+fn box_mesh<const D: usize>(lo: &[f64], hi: &[f64], h: f64) -> (Vec<Point<D>>, Vec<Vec<Idx>>) {
+    let n: Vec<usize> = (0..D)
+        .map(|d| (((hi[d] - lo[d]) / h).ceil() as usize).max(1))
+        .collect();
+    let mut strides = vec![1; D];
+    for d in 1..D {
+        strides[d] = strides[d - 1] * (n[d - 1] + 1);
+    }
+    let n_verts = strides[D - 1] * (n[D - 1] + 1);
+
+    let verts: Vec<Point<D>> = (0..n_verts)
+        .map(|i| {
+            let mut p = Point::<D>::zeros();
+            for d in 0..D {
+                let j = (i / strides[d]) % (n[d] + 1);
+                p[d] = lo[d] + (hi[d] - lo[d]) * j as f64 / n[d] as f64;
+            }
+            p
+        })
+        .collect();
+
+    let perms: &[&[usize]] = match D {
+        2 => &[&[0, 1], &[1, 0]],
+        3 => &[
+            &[0, 1, 2],
+            &[0, 2, 1],
+            &[1, 0, 2],
+            &[1, 2, 0],
+            &[2, 0, 1],
+            &[2, 1, 0],
+        ],
+        _ => unreachable!(),
+    };
+
+    let n_cells: usize = n.iter().product();
+    let mut elems = Vec::with_capacity(n_cells * perms.len());
+    for i_cell in 0..n_cells {
+        let mut first = 0;
+        let mut tmp = i_cell;
+        for d in 0..D {
+            first += (tmp % n[d]) * strides[d];
+            tmp /= n[d];
+        }
+        for perm in perms {
+            let mut e = vec![first as Idx];
+            let mut i = first;
+            for &d in *perm {
+                i += strides[d];
+                e.push(i as Idx);
+            }
+            let pts: Vec<_> = e.iter().map(|&i| verts[i as usize]).collect();
+            if signed_vol(&pts) < 0.0 {
+                e.swap(0, 1);
+            }
+            elems.push(e);
+        }
+    }
+
+    (verts, elems)
+}
+
+/// Indices of the vertices on the boundary faces
+fn boundary_vertices(elems: &[Vec<Idx>]) -> Vec<Idx> {
+    let mut faces: HashMap<Vec<Idx>, usize> = HashMap::new();
+    for e in elems {
+        for i in 0..e.len() {
+            let mut f: Vec<_> = e
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, &k)| k)
+                .collect();
+            f.sort_unstable();
+            *faces.entry(f).or_insert(0) += 1;
+        }
+    }
+    let mut res: Vec<_> = faces
+        .into_iter()
+        .filter(|(_, n)| *n == 1)
+        .flat_map(|(f, _)| f)
+        .collect();
+    res.sort_unstable();
+    res.dedup();
+    res
+}
+
+/// Mesh the domain where the signed distance function `sdf` is negative, within the box
+/// `(lo, hi)`, with size `h`
+///
+/// A structured mesh of the box is built, the elements with all their vertices inside the domain
+/// are kept, and the boundary vertices are projected onto the zero level set (the projection
+/// being reduced where it would invert elements). All the elements and boundary faces have tag 1
+pub fn mesh_from_sdf<const D: usize, E: Elem>(
+    py: Python<'_>,
+    sdf: &Bound<'_, PyAny>,
+    lo: &[f64],
+    hi: &[f64],
+    h: f64,
+) -> PyResult<SimplexMesh<D, E>> {
+    if lo.len() != D || hi.len() != D {
+        return Err(PyValueError::new_err("Invalid dimension for the bounds"));
+    }
+    if h <= 0.0 || (0..D).any(|d| hi[d] <= lo[d]) {
+        return Err(PyValueError::new_err("Invalid bounds or size"));
+    }
+
+    // Keep the elements inside the domain
+    let (verts, elems) = box_mesh::<D>(lo, hi, h);
+    let vals = eval_sdf(py, sdf, &verts)?;
+    let elems: Vec<_> = elems
+        .into_iter()
+        .filter(|e| e.iter().all(|&i| vals[i as usize] < 0.0))
+        .collect();
+    if elems.is_empty() {
+        return Err(PyValueError::new_err("No element inside the domain"));
+    }
+
+    // Renumber the vertices
+    let mut new_idx = vec![Idx::MAX; verts.len()];
+    let mut new_verts = Vec::new();
+    let mut elems = elems;
+    for e in &mut elems {
+        for i in e.iter_mut() {
+            if new_idx[*i as usize] == Idx::MAX {
+                new_idx[*i as usize] = new_verts.len() as Idx;
+                new_verts.push(verts[*i as usize]);
+            }
+            *i = new_idx[*i as usize];
+        }
+    }
+    let mut verts = new_verts;
+
+    // Project the boundary vertices onto the zero level set
+    let bdy = boundary_vertices(&elems);
+    let mut v2e = vec![Vec::new(); verts.len()];
+    for (i_elem, e) in elems.iter().enumerate() {
+        for &i in e {
+            v2e[i as usize].push(i_elem);
+        }
+    }
+    let is_valid = |verts: &[Point<D>], i_elem: usize| {
+        let pts: Vec<_> = elems[i_elem].iter().map(|&i| verts[i as usize]).collect();
+        signed_vol(&pts) > 0.0
+    };
+
+    let eps = 1e-6 * h;
+    for _ in 0..N_PROJECTION_ITER {
+        let mut pts = Vec::with_capacity(bdy.len() * (D + 1));
+        for &i in &bdy {
+            let p = verts[i as usize];
+            pts.push(p);
+            for d in 0..D {
+                let mut q = p;
+                q[d] += eps;
+                pts.push(q);
+            }
+        }
+        let vals = eval_sdf(py, sdf, &pts)?;
+
+        let old = verts.clone();
+        for (&i, vals) in bdy.iter().zip(vals.chunks(D + 1)) {
+            let mut grad = Point::<D>::zeros();
+            for d in 0..D {
+                grad[d] = (vals[d + 1] - vals[0]) / eps;
+            }
+            let g2 = grad.norm_squared();
+            if g2 > f64::EPSILON {
+                verts[i as usize] -= grad * (vals[0] / g2);
+            }
+        }
+
+        for _ in 0..N_BACKTRACK_ITER {
+            let mut modified = false;
+            for &i in &bdy {
+                if v2e[i as usize].iter().any(|&k| !is_valid(&verts, k)) {
+                    verts[i as usize] = 0.5 * (old[i as usize] + verts[i as usize]);
+                    modified = true;
+                }
+            }
+            if !modified {
+                break;
+            }
+        }
+        for &i in &bdy {
+            if v2e[i as usize].iter().any(|&k| !is_valid(&verts, k)) {
+                verts[i as usize] = old[i as usize];
+            }
+        }
+    }
+
+    let n_elems = elems.len();
+    let elems = elems.iter().map(|e| E::from_slice(e)).collect();
+    let mut mesh = SimplexMesh::new(verts, elems, vec![1; n_elems], Vec::new(), Vec::new());
+    mesh.add_boundary_faces();
+    mesh.mut_ftags().for_each(|t| *t = 1);
+
+    Ok(mesh)
+}
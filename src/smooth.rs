@@ -0,0 +1,250 @@
+use pyo3::{exceptions::PyRuntimeError, PyResult};
+use std::collections::HashSet;
+use tucanos::{
+    geometry::{Geometry, LinearGeometry},
+    mesh::{Point, SimplexMesh},
+    topo_elems::{Tetrahedron, Triangle},
+    Idx, Tag,
+};
+
+/// Number of times the displacement of a vertex is halved if it inverts an element
+const N_BACKTRACK_ITER: usize = 10;
+
+/// Signed volume of a tetrahedron
+pub fn tet_vol(verts: &[Point<3>], e: &[Idx; 4]) -> f64 {
+    let p0 = verts[e[0] as usize];
+    let e0 = verts[e[1] as usize] - p0;
+    let e1 = verts[e[2] as usize] - p0;
+    let e2 = verts[e[3] as usize] - p0;
+    e0.cross(&e1).dot(&e2) / 6.0
+}
+
+/// Quality of a triangle, between 0 (degenerate) and 1 (equilateral)
+fn tri_quality(verts: &[Point<3>], f: &[Idx; 3]) -> f64 {
+    let p0 = verts[f[0] as usize];
+    let p1 = verts[f[1] as usize];
+    let p2 = verts[f[2] as usize];
+    let area = 0.5 * (p1 - p0).cross(&(p2 - p0)).norm();
+    let l2 = (p1 - p0).norm_squared() + (p2 - p1).norm_squared() + (p0 - p2).norm_squared();
+    4.0 * 3.0_f64.sqrt() * area / l2
+}
+
+/// Min and mean quality of triangles
+fn quality_stats(verts: &[Point<3>], faces: &[[Idx; 3]]) -> (f64, f64) {
+    let q: Vec<_> = faces.iter().map(|f| tri_quality(verts, f)).collect();
+    let q_min = q.iter().copied().fold(f64::INFINITY, f64::min);
+    let q_mean = q.iter().sum::<f64>() / q.len().max(1) as f64;
+    (q_min, q_mean)
+}
+
+/// Apply `n_iter` Laplacian smoothing passes to the vertices that lie on the boundary faces with
+/// a tag in `tags` (not on their edges or corners), projecting them onto the geometry.
+/// A displacement is halved until none of the adjacent tetrahedra is inverted, and cancelled
+/// if it can't be done in `N_BACKTRACK_ITER` halvings.
+/// Return the min and mean quality of these faces before and after smoothing
+pub fn smooth_boundary(
+    mesh: &mut SimplexMesh<3, Tetrahedron>,
+    tags: &[Tag],
+    geom: &LinearGeometry<3, Triangle>,
+    n_iter: u32,
+) -> PyResult<((f64, f64), (f64, f64))> {
+    let vtags = mesh
+        .get_vertex_tags()
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+        .to_vec();
+    let mut verts: Vec<Point<3>> = mesh.verts().collect();
+    let elems: Vec<[Idx; 4]> = mesh
+        .elems()
+        .map(|e| {
+            let e: Vec<_> = e.into_iter().collect();
+            [e[0], e[1], e[2], e[3]]
+        })
+        .collect();
+    let faces: Vec<[Idx; 3]> = mesh
+        .faces()
+        .zip(mesh.ftags())
+        .filter(|(_, tag)| tags.contains(tag))
+        .map(|(f, _)| {
+            let f: Vec<_> = f.into_iter().collect();
+            [f[0], f[1], f[2]]
+        })
+        .collect();
+
+    let mut neighbors = vec![HashSet::new(); verts.len()];
+    for f in &faces {
+        for j in 0..3 {
+            neighbors[f[j] as usize].insert(f[(j + 1) % 3]);
+            neighbors[f[(j + 1) % 3] as usize].insert(f[j]);
+        }
+    }
+    let mut v2e = vec![Vec::new(); verts.len()];
+    for (i_elem, e) in elems.iter().enumerate() {
+        for &i in e {
+            v2e[i as usize].push(i_elem);
+        }
+    }
+    let movable: Vec<usize> = (0..verts.len())
+        .filter(|&i| usize::try_from(vtags[i].0) == Ok(2) && tags.contains(&vtags[i].1))
+        .filter(|&i| !neighbors[i].is_empty())
+        .collect();
+
+    let before = quality_stats(&verts, &faces);
+
+    for _ in 0..n_iter {
+        for &i in &movable {
+            let old = verts[i];
+            let mut target = Point::<3>::zeros();
+            for &j in &neighbors[i] {
+                target += verts[j as usize];
+            }
+            target /= neighbors[i].len() as f64;
+
+            let mut step = 1.0;
+            for _ in 0..N_BACKTRACK_ITER {
+                let mut p = old + step * (target - old);
+                geom.project(&mut p, &vtags[i]);
+                verts[i] = p;
+                if v2e[i].iter().all(|&k| tet_vol(&verts, &elems[k]) > 0.0) {
+                    break;
+                }
+                verts[i] = old;
+                step *= 0.5;
+            }
+        }
+    }
+
+    let after = quality_stats(&verts, &faces);
+
+    for (p, q) in mesh.mut_verts().zip(verts) {
+        *p = q;
+    }
+    if mesh.get_vol().is_ok() {
+        mesh.compute_volumes();
+    }
+
+    Ok((before, after))
+}
+
+/// Min signed volume of the tetrahedra `elems` adjacent to vertex `i`, if it is moved to `p`
+fn min_vol_at(
+    verts: &mut [Point<3>],
+    elems: &[[Idx; 4]],
+    adjacent: &[usize],
+    i: usize,
+    p: Point<3>,
+) -> f64 {
+    let old = verts[i];
+    verts[i] = p;
+    let res = adjacent
+        .iter()
+        .map(|&k| tet_vol(verts, &elems[k]))
+        .fold(f64::INFINITY, f64::min);
+    verts[i] = old;
+    res
+}
+
+/// Untangle a mesh without changing its connectivity: the vertices of the inverted elements
+/// (with a non positive signed volume) are moved, one at a time, to increase the min signed
+/// volume of their adjacent elements, trying the center of their neighbors and a step along the
+/// gradient of the min volume, halved up to `N_BACKTRACK_ITER` times. `max_iter` passes are made
+/// at most. The vertices on the faces with a tag in `frozen_ftags` are not moved.
+/// Return the number of inverted elements before and after untangling
+pub fn untangle(
+    mesh: &mut SimplexMesh<3, Tetrahedron>,
+    max_iter: u32,
+    frozen_ftags: &[Tag],
+) -> (Idx, Idx) {
+    let mut verts: Vec<Point<3>> = mesh.verts().collect();
+    let elems: Vec<[Idx; 4]> = mesh
+        .elems()
+        .map(|e| {
+            let e: Vec<_> = e.into_iter().collect();
+            [e[0], e[1], e[2], e[3]]
+        })
+        .collect();
+
+    let mut frozen = vec![false; verts.len()];
+    for (f, tag) in mesh.faces().zip(mesh.ftags()) {
+        if frozen_ftags.contains(&tag) {
+            f.into_iter().for_each(|i| frozen[i as usize] = true);
+        }
+    }
+    let mut v2e = vec![Vec::new(); verts.len()];
+    for (i_elem, e) in elems.iter().enumerate() {
+        for &i in e {
+            v2e[i as usize].push(i_elem);
+        }
+    }
+    let n_inverted = |verts: &[Point<3>]| -> Idx {
+        elems.iter().filter(|e| tet_vol(verts, e) <= 0.0).count() as Idx
+    };
+
+    let before = n_inverted(&verts);
+    for _ in 0..max_iter {
+        let mut free: Vec<usize> = elems
+            .iter()
+            .filter(|e| tet_vol(&verts, e) <= 0.0)
+            .flat_map(|e| e.iter().map(|&i| i as usize))
+            .filter(|&i| !frozen[i])
+            .collect();
+        free.sort_unstable();
+        free.dedup();
+        if free.is_empty() {
+            break;
+        }
+
+        for i in free {
+            let adjacent = &v2e[i];
+            let old = verts[i];
+            let mut best = (min_vol_at(&mut verts, &elems, adjacent, i, old), old);
+
+            let mut center = Point::<3>::zeros();
+            let mut n = 0;
+            let mut l = 0.0_f64;
+            for &k in adjacent {
+                for &j in elems[k].iter().filter(|&&j| j as usize != i) {
+                    center += verts[j as usize];
+                    l = l.max((verts[j as usize] - old).norm());
+                    n += 1;
+                }
+            }
+            center /= f64::from(n);
+            let f = min_vol_at(&mut verts, &elems, adjacent, i, center);
+            if f > best.0 {
+                best = (f, center);
+            }
+
+            let h = 1e-6 * l;
+            let mut grad = Point::<3>::zeros();
+            for d in 0..3 {
+                let mut p = old;
+                p[d] += h;
+                grad[d] = (min_vol_at(&mut verts, &elems, adjacent, i, p) - best.0) / h;
+            }
+            if grad.norm() > 0.0 {
+                let dir = grad.normalize();
+                let mut step = l;
+                for _ in 0..N_BACKTRACK_ITER {
+                    let p = old + step * dir;
+                    let f = min_vol_at(&mut verts, &elems, adjacent, i, p);
+                    if f > best.0 {
+                        best = (f, p);
+                        break;
+                    }
+                    step *= 0.5;
+                }
+            }
+            verts[i] = best.1;
+        }
+    }
+    let after = n_inverted(&verts);
+
+    for (p, q) in mesh.mut_verts().zip(verts) {
+        *p = q;
+    }
+    if mesh.get_vol().is_ok() {
+        mesh.compute_volumes();
+    }
+
+    (before, after)
+}
@@ -0,0 +1,473 @@
+use crate::field::sym_index;
+use log::warn;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write},
+};
+use tucanos::{mesh::SimplexMesh, topo_elems::Elem, Tag};
+
+/// Keyword codes of the .solb (libMeshb) format
+const KWD_DIMENSION: i32 = 3;
+const KWD_VERTICES: i32 = 4;
+const KWD_END: i32 = 54;
+const KWD_SOL_AT_VERTICES: i32 = 62;
+const KWD_ITERATIONS: i32 = 77;
+const KWD_TIME: i32 = 78;
+
+/// Solution types of the .solb format
+const SOL_SCALAR: i32 = 1;
+const SOL_VECTOR: i32 = 2;
+const SOL_SYM_MAT: i32 = 3;
+
+/// Keyword of the solutions defined at elements of type `E`
+fn kwd_sol_at_elems<E: Elem>() -> i32 {
+    kwd_sol_at_elems_n(E::N_VERTS as usize)
+}
+
+/// Keyword of the solutions defined at elements with `n_verts` vertices
+const fn kwd_sol_at_elems_n(n_verts: usize) -> i32 {
+    match n_verts {
+        2 => 63,
+        3 => 64,
+        4 => 66,
+        _ => unreachable!(),
+    }
+}
+
+/// Name of a solution keyword, for the error messages
+const fn kwd_sol_name(kwd: i32) -> &'static str {
+    match kwd {
+        KWD_SOL_AT_VERTICES => "SolAtVertices",
+        63 => "SolAtEdges",
+        64 => "SolAtTriangles",
+        66 => "SolAtTetrahedra",
+        _ => "unknown solution keyword",
+    }
+}
+
+/// Types of the fields stored in a solution with `m` components in dimension `dim`. The number
+/// of components of symmetric tensors is only ambiguous in 1D, where scalars are used
+const fn sol_types(dim: usize, m: usize) -> (i32, usize) {
+    if m == 1 {
+        (SOL_SCALAR, 1)
+    } else if m == dim {
+        (SOL_VECTOR, 1)
+    } else if m == dim * (dim + 1) / 2 {
+        (SOL_SYM_MAT, 1)
+    } else {
+        (SOL_SCALAR, m)
+    }
+}
+
+/// Permutation from the order of the components of the symmetric tensors in the .solb files
+/// (lower triangle, row by row) to the order used by tucanos (`SYMTENSOR_2D` / `SYMTENSOR_3D`)
+fn sym_mat_order(dim: usize) -> Vec<usize> {
+    let mut res = Vec::with_capacity(dim * (dim + 1) / 2);
+    for i in 0..dim {
+        for j in 0..=i {
+            res.push(sym_index(dim, i, j));
+        }
+    }
+    res
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn read_i32(r: &mut impl Read) -> Result<i32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+/// Read a count, checking that it is not negative
+fn read_count(r: &mut impl Read) -> Result<u64> {
+    u64::try_from(read_i32(r)?).map_err(|_| invalid_data("negative count"))
+}
+
+/// Check that `n` items of `size` bytes fit in what is left of a file of length `len`
+fn check_count(r: &mut impl Seek, len: u64, n: u64, size: usize) -> Result<usize> {
+    let left = len.saturating_sub(r.stream_position()?);
+    if !n.checked_mul(size as u64).is_some_and(|x| x <= left) {
+        return Err(invalid_data("truncated .solb file"));
+    }
+    Ok(n as usize)
+}
+
+/// Header of a .solb file
+struct Header {
+    /// Size of the reals (4 or 8 bytes)
+    real_size: usize,
+    /// Size of the keyword positions (4 or 8 bytes)
+    pos_size: usize,
+}
+
+impl Header {
+    fn read(r: &mut impl Read) -> Result<Self> {
+        if read_i32(r)? != 1 {
+            return Err(invalid_data("not a little endian .solb file"));
+        }
+        match read_i32(r)? {
+            1 => Ok(Self {
+                real_size: 4,
+                pos_size: 4,
+            }),
+            2 => Ok(Self {
+                real_size: 8,
+                pos_size: 4,
+            }),
+            3 => Ok(Self {
+                real_size: 8,
+                pos_size: 8,
+            }),
+            v => Err(invalid_data(&format!("unsupported .solb version {v}"))),
+        }
+    }
+
+    fn read_pos(&self, r: &mut impl Read) -> Result<u64> {
+        if self.pos_size == 4 {
+            Ok(u64::from(read_i32(r)? as u32))
+        } else {
+            let mut buf = [0; 8];
+            r.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+    }
+
+    fn write_pos(&self, w: &mut impl Write, pos: u64) -> Result<()> {
+        if self.pos_size == 4 {
+            let pos = u32::try_from(pos).map_err(|_| invalid_data("file too large"))?;
+            w.write_all(&pos.to_le_bytes())
+        } else {
+            w.write_all(&pos.to_le_bytes())
+        }
+    }
+
+    fn write_real(&self, w: &mut impl Write, x: f64) -> Result<()> {
+        if self.real_size == 4 {
+            w.write_all(&(x as f32).to_le_bytes())
+        } else {
+            w.write_all(&x.to_le_bytes())
+        }
+    }
+
+    fn read_real(&self, r: &mut impl Read) -> Result<f64> {
+        if self.real_size == 4 {
+            let mut buf = [0; 4];
+            r.read_exact(&mut buf)?;
+            Ok(f64::from(f32::from_le_bytes(buf)))
+        } else {
+            let mut buf = [0; 8];
+            r.read_exact(&mut buf)?;
+            Ok(f64::from_le_bytes(buf))
+        }
+    }
+}
+
+/// Write a solution of shape (n, m) defined at the elements of type `E` in dimension `dim` to a
+/// .solb file (version 2: 64-bit reals and 32-bit positions)
+pub fn write_elem_solb<E: Elem>(fname: &str, dim: usize, sol: &[f64], m: usize) -> Result<()> {
+    let n = sol.len() / m.max(1);
+    let (sol_type, n_types) = sol_types(dim, m);
+    let order = if sol_type == SOL_SYM_MAT {
+        sym_mat_order(dim)
+    } else {
+        (0..m).collect()
+    };
+
+    let sol_pos = 20;
+    let end_pos = sol_pos + 16 + 4 * n_types + 8 * n * m;
+    let end_pos = i32::try_from(end_pos).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "solution too large for a .solb file",
+        )
+    })?;
+
+    let mut w = BufWriter::new(File::create(fname)?);
+    for x in [1, 2, KWD_DIMENSION, sol_pos as i32, dim as i32] {
+        w.write_all(&x.to_le_bytes())?;
+    }
+    for x in [kwd_sol_at_elems::<E>(), end_pos, n as i32, n_types as i32] {
+        w.write_all(&x.to_le_bytes())?;
+    }
+    for _ in 0..n_types {
+        w.write_all(&sol_type.to_le_bytes())?;
+    }
+    for row in sol.chunks(m) {
+        for &k in &order {
+            w.write_all(&row[k].to_le_bytes())?;
+        }
+    }
+    for x in [KWD_END, 0] {
+        w.write_all(&x.to_le_bytes())?;
+    }
+
+    w.flush()
+}
+
+/// Keyword of the elements with `n_verts` vertices in .meshb files, if any
+const fn kwd_elems(n_verts: usize) -> Option<i32> {
+    match n_verts {
+        2 => Some(5),
+        3 => Some(6),
+        4 => Some(8),
+        _ => None,
+    }
+}
+
+/// Write a keyword of elements (1-based vertex indices followed by the tag) to a .meshb file,
+/// `pos` being the position of the keyword
+fn write_meshb_elems<W: Write, F: Elem>(
+    w: &mut W,
+    header: &Header,
+    pos: &mut u64,
+    kwd: i32,
+    conn: &[(F, Tag)],
+) -> Result<()> {
+    *pos += 12 + conn.len() as u64 * 4 * (u64::from(F::N_VERTS) + 1);
+    w.write_all(&kwd.to_le_bytes())?;
+    header.write_pos(w, *pos)?;
+    w.write_all(&(conn.len() as i32).to_le_bytes())?;
+    for (e, tag) in conn {
+        for i in *e {
+            w.write_all(&(i as i32 + 1).to_le_bytes())?;
+        }
+        w.write_all(&i32::from(*tag).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write a mesh to a .meshb file with 32-bit reals (version 1), the coordinates being rounded to
+/// the nearest f32. Faces with a single vertex (for meshes of edges) are not written
+pub fn write_meshb_f32<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    fname: &str,
+) -> Result<()> {
+    let header = Header {
+        real_size: 4,
+        pos_size: 4,
+    };
+    let mut w = BufWriter::new(File::create(fname)?);
+    for x in [1, 1, KWD_DIMENSION, 20, D as i32] {
+        w.write_all(&x.to_le_bytes())?;
+    }
+    let mut pos = 20_u64;
+
+    let n = u64::from(mesh.n_verts());
+    pos += 12 + n * (4 * D as u64 + 4);
+    w.write_all(&KWD_VERTICES.to_le_bytes())?;
+    header.write_pos(&mut w, pos)?;
+    w.write_all(&(mesh.n_verts() as i32).to_le_bytes())?;
+    for p in mesh.verts() {
+        for &x in p.iter() {
+            header.write_real(&mut w, x)?;
+        }
+        w.write_all(&0_i32.to_le_bytes())?;
+    }
+
+    if let Some(kwd) = kwd_elems(E::N_VERTS as usize) {
+        let elems: Vec<_> = mesh.elems().zip(mesh.etags()).collect();
+        write_meshb_elems(&mut w, &header, &mut pos, kwd, &elems)?;
+    }
+    if let Some(kwd) = kwd_elems(E::Face::N_VERTS as usize) {
+        let faces: Vec<_> = mesh.faces().zip(mesh.ftags()).collect();
+        write_meshb_elems(&mut w, &header, &mut pos, kwd, &faces)?;
+    } else if mesh.n_faces() > 0 {
+        warn!(
+            "{} faces with a single vertex are not written",
+            mesh.n_faces()
+        );
+    }
+
+    w.write_all(&KWD_END.to_le_bytes())?;
+    header.write_pos(&mut w, 0)?;
+    w.flush()
+}
+
+/// Check if a .solb file contains a solution defined at the vertices (`Some(false)`) or at the
+/// elements of type `E` (`Some(true)`), or none of them (`None`)
+pub fn solb_at_elems<E: Elem>(fname: &str) -> Result<Option<bool>> {
+    let mut r = BufReader::new(File::open(fname)?);
+    let header = Header::read(&mut r)?;
+    loop {
+        let kwd = read_i32(&mut r)?;
+        if kwd == KWD_SOL_AT_VERTICES {
+            return Ok(Some(false));
+        }
+        if kwd == kwd_sol_at_elems::<E>() {
+            return Ok(Some(true));
+        }
+        let next = header.read_pos(&mut r)?;
+        if kwd == KWD_END || next == 0 {
+            return Ok(None);
+        }
+        r.seek(SeekFrom::Start(next))?;
+    }
+}
+
+/// Dimension and location of the first solution stored in a .solb file
+pub struct SolbLocation {
+    pub dim: usize,
+    /// Keyword of the elements the solution is defined at, or None if it is defined at the
+    /// vertices
+    pub elem_kwd: Option<i32>,
+}
+
+/// Find the dimension and location of the first solution stored in a .solb file, defined either
+/// at the vertices or at the elements of any type
+pub fn solb_location(fname: &str) -> Result<SolbLocation> {
+    let mut r = BufReader::new(File::open(fname)?);
+    let header = Header::read(&mut r)?;
+    let mut dim = 0;
+    loop {
+        let kwd = read_i32(&mut r)?;
+        let next = header.read_pos(&mut r)?;
+        if kwd == KWD_DIMENSION {
+            dim = read_i32(&mut r)? as usize;
+        } else if kwd == KWD_SOL_AT_VERTICES || [2, 3, 4].map(kwd_sol_at_elems_n).contains(&kwd) {
+            if dim == 0 {
+                return Err(invalid_data("missing dimension"));
+            }
+            let elem_kwd = (kwd != KWD_SOL_AT_VERTICES).then_some(kwd);
+            return Ok(SolbLocation { dim, elem_kwd });
+        } else if kwd == KWD_END || next == 0 {
+            return Err(invalid_data("no solution found"));
+        }
+        r.seek(SeekFrom::Start(next))?;
+    }
+}
+
+/// Read a solution defined at the elements of type `E` from a .solb file, and return the values
+/// and the number of components
+pub fn read_elem_solb<E: Elem>(fname: &str) -> Result<(Vec<f64>, usize)> {
+    read_solb_at(fname, kwd_sol_at_elems::<E>())
+}
+
+/// Read the solution stored under keyword `sol_kwd` from a .solb file, and return the values
+/// and the number of components
+pub fn read_solb_at(fname: &str, sol_kwd: i32) -> Result<(Vec<f64>, usize)> {
+    let file = File::open(fname)?;
+    let len = file.metadata()?.len();
+    let mut r = BufReader::new(file);
+    let header = Header::read(&mut r)?;
+    let mut dim = 0;
+    loop {
+        let kwd = read_i32(&mut r)?;
+        let next = header.read_pos(&mut r)?;
+        if kwd == KWD_DIMENSION {
+            dim = read_i32(&mut r)? as usize;
+        } else if kwd == sol_kwd {
+            break;
+        } else if kwd == KWD_END || next == 0 {
+            return Err(invalid_data(&format!(
+                "no {} keyword found",
+                kwd_sol_name(sol_kwd)
+            )));
+        }
+        r.seek(SeekFrom::Start(next))?;
+    }
+
+    if dim == 0 {
+        return Err(invalid_data("missing dimension"));
+    }
+    let n = read_count(&mut r)?;
+    let n_types = read_count(&mut r)?;
+    let n_types = check_count(&mut r, len, n_types, 4)?;
+    let mut types = Vec::with_capacity(n_types);
+    for _ in 0..n_types {
+        types.push(read_i32(&mut r)?);
+    }
+    let (m, order) = match types[..] {
+        [] => return Err(invalid_data("empty solution")),
+        [SOL_VECTOR] => (dim, (0..dim).collect()),
+        [SOL_SYM_MAT] => (dim * (dim + 1) / 2, sym_mat_order(dim)),
+        _ if types.iter().all(|&t| t == SOL_SCALAR) => (types.len(), (0..types.len()).collect()),
+        _ => return Err(invalid_data("unsupported solution type")),
+    };
+
+    let n = check_count(&mut r, len, n, m * header.real_size)?;
+    let mut sol = vec![0.0; n * m];
+    for row in sol.chunks_mut(m) {
+        for &k in &order {
+            row[k] = header.read_real(&mut r)?;
+        }
+    }
+
+    Ok((sol, m))
+}
+
+/// Iteration number and time stored in a .solb file (`Iterations` and `Time` keywords)
+#[derive(Default)]
+pub struct SolbMetadata {
+    pub iteration: Option<i32>,
+    pub time: Option<f64>,
+}
+
+/// Read the iteration number and time stored in a .solb file, if any
+pub fn read_solb_metadata(fname: &str) -> Result<SolbMetadata> {
+    let mut r = BufReader::new(File::open(fname)?);
+    let header = Header::read(&mut r)?;
+    let mut res = SolbMetadata::default();
+    loop {
+        let kwd = read_i32(&mut r)?;
+        if kwd == KWD_END {
+            return Ok(res);
+        }
+        let next = header.read_pos(&mut r)?;
+        if kwd == KWD_ITERATIONS {
+            res.iteration = Some(read_i32(&mut r)?);
+        } else if kwd == KWD_TIME {
+            res.time = Some(header.read_real(&mut r)?);
+        }
+        if next == 0 {
+            return Ok(res);
+        }
+        r.seek(SeekFrom::Start(next))?;
+    }
+}
+
+/// Add the iteration number and / or time to a .solb file that does not contain them yet. The
+/// keywords are written in place of the `End` keyword, which is moved after them
+pub fn write_solb_metadata(fname: &str, metadata: &SolbMetadata) -> Result<()> {
+    let mut f = OpenOptions::new().read(true).write(true).open(fname)?;
+    let header = Header::read(&mut f)?;
+    let end = loop {
+        let pos = f.stream_position()?;
+        let kwd = read_i32(&mut f)?;
+        if kwd == KWD_END {
+            break pos;
+        }
+        if kwd == KWD_ITERATIONS || kwd == KWD_TIME {
+            return Err(invalid_data(
+                "the file already contains an iteration or time",
+            ));
+        }
+        let next = header.read_pos(&mut f)?;
+        if next == 0 {
+            return Err(invalid_data("missing End keyword"));
+        }
+        f.seek(SeekFrom::Start(next))?;
+    };
+
+    f.seek(SeekFrom::Start(end))?;
+    f.set_len(end)?;
+    let mut w = BufWriter::new(f);
+    let mut pos = end;
+    if let Some(iteration) = metadata.iteration {
+        pos += (4 + header.pos_size + 4) as u64;
+        w.write_all(&KWD_ITERATIONS.to_le_bytes())?;
+        header.write_pos(&mut w, pos)?;
+        w.write_all(&iteration.to_le_bytes())?;
+    }
+    if let Some(time) = metadata.time {
+        pos += (4 + header.pos_size + header.real_size) as u64;
+        w.write_all(&KWD_TIME.to_le_bytes())?;
+        header.write_pos(&mut w, pos)?;
+        header.write_real(&mut w, time)?;
+    }
+    w.write_all(&KWD_END.to_le_bytes())?;
+    header.write_pos(&mut w, 0)?;
+    w.flush()
+}
@@ -0,0 +1,235 @@
+use log::warn;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::{Elem, Triangle},
+    Idx, Tag,
+};
+
+/// Size of the header of binary .stl files
+const BINARY_HEADER_SIZE: usize = 80;
+/// Size of a triangle record of binary .stl files, without the attribute bytes
+const BINARY_RECORD_SIZE: usize = 50;
+
+fn invalid_data(offset: usize, msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid .stl file at offset {offset}: {msg}"),
+    )
+}
+
+/// Triangles of a .stl file, before merging the vertices
+struct Soup {
+    /// Vertices of the triangles, 3 per triangle
+    verts: Vec<Point<3>>,
+    /// Tag of each triangle (the index of its solid, starting at 1)
+    tags: Vec<Tag>,
+    /// Name of each solid
+    names: Vec<String>,
+}
+
+/// Read a binary .stl file. The records are 50 bytes long if the file size matches; otherwise
+/// the attribute byte count of each record is taken as the number of extra bytes that follow it
+fn read_binary(data: &[u8]) -> Result<Soup> {
+    if data.len() < BINARY_HEADER_SIZE + 4 {
+        return Err(invalid_data(data.len(), "truncated header"));
+    }
+    let n = u32::from_le_bytes(data[80..84].try_into().unwrap()) as usize;
+    let fixed = data.len() == BINARY_HEADER_SIZE + 4 + BINARY_RECORD_SIZE * n;
+
+    let f32_at = |offset: usize| {
+        f64::from(f32::from_le_bytes(
+            data[offset..offset + 4].try_into().unwrap(),
+        ))
+    };
+    let mut verts = Vec::with_capacity(3 * n);
+    let mut offset = BINARY_HEADER_SIZE + 4;
+    for i in 0..n {
+        if offset + BINARY_RECORD_SIZE > data.len() {
+            return Err(invalid_data(
+                offset,
+                &format!("truncated record for triangle {i} / {n}"),
+            ));
+        }
+        // the normal (first 12 bytes) is not used
+        for j in 0..3 {
+            let o = offset + 12 * (j + 1);
+            verts.push(Point::<3>::new(f32_at(o), f32_at(o + 4), f32_at(o + 8)));
+        }
+        let n_attr = u16::from_le_bytes(data[offset + 48..offset + 50].try_into().unwrap());
+        offset += BINARY_RECORD_SIZE;
+        if !fixed {
+            offset += n_attr as usize;
+        }
+    }
+    if offset != data.len() {
+        return Err(invalid_data(
+            offset,
+            &format!("{} bytes after the last triangle", data.len() - offset),
+        ));
+    }
+
+    Ok(Soup {
+        verts,
+        tags: vec![1; n],
+        names: vec![String::from_utf8_lossy(&data[..BINARY_HEADER_SIZE])
+            .trim_end_matches('\0')
+            .trim()
+            .to_string()],
+    })
+}
+
+/// Read an ASCII .stl file, with one tag per `solid` section
+fn read_ascii(data: &str) -> Result<Soup> {
+    let mut res = Soup {
+        verts: Vec::new(),
+        tags: Vec::new(),
+        names: Vec::new(),
+    };
+    let mut in_solid = false;
+    let mut n_loop_verts = None;
+    let mut offset = 0;
+    for line in data.split_inclusive('\n') {
+        let line_offset = offset;
+        offset += line.len();
+        let mut words = line.split_whitespace();
+        let Some(kwd) = words.next() else {
+            continue;
+        };
+        match (kwd, in_solid) {
+            ("solid", false) => {
+                in_solid = true;
+                res.names.push(words.collect::<Vec<_>>().join(" "));
+            }
+            ("endsolid", true) => in_solid = false,
+            ("facet" | "endfacet", true) => {}
+            ("outer", true) => n_loop_verts = Some(0),
+            ("vertex", true) => {
+                let Some(n) = n_loop_verts.as_mut() else {
+                    return Err(invalid_data(line_offset, "vertex outside of a loop"));
+                };
+                let coords: Vec<f64> = words
+                    .map(str::parse)
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(|_| invalid_data(line_offset, "invalid vertex coordinates"))?;
+                if coords.len() != 3 {
+                    return Err(invalid_data(
+                        line_offset,
+                        "a vertex must have 3 coordinates",
+                    ));
+                }
+                res.verts.push(Point::<3>::from_column_slice(&coords));
+                *n += 1;
+            }
+            ("endloop", true) => {
+                if n_loop_verts != Some(3) {
+                    return Err(invalid_data(line_offset, "a loop must have 3 vertices"));
+                }
+                n_loop_verts = None;
+                res.tags.push(res.names.len() as Tag);
+            }
+            (kwd, _) => {
+                return Err(invalid_data(
+                    line_offset,
+                    &format!("unexpected keyword {kwd}"),
+                ));
+            }
+        }
+    }
+    if in_solid {
+        return Err(invalid_data(offset, "missing endsolid"));
+    }
+    if res.names.len() > Tag::MAX as usize {
+        return Err(invalid_data(offset, "too many solids"));
+    }
+
+    Ok(res)
+}
+
+/// Merge the vertices closer than `tol` (or with the same coordinates if `tol` is None) using a
+/// grid with cells of size `tol`, and build the triangle connectivity. The triangles that become
+/// degenerate are removed
+fn merge_verts(soup: Soup, tol: Option<f64>) -> (SimplexMesh<3, Triangle>, Vec<String>) {
+    let mut verts = Vec::new();
+    let mut ids: Vec<Idx> = Vec::with_capacity(soup.verts.len());
+    if let Some(tol) = tol {
+        let cell =
+            |p: &Point<3>| -> [i64; 3] { std::array::from_fn(|d| (p[d] / tol).floor() as i64) };
+        let mut grid: HashMap<[i64; 3], Vec<Idx>> = HashMap::new();
+        for p in &soup.verts {
+            let c = cell(p);
+            let mut found = None;
+            'search: for offset in 0..27 {
+                let c = [
+                    c[0] + offset % 3 - 1,
+                    c[1] + (offset / 3) % 3 - 1,
+                    c[2] + offset / 9 - 1,
+                ];
+                for &i in grid.get(&c).into_iter().flatten() {
+                    if (verts[i as usize] - p).norm() <= tol {
+                        found = Some(i);
+                        break 'search;
+                    }
+                }
+            }
+            let i = found.unwrap_or_else(|| {
+                verts.push(*p);
+                let i = verts.len() as Idx - 1;
+                grid.entry(c).or_default().push(i);
+                i
+            });
+            ids.push(i);
+        }
+    } else {
+        let mut map: HashMap<[u64; 3], Idx> = HashMap::new();
+        for p in &soup.verts {
+            let key = [p[0].to_bits(), p[1].to_bits(), p[2].to_bits()];
+            ids.push(*map.entry(key).or_insert_with(|| {
+                verts.push(*p);
+                verts.len() as Idx - 1
+            }));
+        }
+    }
+
+    let mut elems = Vec::with_capacity(soup.tags.len());
+    let mut etags = Vec::with_capacity(soup.tags.len());
+    let mut n_degenerate = 0;
+    for (e, &tag) in ids.chunks(3).zip(soup.tags.iter()) {
+        if e[0] == e[1] || e[1] == e[2] || e[2] == e[0] {
+            n_degenerate += 1;
+            continue;
+        }
+        elems.push(Triangle::from_slice(e));
+        etags.push(tag);
+    }
+    if n_degenerate > 0 {
+        warn!("{n_degenerate} degenerate triangles removed after merging the vertices");
+    }
+
+    (
+        SimplexMesh::new(verts, elems, etags, Vec::new(), Vec::new()),
+        soup.names,
+    )
+}
+
+/// Read a .stl file (ASCII or binary) as a surface mesh, merging the vertices closer than `tol`
+/// (or with the same coordinates if `tol` is None). In ASCII files, the triangles of the i-th
+/// `solid` section are tagged `i + 1`; in binary files, all the triangles are tagged 1.
+/// Return the mesh and the names of the solids (the header for binary files)
+pub fn read_stl(fname: &str, tol: Option<f64>) -> Result<(SimplexMesh<3, Triangle>, Vec<String>)> {
+    let data = fs::read(fname)?;
+    // binary files may also start with "solid", but are very unlikely to be valid UTF-8
+    let start = data
+        .iter()
+        .position(|c| !c.is_ascii_whitespace())
+        .unwrap_or(0);
+    let soup = match std::str::from_utf8(&data) {
+        Ok(text) if data[start..].starts_with(b"solid") => read_ascii(text)?,
+        _ => read_binary(&data)?,
+    };
+    Ok(merge_verts(soup, tol))
+}
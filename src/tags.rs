@@ -0,0 +1,232 @@
+use crate::{
+    mesh::{Mesh21, Mesh22, Mesh31, Mesh32, Mesh33},
+    to_numpy_1d,
+};
+use numpy::{Element, PyArray1, PyReadonlyArray1};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::{PyAnyMethods, PyDictMethods},
+    pyfunction,
+    types::{PyAny, PyDict},
+    Bound, FromPyObject, PyRef, PyResult, Python,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::Deref,
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::Elem,
+    Idx, Tag,
+};
+
+/// Smallest tag value
+pub const TAG_MIN: Tag = Tag::MIN;
+/// Largest tag value
+pub const TAG_MAX: Tag = Tag::MAX;
+
+/// Array of element or face tags, extracted from a 1d numpy array of any integer dtype (e.g. the
+/// 64 bit tags of legacy files). The values are checked to be within `TAG_MIN..=TAG_MAX`, so
+/// negative tags are valid as `Tag` is signed
+pub struct TagArray(Vec<Tag>);
+
+impl TagArray {
+    /// Try to extract the array with dtype `T`, None being returned if the dtype is different
+    fn try_extract<T: Element + Copy + Display + TryInto<Tag>>(
+        ob: &Bound<'_, PyAny>,
+    ) -> Option<PyResult<Self>> {
+        let arr = ob.extract::<PyReadonlyArray1<T>>().ok()?;
+        let arr = arr.as_array();
+        let mut res = Vec::with_capacity(arr.len());
+        for (i, &x) in arr.iter().enumerate() {
+            let Ok(tag) = x.try_into() else {
+                return Some(Err(PyValueError::new_err(format!(
+                    "Tag {x} at row {i} is out of range [{TAG_MIN}, {TAG_MAX}]"
+                ))));
+            };
+            res.push(tag);
+        }
+        Some(Ok(Self(res)))
+    }
+}
+
+impl<'py> FromPyObject<'py> for TagArray {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        Self::try_extract::<Tag>(ob)
+            .or_else(|| Self::try_extract::<i64>(ob))
+            .or_else(|| Self::try_extract::<i32>(ob))
+            .or_else(|| Self::try_extract::<i16>(ob))
+            .or_else(|| Self::try_extract::<i8>(ob))
+            .or_else(|| Self::try_extract::<u64>(ob))
+            .or_else(|| Self::try_extract::<u32>(ob))
+            .or_else(|| Self::try_extract::<u16>(ob))
+            .or_else(|| Self::try_extract::<u8>(ob))
+            .unwrap_or_else(|| {
+                Err(PyTypeError::new_err(
+                    "Tags must be given as a 1d numpy array of integers",
+                ))
+            })
+    }
+}
+
+impl Deref for TagArray {
+    type Target = [Tag];
+
+    fn deref(&self) -> &[Tag] {
+        &self.0
+    }
+}
+
+impl From<TagArray> for Vec<Tag> {
+    fn from(tags: TagArray) -> Self {
+        tags.0
+    }
+}
+
+/// Sorted unique values of `tags`, with the number of occurences of each value
+pub fn tag_values(tags: impl Iterator<Item = Tag>) -> BTreeMap<Tag, usize> {
+    let mut res = BTreeMap::new();
+    for tag in tags {
+        *res.entry(tag).or_insert(0) += 1;
+    }
+    res
+}
+
+/// Result of `compare_face_tags`
+struct TagDiff {
+    /// Indices of the faces of the first mesh whose tag differs
+    ids_a: Vec<Idx>,
+    /// Indices of the matching faces of the second mesh
+    ids_b: Vec<Idx>,
+    /// Number of faces for each pair of different tags, the first tag being None for the faces of
+    /// the second mesh that have no match in the first one
+    counts: BTreeMap<(Option<Tag>, Tag), usize>,
+}
+
+/// Match the faces of two meshes whose centers are closer than `tol` and report the pairs with
+/// different tags. The face centers of the first mesh are stored in a grid with cells of size
+/// `tol`, so that only the neighboring cells are searched
+fn compare_face_tags<const D: usize, E: Elem>(
+    a: &SimplexMesh<D, E>,
+    b: &SimplexMesh<D, E>,
+    tol: f64,
+) -> TagDiff {
+    let centers = |mesh: &SimplexMesh<D, E>| -> Vec<Point<D>> {
+        let verts: Vec<Point<D>> = mesh.verts().collect();
+        let n = f64::from(E::Face::N_VERTS);
+        mesh.faces()
+            .map(|f| {
+                f.into_iter()
+                    .fold(Point::<D>::zeros(), |acc, i| acc + verts[i as usize])
+                    / n
+            })
+            .collect()
+    };
+    let cell = |p: &Point<D>| -> [i64; D] { std::array::from_fn(|d| (p[d] / tol).floor() as i64) };
+
+    let centers_a = centers(a);
+    let mut grid: HashMap<[i64; D], Vec<Idx>> = HashMap::new();
+    for (i, p) in centers_a.iter().enumerate() {
+        grid.entry(cell(p)).or_default().push(i as Idx);
+    }
+    let tags_a: Vec<Tag> = a.ftags().collect();
+
+    let mut res = TagDiff {
+        ids_a: Vec::new(),
+        ids_b: Vec::new(),
+        counts: BTreeMap::new(),
+    };
+    for (i_b, (p, tag_b)) in centers(b).iter().zip(b.ftags()).enumerate() {
+        let c = cell(p);
+        let mut best: Option<(f64, Idx)> = None;
+        for offset in 0..3_i64.pow(D as u32) {
+            let mut o = offset;
+            let mut c = c;
+            for x in &mut c {
+                *x += o % 3 - 1;
+                o /= 3;
+            }
+            for &i in grid.get(&c).into_iter().flatten() {
+                let d = (centers_a[i as usize] - p).norm();
+                if d <= tol && !best.is_some_and(|(d_best, _)| d_best <= d) {
+                    best = Some((d, i));
+                }
+            }
+        }
+        match best {
+            Some((_, i_a)) => {
+                let tag_a = tags_a[i_a as usize];
+                if tag_a != tag_b {
+                    res.ids_a.push(i_a);
+                    res.ids_b.push(i_b as Idx);
+                    *res.counts.entry((Some(tag_a), tag_b)).or_insert(0) += 1;
+                }
+            }
+            None => *res.counts.entry((None, tag_b)).or_insert(0) += 1,
+        }
+    }
+    res
+}
+
+/// Compare the face tags of two meshes of the same type: the faces are matched if their centers
+/// are closer than `tol`, and those with different tags are reported.
+/// Return `(ids_a, ids_b, counts)` with the indices of the faces of `mesh_a` and `mesh_b` whose
+/// tags differ, and a dict mapping the pairs `(tag_a, tag_b)` to the number of such faces.
+/// The faces of `mesh_b` that have no match in `mesh_a` are counted with `tag_a = None`
+#[pyfunction]
+#[allow(clippy::type_complexity)]
+pub fn compare_tags<'py>(
+    py: Python<'py>,
+    mesh_a: &Bound<'py, PyAny>,
+    mesh_b: &Bound<'py, PyAny>,
+    tol: f64,
+) -> PyResult<(
+    Bound<'py, PyArray1<Idx>>,
+    Bound<'py, PyArray1<Idx>>,
+    Bound<'py, PyDict>,
+)> {
+    if tol <= 0.0 {
+        return Err(PyValueError::new_err("tol must be positive"));
+    }
+    let res = if let (Ok(a), Ok(b)) = (
+        mesh_a.extract::<PyRef<Mesh33>>(),
+        mesh_b.extract::<PyRef<Mesh33>>(),
+    ) {
+        compare_face_tags(&a.mesh, &b.mesh, tol)
+    } else if let (Ok(a), Ok(b)) = (
+        mesh_a.extract::<PyRef<Mesh32>>(),
+        mesh_b.extract::<PyRef<Mesh32>>(),
+    ) {
+        compare_face_tags(&a.mesh, &b.mesh, tol)
+    } else if let (Ok(a), Ok(b)) = (
+        mesh_a.extract::<PyRef<Mesh31>>(),
+        mesh_b.extract::<PyRef<Mesh31>>(),
+    ) {
+        compare_face_tags(&a.mesh, &b.mesh, tol)
+    } else if let (Ok(a), Ok(b)) = (
+        mesh_a.extract::<PyRef<Mesh22>>(),
+        mesh_b.extract::<PyRef<Mesh22>>(),
+    ) {
+        compare_face_tags(&a.mesh, &b.mesh, tol)
+    } else if let (Ok(a), Ok(b)) = (
+        mesh_a.extract::<PyRef<Mesh21>>(),
+        mesh_b.extract::<PyRef<Mesh21>>(),
+    ) {
+        compare_face_tags(&a.mesh, &b.mesh, tol)
+    } else {
+        return Err(PyValueError::new_err(
+            "mesh_a and mesh_b must be meshes of the same type",
+        ));
+    };
+
+    let counts = PyDict::new_bound(py);
+    for (k, v) in res.counts {
+        counts.set_item(k, v)?;
+    }
+    Ok((
+        to_numpy_1d(py, res.ids_a),
+        to_numpy_1d(py, res.ids_b),
+        counts,
+    ))
+}
@@ -0,0 +1,81 @@
+use pyo3::{exceptions::PyValueError, pyclass, pymethods, PyResult};
+use std::any::Any;
+use tucanos::spatialindex::{ObjectIndex, PointIndex};
+
+/// Check that a tree was built for a given mesh version, and get it with its actual type
+fn get_tree<T: 'static>(
+    index: &(dyn Any + Send + Sync),
+    version: u64,
+    expected: u64,
+) -> PyResult<&T> {
+    if version != expected {
+        return Err(PyValueError::new_err(format!(
+            "The tree was built for mesh version {version}, but the mesh version is {expected}"
+        )));
+    }
+    index
+        .downcast_ref::<T>()
+        .ok_or_else(|| PyValueError::new_err("Invalid tree dimension"))
+}
+
+/// Spatial index of the elements of a mesh, built by `build_elem_tree()` and used to locate
+/// points in the mesh (linear interpolation, tag transfer)
+#[pyclass]
+pub struct ElemTree {
+    index: Box<dyn Any + Send + Sync>,
+    version: u64,
+}
+
+impl ElemTree {
+    pub fn new<const D: usize>(index: ObjectIndex<D>, version: u64) -> Self {
+        Self {
+            index: Box::new(index),
+            version,
+        }
+    }
+
+    /// Get the index, checking that it was built for mesh version `version`
+    pub fn get<const D: usize>(&self, version: u64) -> PyResult<&ObjectIndex<D>> {
+        get_tree(self.index.as_ref(), self.version, version)
+    }
+}
+
+#[pymethods]
+impl ElemTree {
+    /// Get the version of the mesh the tree was built for
+    #[must_use]
+    pub const fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// Spatial index of the vertices of a mesh, built by `build_vert_tree()` and used to find the
+/// nearest vertices (nearest neighbor interpolation)
+#[pyclass]
+pub struct VertTree {
+    index: Box<dyn Any + Send + Sync>,
+    version: u64,
+}
+
+impl VertTree {
+    pub fn new<const D: usize>(index: PointIndex<D>, version: u64) -> Self {
+        Self {
+            index: Box::new(index),
+            version,
+        }
+    }
+
+    /// Get the index, checking that it was built for mesh version `version`
+    pub fn get<const D: usize>(&self, version: u64) -> PyResult<&PointIndex<D>> {
+        get_tree(self.index.as_ref(), self.version, version)
+    }
+}
+
+#[pymethods]
+impl VertTree {
+    /// Get the version of the mesh the tree was built for
+    #[must_use]
+    pub const fn version(&self) -> u64 {
+        self.version
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    mesh::{Mesh22, Mesh33},
+    to_numpy_1d, to_numpy_2d,
+};
+use numpy::{PyArray1, PyArray2, PyReadonlyArray2, PyUntypedArrayMethods};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    pyclass, pymethods, Bound, PyResult, Python,
+};
+use std::sync::{Arc, OnceLock};
+use tucanos::{
+    geom_elems::GElem,
+    mesh::SimplexMesh,
+    spatialindex::{ObjectIndex, PointIndex},
+    topo_elems::{Elem, Tetrahedron, Triangle},
+    Idx, Tag,
+};
+
+macro_rules! create_mesh_view {
+    ($name: ident, $mesh: ident, $dim: expr, $etype: ident) => {
+        #[doc = concat!("Read-only view of a ", stringify!($mesh), ", created by `", stringify!($mesh), ".view()`.")]
+        /// It holds a snapshot of the mesh that is not affected by later modifications of the
+        /// mesh, and can be shared by several threads: the interpolation methods release the GIL
+        /// and the spatial indices are built once, on first use
+        #[pyclass(frozen)]
+        pub struct $name {
+            mesh: Arc<SimplexMesh<$dim, $etype>>,
+            elem_tree: OnceLock<ObjectIndex<$dim>>,
+            vert_tree: OnceLock<PointIndex<$dim>>,
+        }
+
+        #[pymethods]
+        impl $mesh {
+            #[doc = concat!("Create a read-only view of the mesh (`", stringify!($name), "`) that can be shared by several")]
+            /// threads. The mesh is copied once, and the view is not affected by later
+            /// modifications of the mesh
+            #[must_use]
+            pub fn view(&self) -> $name {
+                $name {
+                    mesh: Arc::new(self.mesh.clone()),
+                    elem_tree: OnceLock::new(),
+                    vert_tree: OnceLock::new(),
+                }
+            }
+        }
+
+        #[pymethods]
+        impl $name {
+            /// Get the number of vertices in the mesh
+            #[must_use]
+            pub fn n_verts(&self) -> Idx {
+                self.mesh.n_verts()
+            }
+
+            /// Get the number of elements in the mesh
+            #[must_use]
+            pub fn n_elems(&self) -> Idx {
+                self.mesh.n_elems()
+            }
+
+            /// Get the number of faces in the mesh
+            #[must_use]
+            pub fn n_faces(&self) -> Idx {
+                self.mesh.n_faces()
+            }
+
+            /// Get the total volume of the mesh
+            #[must_use]
+            pub fn vol(&self) -> f64 {
+                self.mesh.gelems().map(|ge| ge.vol()).sum()
+            }
+
+            /// Get the volume of all the elements
+            #[must_use]
+            pub fn vols<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+                let res: Vec<_> = self.mesh.gelems().map(|ge| ge.vol()).collect();
+                to_numpy_1d(py, res)
+            }
+
+            #[doc = concat!("Get a copy of the mesh coordinates as a numpy array of shape (# of vertices, ", stringify!($dim), ")")]
+            #[must_use]
+            pub fn get_coords<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+                let mut coords = Vec::with_capacity(self.mesh.n_verts() as usize * $dim);
+                for v in self.mesh.verts() {
+                    coords.extend(v.iter().copied());
+                }
+                to_numpy_2d(py, coords, $dim)
+            }
+
+            /// Get a copy of the element connectivity as a numpy array of shape (# of elements, m)
+            #[must_use]
+            pub fn get_elems<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Idx>> {
+                let elems = self.mesh.elems().flatten().collect();
+                to_numpy_2d(py, elems, <$etype as Elem>::N_VERTS as usize)
+            }
+
+            /// Get a copy of the element tags as a numpy array of shape (# of elements)
+            #[must_use]
+            pub fn get_etags<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Tag>> {
+                to_numpy_1d(py, self.mesh.etags().collect())
+            }
+
+            /// Get a copy of the face connectivity as a numpy array of shape (# of faces, m)
+            #[must_use]
+            pub fn get_faces<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<Idx>> {
+                let faces = self.mesh.faces().flatten().collect();
+                to_numpy_2d(py, faces, <$etype as Elem>::Face::N_VERTS as usize)
+            }
+
+            /// Get a copy of the face tags as a numpy array of shape (# of faces)
+            #[must_use]
+            pub fn get_ftags<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Tag>> {
+                to_numpy_1d(py, self.mesh.ftags().collect())
+            }
+
+            /// Interpolate a field (scalar or vector) defined at the vertices (P1) to a different mesh using linear interpolation
+            /// The element tree is built on the first call and the GIL is released
+            pub fn interpolate_linear<'py>(
+                &self,
+                py: Python<'py>,
+                other: &$mesh,
+                arr: PyReadonlyArray2<f64>,
+                tol: Option<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                if arr.shape()[0] != self.mesh.n_verts() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0"));
+                }
+                let m = arr.shape()[1];
+                if other.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), m));
+                }
+                if self.mesh.n_elems() == 0 {
+                    return Err(PyValueError::new_err("Cannot interpolate from an empty mesh"));
+                }
+                let arr = arr.as_slice()?.to_vec();
+                let res = py.allow_threads(|| {
+                    let tree = self.elem_tree.get_or_init(|| self.mesh.compute_elem_tree());
+                    self.mesh.interpolate_linear(tree, &other.mesh, &arr, tol)
+                });
+                let res = res.map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(to_numpy_2d(py, res, m))
+            }
+
+            /// Interpolate a field (scalar or vector) defined at the vertices (P1) to a different mesh using nearest neighbor interpolation
+            /// The vertex tree is built on the first call and the GIL is released
+            pub fn interpolate_nearest<'py>(
+                &self,
+                py: Python<'py>,
+                other: &$mesh,
+                arr: PyReadonlyArray2<f64>,
+            ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+                if arr.shape()[0] != self.mesh.n_verts() as usize {
+                    return Err(PyValueError::new_err("Invalid dimension 0"));
+                }
+                let m = arr.shape()[1];
+                if other.mesh.n_verts() == 0 {
+                    return Ok(to_numpy_2d(py, Vec::new(), m));
+                }
+                if self.mesh.n_verts() == 0 {
+                    return Err(PyValueError::new_err("Cannot interpolate from an empty mesh"));
+                }
+                let arr = arr.as_slice()?.to_vec();
+                let res = py.allow_threads(|| {
+                    let tree = self.vert_tree.get_or_init(|| self.mesh.compute_vert_tree());
+                    self.mesh.interpolate_nearest(tree, &other.mesh, &arr)
+                });
+                let res = res.map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Ok(to_numpy_2d(py, res, m))
+            }
+        }
+    };
+}
+
+create_mesh_view!(MeshView33, Mesh33, 3, Tetrahedron);
+create_mesh_view!(MeshView22, Mesh22, 2, Triangle);
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Result, Write},
+};
+use tucanos::{mesh::SimplexMesh, topo_elems::Elem};
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 encoding, with padding
+fn base64(bytes: &[u8]) -> String {
+    let mut res = String::with_capacity(4 * bytes.len().div_ceil(3));
+    for c in bytes.chunks(3) {
+        let n = c
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= c.len() {
+                res.push(BASE64_CHARS[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+/// VTK cell type of the linear simplices with `n_verts` vertices
+fn vtk_type(n_verts: usize) -> u8 {
+    match n_verts {
+        1 => 1,
+        2 => 3,
+        3 => 5,
+        4 => 10,
+        _ => unreachable!(),
+    }
+}
+
+fn f32_bytes(values: impl Iterator<Item = f64>) -> Vec<u8> {
+    values.flat_map(|x| (x as f32).to_le_bytes()).collect()
+}
+
+/// Write an uncompressed inline binary data array, i.e. the base64 encoding of the size of the
+/// data (as a UInt64) followed by the data
+fn write_data_array(
+    w: &mut impl Write,
+    vtk_type: &str,
+    name: &str,
+    n_comp: usize,
+    bytes: &[u8],
+) -> Result<()> {
+    let mut buf = (bytes.len() as u64).to_le_bytes().to_vec();
+    buf.extend_from_slice(bytes);
+    writeln!(
+        w,
+        "<DataArray type=\"{vtk_type}\" Name=\"{name}\" NumberOfComponents=\"{n_comp}\" format=\"binary\">"
+    )?;
+    writeln!(w, "{}", base64(&buf))?;
+    writeln!(w, "</DataArray>")
+}
+
+/// Write fields given as flat arrays with `n` rows, sorted by name
+fn write_fields(w: &mut impl Write, data: &HashMap<String, &[f64]>, n: usize) -> Result<()> {
+    let mut names: Vec<_> = data.keys().collect();
+    names.sort_unstable();
+    for name in names {
+        let arr = data[name];
+        let bytes = f32_bytes(arr.iter().copied());
+        write_data_array(w, "Float32", name, arr.len() / n.max(1), &bytes)?;
+    }
+    Ok(())
+}
+
+/// Write the elements of a mesh to a binary .vtu file with the coordinates and the vertex and
+/// element data (given as flat arrays) stored as f32, e.g. for visualization-only outputs
+pub fn write_vtu_f32<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    fname: &str,
+    vert_data: &HashMap<String, &[f64]>,
+    elem_data: &HashMap<String, &[f64]>,
+) -> Result<()> {
+    let mut w = BufWriter::new(File::create(fname)?);
+    let n_verts = mesh.n_verts() as usize;
+    let n_elems = mesh.n_elems() as usize;
+    let n = E::N_VERTS as usize;
+
+    writeln!(w, "<?xml version=\"1.0\"?>")?;
+    writeln!(
+        w,
+        "<VTKFile type=\"UnstructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+    )?;
+    writeln!(w, "<UnstructuredGrid>")?;
+    writeln!(
+        w,
+        "<Piece NumberOfPoints=\"{n_verts}\" NumberOfCells=\"{n_elems}\">"
+    )?;
+
+    writeln!(w, "<Points>")?;
+    let coords = mesh
+        .verts()
+        .flat_map(|p| (0..3).map(move |i| if i < D { p[i] } else { 0.0 }));
+    write_data_array(&mut w, "Float32", "Points", 3, &f32_bytes(coords))?;
+    writeln!(w, "</Points>")?;
+
+    writeln!(w, "<Cells>")?;
+    let conn: Vec<u8> = mesh
+        .elems()
+        .flat_map(|e| e.into_iter().flat_map(|i| u64::from(i).to_le_bytes()))
+        .collect();
+    write_data_array(&mut w, "UInt64", "connectivity", 1, &conn)?;
+    let offsets: Vec<u8> = (1..=n_elems)
+        .flat_map(|i| ((i * n) as u64).to_le_bytes())
+        .collect();
+    write_data_array(&mut w, "UInt64", "offsets", 1, &offsets)?;
+    write_data_array(&mut w, "UInt8", "types", 1, &vec![vtk_type(n); n_elems])?;
+    writeln!(w, "</Cells>")?;
+
+    writeln!(w, "<PointData>")?;
+    write_fields(&mut w, vert_data, n_verts)?;
+    writeln!(w, "</PointData>")?;
+    writeln!(w, "<CellData>")?;
+    write_fields(&mut w, elem_data, n_elems)?;
+    writeln!(w, "</CellData>")?;
+
+    writeln!(w, "</Piece>")?;
+    writeln!(w, "</UnstructuredGrid>")?;
+    writeln!(w, "</VTKFile>")?;
+    w.flush()
+}
@@ -0,0 +1,383 @@
+use hdf5::{Group, H5Type};
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Result, Write},
+    path::Path,
+};
+use tucanos::{
+    mesh::{Point, SimplexMesh},
+    topo_elems::Elem,
+    Idx, Tag,
+};
+
+/// XDMF topology type of the simplices with `n_verts` vertices
+const fn topology_type(n_verts: usize) -> &'static str {
+    match n_verts {
+        1 => "Polyvertex",
+        2 => "Polyline",
+        3 => "Triangle",
+        4 => "Tetrahedron",
+        _ => unreachable!(),
+    }
+}
+
+/// XDMF attribute type of a field with `m` components
+const fn attribute_type(m: usize) -> &'static str {
+    match m {
+        1 => "Scalar",
+        3 => "Vector",
+        6 => "Tensor6",
+        _ => "Matrix",
+    }
+}
+
+fn other_error(e: impl ToString) -> Error {
+    Error::other(e.to_string())
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Write `data` as a dataset of shape (n, m) and return the corresponding XDMF data item
+fn write_dataset<T: H5Type>(
+    group: &Group,
+    h5_name: &str,
+    path: &str,
+    data: &[T],
+    m: usize,
+    number_type: &str,
+) -> Result<String> {
+    let n = data.len() / m.max(1);
+    group
+        .new_dataset::<T>()
+        .shape((n, m))
+        .create(path)
+        .and_then(|ds| ds.write_raw(data))
+        .map_err(other_error)?;
+    Ok(format!(
+        "<DataItem Dimensions=\"{n} {m}\" NumberType=\"{number_type}\" Precision=\"{}\" Format=\"HDF\">{}:/{}</DataItem>",
+        std::mem::size_of::<T>(),
+        escape(h5_name),
+        escape(path)
+    ))
+}
+
+/// Write a grid with `n_elems` simplices with `n_verts` vertices, given the data items of their
+/// connectivity and tags, the geometry element and the fields as `(name, center, # of
+/// components, data item)`
+#[allow(clippy::too_many_arguments)]
+fn write_grid<W: Write>(
+    w: &mut W,
+    name: &str,
+    n_verts: usize,
+    n_elems: usize,
+    conn: &str,
+    tags: &str,
+    geometry: &str,
+    fields: &[(String, &str, usize, String)],
+) -> Result<()> {
+    writeln!(w, "<Grid Name=\"{name}\" GridType=\"Uniform\">")?;
+    let nodes_per_elem = if n_verts == 2 {
+        " NodesPerElement=\"2\""
+    } else {
+        ""
+    };
+    writeln!(
+        w,
+        "<Topology TopologyType=\"{}\" NumberOfElements=\"{n_elems}\"{nodes_per_elem}>",
+        topology_type(n_verts),
+    )?;
+    writeln!(w, "{conn}")?;
+    writeln!(w, "</Topology>")?;
+    writeln!(w, "{geometry}")?;
+    writeln!(
+        w,
+        "<Attribute Name=\"tag\" AttributeType=\"Scalar\" Center=\"Cell\">"
+    )?;
+    writeln!(w, "{tags}")?;
+    writeln!(w, "</Attribute>")?;
+    for (name, center, m, item) in fields {
+        writeln!(
+            w,
+            "<Attribute Name=\"{}\" AttributeType=\"{}\" Center=\"{center}\">",
+            escape(name.as_str()),
+            attribute_type(*m)
+        )?;
+        writeln!(w, "{item}")?;
+        writeln!(w, "</Attribute>")?;
+    }
+    writeln!(w, "</Grid>")
+}
+
+/// Write a mesh to a .xdmf file, the data being stored in an HDF5 file with the same base name
+/// and the extension .h5: the coordinates, the elements and faces with their tags (as two grids
+/// "mesh" and "boundary") and the vertex and element data given as `(array, # of components)`
+pub fn write_xdmf<const D: usize, E: Elem>(
+    mesh: &SimplexMesh<D, E>,
+    fname: &str,
+    vert_data: &HashMap<String, (&[f64], usize)>,
+    elem_data: &HashMap<String, (&[f64], usize)>,
+) -> Result<()> {
+    let h5_path = Path::new(fname).with_extension("h5");
+    let h5_name = h5_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+    let file = hdf5::File::create(&h5_path).map_err(other_error)?;
+
+    let coords: Vec<f64> = mesh
+        .verts()
+        .flat_map(|p| (0..D).map(move |i| p[i]))
+        .collect();
+    let geometry = format!(
+        "<Geometry GeometryType=\"{}\">\n{}\n</Geometry>",
+        if D == 3 { "XYZ" } else { "XY" },
+        write_dataset(&file, h5_name, "coords", &coords, D, "Float")?
+    );
+
+    let mut fields = Vec::new();
+    for (group, center, data) in [
+        ("vert_data", "Node", vert_data),
+        ("elem_data", "Cell", elem_data),
+    ] {
+        if data.is_empty() {
+            continue;
+        }
+        let g = file.create_group(group).map_err(other_error)?;
+        let mut names: Vec<_> = data.keys().collect();
+        names.sort_unstable();
+        for name in names {
+            let (arr, m) = data[name];
+            let path = format!("{group}/{name}");
+            let item = write_dataset(&g, h5_name, &path, arr, m, "Float")?;
+            fields.push((name.clone(), center, m, item));
+        }
+    }
+
+    let elems: Vec<Idx> = mesh.elems().flatten().collect();
+    let etags: Vec<Tag> = mesh.etags().collect();
+    let faces: Vec<Idx> = mesh.faces().flatten().collect();
+    let ftags: Vec<Tag> = mesh.ftags().collect();
+    let n = E::N_VERTS as usize;
+    let n_face = E::Face::N_VERTS as usize;
+
+    let mut w = BufWriter::new(File::create(fname)?);
+    writeln!(w, "<?xml version=\"1.0\"?>")?;
+    writeln!(w, "<Xdmf Version=\"3.0\">")?;
+    writeln!(w, "<Domain>")?;
+    write_grid(
+        &mut w,
+        "mesh",
+        n,
+        etags.len(),
+        &write_dataset(&file, h5_name, "elems", &elems, n, "UInt")?,
+        &write_dataset(&file, h5_name, "etags", &etags, 1, "Int")?,
+        &geometry,
+        &fields,
+    )?;
+    if !ftags.is_empty() {
+        write_grid(
+            &mut w,
+            "boundary",
+            n_face,
+            ftags.len(),
+            &write_dataset(&file, h5_name, "faces", &faces, n_face, "UInt")?,
+            &write_dataset(&file, h5_name, "ftags", &ftags, 1, "Int")?,
+            &geometry,
+            &[],
+        )?;
+    }
+    writeln!(w, "</Domain>")?;
+    writeln!(w, "</Xdmf>")?;
+    w.flush()
+}
+
+/// A HDF data item: the dimensions and the file and dataset names
+struct DataItem {
+    dims: Vec<usize>,
+    file: String,
+    path: String,
+}
+
+/// The topology, geometry and cell tags of a grid
+#[derive(Default)]
+struct Grid {
+    topology: Option<(String, DataItem)>,
+    geometry: Option<DataItem>,
+    tags: Option<DataItem>,
+}
+
+/// Where the next data item belongs
+enum Section {
+    None,
+    Topology(String),
+    Geometry,
+    Tags,
+}
+
+fn get_attribute(e: &BytesStart, name: &str) -> Result<Option<String>> {
+    e.try_get_attribute(name)
+        .map_err(other_error)?
+        .map(|a| a.unescape_value().map(|v| v.into_owned()))
+        .transpose()
+        .map_err(other_error)
+}
+
+/// Parse the grids of a .xdmf file
+fn read_grids(fname: &str) -> Result<Vec<Grid>> {
+    let mut reader = Reader::from_file(fname).map_err(other_error)?;
+    reader.trim_text(true);
+
+    let mut grids = Vec::new();
+    let mut section = Section::None;
+    let mut item: Option<(Vec<usize>, String)> = None;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(other_error)? {
+            // data items without content are not referenced
+            Event::Empty(e) if e.name().as_ref() == b"DataItem" => {}
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"Grid" => grids.push(Grid::default()),
+                b"Topology" => {
+                    let t = get_attribute(&e, "TopologyType")?
+                        .or(get_attribute(&e, "Type")?)
+                        .unwrap_or_default();
+                    section = Section::Topology(t);
+                }
+                b"Geometry" => section = Section::Geometry,
+                b"Attribute" => {
+                    let is_tag = get_attribute(&e, "Center")?.as_deref() == Some("Cell")
+                        && get_attribute(&e, "Name")?.as_deref() == Some("tag");
+                    section = if is_tag { Section::Tags } else { Section::None };
+                }
+                b"DataItem" => {
+                    if get_attribute(&e, "Format")?.as_deref() != Some("HDF") {
+                        return Err(invalid_data("only HDF data items are supported"));
+                    }
+                    let dims = get_attribute(&e, "Dimensions")?
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<std::result::Result<_, _>>()
+                        .map_err(|_| invalid_data("invalid data item dimensions"))?;
+                    item = Some((dims, String::new()));
+                }
+                _ => {}
+            },
+            Event::Text(t) => {
+                if let Some((_, text)) = item.as_mut() {
+                    text.push_str(&t.unescape().map_err(other_error)?);
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"DataItem" => {
+                    let (dims, text) = item
+                        .take()
+                        .ok_or_else(|| invalid_data("unexpected end of data item"))?;
+                    let (file, path) = text
+                        .trim()
+                        .rsplit_once(':')
+                        .ok_or_else(|| invalid_data(&format!("invalid HDF data item {text}")))?;
+                    let item = DataItem {
+                        dims,
+                        file: file.to_string(),
+                        path: path.to_string(),
+                    };
+                    let grid = grids
+                        .last_mut()
+                        .ok_or_else(|| invalid_data("data item outside of a grid"))?;
+                    match std::mem::replace(&mut section, Section::None) {
+                        Section::Topology(t) => grid.topology = Some((t, item)),
+                        Section::Geometry => grid.geometry = Some(item),
+                        Section::Tags => grid.tags = Some(item),
+                        Section::None => {}
+                    }
+                }
+                b"Topology" | b"Geometry" | b"Attribute" => section = Section::None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(grids)
+}
+
+/// Read a data item with `m` columns, the HDF files being relative to `dir`
+fn read_dataset<T: H5Type>(dir: &Path, item: &DataItem, m: usize) -> Result<Vec<T>> {
+    let valid = match item.dims.as_slice() {
+        [_, k] => *k == m,
+        [_] => m == 1,
+        _ => false,
+    };
+    if !valid {
+        return Err(invalid_data(&format!(
+            "invalid dimensions {:?} for {}, expected {m} columns",
+            item.dims, item.path
+        )));
+    }
+    hdf5::File::open(dir.join(&item.file))
+        .and_then(|f| f.dataset(&item.path))
+        .and_then(|ds| ds.read_raw::<T>())
+        .map_err(other_error)
+}
+
+/// Read the simplices of type `F` and their tags (1 if there is no `tag` attribute) in the
+/// first grid with the corresponding topology
+fn read_conn<F: Elem>(dir: &Path, grids: &[Grid]) -> Result<(Vec<F>, Vec<Tag>)> {
+    let n = F::N_VERTS as usize;
+    let Some((grid, item)) = grids.iter().find_map(|g| {
+        g.topology
+            .as_ref()
+            .filter(|(t, _)| t.as_str() == topology_type(n))
+            .map(|(_, item)| (g, item))
+    }) else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let conn: Vec<F> = read_dataset::<Idx>(dir, item, n)?
+        .chunks(n)
+        .map(F::from_slice)
+        .collect();
+    let tags = if let Some(item) = &grid.tags {
+        read_dataset::<Tag>(dir, item, 1)?
+    } else {
+        vec![1; conn.len()]
+    };
+    if tags.len() != conn.len() {
+        return Err(invalid_data("invalid number of tags"));
+    }
+    Ok((conn, tags))
+}
+
+/// Read a mesh from a .xdmf file with its data in HDF5 files: the elements and faces are read
+/// from the first grids with the corresponding topology types (the other grids being ignored)
+/// and the tags from their cell attributes named `tag`, if any
+pub fn read_xdmf<const D: usize, E: Elem>(fname: &str) -> Result<SimplexMesh<D, E>> {
+    let dir = Path::new(fname).parent().unwrap_or_else(|| Path::new(""));
+    let grids = read_grids(fname)?;
+    let (elems, etags) = read_conn::<E>(dir, &grids)?;
+    if elems.is_empty() {
+        return Err(invalid_data(&format!(
+            "no {} grid found",
+            topology_type(E::N_VERTS as usize)
+        )));
+    }
+    let (faces, ftags) = read_conn::<E::Face>(dir, &grids)?;
+    let geometry = grids
+        .iter()
+        .find_map(|g| g.geometry.as_ref())
+        .ok_or_else(|| invalid_data("no geometry found"))?;
+    let verts = read_dataset::<f64>(dir, geometry, D)?
+        .chunks(D)
+        .map(Point::<D>::from_column_slice)
+        .collect();
+    Ok(SimplexMesh::new(verts, elems, etags, faces, ftags))
+}